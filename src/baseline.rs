@@ -0,0 +1,252 @@
+//! Overlay baselines. By default a baseline is a plain file in
+//! `baselines/`. With the experimental `object_baselines` setting it is a
+//! blob in git's object store instead, recorded in
+//! `FileEntry::baseline_blob` and kept from `git gc` by
+//! `refs/shadow/baselines/<path hash>`. Content that is already in HEAD
+//! then takes no extra space.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::config::{FileType, ShadowConfig};
+use crate::fs_util;
+use crate::git::GitRepo;
+use crate::path;
+use crate::settings::Settings;
+
+/// Namespace of the refs pinning baseline blobs
+pub const REF_PREFIX: &str = "refs/shadow/baselines/";
+
+/// Ref pinning the baseline blob of `file_path`
+pub fn ref_name(file_path: &str) -> String {
+    format!("{}{}", REF_PREFIX, path::path_hash(file_path))
+}
+
+/// Plain baseline file of `file_path`
+pub fn file_path(git: &GitRepo, file_path: &str) -> PathBuf {
+    git.shadow_dir
+        .join("baselines")
+        .join(path::encode_path(file_path))
+}
+
+/// Read the baseline of `file_path`. `blob` is the entry's `baseline_blob`.
+pub fn read(git: &GitRepo, file_path: &str, blob: Option<&str>) -> Result<Vec<u8>> {
+    match blob {
+        Some(oid) => git
+            .read_blob(oid)
+            .with_context(|| format!("failed to read baseline for {}", file_path)),
+        None => std::fs::read(self::file_path(git, file_path))
+            .with_context(|| format!("failed to read baseline for {}", file_path)),
+    }
+}
+
+/// `read` for text baselines; invalid UTF-8 is an error
+pub fn read_to_string(git: &GitRepo, file_path: &str, blob: Option<&str>) -> Result<String> {
+    let content = read(git, file_path, blob)?;
+    String::from_utf8(content)
+        .with_context(|| format!("baseline for {} is not valid UTF-8", file_path))
+}
+
+/// Object id of the baseline of `file_path`; a plain baseline is hashed
+/// in-process without being stored
+pub fn oid(git: &GitRepo, file_path: &str, blob: Option<&str>) -> Result<String> {
+    match blob {
+        Some(oid) => Ok(oid.to_string()),
+        None => Ok(crate::git::blob_oid(&read(git, file_path, None)?)),
+    }
+}
+
+/// True if the baseline of `file_path` can be read
+pub fn exists(git: &GitRepo, file_path: &str, blob: Option<&str>) -> bool {
+    match blob {
+        Some(oid) => git.read_blob(oid).is_ok(),
+        None => self::file_path(git, file_path).exists(),
+    }
+}
+
+/// Store `content` as the baseline of `file_path`, in the object store when
+/// `object_baselines` is enabled. Returns the value for
+/// `FileEntry::baseline_blob`; the other storage is cleared.
+pub fn write(git: &GitRepo, file_path: &str, content: &[u8]) -> Result<Option<String>> {
+    let use_objects = Settings::load(&git.shadow_dir)?.object_baselines;
+    write_to(git, file_path, content, use_objects)
+}
+
+fn write_to(
+    git: &GitRepo,
+    file_path: &str,
+    content: &[u8],
+    use_objects: bool,
+) -> Result<Option<String>> {
+    let plain = self::file_path(git, file_path);
+    if use_objects {
+        let oid = git.write_blob(content)?;
+        git.update_ref(&ref_name(file_path), &oid)?;
+        if plain.exists() {
+            std::fs::remove_file(&plain)?;
+        }
+        Ok(Some(oid))
+    } else {
+        fs_util::atomic_write(&plain, content)
+            .with_context(|| format!("failed to save baseline for {}", file_path))?;
+        git.delete_ref(&ref_name(file_path))?;
+        Ok(None)
+    }
+}
+
+/// Delete the baseline of `file_path` from both storages
+pub fn remove(git: &GitRepo, file_path: &str) -> Result<()> {
+    let plain = self::file_path(git, file_path);
+    if plain.exists() {
+        std::fs::remove_file(&plain)?;
+    }
+    git.delete_ref(&ref_name(file_path))
+}
+
+/// Move every overlay baseline into the storage selected by
+/// `object_baselines`. Returns the migrated paths; `config` is updated but
+/// not saved.
+pub fn migrate(git: &GitRepo, config: &mut ShadowConfig) -> Result<Vec<String>> {
+    let use_objects = Settings::load(&git.shadow_dir)?.object_baselines;
+    let pending: Vec<(String, Option<String>)> = config
+        .files
+        .iter()
+        .filter(|(_, e)| e.file_type == FileType::Overlay)
+        .filter(|(_, e)| e.baseline_blob.is_some() != use_objects)
+        .map(|(p, e)| (p.clone(), e.baseline_blob.clone()))
+        .collect();
+
+    let mut migrated = Vec::new();
+    for (file_path, blob) in pending {
+        // Entries without a readable baseline are left to `doctor`
+        let Ok(content) = read(git, &file_path, blob.as_deref()) else {
+            continue;
+        };
+        let blob = write_to(git, &file_path, &content, use_objects)?;
+        config.set_baseline_blob(&file_path, blob);
+        migrated.push(file_path);
+    }
+    Ok(migrated)
+}
+
+/// Overlays whose baseline is stored differently than `object_baselines`
+/// asks for
+pub fn pending_migration(git: &GitRepo, config: &ShadowConfig) -> Result<usize> {
+    let use_objects = Settings::load(&git.shadow_dir)?.object_baselines;
+    Ok(config
+        .files
+        .values()
+        .filter(|e| e.file_type == FileType::Overlay)
+        .filter(|e| e.baseline_blob.is_some() != use_objects)
+        .count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.email", "t@t.com"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::fs::write(root.join("CLAUDE.md"), "# Team\n").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "CLAUDE.md"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+
+        let repo = GitRepo::discover(&root).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("baselines")).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("stash")).unwrap();
+        (dir, repo)
+    }
+
+    fn enable_object_baselines(git: &GitRepo) {
+        Settings {
+            object_baselines: true,
+            ..Default::default()
+        }
+        .save(&git.shadow_dir)
+        .unwrap();
+    }
+
+    #[test]
+    fn test_write_plain_file_by_default() {
+        let (_dir, git) = make_test_repo();
+
+        let blob = write(&git, "CLAUDE.md", b"# Team\n").unwrap();
+
+        assert!(blob.is_none());
+        assert!(file_path(&git, "CLAUDE.md").exists());
+        assert_eq!(read(&git, "CLAUDE.md", None).unwrap(), b"# Team\n");
+    }
+
+    #[test]
+    fn test_write_object_reuses_head_blob() {
+        let (_dir, git) = make_test_repo();
+        enable_object_baselines(&git);
+
+        let blob = write(&git, "CLAUDE.md", b"# Team\n").unwrap().unwrap();
+
+        // Same content as HEAD: the existing blob is reused
+        assert_eq!(blob, git.blob_id("HEAD", "CLAUDE.md").unwrap());
+        assert!(git.ref_exists(&ref_name("CLAUDE.md")));
+        assert!(!file_path(&git, "CLAUDE.md").exists());
+        assert_eq!(
+            read_to_string(&git, "CLAUDE.md", Some(&blob)).unwrap(),
+            "# Team\n"
+        );
+
+        remove(&git, "CLAUDE.md").unwrap();
+        assert!(!git.ref_exists(&ref_name("CLAUDE.md")));
+    }
+
+    #[test]
+    fn test_migrate_both_ways() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), git.head_commit().unwrap())
+            .unwrap();
+        write(&git, "CLAUDE.md", b"# Old team\n").unwrap();
+
+        enable_object_baselines(&git);
+        assert_eq!(pending_migration(&git, &config).unwrap(), 1);
+        assert_eq!(migrate(&git, &mut config).unwrap(), vec!["CLAUDE.md"]);
+        let blob = config.get("CLAUDE.md").unwrap().baseline_blob.clone();
+        assert!(blob.is_some());
+        assert!(!file_path(&git, "CLAUDE.md").exists());
+        assert_eq!(
+            read(&git, "CLAUDE.md", blob.as_deref()).unwrap(),
+            b"# Old team\n"
+        );
+        assert_eq!(pending_migration(&git, &config).unwrap(), 0);
+
+        Settings::default().save(&git.shadow_dir).unwrap();
+        assert_eq!(migrate(&git, &mut config).unwrap(), vec!["CLAUDE.md"]);
+        assert!(config.get("CLAUDE.md").unwrap().baseline_blob.is_none());
+        assert!(!git.ref_exists(&ref_name("CLAUDE.md")));
+        assert_eq!(read(&git, "CLAUDE.md", None).unwrap(), b"# Old team\n");
+    }
+}
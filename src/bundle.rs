@@ -0,0 +1,370 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+
+use crate::config::FileType;
+use crate::diff_util;
+
+/// Current bundle format version
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Current version of the flat format written by `export --format json`
+pub const JSON_BUNDLE_VERSION: u32 = 1;
+
+/// Portable set of shadow changes written by `export` and read by `import`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Bundle {
+    pub version: u32,
+    pub overlays: Vec<OverlayPatch>,
+    pub phantoms: Vec<PhantomContent>,
+}
+
+/// An overlay's shadow changes. `baseline` and `content` are the full texts
+/// used for the 3-way merge on import; `patch` is the same change as a
+/// unified diff for reviewing the bundle.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OverlayPatch {
+    pub path: String,
+    #[serde(default)]
+    pub baseline_commit: Option<String>,
+    pub baseline: String,
+    pub content: String,
+    #[serde(default)]
+    pub patch: String,
+}
+
+/// A phantom file, or every file of a phantom directory
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PhantomContent {
+    pub path: String,
+    #[serde(default)]
+    pub is_directory: bool,
+    pub files: Vec<BundledFile>,
+}
+
+/// One file, by its path from the repository root. The bundle format
+/// stores the content as text; only the JSON format can carry other bytes.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BundledFile {
+    pub path: String,
+    #[serde(with = "text_content")]
+    pub content: Vec<u8>,
+}
+
+mod text_content {
+    use serde::{de, ser, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(content: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        let text = std::str::from_utf8(content)
+            .map_err(|_| ser::Error::custom("content is not valid UTF-8"))?;
+        serializer.serialize_str(text)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        String::deserialize(deserializer)
+            .map(String::into_bytes)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Flat bundle written by `export --format json`: one entry per file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JsonBundle {
+    pub version: u32,
+    pub files: Vec<JsonFile>,
+}
+
+/// An overlay carries its `baseline` and the `diff` to its current content.
+/// A phantom file carries its whole `content` in base64, so binary files
+/// travel too. A phantom directory is an entry with `directory` set, followed
+/// by one phantom entry per file inside it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct JsonFile {
+    pub path: String,
+    #[serde(rename = "type")]
+    pub file_type: FileType,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub directory: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+impl JsonBundle {
+    pub fn from_bundle(bundle: &Bundle) -> Self {
+        let mut files = Vec::new();
+        for overlay in &bundle.overlays {
+            files.push(JsonFile {
+                path: overlay.path.clone(),
+                file_type: FileType::Overlay,
+                directory: false,
+                baseline: Some(overlay.baseline.clone()),
+                content: None,
+                diff: Some(overlay.patch.clone()),
+            });
+        }
+        for phantom in &bundle.phantoms {
+            if phantom.is_directory {
+                files.push(JsonFile {
+                    path: phantom.path.clone(),
+                    file_type: FileType::Phantom,
+                    directory: true,
+                    baseline: None,
+                    content: None,
+                    diff: None,
+                });
+            }
+            for file in &phantom.files {
+                files.push(JsonFile {
+                    path: file.path.clone(),
+                    file_type: FileType::Phantom,
+                    directory: false,
+                    baseline: None,
+                    content: Some(BASE64.encode(&file.content)),
+                    diff: None,
+                });
+            }
+        }
+        Self {
+            version: JSON_BUNDLE_VERSION,
+            files,
+        }
+    }
+
+    /// Rebuild overlay contents and group phantom directories, so `import`
+    /// handles both formats the same way
+    pub fn into_bundle(self) -> Result<Bundle> {
+        let mut bundle = Bundle::new();
+        for file in self.files {
+            let path = file.path;
+            match file.file_type {
+                FileType::Overlay => {
+                    let Some(baseline) = file.baseline else {
+                        bail!("overlay {} has no baseline", path);
+                    };
+                    let patch = file.diff.unwrap_or_default();
+                    let content = diff_util::apply_git_patch(&baseline, &patch)
+                        .with_context(|| format!("invalid diff for {}", path))?;
+                    bundle.overlays.push(OverlayPatch {
+                        path,
+                        baseline_commit: None,
+                        baseline,
+                        content,
+                        patch,
+                    });
+                }
+                FileType::Phantom if file.directory => bundle.phantoms.push(PhantomContent {
+                    path,
+                    is_directory: true,
+                    files: Vec::new(),
+                }),
+                FileType::Phantom => {
+                    let Some(encoded) = file.content else {
+                        bail!("phantom {} has no content", path);
+                    };
+                    let content = BASE64
+                        .decode(encoded)
+                        .with_context(|| format!("invalid base64 content for {}", path))?;
+                    let parent = bundle
+                        .phantoms
+                        .iter_mut()
+                        .find(|p| p.is_directory && path.starts_with(&format!("{}/", p.path)));
+                    match parent {
+                        Some(directory) => directory.files.push(BundledFile { path, content }),
+                        None => bundle.phantoms.push(PhantomContent {
+                            path: path.clone(),
+                            is_directory: false,
+                            files: vec![BundledFile { path, content }],
+                        }),
+                    }
+                }
+            }
+        }
+        Ok(bundle)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        Ok(())
+    }
+}
+
+impl Bundle {
+    pub fn new() -> Self {
+        Self {
+            version: BUNDLE_VERSION,
+            overlays: Vec::new(),
+            phantoms: Vec::new(),
+        }
+    }
+
+    /// Load either format; a JSON bundle is recognized by its `files` list
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let not_a_bundle = || format!("{} is not a git-shadow bundle", path.display());
+        let value: serde_json::Value = serde_json::from_str(&content).with_context(not_a_bundle)?;
+
+        if value.get("files").is_some() {
+            let json: JsonBundle = serde_json::from_value(value).with_context(not_a_bundle)?;
+            check_version(path, "JSON bundle", json.version, JSON_BUNDLE_VERSION)?;
+            return json.into_bundle();
+        }
+
+        let bundle: Bundle = serde_json::from_value(value).with_context(not_a_bundle)?;
+        check_version(path, "bundle", bundle.version, BUNDLE_VERSION)?;
+        Ok(bundle)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        Ok(())
+    }
+}
+
+fn check_version(path: &Path, format: &str, version: u32, supported: u32) -> Result<()> {
+    if version > supported {
+        bail!(
+            "{} uses {} version {}; this git-shadow supports up to {}",
+            path.display(),
+            format,
+            version,
+            supported
+        );
+    }
+    Ok(())
+}
+
+impl Default for Bundle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("shadow.patch");
+
+        let mut bundle = Bundle::new();
+        bundle.overlays.push(OverlayPatch {
+            path: "CLAUDE.md".to_string(),
+            baseline_commit: Some("abc1234".to_string()),
+            baseline: "# Team\n".to_string(),
+            content: "# Team\n# Mine\n".to_string(),
+            patch: String::new(),
+        });
+        bundle.phantoms.push(PhantomContent {
+            path: "local.md".to_string(),
+            is_directory: false,
+            files: vec![BundledFile {
+                path: "local.md".to_string(),
+                content: b"# Local\n".to_vec(),
+            }],
+        });
+        bundle.save(&file).unwrap();
+
+        assert_eq!(Bundle::load(&file).unwrap(), bundle);
+    }
+
+    #[test]
+    fn test_json_bundle_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("shadow.json");
+
+        let baseline = "# Team\nline2\n";
+        let content = "# Team\n# Mine\nline2";
+        let mut bundle = Bundle::new();
+        bundle.overlays.push(OverlayPatch {
+            path: "CLAUDE.md".to_string(),
+            baseline_commit: None,
+            baseline: baseline.to_string(),
+            content: content.to_string(),
+            patch: diff_util::git_patch(baseline, content, "CLAUDE.md"),
+        });
+        bundle.phantoms.push(PhantomContent {
+            path: "logo.png".to_string(),
+            is_directory: false,
+            files: vec![BundledFile {
+                path: "logo.png".to_string(),
+                content: vec![0x89, b'P', b'N', b'G', 0, 0xff],
+            }],
+        });
+        bundle.phantoms.push(PhantomContent {
+            path: "notes".to_string(),
+            is_directory: true,
+            files: vec![
+                BundledFile {
+                    path: "notes/a.md".to_string(),
+                    content: b"a\n".to_vec(),
+                },
+                BundledFile {
+                    path: "notes/sub/b.md".to_string(),
+                    content: b"b\n".to_vec(),
+                },
+            ],
+        });
+        JsonBundle::from_bundle(&bundle).save(&file).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(json["files"][0]["type"], "overlay");
+        assert_eq!(json["files"][1]["content"], "iVBORwD/");
+        assert!(json["files"][1].get("baseline").is_none());
+
+        assert_eq!(Bundle::load(&file).unwrap(), bundle);
+    }
+
+    #[test]
+    fn test_json_bundle_rejects_diff_for_other_baseline() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("shadow.json");
+        let json = serde_json::json!({
+            "version": 1,
+            "files": [{
+                "path": "CLAUDE.md",
+                "type": "overlay",
+                "baseline": "other\n",
+                "diff": diff_util::git_patch("# Team\n", "# Mine\n", "CLAUDE.md"),
+            }],
+        });
+        std::fs::write(&file, json.to_string()).unwrap();
+
+        let err = Bundle::load(&file).unwrap_err();
+        assert!(format!("{:#}", err).contains("invalid diff for CLAUDE.md"));
+    }
+
+    #[test]
+    fn test_load_rejects_newer_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("shadow.patch");
+        std::fs::write(&file, r#"{"version": 99, "overlays": [], "phantoms": []}"#).unwrap();
+
+        let err = Bundle::load(&file).unwrap_err();
+        assert!(err.to_string().contains("bundle version 99"));
+    }
+
+    #[test]
+    fn test_load_rejects_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("plain.patch");
+        std::fs::write(&file, "--- a/CLAUDE.md\n+++ b/CLAUDE.md\n").unwrap();
+
+        let err = Bundle::load(&file).unwrap_err();
+        assert!(err.to_string().contains("not a git-shadow bundle"));
+    }
+}
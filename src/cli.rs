@@ -1,4 +1,6 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(
@@ -12,67 +14,418 @@ pub struct Cli {
 
 #[derive(Subcommand)]
 pub enum Commands {
-    /// Set up Git hooks
-    Install,
+    /// Create .git/shadow/ without touching Git hooks
+    Init,
+
+    /// Set up .git/shadow/ and Git hooks
+    Install {
+        /// Only create .git/shadow/ and print how to call git-shadow from your own hooks
+        #[arg(long)]
+        no_hooks: bool,
+
+        /// Also set `git st` as an alias for `git-shadow status --git`
+        #[arg(long)]
+        alias: bool,
+
+        /// Update outdated hooks even if they were edited by hand
+        #[arg(long, conflicts_with = "no_hooks")]
+        force: bool,
+
+        /// Register the files matching the default patterns
+        /// (~/.config/git-shadow/defaults.json or `default_add` in settings.json)
+        #[arg(long)]
+        apply_defaults: bool,
+
+        /// Shell for the shebang of generated hooks (default /bin/sh),
+        /// e.g. /bin/bash or "/usr/bin/env bash"
+        #[arg(long, value_name = "PATH", conflicts_with = "no_hooks")]
+        shell: Option<String>,
+    },
 
-    /// Register a file for shadow management
+    /// Remove the Git hooks written by `install`
+    Uninstall {
+        /// Also delete .git/shadow/ (only when no file is managed)
+        #[arg(long)]
+        purge: bool,
+    },
+
+    /// Register files for shadow management
     Add {
-        /// Target file path
-        file: String,
+        /// Target file paths
+        #[arg(required = true, num_args = 1..)]
+        files: Vec<String>,
         /// Register as a phantom (local-only file)
         #[arg(long)]
         phantom: bool,
+        /// Register as an overlay (default for tracked files)
+        #[arg(long, conflicts_with = "phantom")]
+        overlay: bool,
         /// Skip adding to .git/info/exclude (phantom only)
         #[arg(long)]
         no_exclude: bool,
-        /// Ignore file size limit
+        /// Where to write the exclude entry (phantom only)
+        #[arg(
+            long,
+            value_enum,
+            default_value = "info-exclude",
+            conflicts_with = "no_exclude"
+        )]
+        exclude_target: ExcludeTarget,
+        /// Register every file below a directory as its own phantom
+        #[arg(long, requires = "phantom")]
+        expand: bool,
+        /// With --expand: one exclude entry per file, or one for the directory
+        #[arg(long, value_enum, default_value = "files", requires = "expand")]
+        expand_exclude: ExpandExclude,
+        /// Use the file at this ref (branch, tag, commit) as the overlay baseline
+        #[arg(long, value_name = "REF", conflicts_with = "phantom")]
+        baseline: Option<String>,
+        /// Take the overlay baseline from HEAD (default), the index or the
+        /// working tree
+        #[arg(long, value_enum, value_name = "SOURCE", conflicts_with_all = ["phantom", "baseline"])]
+        baseline_from: Option<BaselineSource>,
+        /// Allow binary files as replace-only overlays (no merging)
+        #[arg(long, conflicts_with = "phantom")]
+        binary: bool,
+        /// Ignore file size limit and skip confirmations
         #[arg(long)]
         force: bool,
+        /// Register existing local changes as shadow changes without asking
+        #[arg(short, long)]
+        yes: bool,
+        /// Show what would be registered without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Why the file is managed (shown by `status`)
+        #[arg(long, value_name = "TEXT")]
+        note: Option<String>,
+        /// Treat the arguments as glob patterns relative to the repository
+        /// root (quote them so the shell does not expand them)
+        #[arg(long, conflicts_with = "expand")]
+        glob: bool,
+        /// Create missing phantom files from this template (built-in, or a
+        /// file in ~/.config/git-shadow/templates/)
+        #[arg(long, value_name = "NAME", requires = "phantom", conflicts_with_all = ["expand", "glob"])]
+        template: Option<String>,
     },
 
     /// Unregister a file from shadow management
     Remove {
-        /// Target file path
-        file: String,
+        /// Target file paths
+        #[arg(required = true, num_args = 1..)]
+        files: Vec<String>,
         /// Skip confirmation prompt
         #[arg(long)]
         force: bool,
+        /// Keep the current content (with shadow changes) of overlay files
+        #[arg(long)]
+        keep_shadow: bool,
     },
 
     /// Show managed files and their status
-    Status,
+    Status {
+        /// Output machine-readable JSON
+        #[arg(long)]
+        json: bool,
+
+        /// One tab-separated `TYPE PATH STATE ADDED REMOVED` line per file,
+        /// stable for scripts
+        #[arg(long, conflicts_with = "json")]
+        porcelain: bool,
+
+        /// Show `git status` with shadow-managed files in their own section
+        #[arg(long, conflicts_with_all = ["json", "porcelain"])]
+        git: bool,
+
+        /// Print only the summary line
+        #[arg(long, conflicts_with_all = ["json", "porcelain", "git"])]
+        summary_only: bool,
+
+        /// Ignore CRLF/LF differences and trailing whitespace in line counts
+        #[arg(long)]
+        ignore_whitespace: bool,
+
+        /// Exit with a problem-specific code on stash remnants (3), a stale
+        /// lock (4), suspended files (5) or outdated baselines (6)
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// List managed file paths
+    List {
+        /// Only list files of this type
+        #[arg(long = "type", value_enum)]
+        file_type: Option<FileTypeArg>,
+        /// Separate paths with NUL instead of newline
+        #[arg(short = 'z', long)]
+        null: bool,
+    },
 
     /// Show shadow changes as a diff
     Diff {
         /// Target file path (omit for all files)
         file: Option<String>,
+        /// Show only a per-file summary of changed lines
+        #[arg(long)]
+        stat: bool,
+        /// Open changes in an external diff tool (defaults to `git config diff.tool`)
+        #[arg(long, num_args = 0..=1, default_missing_value = "", conflicts_with = "stat")]
+        tool: Option<String>,
+        /// Highlight changed words instead of whole lines
+        #[arg(long, conflicts_with_all = ["stat", "tool"])]
+        word_diff: bool,
+        /// Compare phantom files with their latest snapshot instead of
+        /// showing them as new files
+        #[arg(long, conflicts_with_all = ["stat", "tool"])]
+        since_snapshot: bool,
+        /// Exit with 1 if any file has shadow changes, 0 otherwise
+        #[arg(long)]
+        exit_code: bool,
+        /// Print nothing; implies --exit-code
+        #[arg(long, conflicts_with = "tool")]
+        quiet: bool,
+        /// Ignore CRLF/LF differences and trailing whitespace
+        #[arg(long, conflicts_with = "tool")]
+        ignore_whitespace: bool,
+        /// When to color the output
+        #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorWhen::Auto)]
+        color: ColorWhen,
     },
 
     /// Update baseline and re-apply shadow changes
     Rebase {
-        /// Target file path (omit for all files)
+        /// Target file path
+        #[arg(conflicts_with = "all")]
         file: Option<String>,
+        /// Rebase all overlay files
+        #[arg(long)]
+        all: bool,
+        /// Replace the baseline with the file at REF instead of merging
+        /// (the working tree is not touched)
+        #[arg(long, value_name = "REF")]
+        set: Option<String>,
+        /// Make the working tree content the baseline at HEAD instead of
+        /// merging (the shadow changes become empty)
+        #[arg(long, conflicts_with = "set")]
+        adopt: bool,
+        /// Skip the --adopt confirmation
+        #[arg(short, long, requires = "adopt")]
+        yes: bool,
+        /// Ask how to handle each conflicted file (edit / skip / abort);
+        /// ignored when stdin is not a terminal
+        #[arg(short, long, conflicts_with_all = ["set", "adopt"])]
+        interactive: bool,
+        /// Resolve conflicts by taking the shadow changes
+        #[arg(long, conflicts_with_all = ["theirs", "set", "adopt", "interactive"])]
+        ours: bool,
+        /// Resolve conflicts by taking the HEAD version
+        #[arg(long, conflicts_with_all = ["set", "adopt", "interactive"])]
+        theirs: bool,
     },
 
     /// Recover from abnormal state
     Restore {
         /// Target file path (omit for all files)
         file: Option<String>,
+        /// Only show what would be restored
+        #[arg(long, visible_alias = "dry-run")]
+        list: bool,
+        /// Remove the lockfile even if its process is still running
+        #[arg(long)]
+        force_lock: bool,
+        /// Also check that overlay baselines match HEAD; fail on problems
+        #[arg(long, conflicts_with = "list")]
+        verify: bool,
     },
 
     /// Suspend shadow changes for branch switching
-    Suspend,
+    Suspend {
+        /// Target file paths (omit for all files)
+        files: Vec<String>,
+    },
 
     /// Resume suspended shadow changes
-    Resume,
+    Resume {
+        /// Target file paths (omit for all suspended files)
+        files: Vec<String>,
+        /// Delete suspended content that no managed file refers to
+        #[arg(long)]
+        discard_orphans: bool,
+        /// Resolve conflicts by taking the shadow changes
+        #[arg(long, conflicts_with = "theirs")]
+        ours: bool,
+        /// Resolve conflicts by taking the HEAD version
+        #[arg(long)]
+        theirs: bool,
+    },
+
+    /// Show the history of shadow operations
+    Log {
+        /// Only show entries for this file
+        #[arg(long)]
+        file: Option<String>,
+    },
 
     /// Diagnose hooks and configuration
-    Doctor,
+    Doctor {
+        /// Automatically repair problems that can be fixed mechanically
+        #[arg(long)]
+        fix: bool,
+        /// Skip confirmation for fixes that write to the working tree
+        #[arg(long, requires = "fix")]
+        yes: bool,
+    },
+
+    /// Back up phantom files to .git/shadow/snapshots/
+    Snapshot {
+        /// Restore the snapshot with this timestamp
+        #[arg(long, value_name = "TIMESTAMP", conflicts_with = "list")]
+        restore: Option<String>,
+        /// List snapshots
+        #[arg(long)]
+        list: bool,
+    },
+
+    /// Write all shadow changes to a bundle file
+    Export {
+        /// Bundle file to write
+        output: PathBuf,
+        /// Bundle format
+        #[arg(long, value_enum, default_value_t = ExportFormat::Bundle)]
+        format: ExportFormat,
+    },
+
+    /// Apply shadow changes from a bundle written by `export`
+    Import {
+        /// Bundle file to read
+        input: PathBuf,
+    },
+
+    /// Show or edit the note of a managed file
+    Note {
+        /// Managed file path
+        file: String,
+        /// New note (omit to print the current one)
+        #[arg(conflicts_with = "clear")]
+        text: Option<String>,
+        /// Remove the note
+        #[arg(long)]
+        clear: bool,
+    },
+
+    /// Remove git-shadow from the repository (files, exclude entries, hooks, .git/shadow/)
+    Clean {
+        /// Skip confirmation prompt
+        #[arg(long)]
+        force: bool,
+        /// Keep the current content (with shadow changes) of overlay files
+        #[arg(long)]
+        keep_shadow: bool,
+        /// Show what would be removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Check the shadow state for CI (exit code 2 on problems)
+    Verify {
+        /// Print nothing; only set the exit code
+        #[arg(long, short)]
+        quiet: bool,
+        /// Output machine-readable JSON
+        #[arg(long, conflicts_with = "quiet")]
+        json: bool,
+        /// Also fail on warnings (e.g. competing hook managers, baseline drift)
+        #[arg(long)]
+        strict: bool,
+    },
 
     /// Internal subcommand called from hooks
     #[command(hide = true)]
     Hook {
-        /// Hook name (pre-commit, post-commit, post-merge)
+        /// Hook name (pre-commit, post-commit, post-merge, post-checkout)
         hook_name: String,
+        /// Arguments passed to the hook by git
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
     },
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ExcludeTarget {
+    /// .git/info/exclude (local only)
+    InfoExclude,
+    /// .gitignore at the repository root (committed and shared)
+    Gitignore,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum BaselineSource {
+    /// The file in HEAD (or the --baseline ref)
+    Head,
+    /// The staged content
+    Index,
+    /// The current file, so there are no shadow changes yet
+    Worktree,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ExpandExclude {
+    /// One exclude entry per registered file
+    Files,
+    /// A single `<dir>/` entry covering the whole directory
+    Dir,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ColorWhen {
+    /// Color only when stdout is a terminal and NO_COLOR is unset
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    /// Overlays and phantoms as text, with a reviewable patch per overlay
+    Bundle,
+    /// One entry per file: overlays as baseline and diff, phantom content in
+    /// base64 (binary phantoms included)
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum FileTypeArg {
+    Overlay,
+    Phantom,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_hook(argv: &[&str]) -> (String, Vec<String>) {
+        let cli = Cli::try_parse_from(argv).unwrap();
+        match cli.command {
+            Commands::Hook { hook_name, args } => (hook_name, args),
+            _ => panic!("expected hook command"),
+        }
+    }
+
+    #[test]
+    fn test_hook_without_args() {
+        let (name, args) = parse_hook(&["git-shadow", "hook", "pre-commit"]);
+        assert_eq!(name, "pre-commit");
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn test_hook_passes_git_args_through() {
+        let (name, args) = parse_hook(&["git-shadow", "hook", "post-checkout", "abc", "def", "1"]);
+        assert_eq!(name, "post-checkout");
+        assert_eq!(args, vec!["abc", "def", "1"]);
+
+        // Hyphenated values are not parsed as options
+        let (_, args) = parse_hook(&["git-shadow", "hook", "post-rewrite", "--amend", "-x"]);
+        assert_eq!(args, vec!["--amend", "-x"]);
+    }
+}
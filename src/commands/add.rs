@@ -1,42 +1,454 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
+use is_terminal::IsTerminal;
 
-use crate::config::{ExcludeMode, ShadowConfig};
+use crate::baseline;
+use crate::cli::{BaselineSource, ExcludeTarget, ExpandExclude};
+use crate::commands::suspend;
+use crate::config::{ExcludeMode, FileEntry, FileType, ShadowConfig};
+use crate::defaults;
+use crate::diff_util::diff_stats;
 use crate::error::ShadowError;
 use crate::exclude::ExcludeManager;
-use crate::git::GitRepo;
-use crate::{fs_util, path};
+use crate::fs_util::{self, WalkEntry};
+use crate::git::{short_hash, GitRepo, HOOK_NAMES};
+use crate::history::{self, Action};
+use crate::path;
+use crate::settings::Settings;
+use crate::templates::{self, Template};
 
-pub fn run(file: &str, phantom: bool, no_exclude: bool, force: bool) -> Result<()> {
+/// Command-line flags of `git-shadow add`
+#[derive(Debug)]
+pub struct AddArgs {
+    pub phantom: bool,
+    pub overlay: bool,
+    pub no_exclude: bool,
+    pub exclude_target: ExcludeTarget,
+    /// `--expand` with its exclude scope
+    pub expand: Option<ExpandExclude>,
+    pub baseline: Option<String>,
+    pub baseline_from: Option<BaselineSource>,
+    pub binary: bool,
+    pub force: bool,
+    pub yes: bool,
+    pub dry_run: bool,
+    pub note: Option<String>,
+    /// Resolve each argument as a glob from the repository root
+    pub glob: bool,
+    /// Template name for phantom files that do not exist yet
+    pub template: Option<String>,
+}
+
+pub fn run(files: &[String], args: AddArgs) -> Result<()> {
     let git = GitRepo::discover(&std::env::current_dir()?)?;
-    let normalized = path::normalize_path(file, &git.root)?;
 
-    // Warn if hooks not installed
-    if !git.hooks_installed() {
+    let missing = git.missing_hooks();
+    if !missing.is_empty() {
         eprintln!(
             "{}",
-            "warning: hooks not installed. Run `git-shadow install`".yellow()
+            format!(
+                "warning: {} not installed. Run `git-shadow install`",
+                describe_missing_hooks(&missing)
+            )
+            .yellow()
         );
     }
 
     let mut config = ShadowConfig::load(&git.shadow_dir)?;
-
-    if phantom {
-        add_phantom(&git, &mut config, &normalized, no_exclude)?;
+    suspend::check_not_suspended(&config, args.force)?;
+    let mode = if args.phantom {
+        AddMode::Phantom
+    } else if args.overlay {
+        AddMode::Overlay
+    } else {
+        AddMode::Auto
+    };
+    let exclude = if args.no_exclude {
+        ExcludeMode::None
     } else {
-        add_overlay(&git, &mut config, &normalized, force)?;
+        match args.exclude_target {
+            ExcludeTarget::InfoExclude => ExcludeMode::GitInfoExclude,
+            ExcludeTarget::Gitignore => ExcludeMode::Gitignore,
+        }
+    };
+    let options = AddOptions {
+        exclude,
+        expand: args.expand,
+        baseline_ref: args.baseline.unwrap_or_else(|| "HEAD".to_string()),
+        baseline_from: args.baseline_from,
+        binary: args.binary,
+        force: args.force,
+        max_file_size: Settings::load(&git.shadow_dir)?.max_file_size()?,
+        confirm_local_changes: !(args.yes || args.force) && std::io::stdin().is_terminal(),
+        dry_run: args.dry_run,
+        note: args.note,
+        template: args.template.as_deref().map(templates::load).transpose()?,
+    };
+    let files = resolve_args(&git, &config, files, args.glob)?;
+    if files.is_empty() {
+        return Ok(());
     }
+    let files = files.as_slice();
+    let summary = add_files(&git, &mut config, files, mode, &options)?;
 
-    config.save(&git.shadow_dir)?;
+    if options.dry_run {
+        println!(
+            "dry run: {} would be registered, {} would fail (nothing was changed)",
+            summary.registered, summary.failed
+        );
+        if summary.failed > 0 {
+            bail!("{} file(s) would fail to register", summary.failed);
+        }
+        return Ok(());
+    }
+
+    if files.len() > 1 {
+        println!(
+            "summary: {} registered, {} failed",
+            summary.registered, summary.failed
+        );
+    }
+
+    if summary.failed > 0 {
+        bail!("failed to register {} file(s)", summary.failed);
+    }
     Ok(())
 }
 
-fn add_overlay(
+/// How to decide between overlay and phantom registration
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AddMode {
+    Overlay,
+    Phantom,
+    /// Tracked files become overlays, untracked files become phantoms
+    Auto,
+}
+
+/// Per-invocation settings shared by every file in an `add`
+#[derive(Debug)]
+pub(crate) struct AddOptions {
+    /// Exclude target for phantoms
+    pub(crate) exclude: ExcludeMode,
+    /// Register directories file by file (`--expand`)
+    pub(crate) expand: Option<ExpandExclude>,
+    /// Ref whose content becomes the overlay baseline
+    pub(crate) baseline_ref: String,
+    /// `--baseline-from`; None means the ref, with a warning for staged changes
+    pub(crate) baseline_from: Option<BaselineSource>,
+    /// Register overlays as replace-only binary overlays
+    pub(crate) binary: bool,
+    /// Skip the size limit for overlays
+    pub(crate) force: bool,
+    /// Effective size limit for overlays in bytes
+    pub(crate) max_file_size: u64,
+    /// Ask before taking over existing local changes as shadow changes
+    pub(crate) confirm_local_changes: bool,
+    /// Run every check and report the planned operations without writing
+    pub(crate) dry_run: bool,
+    /// Note stored with every registered entry
+    pub(crate) note: Option<String>,
+    /// Initial content written for phantom files that do not exist yet
+    pub(crate) template: Option<Template>,
+}
+
+impl Default for AddOptions {
+    fn default() -> Self {
+        Self {
+            exclude: ExcludeMode::GitInfoExclude,
+            expand: None,
+            baseline_ref: "HEAD".to_string(),
+            baseline_from: None,
+            binary: false,
+            force: false,
+            max_file_size: fs_util::DEFAULT_SIZE_LIMIT,
+            confirm_local_changes: false,
+            dry_run: false,
+            note: None,
+            template: None,
+        }
+    }
+}
+
+/// "hooks" when none is installed, else the missing hook names
+fn describe_missing_hooks(missing: &[&str]) -> String {
+    match missing {
+        [name] => format!("{} hook", name),
+        _ if missing.len() == HOOK_NAMES.len() => "hooks".to_string(),
+        _ => format!("{} hooks", missing.join(", ")),
+    }
+}
+
+/// Outcome counts for a multi-file `add`
+#[derive(Debug, Default)]
+struct AddSummary {
+    registered: usize,
+    failed: usize,
+}
+
+/// Register each file independently. A failure is reported and skipped; the
+/// config is saved after every successful registration so earlier successes
+/// survive a later error. In dry-run mode only the in-memory config changes,
+/// so duplicates within one invocation are still detected.
+fn add_files(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    files: &[String],
+    mode: AddMode,
+    options: &AddOptions,
+) -> Result<AddSummary> {
+    let mut summary = AddSummary::default();
+
+    let expanded;
+    let files = if mode == AddMode::Phantom && options.expand.is_some() {
+        expanded = expand_directories(git, config, files, options)?;
+        expanded.as_slice()
+    } else {
+        files
+    };
+
+    let submodules = git.submodule_paths()?;
+    for file in files {
+        let result = path::normalize_path(file, "", &git.root).and_then(|normalized| {
+            if let Some(submodule) = containing_submodule(&submodules, &normalized) {
+                bail!(
+                    "{} is inside submodule {}. Run git-shadow from {} to manage it there",
+                    normalized,
+                    submodule,
+                    submodule
+                );
+            }
+            let existing = config.get(&normalized).cloned();
+            match mode {
+                AddMode::Overlay => match existing {
+                    Some(entry) if entry.file_type == FileType::Phantom => {
+                        promote_phantom(git, config, &normalized, entry, options)
+                    }
+                    _ => add_overlay(git, config, &normalized, options),
+                },
+                AddMode::Phantom => match existing {
+                    Some(entry) if entry.file_type == FileType::Overlay => {
+                        demote_overlay(git, config, &normalized, entry, options)
+                    }
+                    _ => add_phantom(git, config, &normalized, options, false),
+                },
+                AddMode::Auto => {
+                    if git.is_tracked(&normalized)? {
+                        add_overlay(git, config, &normalized, options)
+                    } else {
+                        add_phantom(git, config, &normalized, options, true)
+                    }
+                }
+            }
+            .map(|()| normalized)
+        });
+
+        match result {
+            Ok(_) if options.dry_run => summary.registered += 1,
+            Ok(normalized) => {
+                // A converted entry keeps its note unless a new one is given
+                if let (Some(entry), Some(note)) =
+                    (config.files.get_mut(&normalized), &options.note)
+                {
+                    entry.note = Some(note.clone());
+                }
+                config.save(&git.shadow_dir)?;
+                let commit = config
+                    .get(&normalized)
+                    .and_then(|e| e.baseline_commit.as_deref());
+                history::record(&git.shadow_dir, Action::Add, Some(&normalized), commit);
+                summary.registered += 1;
+            }
+            Err(e) => {
+                eprintln!("{}", format!("error: {}: {}", file, e).red());
+                summary.failed += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// The submodule `path` is in (or is itself), if any
+fn containing_submodule<'a>(submodules: &'a [String], path: &str) -> Option<&'a str> {
+    submodules
+        .iter()
+        .find(|submodule| {
+            path == submodule.as_str()
+                || path
+                    .strip_prefix(submodule.as_str())
+                    .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .map(String::as_str)
+}
+
+/// The arguments as paths from the repository root: `--glob` patterns are
+/// matched from the root, plain paths are taken from the current directory
+fn resolve_args(
+    git: &GitRepo,
+    config: &ShadowConfig,
+    files: &[String],
+    glob: bool,
+) -> Result<Vec<String>> {
+    if glob {
+        return expand_globs(git, config, files);
+    }
+    Ok(files
+        .iter()
+        .map(|file| path::in_cwd(file, &git.prefix))
+        .collect())
+}
+
+/// `--glob`: the files each pattern matches from the repository root, for
+/// patterns the shell did not expand (or that should not depend on the
+/// current directory). Patterns without a match get a warning; files that
+/// are already managed are skipped and counted.
+fn expand_globs(git: &GitRepo, config: &ShadowConfig, patterns: &[String]) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for pattern in patterns {
+        let matched: Vec<String> = defaults::expand(&git.root, std::slice::from_ref(pattern))?
+            .into_iter()
+            .filter(|file| git.root.join(file).is_file())
+            .collect();
+        if matched.is_empty() {
+            eprintln!(
+                "{}",
+                format!("warning: no files match '{}'", pattern).yellow()
+            );
+        }
+        files.extend(matched);
+    }
+    files.sort();
+    files.dedup();
+
+    let matched = files.len();
+    files.retain(|file| config.get(file).is_none());
+    if files.len() < matched {
+        println!("skipped {} already managed file(s)", matched - files.len());
+    }
+    Ok(files)
+}
+
+/// Register the files matching the default patterns (`defaults::patterns()`)
+/// that are not managed yet, tracked ones as overlays and the rest as
+/// phantoms. Failures are reported per file. Returns how many were registered.
+pub(crate) fn add_defaults(git: &GitRepo, config: &mut ShadowConfig) -> Result<usize> {
+    let settings = Settings::load(&git.shadow_dir)?;
+    let patterns = defaults::patterns(&settings)?;
+    let files: Vec<String> = defaults::expand(&git.root, &patterns)?
+        .into_iter()
+        .filter(|file| config.get(file).is_none())
+        .collect();
+    if files.is_empty() {
+        return Ok(0);
+    }
+
+    let options = AddOptions {
+        max_file_size: settings.max_file_size()?,
+        ..AddOptions::default()
+    };
+    let summary = add_files(git, config, &files, AddMode::Auto, &options)?;
+    Ok(summary.registered)
+}
+
+/// `--expand`: replace each directory argument with the files below it that
+/// are not managed yet, so re-running picks up files added later. With
+/// `--expand-exclude dir` the directory gets its single exclude entry here.
+fn expand_directories(
+    git: &GitRepo,
+    config: &ShadowConfig,
+    files: &[String],
+    options: &AddOptions,
+) -> Result<Vec<String>> {
+    let mut expanded = Vec::new();
+
+    for file in files {
+        let Ok(normalized) = path::normalize_path(file, "", &git.root) else {
+            // Reported by the registration loop
+            expanded.push(file.clone());
+            continue;
+        };
+        let dir = git.root.join(&normalized);
+        if !dir.is_dir() {
+            expanded.push(normalized);
+            continue;
+        }
+        if config.get(&normalized).is_some() {
+            bail!(
+                "{} is registered as a phantom directory. Run `git-shadow remove {}` before expanding it",
+                normalized,
+                normalized
+            );
+        }
+
+        let mut found = 0;
+        let mut already = 0;
+        for entry in fs_util::walk_dir(&dir)? {
+            match entry {
+                WalkEntry::File { path, .. } => {
+                    found += 1;
+                    let file_path = format!(
+                        "{}/{}",
+                        normalized,
+                        path.to_string_lossy().replace('\\', "/")
+                    );
+                    if config.get(&file_path).is_some() {
+                        already += 1;
+                    } else {
+                        expanded.push(file_path);
+                    }
+                }
+                WalkEntry::Symlink { path, .. } => eprintln!(
+                    "{}",
+                    format!(
+                        "warning: skipping symlink {}/{}",
+                        normalized,
+                        path.to_string_lossy()
+                    )
+                    .yellow()
+                ),
+            }
+        }
+
+        if found == 0 {
+            eprintln!(
+                "{}",
+                format!("warning: {} contains no files", normalized).yellow()
+            );
+        } else if already > 0 {
+            println!("{}: {} file(s) already managed", normalized, already);
+        }
+
+        if options.expand == Some(ExpandExclude::Dir) && found > 0 {
+            if let Some(manager) = ExcludeManager::for_mode(git, &options.exclude) {
+                let exclude_path = format!("{}/", normalized);
+                if options.dry_run {
+                    println!("would add {} to {}", exclude_path, manager.display_name());
+                } else {
+                    manager
+                        .add_entry(&exclude_path)
+                        .with_context(|| format!("failed to add to {}", manager.display_name()))?;
+                }
+            }
+        }
+    }
+
+    Ok(expanded)
+}
+
+pub(crate) fn add_overlay(
     git: &GitRepo,
     config: &mut ShadowConfig,
     normalized: &str,
-    force: bool,
+    options: &AddOptions,
 ) -> Result<()> {
+    let baseline_ref = options.baseline_ref.as_str();
+    let binary = options.binary;
+
+    // Before the local-changes prompt and any baseline write
+    if config.get(normalized).is_some() {
+        return Err(ShadowError::AlreadyManaged(normalized.to_string()).into());
+    }
+
     // Check file is tracked
     if !git.is_tracked(normalized)? {
         return Err(ShadowError::FileNotTracked(normalized.to_string()).into());
@@ -44,45 +456,285 @@ fn add_overlay(
 
     let file_path = git.root.join(normalized);
 
-    // Binary check
-    if fs_util::is_binary(&file_path)? {
+    // Binary check as git sees it, `.gitattributes` first (binary overlays
+    // are replace-only and skip it)
+    if !binary && git.is_binary(normalized)? {
         return Err(ShadowError::BinaryFile(normalized.to_string()).into());
     }
 
+    // A filtered file (e.g. an LFS pointer) is committed in another form
+    // than the working tree holds, so baseline and file would always differ
+    if let Some(filter) = git.filter_attr(normalized)? {
+        return Err(ShadowError::FilteredFile(normalized.to_string(), filter).into());
+    }
+
     // Size check
-    fs_util::check_size(&file_path, force)?;
+    fs_util::check_size(&file_path, options.force, options.max_file_size)?;
 
-    // Get HEAD content as baseline
-    let commit = git.head_commit()?;
-    let baseline_content = git.show_file("HEAD", normalized)?;
+    // Resolve the baseline ref to a full hash and read the file there, or in
+    // the index or working tree (recorded against HEAD)
+    let commit = git.resolve_commit(baseline_ref)?;
+    let (baseline_content, source) = match options.baseline_from {
+        None | Some(BaselineSource::Head) => {
+            let content = git.show_file(&commit, normalized).map_err(|_| {
+                ShadowError::FileNotInRef(normalized.to_string(), baseline_ref.to_string())
+            })?;
+            (content, baseline_ref)
+        }
+        Some(BaselineSource::Index) => {
+            let content = git
+                .show_file("", normalized)
+                .with_context(|| format!("{} is not in the index", normalized))?;
+            (content, "the index")
+        }
+        Some(BaselineSource::Worktree) => (std::fs::read(&file_path)?, "the working tree"),
+    };
+    let from = match options.baseline_from {
+        Some(BaselineSource::Index | BaselineSource::Worktree) => format!(", from {}", source),
+        _ => String::new(),
+    };
 
-    // Save baseline
+    let is_head = commit == git.head_commit()?;
+    let short_commit = short_hash(&commit).to_string();
     let encoded = path::encode_path(normalized);
-    let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
-    fs_util::atomic_write(&baseline_path, &baseline_content).context("failed to save baseline")?;
+    let kind = if binary { "binary overlay" } else { "overlay" };
 
-    // Add to config
-    config.add_overlay(normalized.to_string(), commit)?;
+    // Changes already in the working tree become shadow changes right away
+    let local_changes =
+        describe_local_changes(&std::fs::read(&file_path)?, &baseline_content, binary);
+    if let Some(changes) = &local_changes {
+        if !options.dry_run
+            && options.confirm_local_changes
+            && !confirm_local_changes(normalized, changes)?
+        {
+            bail!("local changes were not confirmed (use --yes to register them)");
+        }
+    }
 
-    println!(
-        "registered {} as overlay (baseline: {})",
-        normalized,
-        &config
-            .get(normalized)
-            .unwrap()
-            .baseline_commit
-            .as_deref()
-            .unwrap_or("?")[..7]
-    );
+    if options.dry_run {
+        // Registered in memory only, so later duplicates are still caught
+        config.add_overlay_with(normalized.to_string(), commit, binary)?;
+        println!(
+            "would register {} as {} (baseline: {}{})",
+            normalized, kind, short_commit, from
+        );
+        println!(
+            "  save {} bytes from {} to .git/shadow/baselines/{}",
+            baseline_content.len(),
+            source,
+            encoded
+        );
+        println!("  add {} to config.json", normalized);
+        if let Some(changes) = &local_changes {
+            println!(
+                "  take over existing local changes ({}) as shadow changes",
+                changes
+            );
+        }
+    } else {
+        // Save baseline
+        let blob = baseline::write(git, normalized, &baseline_content)
+            .context("failed to save baseline")?;
+
+        // Add to config
+        config.add_overlay_with(normalized.to_string(), commit, binary)?;
+        config.set_baseline_blob(normalized, blob);
+
+        println!(
+            "registered {} as {} (baseline: {}{})",
+            normalized, kind, short_commit, from
+        );
+        if let Some(changes) = &local_changes {
+            println!(
+                "  existing local changes ({}) are now shadow changes",
+                changes
+            );
+        }
+    }
+
+    if options.baseline_from.is_none() && git.staged_paths()?.iter().any(|p| p == normalized) {
+        eprintln!(
+            "{}",
+            format!(
+                "warning: {} has staged changes that are not in the baseline ({}); commits will leave them out. Use --baseline-from index to keep them",
+                normalized, baseline_ref
+            )
+            .yellow()
+        );
+    }
+    if options.baseline_from == Some(BaselineSource::Worktree)
+        && git.show_file("HEAD", normalized).ok().as_ref() != Some(&baseline_content)
+    {
+        eprintln!(
+            "{}",
+            format!(
+                "warning: {} differs from HEAD; the next commit that includes it commits the current content",
+                normalized
+            )
+            .yellow()
+        );
+    }
+    if !is_head {
+        eprintln!(
+            "{}",
+            format!(
+                "warning: baseline for {} is not HEAD. Commits will contain the {} version until you run `git-shadow rebase {}`",
+                normalized, baseline_ref, normalized
+            )
+            .yellow()
+        );
+    }
     Ok(())
 }
 
-fn add_phantom(
+/// `add --overlay` on a phantom that has since been committed: register it as
+/// an overlay in place and drop its exclude entry
+fn promote_phantom(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    normalized: &str,
+    entry: FileEntry,
+    options: &AddOptions,
+) -> Result<()> {
+    if entry.suspended {
+        return Err(ShadowError::Suspended.into());
+    }
+    if entry.is_directory {
+        bail!(
+            "{} is a phantom directory and cannot become an overlay",
+            normalized
+        );
+    }
+    if !git.is_tracked(normalized)? {
+        bail!(
+            "{} is a phantom and not tracked by Git. Commit it before registering it as an overlay",
+            normalized
+        );
+    }
+
+    let manager = ExcludeManager::for_mode(git, &entry.exclude_mode);
+    let exclude_path = normalized.to_string();
+    config.remove(normalized)?;
+    let result = add_overlay(git, config, normalized, options).and_then(|()| {
+        match &manager {
+            Some(manager) if options.dry_run => {
+                println!("  remove {} from {}", exclude_path, manager.display_name());
+            }
+            Some(manager) => {
+                if let Err(e) = manager.remove_entry(&exclude_path) {
+                    baseline::remove(git, normalized)?;
+                    return Err(e).with_context(|| {
+                        format!("failed to remove from {}", manager.display_name())
+                    });
+                }
+            }
+            None => {}
+        }
+        if !options.dry_run {
+            println!("  promoted from phantom");
+        }
+        Ok(())
+    });
+
+    finish_conversion(config, normalized, entry, result)
+}
+
+/// `add --phantom` on an overlay that has been removed from the index
+/// (`git rm --cached`): register it as a phantom and drop its baseline. The
+/// working tree content is kept as the local-only file.
+fn demote_overlay(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    normalized: &str,
+    entry: FileEntry,
+    options: &AddOptions,
+) -> Result<()> {
+    if entry.suspended {
+        return Err(ShadowError::Suspended.into());
+    }
+    if git.is_tracked(normalized)? {
+        bail!(
+            "{} is an overlay and still tracked by Git. Run `git rm --cached {}` before registering it as a phantom",
+            normalized,
+            normalized
+        );
+    }
+
+    config.remove(normalized)?;
+    let result = add_phantom(git, config, normalized, options, false).and_then(|()| {
+        if options.dry_run {
+            println!("  delete the overlay baseline");
+        } else {
+            baseline::remove(git, normalized)?;
+            println!("  demoted from overlay");
+        }
+        Ok(())
+    });
+
+    finish_conversion(config, normalized, entry, result)
+}
+
+/// Carry the note over to the converted entry, or put the old entry back
+/// when the conversion failed
+fn finish_conversion(
+    config: &mut ShadowConfig,
+    normalized: &str,
+    entry: FileEntry,
+    result: Result<()>,
+) -> Result<()> {
+    match result {
+        Ok(()) => {
+            if let Some(converted) = config.files.get_mut(normalized) {
+                converted.note = entry.note;
+            }
+            Ok(())
+        }
+        Err(e) => {
+            config.files.insert(normalized.to_string(), entry);
+            Err(e)
+        }
+    }
+}
+
+/// Summary of how the working tree differs from the baseline, or None when
+/// they are identical
+fn describe_local_changes(current: &[u8], baseline: &[u8], binary: bool) -> Option<String> {
+    if current == baseline {
+        return None;
+    }
+    if binary {
+        return Some("binary content differs".to_string());
+    }
+    let (added, removed) = diff_stats(
+        &String::from_utf8_lossy(baseline),
+        &String::from_utf8_lossy(current),
+    );
+    Some(format!("+{} -{} lines", added, removed))
+}
+
+fn confirm_local_changes(normalized: &str, changes: &str) -> Result<bool> {
+    eprintln!(
+        "{} already has local changes ({}). Register them as shadow changes? [y/N]",
+        normalized, changes
+    );
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let input = input.trim().to_lowercase();
+    Ok(input == "y" || input == "yes")
+}
+
+pub(crate) fn add_phantom(
     git: &GitRepo,
     config: &mut ShadowConfig,
     normalized: &str,
-    no_exclude: bool,
+    options: &AddOptions,
+    auto_detected: bool,
 ) -> Result<()> {
+    // Before the template creates a file that would then be left unexcluded
+    if config.get(normalized).is_some() {
+        return Err(ShadowError::AlreadyManaged(normalized.to_string()).into());
+    }
+
     // Phantom files should NOT be tracked
     if git.is_tracked(normalized)? {
         return Err(anyhow::anyhow!(
@@ -92,34 +744,125 @@ fn add_phantom(
     }
 
     let full_path = git.root.join(normalized);
+    let created = match &options.template {
+        Some(template) => apply_template(&full_path, normalized, template, options.dry_run)?,
+        None => false,
+    };
     let is_dir = full_path.is_dir();
 
-    let exclude_mode = if no_exclude {
-        ExcludeMode::None
+    let suffix = if auto_detected {
+        " (auto-detected)"
     } else {
-        // Add to .git/info/exclude (with trailing / for directories)
-        let exclude_path = if is_dir {
-            format!("{}/", normalized)
-        } else {
-            normalized.to_string()
-        };
-        let manager = ExcludeManager::new(&git.git_dir);
-        manager
-            .add_entry(&exclude_path)
-            .context("failed to add to .git/info/exclude")?;
-        ExcludeMode::GitInfoExclude
+        ""
     };
+    let kind = if is_dir {
+        "phantom directory"
+    } else {
+        "phantom"
+    };
+    let exclude_path = if is_dir {
+        format!("{}/", normalized)
+    } else {
+        normalized.to_string()
+    };
+    // `--expand-exclude dir` already excluded the whole directory
+    let mut manager = if options.expand == Some(ExpandExclude::Dir) {
+        None
+    } else {
+        ExcludeManager::for_mode(git, &options.exclude)
+    };
+    // An entry would only duplicate an existing ignore rule
+    let mut exclude = &options.exclude;
+    if manager.is_some() && git.is_ignored(normalized)? {
+        manager = None;
+        exclude = &ExcludeMode::AlreadyIgnored;
+    }
 
-    config.add_phantom(normalized.to_string(), exclude_mode, is_dir)?;
+    if options.dry_run {
+        config.add_phantom(normalized.to_string(), exclude.clone(), is_dir)?;
+        println!("would register {} as {}{}", normalized, kind, suffix);
+        if let Some(manager) = &manager {
+            println!("  add {} to {}", exclude_path, manager.display_name());
+        }
+        if *exclude == ExcludeMode::AlreadyIgnored {
+            println!(
+                "  skip the exclude entry ({} is already ignored)",
+                normalized
+            );
+        }
+        println!("  add {} to config.json", normalized);
+        return Ok(());
+    }
 
+    // Add the exclude entry (with trailing / for directories)
+    if let Some(manager) = &manager {
+        if let Err(e) = manager.add_entry(&exclude_path) {
+            // A created file without its exclude entry could be committed
+            if created {
+                std::fs::remove_file(&full_path)?;
+            }
+            return Err(e).with_context(|| format!("failed to add to {}", manager.display_name()));
+        }
+    }
+
+    config.add_phantom(normalized.to_string(), exclude.clone(), is_dir)?;
+    // `diff` lists the files added, removed or modified since now
     if is_dir {
-        println!("registered {} as phantom directory", normalized);
-    } else {
-        println!("registered {} as phantom", normalized);
+        let manifest = fs_util::dir_manifest(&full_path)
+            .with_context(|| format!("failed to list files in {}", normalized))?;
+        if let Some(entry) = config.files.get_mut(normalized) {
+            entry.manifest = Some(manifest);
+        }
+    }
+
+    println!("registered {} as {}{}", normalized, kind, suffix);
+    if *exclude == ExcludeMode::AlreadyIgnored {
+        println!(
+            "  {} is already ignored by git; no exclude entry was added",
+            normalized
+        );
+    } else if *exclude == ExcludeMode::Gitignore {
+        eprintln!(
+            "{}",
+            "note: added to .gitignore, which is committed and shared with the team".yellow()
+        );
     }
     Ok(())
 }
 
+/// Create a missing phantom file from `template`. An existing file is
+/// registered as it is. Returns whether a file was created.
+fn apply_template(
+    full_path: &std::path::Path,
+    normalized: &str,
+    template: &Template,
+    dry_run: bool,
+) -> Result<bool> {
+    if full_path.exists() {
+        println!(
+            "{} already exists; template '{}' was not applied",
+            normalized, template.name
+        );
+        return Ok(false);
+    }
+    if dry_run {
+        println!(
+            "would create {} from template '{}'",
+            normalized, template.name
+        );
+        return Ok(false);
+    }
+
+    if let Some(parent) = full_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create parent directory for {}", normalized))?;
+    }
+    std::fs::write(full_path, &template.content)
+        .with_context(|| format!("failed to write {}", normalized))?;
+    println!("created {} from template '{}'", normalized, template.name);
+    Ok(true)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,7 +911,7 @@ mod tests {
     fn test_add_overlay_creates_config_entry() {
         let (_dir, git) = make_test_repo();
         let mut config = ShadowConfig::new();
-        add_overlay(&git, &mut config, "CLAUDE.md", false).unwrap();
+        add_overlay(&git, &mut config, "CLAUDE.md", &AddOptions::default()).unwrap();
 
         let entry = config.get("CLAUDE.md").unwrap();
         assert_eq!(entry.file_type, crate::config::FileType::Overlay);
@@ -179,20 +922,53 @@ mod tests {
     fn test_add_overlay_saves_baseline() {
         let (_dir, git) = make_test_repo();
         let mut config = ShadowConfig::new();
-        add_overlay(&git, &mut config, "CLAUDE.md", false).unwrap();
+        add_overlay(&git, &mut config, "CLAUDE.md", &AddOptions::default()).unwrap();
 
-        let baseline = git.shadow_dir.join("baselines").join("CLAUDE.md");
+        let baseline = git
+            .shadow_dir
+            .join("baselines")
+            .join(crate::path::encode_path("CLAUDE.md"));
         assert!(baseline.exists());
         let content = std::fs::read_to_string(&baseline).unwrap();
         assert_eq!(content, "# Team CLAUDE\n");
     }
 
+    #[test]
+    fn test_add_overlay_baseline_from_index_and_worktree() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Staged\n").unwrap();
+        git_in(&git, &["add", "CLAUDE.md"]);
+        std::fs::write(git.root.join("CLAUDE.md"), "# Staged\n# Mine\n").unwrap();
+        let head = git.head_commit().unwrap();
+
+        for (source, expected) in [
+            (BaselineSource::Head, "# Team CLAUDE\n"),
+            (BaselineSource::Index, "# Staged\n"),
+            (BaselineSource::Worktree, "# Staged\n# Mine\n"),
+        ] {
+            let mut config = ShadowConfig::new();
+            let options = AddOptions {
+                baseline_from: Some(source),
+                ..AddOptions::default()
+            };
+            add_overlay(&git, &mut config, "CLAUDE.md", &options).unwrap();
+            assert_eq!(
+                baseline::read_to_string(&git, "CLAUDE.md", None).unwrap(),
+                expected
+            );
+            assert_eq!(
+                config.get("CLAUDE.md").unwrap().baseline_commit.as_deref(),
+                Some(head.as_str())
+            );
+        }
+    }
+
     #[test]
     fn test_add_overlay_rejects_untracked() {
         let (_dir, git) = make_test_repo();
         std::fs::write(git.root.join("new.md"), "new").unwrap();
         let mut config = ShadowConfig::new();
-        let result = add_overlay(&git, &mut config, "new.md", false);
+        let result = add_overlay(&git, &mut config, "new.md", &AddOptions::default());
         assert!(result.is_err());
     }
 
@@ -215,17 +991,110 @@ mod tests {
             .unwrap();
 
         let mut config = ShadowConfig::new();
-        let result = add_overlay(&git, &mut config, "bin.dat", false);
+        let result = add_overlay(&git, &mut config, "bin.dat", &AddOptions::default());
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_add_overlay_follows_gitattributes() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join(".gitattributes"), "CLAUDE.md binary\n").unwrap();
+
+        let mut config = ShadowConfig::new();
+        let err = add_overlay(&git, &mut config, "CLAUDE.md", &AddOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("binary file"));
+
+        // `text` allows null bytes
+        std::fs::write(git.root.join(".gitattributes"), "CLAUDE.md text\n").unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), b"# Team\0\n").unwrap();
+        add_overlay(&git, &mut config, "CLAUDE.md", &AddOptions::default()).unwrap();
+    }
+
+    #[test]
+    fn test_add_overlay_rejects_filtered_file() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join(".gitattributes"), "*.md filter=lfs\n").unwrap();
+
+        let mut config = ShadowConfig::new();
+        let err = add_overlay(&git, &mut config, "CLAUDE.md", &AddOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("filter (lfs)"));
+        assert!(config.get("CLAUDE.md").is_none());
+
+        std::fs::write(git.root.join(".gitattributes"), "*.md -filter\n").unwrap();
+        add_overlay(&git, &mut config, "CLAUDE.md", &AddOptions::default()).unwrap();
+    }
+
+    #[test]
+    fn test_add_overlay_binary_flag_allows_binary() {
+        let (_dir, git) = make_test_repo();
+        let mut content = b"hello".to_vec();
+        content.push(0x00);
+        std::fs::write(git.root.join("bin.dat"), &content).unwrap();
+        std::process::Command::new("git")
+            .args(["add", "bin.dat"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "add binary"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+
+        let mut config = ShadowConfig::new();
+        add_overlay(
+            &git,
+            &mut config,
+            "bin.dat",
+            &AddOptions {
+                binary: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert!(config.get("bin.dat").unwrap().is_binary);
+        let baseline = std::fs::read(
+            git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("bin.dat")),
+        )
+        .unwrap();
+        assert_eq!(baseline, content);
+    }
+
+    #[test]
+    fn test_add_overlay_uses_configured_size_limit() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let options = AddOptions {
+            max_file_size: 4,
+            ..Default::default()
+        };
+
+        let err = add_overlay(&git, &mut config, "CLAUDE.md", &options).unwrap_err();
+        assert!(err.to_string().contains("> 4 bytes"), "{}", err);
+        assert!(config.get("CLAUDE.md").is_none());
+    }
+
     #[test]
     fn test_add_overlay_rejects_duplicate() {
         let (_dir, git) = make_test_repo();
         let mut config = ShadowConfig::new();
-        add_overlay(&git, &mut config, "CLAUDE.md", false).unwrap();
-        let result = add_overlay(&git, &mut config, "CLAUDE.md", false);
-        assert!(result.is_err());
+        add_overlay(&git, &mut config, "CLAUDE.md", &AddOptions::default()).unwrap();
+
+        // Shadow changes must not be taken over into the stored baseline
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team CLAUDE\n# Mine\n").unwrap();
+        let options = AddOptions {
+            baseline_from: Some(BaselineSource::Worktree),
+            ..AddOptions::default()
+        };
+        let err = add_overlay(&git, &mut config, "CLAUDE.md", &options).unwrap_err();
+        assert!(err.to_string().contains("already managed"));
+        assert_eq!(
+            crate::baseline::read(&git, "CLAUDE.md", None).unwrap(),
+            b"# Team CLAUDE\n"
+        );
     }
 
     #[test]
@@ -237,7 +1106,14 @@ mod tests {
         std::fs::write(phantom_dir.join("CLAUDE.md"), "# Local\n").unwrap();
 
         let mut config = ShadowConfig::new();
-        add_phantom(&git, &mut config, "src/components/CLAUDE.md", false).unwrap();
+        add_phantom(
+            &git,
+            &mut config,
+            "src/components/CLAUDE.md",
+            &AddOptions::default(),
+            false,
+        )
+        .unwrap();
 
         let entry = config.get("src/components/CLAUDE.md").unwrap();
         assert_eq!(entry.file_type, crate::config::FileType::Phantom);
@@ -253,7 +1129,14 @@ mod tests {
         std::fs::create_dir_all(git.git_dir.join("info")).unwrap();
 
         let mut config = ShadowConfig::new();
-        add_phantom(&git, &mut config, "src/CLAUDE.md", false).unwrap();
+        add_phantom(
+            &git,
+            &mut config,
+            "src/CLAUDE.md",
+            &AddOptions::default(),
+            false,
+        )
+        .unwrap();
 
         let manager = ExcludeManager::new(&git.git_dir);
         let entries = manager.list_entries().unwrap();
@@ -267,7 +1150,17 @@ mod tests {
         std::fs::write(git.root.join("src/CLAUDE.md"), "# Local\n").unwrap();
 
         let mut config = ShadowConfig::new();
-        add_phantom(&git, &mut config, "src/CLAUDE.md", true).unwrap();
+        add_phantom(
+            &git,
+            &mut config,
+            "src/CLAUDE.md",
+            &AddOptions {
+                exclude: ExcludeMode::None,
+                ..Default::default()
+            },
+            false,
+        )
+        .unwrap();
 
         let entry = config.get("src/CLAUDE.md").unwrap();
         assert_eq!(entry.exclude_mode, ExcludeMode::None);
@@ -281,7 +1174,7 @@ mod tests {
         std::fs::write(git.root.join(".claude/settings.json"), "{}").unwrap();
 
         let mut config = ShadowConfig::new();
-        add_phantom(&git, &mut config, ".claude", false).unwrap();
+        add_phantom(&git, &mut config, ".claude", &AddOptions::default(), false).unwrap();
 
         let entry = config.get(".claude").unwrap();
         assert_eq!(entry.file_type, crate::config::FileType::Phantom);
@@ -296,7 +1189,7 @@ mod tests {
         std::fs::create_dir_all(git.git_dir.join("info")).unwrap();
 
         let mut config = ShadowConfig::new();
-        add_phantom(&git, &mut config, ".claude", false).unwrap();
+        add_phantom(&git, &mut config, ".claude", &AddOptions::default(), false).unwrap();
 
         let manager = ExcludeManager::new(&git.git_dir);
         let entries = manager.list_entries().unwrap();
@@ -314,7 +1207,17 @@ mod tests {
         std::fs::write(git.root.join("codemaps/map.json"), "{}").unwrap();
 
         let mut config = ShadowConfig::new();
-        add_phantom(&git, &mut config, "codemaps", true).unwrap();
+        add_phantom(
+            &git,
+            &mut config,
+            "codemaps",
+            &AddOptions {
+                exclude: ExcludeMode::None,
+                ..Default::default()
+            },
+            false,
+        )
+        .unwrap();
 
         let entry = config.get("codemaps").unwrap();
         assert!(entry.is_directory);
@@ -327,17 +1230,703 @@ mod tests {
         std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
 
         let mut config = ShadowConfig::new();
-        add_phantom(&git, &mut config, "local.md", false).unwrap();
+        add_phantom(&git, &mut config, "local.md", &AddOptions::default(), false).unwrap();
 
         let entry = config.get("local.md").unwrap();
         assert!(!entry.is_directory);
     }
 
+    #[test]
+    fn test_add_phantom_already_ignored_skips_exclude() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join(".gitignore"), "*.local\n").unwrap();
+        std::fs::write(git.root.join("notes.local"), "x\n").unwrap();
+
+        let mut config = ShadowConfig::new();
+        add_phantom(
+            &git,
+            &mut config,
+            "notes.local",
+            &AddOptions::default(),
+            false,
+        )
+        .unwrap();
+
+        let entry = config.get("notes.local").unwrap();
+        assert_eq!(entry.exclude_mode, ExcludeMode::AlreadyIgnored);
+        assert!(ExcludeManager::new(&git.common_dir)
+            .list_entries()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_add_phantom_template_only_for_missing_files() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join("kept.md"), "# Kept\n").unwrap();
+        let options = AddOptions {
+            template: Some(Template {
+                name: "notes".to_string(),
+                content: b"# Notes\n".to_vec(),
+            }),
+            ..AddOptions::default()
+        };
+
+        let mut config = ShadowConfig::new();
+        add_phantom(&git, &mut config, "docs/new.md", &options, false).unwrap();
+        add_phantom(&git, &mut config, "kept.md", &options, false).unwrap();
+
+        let read = |file: &str| std::fs::read_to_string(git.root.join(file)).unwrap();
+        assert_eq!(read("docs/new.md"), "# Notes\n");
+        assert_eq!(read("kept.md"), "# Kept\n");
+        assert!(config.get("docs/new.md").is_some());
+        assert!(config.get("kept.md").is_some());
+    }
+
+    #[test]
+    fn test_add_phantom_template_not_written_for_registered_path() {
+        let (_dir, git) = make_test_repo();
+        let options = AddOptions {
+            template: Some(Template {
+                name: "notes".to_string(),
+                content: b"# Notes\n".to_vec(),
+            }),
+            ..AddOptions::default()
+        };
+
+        // Registered, but the file was deleted since
+        let mut config = ShadowConfig::new();
+        config
+            .add_phantom("gone.md".to_string(), ExcludeMode::GitInfoExclude, false)
+            .unwrap();
+        let err = add_phantom(&git, &mut config, "gone.md", &options, false).unwrap_err();
+        assert!(err.to_string().contains("already managed"));
+        assert!(!git.root.join("gone.md").exists());
+    }
+
+    #[test]
+    fn test_add_phantom_gitignore_target() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join(".gitignore"), "target/\n").unwrap();
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+
+        let mut config = ShadowConfig::new();
+        add_phantom(
+            &git,
+            &mut config,
+            "local.md",
+            &AddOptions {
+                exclude: ExcludeMode::Gitignore,
+                ..Default::default()
+            },
+            false,
+        )
+        .unwrap();
+
+        let entry = config.get("local.md").unwrap();
+        assert_eq!(entry.exclude_mode, ExcludeMode::Gitignore);
+        let manager = ExcludeManager::gitignore(&git.root);
+        assert_eq!(
+            manager.list_entries().unwrap(),
+            vec!["local.md".to_string()]
+        );
+        let content = std::fs::read_to_string(git.root.join(".gitignore")).unwrap();
+        assert!(content.starts_with("target/\n"));
+
+        // Nothing written to .git/info/exclude
+        let info_exclude = ExcludeManager::new(&git.common_dir);
+        assert!(info_exclude.list_entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_add_overlay_with_baseline_ref() {
+        let (_dir, git) = make_test_repo();
+        let first = git.head_commit().unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team v2\n").unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-am", "v2"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+
+        let mut config = ShadowConfig::new();
+        add_overlay(
+            &git,
+            &mut config,
+            "CLAUDE.md",
+            &AddOptions {
+                baseline_ref: first[..7].to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        // Short hash is stored as the full hash
+        let entry = config.get("CLAUDE.md").unwrap();
+        assert_eq!(entry.baseline_commit.as_deref(), Some(first.as_str()));
+        let baseline = std::fs::read_to_string(
+            git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+        )
+        .unwrap();
+        assert_eq!(baseline, "# Team CLAUDE\n");
+    }
+
+    #[test]
+    fn test_add_overlay_baseline_ref_missing_file() {
+        let (_dir, git) = make_test_repo();
+        let first = git.head_commit().unwrap();
+        std::fs::write(git.root.join("later.md"), "# Later\n").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "later.md"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "later"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+
+        let mut config = ShadowConfig::new();
+        let err = add_overlay(
+            &git,
+            &mut config,
+            "later.md",
+            &AddOptions {
+                baseline_ref: first.to_string(),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("does not exist at"));
+        assert!(config.get("later.md").is_none());
+    }
+
+    #[test]
+    fn test_add_overlay_invalid_baseline_ref() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let result = add_overlay(
+            &git,
+            &mut config,
+            "CLAUDE.md",
+            &AddOptions {
+                baseline_ref: "no-such-tag".to_string(),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_add_phantom_rejects_tracked() {
         let (_dir, git) = make_test_repo();
         let mut config = ShadowConfig::new();
-        let result = add_phantom(&git, &mut config, "CLAUDE.md", false);
+        let result = add_phantom(
+            &git,
+            &mut config,
+            "CLAUDE.md",
+            &AddOptions::default(),
+            false,
+        );
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_add_files_mixed_overlay_and_phantom() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+        std::fs::create_dir_all(git.git_dir.join("info")).unwrap();
+
+        let mut config = ShadowConfig::new();
+        let files = vec!["CLAUDE.md".to_string(), "local.md".to_string()];
+        let summary = add_files(
+            &git,
+            &mut config,
+            &files,
+            AddMode::Auto,
+            &AddOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(summary.registered, 2);
+        assert_eq!(summary.failed, 0);
+
+        let loaded = ShadowConfig::load(&git.shadow_dir).unwrap();
+        assert_eq!(loaded.files.len(), 2);
+        assert_eq!(
+            loaded.get("CLAUDE.md").unwrap().file_type,
+            crate::config::FileType::Overlay
+        );
+        assert_eq!(
+            loaded.get("local.md").unwrap().file_type,
+            crate::config::FileType::Phantom
+        );
+    }
+
+    #[test]
+    fn test_add_from_subdirectory_uses_cwd() {
+        let (_dir, git) = make_test_repo();
+        std::fs::create_dir_all(git.root.join("sub")).unwrap();
+        std::fs::write(git.root.join("sub/notes.md"), "# Sub\n").unwrap();
+        std::fs::write(git.root.join("notes.md"), "# Root\n").unwrap();
+        std::fs::create_dir_all(git.git_dir.join("info")).unwrap();
+        let sub = GitRepo::discover(&git.root.join("sub")).unwrap();
+        assert_eq!(sub.prefix, "sub/");
+
+        let mut config = ShadowConfig::new();
+        let args = vec!["notes.md".to_string(), "../CLAUDE.md".to_string()];
+        let files = resolve_args(&sub, &config, &args, false).unwrap();
+        let summary = add_files(
+            &sub,
+            &mut config,
+            &files,
+            AddMode::Auto,
+            &AddOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(summary.registered, 2);
+        let paths: Vec<&str> = config.files.keys().map(String::as_str).collect();
+        assert_eq!(paths, vec!["CLAUDE.md", "sub/notes.md"]);
+    }
+
+    fn expand_options(scope: ExpandExclude) -> AddOptions {
+        AddOptions {
+            expand: Some(scope),
+            ..AddOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_add_files_expand_registers_each_file() {
+        let (_dir, git) = make_test_repo();
+        std::fs::create_dir_all(git.root.join(".claude/commands")).unwrap();
+        std::fs::write(git.root.join(".claude/settings.json"), "{}").unwrap();
+        std::fs::write(git.root.join(".claude/commands/a.md"), "a\n").unwrap();
+        std::fs::create_dir_all(git.git_dir.join("info")).unwrap();
+
+        let mut config = ShadowConfig::new();
+        let files = vec![".claude".to_string()];
+        let options = expand_options(ExpandExclude::Files);
+        let summary = add_files(&git, &mut config, &files, AddMode::Phantom, &options).unwrap();
+        assert_eq!(summary.registered, 2);
+
+        let paths: Vec<&str> = config.files.keys().map(String::as_str).collect();
+        assert_eq!(
+            paths,
+            vec![".claude/commands/a.md", ".claude/settings.json"]
+        );
+        assert!(!config.get(".claude/settings.json").unwrap().is_directory);
+        let entries = ExcludeManager::new(&git.git_dir).list_entries().unwrap();
+        assert!(entries.contains(&".claude/settings.json".to_string()));
+        assert!(!entries.contains(&".claude/".to_string()));
+
+        // Re-expanding picks up new files only
+        std::fs::write(git.root.join(".claude/new.md"), "new\n").unwrap();
+        let summary = add_files(&git, &mut config, &files, AddMode::Phantom, &options).unwrap();
+        assert_eq!(summary.registered, 1);
+        assert_eq!(summary.failed, 0);
+        assert!(config.get(".claude/new.md").is_some());
+    }
+
+    #[test]
+    fn test_add_files_expand_with_directory_exclude() {
+        let (_dir, git) = make_test_repo();
+        std::fs::create_dir_all(git.root.join(".claude")).unwrap();
+        std::fs::write(git.root.join(".claude/a.md"), "a\n").unwrap();
+        std::fs::write(git.root.join(".claude/b.md"), "b\n").unwrap();
+        std::fs::create_dir_all(git.git_dir.join("info")).unwrap();
+
+        let mut config = ShadowConfig::new();
+        let files = vec![".claude".to_string()];
+        let options = expand_options(ExpandExclude::Dir);
+        let summary = add_files(&git, &mut config, &files, AddMode::Phantom, &options).unwrap();
+        assert_eq!(summary.registered, 2);
+
+        let entries = ExcludeManager::new(&git.git_dir).list_entries().unwrap();
+        assert_eq!(entries, vec![".claude/"]);
+    }
+
+    #[test]
+    fn test_add_files_expand_rejects_phantom_directory() {
+        let (_dir, git) = make_test_repo();
+        std::fs::create_dir_all(git.root.join(".claude")).unwrap();
+        std::fs::write(git.root.join(".claude/a.md"), "a\n").unwrap();
+
+        let mut config = ShadowConfig::new();
+        config
+            .add_phantom(".claude".to_string(), ExcludeMode::None, true)
+            .unwrap();
+        let files = vec![".claude".to_string()];
+        let err = add_files(
+            &git,
+            &mut config,
+            &files,
+            AddMode::Phantom,
+            &expand_options(ExpandExclude::Files),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("phantom directory"));
+    }
+
+    #[test]
+    fn test_add_files_stores_note() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let options = AddOptions {
+            note: Some("team rules + my shortcuts".to_string()),
+            ..AddOptions::default()
+        };
+        let files = vec!["CLAUDE.md".to_string()];
+        add_files(&git, &mut config, &files, AddMode::Overlay, &options).unwrap();
+
+        let loaded = ShadowConfig::load(&git.shadow_dir).unwrap();
+        assert_eq!(
+            loaded.get("CLAUDE.md").unwrap().note.as_deref(),
+            Some("team rules + my shortcuts")
+        );
+    }
+
+    #[test]
+    fn test_add_files_dry_run_writes_nothing() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+        std::fs::create_dir_all(git.git_dir.join("info")).unwrap();
+
+        let mut config = ShadowConfig::new();
+        let files = vec![
+            "CLAUDE.md".to_string(),
+            "local.md".to_string(),
+            "CLAUDE.md".to_string(),
+        ];
+        let summary = add_files(
+            &git,
+            &mut config,
+            &files,
+            AddMode::Auto,
+            &AddOptions {
+                dry_run: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // The repeated CLAUDE.md is reported as already managed
+        assert_eq!(summary.registered, 2);
+        assert_eq!(summary.failed, 1);
+
+        assert!(!git.shadow_dir.join("config.json").exists());
+        assert!(!git
+            .shadow_dir
+            .join("baselines")
+            .join(crate::path::encode_path("CLAUDE.md"))
+            .exists());
+        let manager = ExcludeManager::new(&git.git_dir);
+        assert!(manager.list_entries().unwrap().is_empty());
+        assert!(history::read(&git.shadow_dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_add_files_continues_after_failure() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join("untracked.md"), "new").unwrap();
+        std::fs::write(git.root.join("other.md"), "# Other\n").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "other.md"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "add other"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+
+        let mut config = ShadowConfig::new();
+        let files = vec![
+            "CLAUDE.md".to_string(),
+            "untracked.md".to_string(),
+            "other.md".to_string(),
+        ];
+        let summary = add_files(
+            &git,
+            &mut config,
+            &files,
+            AddMode::Overlay,
+            &AddOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(summary.registered, 2);
+        assert_eq!(summary.failed, 1);
+
+        // Successful registrations are persisted despite the failure
+        let loaded = ShadowConfig::load(&git.shadow_dir).unwrap();
+        assert!(loaded.get("CLAUDE.md").is_some());
+        assert!(loaded.get("other.md").is_some());
+        assert!(loaded.get("untracked.md").is_none());
+    }
+
+    #[test]
+    fn test_add_files_explicit_phantom_rejects_tracked() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let files = vec!["CLAUDE.md".to_string()];
+        let summary = add_files(
+            &git,
+            &mut config,
+            &files,
+            AddMode::Phantom,
+            &AddOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(summary.failed, 1);
+        assert!(config.get("CLAUDE.md").is_none());
+    }
+
+    #[test]
+    fn test_add_files_auto_detects_untracked_as_phantom() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join("notes.md"), "# Notes\n").unwrap();
+        std::fs::create_dir_all(git.git_dir.join("info")).unwrap();
+
+        let mut config = ShadowConfig::new();
+        let files = vec!["notes.md".to_string()];
+        add_files(
+            &git,
+            &mut config,
+            &files,
+            AddMode::Auto,
+            &AddOptions::default(),
+        )
+        .unwrap();
+
+        let entry = config.get("notes.md").unwrap();
+        assert_eq!(entry.file_type, crate::config::FileType::Phantom);
+        assert_eq!(entry.exclude_mode, ExcludeMode::GitInfoExclude);
+    }
+
+    #[test]
+    fn test_expand_globs_from_root_skips_managed() {
+        let (_dir, git) = make_test_repo();
+        std::fs::create_dir_all(git.root.join("docs/guides")).unwrap();
+        std::fs::write(git.root.join("local.md"), "").unwrap();
+        std::fs::write(git.root.join("docs/a.md"), "").unwrap();
+        std::fs::write(git.root.join("docs/guides/b.md"), "").unwrap();
+        let mut config = ShadowConfig::new();
+        add_overlay(&git, &mut config, "CLAUDE.md", &AddOptions::default()).unwrap();
+
+        let patterns = vec!["*.md".to_string(), "none/*.md".to_string()];
+        assert_eq!(
+            expand_globs(&git, &config, &patterns).unwrap(),
+            ["local.md"]
+        );
+
+        let patterns = vec!["docs/**/*.md".to_string(), "docs/*".to_string()];
+        assert_eq!(
+            expand_globs(&git, &config, &patterns).unwrap(),
+            ["docs/a.md", "docs/guides/b.md"]
+        );
+    }
+
+    #[test]
+    fn test_add_defaults_uses_repository_settings() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join("notes.md"), "# Notes\n").unwrap();
+        std::fs::create_dir_all(git.git_dir.join("info")).unwrap();
+        let settings = Settings {
+            default_add: Some(vec![
+                "CLAUDE.md".to_string(),
+                "*.md".to_string(),
+                "missing.md".to_string(),
+            ]),
+            ..Default::default()
+        };
+        settings.save(&git.shadow_dir).unwrap();
+
+        let mut config = ShadowConfig::new();
+        assert_eq!(add_defaults(&git, &mut config).unwrap(), 2);
+        assert_eq!(
+            config.get("CLAUDE.md").unwrap().file_type,
+            FileType::Overlay
+        );
+        assert_eq!(config.get("notes.md").unwrap().file_type, FileType::Phantom);
+
+        // Already managed files are left alone
+        assert_eq!(add_defaults(&git, &mut config).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_describe_missing_hooks() {
+        assert_eq!(describe_missing_hooks(&["post-merge"]), "post-merge hook");
+        assert_eq!(
+            describe_missing_hooks(&["post-merge", "post-checkout"]),
+            "post-merge, post-checkout hooks"
+        );
+        assert_eq!(describe_missing_hooks(HOOK_NAMES), "hooks");
+    }
+
+    #[test]
+    fn test_containing_submodule() {
+        let submodules = vec!["vendor/lib".to_string()];
+        assert_eq!(
+            containing_submodule(&submodules, "vendor/lib"),
+            Some("vendor/lib")
+        );
+        assert_eq!(
+            containing_submodule(&submodules, "vendor/lib/CLAUDE.md"),
+            Some("vendor/lib")
+        );
+        assert_eq!(containing_submodule(&submodules, "vendor/library.md"), None);
+        assert_eq!(containing_submodule(&submodules, "CLAUDE.md"), None);
+    }
+
+    #[test]
+    fn test_describe_local_changes() {
+        assert_eq!(describe_local_changes(b"a\n", b"a\n", false), None);
+        assert_eq!(
+            describe_local_changes(b"a\nb\nc\n", b"a\nx\n", false).as_deref(),
+            Some("+2 -1 lines")
+        );
+        assert_eq!(
+            describe_local_changes(b"\x00new", b"\x00old", true).as_deref(),
+            Some("binary content differs")
+        );
+    }
+
+    #[test]
+    fn test_add_overlay_keeps_existing_local_changes_as_shadow() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team CLAUDE\n# Mine\n").unwrap();
+        let mut config = ShadowConfig::new();
+
+        // Non-interactive: registered without a prompt
+        add_overlay(&git, &mut config, "CLAUDE.md", &AddOptions::default()).unwrap();
+
+        let baseline = std::fs::read_to_string(
+            git.shadow_dir
+                .join("baselines")
+                .join(path::encode_path("CLAUDE.md")),
+        )
+        .unwrap();
+        assert_eq!(baseline, "# Team CLAUDE\n");
+        let current = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(current, "# Team CLAUDE\n# Mine\n");
+    }
+
+    fn git_in(git: &GitRepo, args: &[&str]) {
+        std::process::Command::new("git")
+            .args(args)
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_add_files_promotes_committed_phantom() {
+        let (_dir, git) = make_test_repo();
+        std::fs::create_dir_all(git.git_dir.join("info")).unwrap();
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+
+        let mut config = ShadowConfig::new();
+        let files = vec!["local.md".to_string()];
+        let options = AddOptions {
+            note: Some("mine".to_string()),
+            ..AddOptions::default()
+        };
+        add_files(&git, &mut config, &files, AddMode::Phantom, &options).unwrap();
+
+        // Still a phantom while untracked
+        let summary = add_files(
+            &git,
+            &mut config,
+            &files,
+            AddMode::Overlay,
+            &AddOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(summary.failed, 1);
+        assert_eq!(config.get("local.md").unwrap().file_type, FileType::Phantom);
+
+        git_in(&git, &["add", "-f", "local.md"]);
+        git_in(&git, &["commit", "-m", "share local.md"]);
+        add_files(
+            &git,
+            &mut config,
+            &files,
+            AddMode::Overlay,
+            &AddOptions::default(),
+        )
+        .unwrap();
+
+        let loaded = ShadowConfig::load(&git.shadow_dir).unwrap();
+        let entry = loaded.get("local.md").unwrap();
+        assert_eq!(entry.file_type, FileType::Overlay);
+        assert_eq!(entry.note.as_deref(), Some("mine"));
+        assert_eq!(
+            baseline::read_to_string(&git, "local.md", None).unwrap(),
+            "# Local\n"
+        );
+        let manager = ExcludeManager::new(&git.git_dir);
+        assert!(!manager
+            .list_entries()
+            .unwrap()
+            .contains(&"local.md".to_string()));
+    }
+
+    #[test]
+    fn test_add_files_demotes_untracked_overlay() {
+        let (_dir, git) = make_test_repo();
+        std::fs::create_dir_all(git.git_dir.join("info")).unwrap();
+        let mut config = ShadowConfig::new();
+        let files = vec!["CLAUDE.md".to_string()];
+        add_files(
+            &git,
+            &mut config,
+            &files,
+            AddMode::Overlay,
+            &AddOptions::default(),
+        )
+        .unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Mine only\n").unwrap();
+
+        // Refused while the file is still tracked
+        let summary = add_files(
+            &git,
+            &mut config,
+            &files,
+            AddMode::Phantom,
+            &AddOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(summary.failed, 1);
+        assert!(baseline::exists(&git, "CLAUDE.md", None));
+
+        git_in(&git, &["rm", "--cached", "-q", "CLAUDE.md"]);
+        add_files(
+            &git,
+            &mut config,
+            &files,
+            AddMode::Phantom,
+            &AddOptions::default(),
+        )
+        .unwrap();
+
+        let loaded = ShadowConfig::load(&git.shadow_dir).unwrap();
+        assert_eq!(
+            loaded.get("CLAUDE.md").unwrap().file_type,
+            FileType::Phantom
+        );
+        assert!(!baseline::exists(&git, "CLAUDE.md", None));
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap(),
+            "# Mine only\n"
+        );
+        let manager = ExcludeManager::new(&git.git_dir);
+        assert!(manager
+            .list_entries()
+            .unwrap()
+            .contains(&"CLAUDE.md".to_string()));
+    }
 }
@@ -0,0 +1,299 @@
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use is_terminal::IsTerminal;
+
+use crate::baseline;
+use crate::commands::install::{self, HookUninstall};
+use crate::commands::remove;
+use crate::config::{FileType, ShadowConfig};
+use crate::error::ShadowError;
+use crate::exclude::ExcludeManager;
+use crate::git::GitRepo;
+use crate::lock::{self, LockStatus};
+use crate::path;
+
+pub fn run(force: bool, keep_shadow: bool, dry_run: bool) -> Result<()> {
+    let git = GitRepo::discover(&std::env::current_dir()?)?;
+    let config = ShadowConfig::load(&git.shadow_dir)?;
+    check_cleanable(&git, &config)?;
+
+    let hooks = install::plan_uninstall(&git);
+    let plan = describe_plan(&git, &config, &hooks, keep_shadow)?;
+    if plan.is_empty() {
+        println!("git-shadow is not set up in this repository; nothing to clean");
+        return Ok(());
+    }
+    install::warn_hand_written(&hooks);
+
+    if dry_run {
+        println!("would:");
+        for step in &plan {
+            println!("  {}", step);
+        }
+        println!("dry run: nothing was changed");
+        return Ok(());
+    }
+
+    if !force {
+        if !std::io::stdin().is_terminal() {
+            bail!("--force is required in non-interactive mode");
+        }
+        eprintln!("This will:");
+        for step in &plan {
+            eprintln!("  {}", step);
+        }
+        eprintln!("Remove git-shadow from this repository? [y/N]");
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        let input = input.trim().to_lowercase();
+        if input != "y" && input != "yes" {
+            println!("aborted");
+            return Ok(());
+        }
+    }
+
+    clean(&git, &config, &hooks, keep_shadow)?;
+    println!("{}", "removed git-shadow from this repository".green());
+    Ok(())
+}
+
+/// Refuse while shadow content lives only in `.git/shadow/` (suspended
+/// files, stash remnants) or a commit is running
+pub(crate) fn check_cleanable(git: &GitRepo, config: &ShadowConfig) -> Result<()> {
+    if matches!(
+        lock::check_lock(&git.shadow_dir)?,
+        LockStatus::HeldByUs | LockStatus::HeldByOther(_)
+    ) {
+        bail!("cannot clean while a commit is in progress");
+    }
+
+    if !path::stored_files(&git.shadow_dir.join("stash"))?.is_empty() {
+        return Err(ShadowError::StashRemaining.into());
+    }
+
+    if config.is_suspended() {
+        return Err(ShadowError::Suspended.into());
+    }
+    Ok(())
+}
+
+/// One line per change `clean()` makes, in order
+fn describe_plan(
+    git: &GitRepo,
+    config: &ShadowConfig,
+    hooks: &[(&'static str, HookUninstall)],
+    keep_shadow: bool,
+) -> Result<Vec<String>> {
+    let mut plan = Vec::new();
+
+    for (file_path, entry) in &config.files {
+        match entry.file_type {
+            FileType::Overlay if keep_shadow => {
+                plan.push(format!("unregister {} (content is kept)", file_path))
+            }
+            FileType::Overlay => plan.push(format!(
+                "reset {} to its baseline (shadow changes are discarded)",
+                file_path
+            )),
+            FileType::Phantom => match ExcludeManager::for_mode(git, &entry.exclude_mode) {
+                Some(manager) => plan.push(format!(
+                    "unregister {} and remove it from {} (the file is kept)",
+                    file_path,
+                    manager.display_name()
+                )),
+                None => plan.push(format!("unregister {} (the file is kept)", file_path)),
+            },
+        }
+    }
+
+    for (hook_name, action) in hooks {
+        match action {
+            HookUninstall::Delete => plan.push(format!("delete the {} hook", hook_name)),
+            HookUninstall::RestoreBackup => plan.push(format!(
+                "restore {}.pre-shadow as the {} hook",
+                hook_name, hook_name
+            )),
+            HookUninstall::KeepHandWritten => {}
+        }
+    }
+
+    if let Some(key) = install::installed_status_alias(git) {
+        plan.push(format!("unset {}", key));
+    }
+
+    let refs = git.list_refs(baseline::REF_PREFIX)?;
+    if !refs.is_empty() {
+        plan.push(format!(
+            "delete {} ref(s) under {}",
+            refs.len(),
+            baseline::REF_PREFIX
+        ));
+    }
+
+    if git.shadow_dir.exists() {
+        plan.push("delete .git/shadow/".to_string());
+    }
+    Ok(plan)
+}
+
+/// Undo everything git-shadow set up. Managed files are handled first so an
+/// error leaves `.git/shadow/` in place for another attempt.
+fn clean(
+    git: &GitRepo,
+    config: &ShadowConfig,
+    hooks: &[(&'static str, HookUninstall)],
+    keep_shadow: bool,
+) -> Result<()> {
+    for (file_path, entry) in &config.files {
+        match entry.file_type {
+            FileType::Overlay => {
+                remove::remove_overlay(git, file_path, entry.baseline_blob.as_deref(), keep_shadow)
+            }
+            FileType::Phantom => {
+                remove::remove_phantom(git, file_path, &entry.exclude_mode, entry.is_directory)
+            }
+        }
+        .with_context(|| format!("failed to unregister {}", file_path))?;
+    }
+
+    install::uninstall_hooks(git, hooks)?;
+    if let Some(key) = install::installed_status_alias(git) {
+        git.config_unset(key)?;
+    }
+
+    for name in git.list_refs(baseline::REF_PREFIX)? {
+        git.delete_ref(&name)?;
+    }
+    if git.shadow_dir.exists() {
+        std::fs::remove_dir_all(&git.shadow_dir).context("failed to delete .git/shadow/")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ExcludeMode;
+
+    fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.email", "t@t.com"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+
+        std::fs::write(root.join("CLAUDE.md"), "# Team\n").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "CLAUDE.md"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+
+        let repo = GitRepo::discover(&root).unwrap();
+        install::install_hooks(&repo, false).unwrap();
+        (dir, repo)
+    }
+
+    /// Overlay CLAUDE.md with shadow changes and phantom local.md
+    fn setup_files(git: &GitRepo) -> ShadowConfig {
+        let mut config = ShadowConfig::new();
+        baseline::write(git, "CLAUDE.md", b"# Team\n").unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), git.head_commit().unwrap())
+            .unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# Mine\n").unwrap();
+
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+        ExcludeManager::new(&git.common_dir)
+            .add_entry("local.md")
+            .unwrap();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::GitInfoExclude, false)
+            .unwrap();
+        config.save(&git.shadow_dir).unwrap();
+        config
+    }
+
+    #[test]
+    fn test_clean_removes_everything() {
+        let (_dir, git) = make_test_repo();
+        let config = setup_files(&git);
+
+        let hooks = install::plan_uninstall(&git);
+        assert_eq!(hooks.len(), 4);
+        clean(&git, &config, &hooks, false).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap(),
+            "# Team\n"
+        );
+        assert!(git.root.join("local.md").exists());
+        assert!(!ExcludeManager::new(&git.common_dir)
+            .list_entries()
+            .unwrap()
+            .contains(&"local.md".to_string()));
+        assert!(!git.shadow_dir.exists());
+        assert!(!git.missing_hooks().is_empty());
+        assert!(!git.hooks_dir().join("pre-commit").exists());
+    }
+
+    #[test]
+    fn test_clean_keep_shadow_and_chained_hook() {
+        let (_dir, git) = make_test_repo();
+        let config = setup_files(&git);
+        let chained = git.hooks_dir().join("pre-commit.pre-shadow");
+        std::fs::write(&chained, "#!/bin/sh\necho lint\n").unwrap();
+
+        let hooks = install::plan_uninstall(&git);
+        assert!(hooks.contains(&("pre-commit", HookUninstall::RestoreBackup)));
+        clean(&git, &config, &hooks, true).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap(),
+            "# Team\n# Mine\n"
+        );
+        assert!(!chained.exists());
+        assert_eq!(
+            std::fs::read_to_string(git.hooks_dir().join("pre-commit")).unwrap(),
+            "#!/bin/sh\necho lint\n"
+        );
+    }
+
+    #[test]
+    fn test_describe_plan_and_guards() {
+        let (_dir, git) = make_test_repo();
+        let mut config = setup_files(&git);
+        let hooks = install::plan_uninstall(&git);
+
+        let plan = describe_plan(&git, &config, &hooks, false).unwrap();
+        assert_eq!(
+            plan[0],
+            "reset CLAUDE.md to its baseline (shadow changes are discarded)"
+        );
+        assert_eq!(
+            plan[1],
+            "unregister local.md and remove it from .git/info/exclude (the file is kept)"
+        );
+        assert_eq!(plan.last().unwrap(), "delete .git/shadow/");
+
+        config.files.get_mut("CLAUDE.md").unwrap().suspended = true;
+        assert!(check_cleanable(&git, &config).is_err());
+    }
+}
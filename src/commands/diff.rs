@@ -1,38 +1,141 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
 use anyhow::Result;
+use colored::Colorize;
+use is_terminal::IsTerminal;
 
+use crate::baseline;
+use crate::cli::ColorWhen;
+use crate::commands::snapshot;
 use crate::config::{FileEntry, FileType, ShadowConfig};
-use crate::diff_util;
+use crate::diff_util::{self, StatEntry};
 use crate::error::ShadowError;
+use crate::fs_util::{self, WalkEntry};
 use crate::git::GitRepo;
 use crate::path;
 
-pub fn run(file: Option<&str>) -> Result<()> {
+/// Phantom directories above these limits are summarized instead of diffed
+const PHANTOM_DIR_MAX_FILES: usize = 100;
+const PHANTOM_DIR_MAX_BYTES: u64 = fs_util::DEFAULT_SIZE_LIMIT;
+
+/// Exit code of `--exit-code`/`--quiet` when a file has shadow changes
+pub const EXIT_DIFFERENT: i32 = 1;
+
+/// Command-line flags of `git-shadow diff`
+#[derive(Debug)]
+pub struct DiffArgs<'a> {
+    pub stat: bool,
+    pub tool: Option<&'a str>,
+    pub word_diff: bool,
+    pub since_snapshot: bool,
+    pub exit_code: bool,
+    /// Print nothing; implies `exit_code`
+    pub quiet: bool,
+    /// Compare text with `diff_util::normalize_whitespace`
+    pub ignore_whitespace: bool,
+}
+
+/// Show shadow changes and return the process exit code: always 0 unless
+/// `--exit-code` or `--quiet` asks for `EXIT_DIFFERENT` on changes
+pub fn run(file: Option<&str>, args: DiffArgs, color: ColorWhen) -> Result<i32> {
     let git = GitRepo::discover(&std::env::current_dir()?)?;
     let config = ShadowConfig::load(&git.shadow_dir)?;
-
-    if config.suspended {
-        return Err(ShadowError::Suspended.into());
-    }
+    let DiffArgs {
+        stat,
+        tool,
+        word_diff,
+        since_snapshot,
+        exit_code,
+        quiet,
+        ignore_whitespace,
+    } = args;
+
+    let color = diff_util::should_color(
+        color,
+        std::env::var_os("NO_COLOR").as_deref(),
+        std::io::stdout().is_terminal(),
+    );
+    colored::control::set_override(color);
 
     if config.files.is_empty() {
-        println!("no managed files");
-        return Ok(());
+        if !quiet {
+            println!("no managed files");
+        }
+        return Ok(0);
     }
 
+    let tool_command = match tool {
+        Some(name) => {
+            let command = resolve_tool_command(&git, name);
+            if command.is_none() {
+                eprintln!(
+                    "{}",
+                    "warning: no diff tool configured (set `git config diff.tool`); showing built-in diff"
+                        .yellow()
+                );
+            }
+            command
+        }
+        None => None,
+    };
+
     let mut found = false;
+    let mut changed = false;
+    let mut stats = Vec::new();
 
     for (file_path, entry) in &config.files {
         if let Some(target) = file {
-            let normalized = path::normalize_path(target, &git.root)?;
+            let normalized = path::normalize_path(target, &git.prefix, &git.root)?;
             if *file_path != normalized {
                 continue;
             }
         }
         found = true;
 
+        // Suspended shadow content is parked outside the working tree
+        if entry.suspended {
+            if file.is_some() {
+                return Err(ShadowError::Suspended.into());
+            }
+            if !stat && !quiet {
+                println!("{}: suspended", file_path);
+            }
+            continue;
+        }
+
+        if exit_code || quiet {
+            changed |= has_changes(&git, file_path, entry, since_snapshot, ignore_whitespace)?;
+        }
+        if quiet {
+            continue;
+        }
+
+        if stat {
+            if let Some(entry_stat) = collect_stat(&git, file_path, entry, ignore_whitespace) {
+                stats.push(entry_stat);
+            }
+            continue;
+        }
+
+        if let Some(command) = &tool_command {
+            open_in_tool(&git, file_path, entry, command)?;
+            continue;
+        }
+
         match entry.file_type {
             FileType::Overlay => {
-                show_overlay_diff(&git, file_path)?;
+                show_overlay_diff(&git, file_path, entry, color, word_diff, ignore_whitespace)?;
+            }
+            FileType::Phantom if since_snapshot && !entry.is_directory => {
+                show_phantom_snapshot_diff(
+                    &git,
+                    file_path,
+                    entry,
+                    color,
+                    word_diff,
+                    ignore_whitespace,
+                )?;
             }
             FileType::Phantom => {
                 show_phantom_diff(&git, file_path, entry)?;
@@ -40,35 +143,313 @@ pub fn run(file: Option<&str>) -> Result<()> {
         }
     }
 
-    if !found {
+    if stat && found {
+        diff_util::print_stat(&stats);
+    }
+
+    if !found && !quiet {
         if let Some(target) = file {
             println!("{} is not managed by git-shadow", target);
         }
     }
 
-    Ok(())
+    Ok(if changed { EXIT_DIFFERENT } else { 0 })
+}
+
+/// Whether `diff` has something to show for one entry: an overlay that
+/// differs from its baseline, or a phantom that exists (with
+/// `since_snapshot`, a phantom file that differs from its latest snapshot;
+/// for a directory with a manifest, one whose files changed since `add`)
+fn has_changes(
+    git: &GitRepo,
+    file_path: &str,
+    entry: &FileEntry,
+    since_snapshot: bool,
+    ignore_whitespace: bool,
+) -> Result<bool> {
+    let worktree_path = git.root.join(file_path);
+    match entry.file_type {
+        FileType::Overlay => {
+            let baseline =
+                baseline::read(git, file_path, entry.baseline_blob.as_deref()).unwrap_or_default();
+            let current = std::fs::read(&worktree_path).unwrap_or_default();
+            Ok(differs(
+                &baseline,
+                &current,
+                ignore_whitespace && !entry.is_binary,
+            ))
+        }
+        FileType::Phantom if since_snapshot && worktree_path.is_file() => {
+            match snapshot::latest_copy(&git.shadow_dir, file_path)? {
+                Some((_, copy)) => Ok(differs(
+                    &std::fs::read(copy)?,
+                    &std::fs::read(&worktree_path)?,
+                    ignore_whitespace,
+                )),
+                None => Ok(true),
+            }
+        }
+        FileType::Phantom => match &entry.manifest {
+            Some(manifest) if entry.is_directory => {
+                Ok(!manifest_changes(manifest, &current_manifest(&worktree_path)?).is_empty())
+            }
+            _ => Ok(worktree_path.exists()),
+        },
+    }
+}
+
+/// Files of a phantom directory now; a missing directory has none
+fn current_manifest(dir: &Path) -> Result<BTreeMap<String, String>> {
+    if !dir.is_dir() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(fs_util::dir_manifest(dir)?)
+}
+
+/// Files added (`+`), removed (`-`) and modified (`M`) between two
+/// manifests, sorted by path
+fn manifest_changes(
+    registered: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) -> Vec<(char, String)> {
+    let mut changes: Vec<(char, String)> = current
+        .iter()
+        .filter_map(|(path, hash)| match registered.get(path) {
+            None => Some(('+', path.clone())),
+            Some(old) if old != hash => Some(('M', path.clone())),
+            Some(_) => None,
+        })
+        .chain(
+            registered
+                .keys()
+                .filter(|path| !current.contains_key(*path))
+                .map(|path| ('-', path.clone())),
+        )
+        .collect();
+    changes.sort_by(|a, b| a.1.cmp(&b.1));
+    changes
+}
+
+/// Byte comparison, or text comparison under `--ignore-whitespace` when both
+/// sides are UTF-8
+fn differs(old: &[u8], new: &[u8], ignore_whitespace: bool) -> bool {
+    if old == new {
+        return false;
+    }
+    match (std::str::from_utf8(old), std::str::from_utf8(new)) {
+        (Ok(old), Ok(new)) if ignore_whitespace => {
+            diff_util::normalize_whitespace(old, true) != diff_util::normalize_whitespace(new, true)
+        }
+        _ => true,
+    }
+}
+
+/// Build the shell command for `--tool`. An empty name falls back to
+/// `git config diff.tool`; a configured `difftool.<name>.cmd` is used as-is,
+/// otherwise the tool is invoked as `<tool> "$LOCAL" "$REMOTE"`.
+fn resolve_tool_command(git: &GitRepo, name: &str) -> Option<String> {
+    let name = if name.is_empty() {
+        git.config_get("diff.tool")?
+    } else {
+        name.to_string()
+    };
+
+    if let Some(cmd) = git.config_get(&format!("difftool.{}.cmd", name)) {
+        return Some(cmd);
+    }
+    Some(format!("{} \"$LOCAL\" \"$REMOTE\"", name))
 }
 
-fn show_overlay_diff(git: &GitRepo, file_path: &str) -> Result<()> {
-    let encoded = path::encode_path(file_path);
-    let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
+/// Open one managed file in the external diff tool. Overlays compare the
+/// baseline with the working tree; phantoms compare an empty file with it.
+fn open_in_tool(git: &GitRepo, file_path: &str, entry: &FileEntry, command: &str) -> Result<()> {
     let worktree_path = git.root.join(file_path);
 
-    let baseline = std::fs::read_to_string(&baseline_path).unwrap_or_default();
+    let old = match entry.file_type {
+        FileType::Overlay => {
+            baseline::read(git, file_path, entry.baseline_blob.as_deref()).unwrap_or_default()
+        }
+        FileType::Phantom => {
+            if entry.is_directory || !worktree_path.is_file() {
+                return show_phantom_diff(git, file_path, entry);
+            }
+            Vec::new()
+        }
+    };
+    let new = std::fs::read(&worktree_path).unwrap_or_default();
+
+    if old == new {
+        println!("{}: no shadow changes", file_path);
+        return Ok(());
+    }
+
+    diff_util::run_difftool(command, &old, &new, file_path)
+}
+
+/// Line counts for `--stat`. Returns None for entries without file content
+/// (phantom directories, missing phantoms, unchanged overlays).
+fn collect_stat(
+    git: &GitRepo,
+    file_path: &str,
+    entry: &FileEntry,
+    ignore_whitespace: bool,
+) -> Option<StatEntry> {
+    let worktree_path = git.root.join(file_path);
+
+    match entry.file_type {
+        FileType::Overlay => {
+            let blob = entry.baseline_blob.as_deref();
+            if entry.is_binary {
+                let baseline = baseline::read(git, file_path, blob).unwrap_or_default();
+                let current = std::fs::read(&worktree_path).unwrap_or_default();
+                return (baseline != current).then(|| StatEntry {
+                    path: file_path.to_string(),
+                    added: 0,
+                    removed: 0,
+                    new_file: false,
+                    binary: true,
+                });
+            }
+            let baseline = baseline::read_to_string(git, file_path, blob).unwrap_or_default();
+            let current = std::fs::read_to_string(&worktree_path).unwrap_or_default();
+            let (added, removed) = diff_util::diff_stats(
+                &diff_util::normalize_whitespace(&baseline, ignore_whitespace),
+                &diff_util::normalize_whitespace(&current, ignore_whitespace),
+            );
+            if added == 0 && removed == 0 {
+                return None;
+            }
+            Some(StatEntry {
+                path: file_path.to_string(),
+                added,
+                removed,
+                new_file: false,
+                binary: false,
+            })
+        }
+        FileType::Phantom => {
+            if entry.is_directory || !worktree_path.is_file() {
+                return None;
+            }
+            let content = std::fs::read_to_string(&worktree_path).unwrap_or_default();
+            Some(StatEntry {
+                path: file_path.to_string(),
+                added: content.lines().count(),
+                removed: 0,
+                new_file: true,
+                binary: false,
+            })
+        }
+    }
+}
+
+fn show_overlay_diff(
+    git: &GitRepo,
+    file_path: &str,
+    entry: &FileEntry,
+    color: bool,
+    word_diff: bool,
+    ignore_whitespace: bool,
+) -> Result<()> {
+    let blob = entry.baseline_blob.as_deref();
+    let worktree_path = git.root.join(file_path);
+
+    if entry.is_binary {
+        let baseline = baseline::read(git, file_path, blob).unwrap_or_default();
+        let current = std::fs::read(&worktree_path).unwrap_or_default();
+        if baseline == current {
+            println!("{}: no shadow changes", file_path);
+        } else {
+            println!("{}: binary differs", file_path);
+        }
+        return Ok(());
+    }
+
+    let baseline = baseline::read_to_string(git, file_path, blob).unwrap_or_default();
     let current = std::fs::read_to_string(&worktree_path).unwrap_or_default();
+    let baseline = diff_util::normalize_whitespace(&baseline, ignore_whitespace);
+    let current = diff_util::normalize_whitespace(&current, ignore_whitespace);
 
     if baseline == current {
         println!("{}: no shadow changes", file_path);
         return Ok(());
     }
 
-    diff_util::print_colored_diff(
+    print_text_diff(
         &baseline,
         &current,
         &format!("a/{} (baseline)", file_path),
         &format!("b/{} (shadow)", file_path),
+        color,
+        word_diff,
     );
+    Ok(())
+}
+
+fn print_text_diff(
+    old: &str,
+    new: &str,
+    old_label: &str,
+    new_label: &str,
+    color: bool,
+    word_diff: bool,
+) {
+    if word_diff {
+        print!(
+            "{}",
+            diff_util::word_diff(old, new, old_label, new_label, color)
+        );
+    } else if color {
+        diff_util::print_colored_diff(old, new, old_label, new_label);
+    } else {
+        print!(
+            "{}",
+            diff_util::unified_diff(old, new, old_label, new_label)
+        );
+    }
+}
+
+/// `--since-snapshot`: a phantom file against its copy in the newest snapshot
+/// that has one. Without a snapshot it is shown as a new file, as usual.
+fn show_phantom_snapshot_diff(
+    git: &GitRepo,
+    file_path: &str,
+    entry: &FileEntry,
+    color: bool,
+    word_diff: bool,
+    ignore_whitespace: bool,
+) -> Result<()> {
+    let Some((id, copy)) = snapshot::latest_copy(&git.shadow_dir, file_path)? else {
+        return show_phantom_diff(git, file_path, entry);
+    };
+
+    let worktree_path = git.root.join(file_path);
+    if !worktree_path.is_file() {
+        println!("{}: file does not exist (last snapshot {})", file_path, id);
+        return Ok(());
+    }
 
+    let old = std::fs::read(&copy)?;
+    let new = std::fs::read(&worktree_path)?;
+    if !differs(&old, &new, ignore_whitespace) {
+        println!("{}: no changes since snapshot {}", file_path, id);
+        return Ok(());
+    }
+    let (Ok(old), Ok(new)) = (String::from_utf8(old), String::from_utf8(new)) else {
+        println!("{}: binary differs from snapshot {}", file_path, id);
+        return Ok(());
+    };
+    let old = diff_util::normalize_whitespace(&old, ignore_whitespace);
+    let new = diff_util::normalize_whitespace(&new, ignore_whitespace);
+
+    print_text_diff(
+        &old,
+        &new,
+        &format!("a/{} (snapshot {})", file_path, id),
+        &format!("b/{} (phantom)", file_path),
+        color,
+        word_diff,
+    );
     Ok(())
 }
 
@@ -76,11 +457,10 @@ fn show_phantom_diff(git: &GitRepo, file_path: &str, entry: &FileEntry) -> Resul
     let worktree_path = git.root.join(file_path);
 
     if entry.is_directory {
-        if worktree_path.is_dir() {
-            let count = std::fs::read_dir(&worktree_path)
-                .map(|entries| entries.count())
-                .unwrap_or(0);
-            println!("{}: phantom directory ({} entries)", file_path, count);
+        if let Some(manifest) = &entry.manifest {
+            show_phantom_dir_changes(file_path, manifest, &current_manifest(&worktree_path)?);
+        } else if worktree_path.is_dir() {
+            show_phantom_dir_diff(git, file_path)?;
         } else {
             println!("{}: phantom directory does not exist", file_path);
         }
@@ -98,6 +478,87 @@ fn show_phantom_diff(git: &GitRepo, file_path: &str, entry: &FileEntry) -> Resul
     Ok(())
 }
 
+/// List the files of a phantom directory that changed since it was
+/// registered. Contents are not diffed.
+fn show_phantom_dir_changes(
+    file_path: &str,
+    registered: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) {
+    let dir_label = file_path.trim_end_matches('/');
+    let changes = manifest_changes(registered, current);
+    if changes.is_empty() {
+        println!("{}: no files changed since it was registered", dir_label);
+        return;
+    }
+
+    println!("{}: changes since it was registered", dir_label);
+    for (marker, path) in changes {
+        let line = format!("{} {}/{}", marker, dir_label, path);
+        match marker {
+            '+' => println!("{}", line.green()),
+            '-' => println!("{}", line.red()),
+            _ => println!("{}", line.yellow()),
+        }
+    }
+}
+
+/// Show every file under a phantom directory as a new file diff.
+/// Symlinks are listed without being followed; large directories are summarized.
+fn show_phantom_dir_diff(git: &GitRepo, file_path: &str) -> Result<()> {
+    let dir = git.root.join(file_path);
+    let dir_label = file_path.trim_end_matches('/');
+    let entries = fs_util::walk_dir(&dir)?;
+
+    if entries.is_empty() {
+        println!("{}: phantom directory is empty", dir_label);
+        return Ok(());
+    }
+
+    let (file_count, total_bytes) = entries.iter().fold((0, 0), |(count, bytes), e| match e {
+        WalkEntry::File { size, .. } => (count + 1, bytes + size),
+        WalkEntry::Symlink { .. } => (count, bytes),
+    });
+    let summarize = file_count > PHANTOM_DIR_MAX_FILES || total_bytes > PHANTOM_DIR_MAX_BYTES;
+
+    if summarize {
+        eprintln!(
+            "{}",
+            format!(
+                "warning: {} has {} files ({} bytes), showing summary only",
+                dir_label, file_count, total_bytes
+            )
+            .yellow()
+        );
+    }
+
+    for walked in &entries {
+        match walked {
+            WalkEntry::Symlink { path, target } => {
+                println!(
+                    "{}/{} -> {} (symlink)",
+                    dir_label,
+                    path.display(),
+                    target.display()
+                );
+            }
+            WalkEntry::File { path, size } => {
+                let label = format!("{}/{}", dir_label, path.display());
+                if summarize {
+                    println!("{} ({} bytes)", label, size);
+                } else if fs_util::is_binary(&dir.join(path)).unwrap_or(false) {
+                    println!("{}: binary file", label);
+                } else {
+                    let content = std::fs::read_to_string(dir.join(path)).unwrap_or_default();
+                    diff_util::print_new_file_diff(&content, &label);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::config::{ExcludeMode, ShadowConfig};
@@ -240,8 +701,196 @@ mod tests {
         config.save(&git.shadow_dir).unwrap();
 
         // Verify we can match specific file
-        let normalized = path::normalize_path("CLAUDE.md", &git.root).unwrap();
+        let normalized = path::normalize_path("CLAUDE.md", "", &git.root).unwrap();
         assert_eq!(normalized, "CLAUDE.md");
         assert!(config.get(&normalized).is_some());
     }
+
+    #[test]
+    fn test_collect_stat_overlay_and_phantom() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let commit = git.head_commit().unwrap();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# A\n# B\n").unwrap();
+
+        std::fs::write(git.root.join("local.md"), "one\ntwo\n").unwrap();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+
+        let overlay =
+            super::collect_stat(&git, "CLAUDE.md", config.get("CLAUDE.md").unwrap(), false)
+                .unwrap();
+        assert_eq!((overlay.added, overlay.removed), (2, 0));
+        assert!(!overlay.new_file);
+
+        let phantom =
+            super::collect_stat(&git, "local.md", config.get("local.md").unwrap(), false).unwrap();
+        assert_eq!((phantom.added, phantom.removed), (2, 0));
+        assert!(phantom.new_file);
+    }
+
+    #[test]
+    fn test_resolve_tool_command() {
+        let (_dir, git) = make_test_repo();
+
+        assert_eq!(super::resolve_tool_command(&git, ""), None);
+        assert_eq!(
+            super::resolve_tool_command(&git, "meld").as_deref(),
+            Some("meld \"$LOCAL\" \"$REMOTE\"")
+        );
+
+        std::process::Command::new("git")
+            .args(["config", "diff.tool", "mytool"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args([
+                "config",
+                "difftool.mytool.cmd",
+                "mytool --diff $LOCAL $REMOTE",
+            ])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        assert_eq!(
+            super::resolve_tool_command(&git, "").as_deref(),
+            Some("mytool --diff $LOCAL $REMOTE")
+        );
+    }
+
+    #[test]
+    fn test_open_in_tool_passes_baseline_and_current() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let commit = git.head_commit().unwrap();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# Mine\n").unwrap();
+
+        let out = git.shadow_dir.join("tool-out.txt");
+        let command = format!("cat \"$LOCAL\" \"$REMOTE\" > '{}'", out.display());
+        super::open_in_tool(
+            &git,
+            "CLAUDE.md",
+            config.get("CLAUDE.md").unwrap(),
+            &command,
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&out).unwrap(),
+            "# Team\n# Team\n# Mine\n"
+        );
+    }
+
+    #[test]
+    fn test_collect_stat_skips_unchanged_overlay() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let commit = git.head_commit().unwrap();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+
+        assert!(
+            super::collect_stat(&git, "CLAUDE.md", config.get("CLAUDE.md").unwrap(), false)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_has_changes_for_exit_code() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let commit = git.head_commit().unwrap();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+        let overlay = config.get("CLAUDE.md").unwrap();
+        let phantom = config.get("local.md").unwrap();
+
+        assert!(!super::has_changes(&git, "CLAUDE.md", overlay, false, false).unwrap());
+        assert!(!super::has_changes(&git, "local.md", phantom, false, false).unwrap());
+
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# Mine\n").unwrap();
+        std::fs::write(git.root.join("local.md"), "local\n").unwrap();
+        assert!(super::has_changes(&git, "CLAUDE.md", overlay, false, false).unwrap());
+        assert!(super::has_changes(&git, "local.md", phantom, false, false).unwrap());
+
+        // Line endings and trailing blanks only count without --ignore-whitespace
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team  \r\n").unwrap();
+        assert!(super::has_changes(&git, "CLAUDE.md", overlay, false, false).unwrap());
+        assert!(!super::has_changes(&git, "CLAUDE.md", overlay, false, true).unwrap());
+        assert!(super::collect_stat(&git, "CLAUDE.md", overlay, true).is_none());
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap(),
+            "# Team  \r\n"
+        );
+    }
+
+    #[test]
+    fn test_phantom_directory_changes_since_registration() {
+        let (_dir, git) = make_test_repo();
+        let dir = git.root.join(".claude");
+        std::fs::create_dir_all(dir.join("commands")).unwrap();
+        std::fs::write(dir.join("settings.json"), "{}").unwrap();
+        std::fs::write(dir.join("commands/old.md"), "old\n").unwrap();
+        std::fs::write(dir.join("notes.md"), "notes\n").unwrap();
+
+        let mut config = ShadowConfig::new();
+        config
+            .add_phantom(".claude".to_string(), ExcludeMode::None, true)
+            .unwrap();
+        let entry = config.files.get_mut(".claude").unwrap();
+        entry.manifest = Some(fs_util::dir_manifest(&dir).unwrap());
+        let entry = config.get(".claude").unwrap();
+        assert!(!super::has_changes(&git, ".claude", entry, false, false).unwrap());
+
+        std::fs::write(dir.join("settings.json"), r#"{"a": 1}"#).unwrap();
+        std::fs::remove_file(dir.join("commands/old.md")).unwrap();
+        std::fs::write(dir.join("commands/new.md"), "new\n").unwrap();
+        assert!(super::has_changes(&git, ".claude", entry, false, false).unwrap());
+
+        let changes = super::manifest_changes(
+            entry.manifest.as_ref().unwrap(),
+            &super::current_manifest(&dir).unwrap(),
+        );
+        assert_eq!(
+            changes,
+            vec![
+                ('+', "commands/new.md".to_string()),
+                ('-', "commands/old.md".to_string()),
+                ('M', "settings.json".to_string()),
+            ]
+        );
+    }
 }
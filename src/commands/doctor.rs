@@ -1,38 +1,34 @@
 use anyhow::Result;
 use colored::Colorize;
+use is_terminal::IsTerminal;
 
+use crate::baseline;
+use crate::commands::{install, restore};
 use crate::config::{FileType, ShadowConfig};
-use crate::git::GitRepo;
+use crate::git::{self, short_hash, GitRepo, HookState, HOOK_NAMES};
 use crate::lock::{self, LockStatus};
+use crate::merge;
 use crate::path;
+use crate::settings::Settings;
 
-const HOOK_NAMES: &[&str] = &["pre-commit", "post-commit", "post-merge"];
-const COMPETING_HOOKS: &[&str] = &[".husky", ".pre-commit-config.yaml", "lefthook.yml"];
-
-pub fn run() -> Result<()> {
+pub fn run(fix: bool, yes: bool) -> Result<()> {
     let git = GitRepo::discover(&std::env::current_dir()?)?;
-    let config = ShadowConfig::load(&git.shadow_dir)?;
-
-    let mut issues = Vec::new();
-    let mut warnings = Vec::new();
-
-    // 1. Check hook files
-    check_hooks(&git, &mut issues, &mut warnings);
-
-    // 2. Check competing hook managers
-    check_competing_hooks(&git, &mut warnings);
+    let mut config = ShadowConfig::load(&git.shadow_dir)?;
 
-    // 3. Check config integrity
-    check_config_integrity(&git, &config, &mut issues);
-
-    // 4. Check stash remnants
-    check_stash(&git, &mut warnings);
+    let (mut issues, mut warnings) = run_checks(&git, &config, false);
 
-    // 5. Check lock
-    check_lock(&git, &mut warnings);
+    if fix && !(issues.is_empty() && warnings.is_empty()) {
+        let fixed = apply_fixes(&git, &mut config, yes)?;
+        for message in &fixed {
+            println!("{} {}", "fixed:".green(), message);
+        }
 
-    // 6. Check suspended state
-    check_suspended(&config, &git, &mut warnings);
+        // Re-check so only problems that still need manual action are shown
+        (issues, warnings) = run_checks(&git, &config, false);
+        if !(issues.is_empty() && warnings.is_empty()) {
+            println!("{}", "remaining problems require manual action:".yellow());
+        }
+    }
 
     // Print results
     if issues.is_empty() && warnings.is_empty() {
@@ -55,12 +51,194 @@ pub fn run() -> Result<()> {
     Ok(())
 }
 
+/// Run all checks, returning (issues, warnings). With `pending_as_issues`,
+/// stash remnants and suspended changes are reported as issues instead of
+/// warnings (`verify` fails on them without `--strict`).
+pub(crate) fn run_checks(
+    git: &GitRepo,
+    config: &ShadowConfig,
+    pending_as_issues: bool,
+) -> (Vec<String>, Vec<String>) {
+    let mut issues = Vec::new();
+    let mut warnings = Vec::new();
+
+    // 1. Check hook files
+    check_hooks(git, &mut issues, &mut warnings);
+
+    // 2. Check competing hook managers
+    check_competing_hooks(git, &mut warnings);
+
+    // 3. Check config integrity
+    check_config_integrity(git, config, &mut issues);
+
+    // 4. Check baseline content matches the recorded commit
+    check_baseline_content(git, config, &mut warnings);
+
+    // 5. Check stash remnants
+    check_stash(
+        git,
+        if pending_as_issues {
+            &mut issues
+        } else {
+            &mut warnings
+        },
+    );
+
+    // 6. Check lock
+    check_lock(git, &mut warnings);
+
+    // 7. Check suspended state
+    check_suspended(
+        config,
+        git,
+        if pending_as_issues {
+            &mut issues
+        } else {
+            &mut warnings
+        },
+    );
+
+    // 8. Check leftover merge conflict backups
+    check_conflict_backups(git, config, &mut warnings);
+
+    // 9. Check baseline/stash files of paths that are no longer managed
+    check_orphans(git, config, &mut warnings);
+
+    // 10. Check baselines are stored where `object_baselines` asks
+    check_baseline_storage(git, config, &mut warnings);
+
+    // 11. Check phantoms that git tracks
+    warnings.extend(tracked_phantom_warnings(git, config));
+
+    // 12. Check temp files left by an interrupted write
+    check_temp_files(git, &mut warnings);
+
+    // 13. Check the git version supports the commands git-shadow runs
+    check_git_version(&mut warnings);
+
+    (issues, warnings)
+}
+
+/// Repair problems that can be fixed mechanically. Writing stashed content
+/// back to the working tree asks for confirmation unless `yes` is set.
+/// Returns a description of each applied fix.
+fn apply_fixes(git: &GitRepo, config: &mut ShadowConfig, yes: bool) -> Result<Vec<String>> {
+    let mut fixed = Vec::new();
+
+    // Hooks: missing, not executable, or not calling git-shadow
+    let mut hook_issues = Vec::new();
+    let mut hook_warnings = Vec::new();
+    check_hooks(git, &mut hook_issues, &mut hook_warnings);
+    if !hook_issues.is_empty() || !hook_warnings.is_empty() {
+        install::install_hooks(git, false)?;
+        fixed.push("reinstalled git-shadow hooks".to_string());
+    }
+
+    // Missing baselines can be regenerated from the recorded commit
+    let mut config_changed = false;
+    let missing: Vec<(String, String)> = config
+        .files
+        .iter()
+        .filter(|(_, e)| e.file_type == FileType::Overlay)
+        .filter(|(p, e)| !baseline::exists(git, p, e.baseline_blob.as_deref()))
+        .filter_map(|(p, e)| Some((p.clone(), e.baseline_commit.clone()?)))
+        .collect();
+    for (file_path, commit) in missing {
+        if let Ok(content) = git.show_file(&commit, &file_path) {
+            let blob = baseline::write(git, &file_path, &content)?;
+            config.set_baseline_blob(&file_path, blob);
+            config_changed = true;
+            fixed.push(format!("regenerated baseline for {}", file_path));
+        }
+    }
+
+    // Orphan baselines belong to no managed file. Orphan stash entries may
+    // still hold the only copy of a file, so they are left to `restore`.
+    for (file_path, storage_path) in orphan_entries(git, config, "baselines") {
+        std::fs::remove_file(&storage_path)?;
+        fixed.push(format!("removed orphan baseline for {}", file_path));
+    }
+    for name in orphan_baseline_refs(git, config) {
+        git.delete_ref(&name)?;
+        fixed.push(format!("removed orphan baseline ref {}", name));
+    }
+
+    // Lock: only stale locks are safe to remove
+    let lock_status = lock::check_lock(&git.shadow_dir).ok();
+    if let Some(LockStatus::Stale(info)) = &lock_status {
+        lock::release_lock(&git.shadow_dir)?;
+        fixed.push(format!("removed stale lockfile (PID {})", info.pid));
+    }
+    let lock_held = matches!(lock_status, Some(LockStatus::HeldByOther(_)));
+
+    // Temp files: a running commit may still be writing them
+    if !lock_held {
+        for file in path::remove_orphan_temp_files(&git.shadow_dir)? {
+            fixed.push(format!("removed leftover temp file {}", file.display()));
+        }
+    }
+
+    // Baseline storage: a running commit may be reading the baselines
+    if !lock_held {
+        let migrated = baseline::migrate(git, config)?;
+        if !migrated.is_empty() {
+            config_changed = true;
+            fixed.push(format!(
+                "moved {} baseline(s) to {}",
+                migrated.len(),
+                storage_name(git)
+            ));
+        }
+    }
+    if config_changed {
+        config.save(&git.shadow_dir)?;
+    }
+
+    // Stash remnants: restoring writes to the working tree, so confirm first.
+    // Never touch the stash while another process holds the lock.
+    let mut stash_warnings = Vec::new();
+    check_stash(git, &mut stash_warnings);
+    if !stash_warnings.is_empty() && !lock_held && confirm_restore(yes)? {
+        let restored = restore::restore_stash(git, None)?;
+        for file in restored {
+            fixed.push(format!("restored {} from stash", file));
+        }
+    }
+
+    Ok(fixed)
+}
+
+fn confirm_restore(yes: bool) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        eprintln!(
+            "{}",
+            "skipping stash restore: --yes is required in non-interactive mode".yellow()
+        );
+        return Ok(false);
+    }
+
+    eprintln!("Stashed files will be written back to the working tree. Continue? [y/N]");
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let input = input.trim().to_lowercase();
+    Ok(input == "y" || input == "yes")
+}
+
 fn check_hooks(git: &GitRepo, issues: &mut Vec<String>, warnings: &mut Vec<String>) {
+    let hooks_dir = git.hooks_dir();
+    let location = git
+        .custom_hooks_dir()
+        .map(|dir| format!(" in {} (core.hooksPath)", dir.display()))
+        .unwrap_or_default();
     for hook_name in HOOK_NAMES {
-        let hook_path = git.git_dir.join("hooks").join(hook_name);
+        let hook_path = hooks_dir.join(hook_name);
 
-        if !hook_path.exists() {
-            issues.push(format!("{} hook does not exist", hook_name));
+        let state = git.hook_state(hook_name);
+        if state == HookState::Missing {
+            issues.push(format!("{} hook does not exist{}", hook_name, location));
             continue;
         }
 
@@ -75,20 +253,20 @@ fn check_hooks(git: &GitRepo, issues: &mut Vec<String>, warnings: &mut Vec<Strin
             }
         }
 
-        // Check content calls git-shadow
-        if let Ok(content) = std::fs::read_to_string(&hook_path) {
-            if !content.contains("git-shadow hook") && !content.contains("git shadow hook") {
-                warnings.push(format!("{} hook does not call git-shadow", hook_name));
-            }
+        if state == HookState::Foreign {
+            warnings.push(format!("{} hook does not call git-shadow", hook_name));
+        } else if std::fs::read_to_string(&hook_path).is_ok_and(|c| install::is_outdated_hook(&c)) {
+            warnings.push(format!(
+                "{} hook is outdated. Run `git-shadow install`",
+                hook_name
+            ));
         }
     }
 }
 
 fn check_competing_hooks(git: &GitRepo, warnings: &mut Vec<String>) {
-    for marker in COMPETING_HOOKS {
-        if git.root.join(marker).exists() {
-            warnings.push(format!("competing hook manager detected: {}", marker));
-        }
+    for marker in install::detect_competing_hooks(git) {
+        warnings.push(format!("competing hook manager detected: {}", marker));
     }
 }
 
@@ -101,9 +279,7 @@ fn check_config_integrity(git: &GitRepo, config: &ShadowConfig, issues: &mut Vec
                     issues.push(format!("{} does not exist in working tree", file_path));
                 }
 
-                let encoded = path::encode_path(file_path);
-                let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
-                if !baseline_path.exists() {
+                if !baseline::exists(git, file_path, entry.baseline_blob.as_deref()) {
                     issues.push(format!("baseline file for {} does not exist", file_path));
                 }
             }
@@ -127,27 +303,71 @@ fn check_config_integrity(git: &GitRepo, config: &ShadowConfig, issues: &mut Vec
     }
 }
 
-fn check_stash(git: &GitRepo, warnings: &mut Vec<String>) {
-    let stash_dir = git.shadow_dir.join("stash");
-    if stash_dir.exists() {
-        let has_files = std::fs::read_dir(&stash_dir)
-            .ok()
-            .map(|entries| {
-                entries
-                    .filter_map(|e| e.ok())
-                    .any(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
-            })
-            .unwrap_or(false);
-
-        if has_files {
-            warnings.push("stash has remaining files. Run `git-shadow restore`".to_string());
+/// Compare each overlay baseline with the blob at its baseline commit.
+/// Sizes are compared first so large files are only hashed when needed.
+fn check_baseline_content(git: &GitRepo, config: &ShadowConfig, warnings: &mut Vec<String>) {
+    for (file_path, entry) in &config.files {
+        if entry.file_type != FileType::Overlay {
+            continue;
+        }
+        let Some(commit) = &entry.baseline_commit else {
+            continue;
+        };
+        if let Some(blob) = &entry.baseline_blob {
+            // Object baselines are compared by object id alone
+            if git.blob_id(commit, file_path).is_ok_and(|id| id != *blob) {
+                push_content_mismatch(warnings, file_path, commit);
+            }
+            continue;
+        }
+        let baseline_path = baseline::file_path(git, file_path);
+        let Ok(metadata) = std::fs::metadata(&baseline_path) else {
+            // Missing baselines are reported by check_config_integrity
+            continue;
+        };
+        let Ok(expected_size) = git.blob_size(commit, file_path) else {
+            continue;
+        };
+
+        let matches = metadata.len() == expected_size
+            && match (
+                git.hash_object(&baseline_path),
+                git.blob_id(commit, file_path),
+            ) {
+                (Ok(actual), Ok(expected)) => actual == expected,
+                _ => true,
+            };
+
+        if !matches {
+            push_content_mismatch(warnings, file_path, commit);
         }
     }
 }
 
+fn push_content_mismatch(warnings: &mut Vec<String>, file_path: &str, commit: &str) {
+    warnings.push(format!(
+        "baseline file for {} differs from commit {}. Consider `git-shadow rebase {}`",
+        file_path,
+        short_hash(commit),
+        file_path
+    ));
+}
+
+fn check_stash(git: &GitRepo, warnings: &mut Vec<String>) {
+    let has_files =
+        path::stored_files(&git.shadow_dir.join("stash")).is_ok_and(|files| !files.is_empty());
+    if has_files {
+        warnings.push("stash has remaining files. Run `git-shadow restore`".to_string());
+    }
+}
+
 fn check_suspended(config: &ShadowConfig, git: &GitRepo, warnings: &mut Vec<String>) {
-    if config.suspended {
-        warnings.push("shadow changes are suspended. Run `git-shadow resume`".to_string());
+    let suspended = config.suspended_files();
+    if !suspended.is_empty() {
+        warnings.push(format!(
+            "shadow changes are suspended ({}). Run `git-shadow resume`",
+            suspended.join(", ")
+        ));
 
         // Check if suspended directory exists and has files
         let suspended_dir = git.shadow_dir.join("suspended");
@@ -157,19 +377,209 @@ fn check_suspended(config: &ShadowConfig, git: &GitRepo, warnings: &mut Vec<Stri
     }
 }
 
+/// Phantoms that git tracks (e.g. after `git add -f`). pre-commit would
+/// unstage them on every commit; shared with its soft checks.
+pub(crate) fn tracked_phantom_warnings(git: &GitRepo, config: &ShadowConfig) -> Vec<String> {
+    config
+        .files
+        .iter()
+        .filter(|(_, entry)| entry.file_type == FileType::Phantom)
+        .filter(|(file_path, _)| git.is_tracked(file_path).unwrap_or(false))
+        .map(|(file_path, entry)| {
+            if entry.is_directory {
+                format!(
+                    "{} is registered as phantom but tracked by git. Run `git rm -r --cached {}` to untrack it",
+                    file_path, file_path
+                )
+            } else {
+                format!(
+                    "{} is registered as phantom but tracked by git. Run `git-shadow add --overlay {}` to make it an overlay, or `git rm --cached {}` to untrack it",
+                    file_path, file_path, file_path
+                )
+            }
+        })
+        .collect()
+}
+
+fn check_conflict_backups(git: &GitRepo, config: &ShadowConfig, warnings: &mut Vec<String>) {
+    for (file_path, entry) in &config.files {
+        if entry.file_type != FileType::Overlay {
+            continue;
+        }
+        let backup = merge::orig_path(file_path);
+        if git.root.join(&backup).exists() {
+            warnings.push(format!(
+                "{} is left over from a merge conflict. Delete it once {} is resolved",
+                backup, file_path
+            ));
+        }
+    }
+}
+
+fn check_orphans(git: &GitRepo, config: &ShadowConfig, warnings: &mut Vec<String>) {
+    for (file_path, storage_path) in orphan_entries(git, config, "baselines") {
+        warnings.push(format!(
+            "orphan baseline for {} ({}). It is no longer managed; `doctor --fix` removes it",
+            file_path,
+            storage_path.display()
+        ));
+    }
+    for (file_path, _) in orphan_entries(git, config, "stash") {
+        warnings.push(format!(
+            "orphan stash entry for {}. Run `git-shadow restore {}` to recover it",
+            file_path, file_path
+        ));
+    }
+    for name in orphan_baseline_refs(git, config) {
+        warnings.push(format!(
+            "orphan baseline ref {}. It is no longer managed; `doctor --fix` removes it",
+            name
+        ));
+    }
+}
+
+fn check_temp_files(git: &GitRepo, warnings: &mut Vec<String>) {
+    for file in path::orphan_temp_files(&git.shadow_dir) {
+        warnings.push(format!(
+            "leftover temp file {} from an interrupted write. `doctor --fix` removes it",
+            file.display()
+        ));
+    }
+}
+
+/// git features git-shadow relies on, with the release that added them
+const GIT_FEATURES: &[((u32, u32, u32), &str)] = &[
+    (
+        (1, 8, 2),
+        "`check-ignore` (skipping exclude entries for ignored phantoms)",
+    ),
+    (
+        (2, 5, 0),
+        "`rev-parse --git-common-dir` (worktrees sharing .git/shadow/)",
+    ),
+    (
+        (2, 11, 0),
+        "`status --porcelain=v2` (partial-staging check in pre-commit)",
+    ),
+];
+
+fn check_git_version(warnings: &mut Vec<String>) {
+    let output = match git::version_output() {
+        Ok(output) => output,
+        Err(e) => {
+            warnings.push(format!("could not determine the git version: {}", e));
+            return;
+        }
+    };
+    match git::parse_version(&output) {
+        Some(version) => warnings.extend(git_version_warnings(version)),
+        None => warnings.push(format!(
+            "could not parse the git version from `{}`; version requirements were not checked",
+            output
+        )),
+    }
+}
+
+/// One warning per feature that `version` lacks
+fn git_version_warnings(version: (u32, u32, u32)) -> Vec<String> {
+    let (major, minor, patch) = version;
+    GIT_FEATURES
+        .iter()
+        .filter(|(required, _)| version < *required)
+        .map(|((r_major, r_minor, r_patch), feature)| {
+            format!(
+                "git {}.{}.{} lacks {}, which needs git {}.{}.{} or later",
+                major, minor, patch, feature, r_major, r_minor, r_patch
+            )
+        })
+        .collect()
+}
+
+/// `refs/shadow/baselines/*` refs that pin no managed overlay's baseline
+fn orphan_baseline_refs(git: &GitRepo, config: &ShadowConfig) -> Vec<String> {
+    let Ok(refs) = git.list_refs(baseline::REF_PREFIX) else {
+        return Vec::new();
+    };
+    refs.into_iter()
+        .filter(|name| {
+            !config
+                .files
+                .iter()
+                .any(|(p, e)| e.baseline_blob.is_some() && baseline::ref_name(p) == *name)
+        })
+        .collect()
+}
+
+fn check_baseline_storage(git: &GitRepo, config: &ShadowConfig, warnings: &mut Vec<String>) {
+    let Ok(pending) = baseline::pending_migration(git, config) else {
+        return;
+    };
+    if pending > 0 {
+        warnings.push(format!(
+            "{} baseline(s) are not stored in {} as `object_baselines` asks. `doctor --fix` moves them",
+            pending,
+            storage_name(git)
+        ));
+    }
+}
+
+/// Where `object_baselines` puts baselines, for messages
+fn storage_name(git: &GitRepo) -> &'static str {
+    let objects = Settings::load(&git.shadow_dir).is_ok_and(|s| s.object_baselines);
+    if objects {
+        "the git object store"
+    } else {
+        ".git/shadow/baselines/"
+    }
+}
+
+/// Files in a storage directory whose decoded path is not in the config,
+/// as (decoded path, storage file)
+fn orphan_entries(
+    git: &GitRepo,
+    config: &ShadowConfig,
+    dir: &str,
+) -> Vec<(String, std::path::PathBuf)> {
+    let Ok(entries) = path::stored_files(&git.shadow_dir.join(dir)) else {
+        return Vec::new();
+    };
+    let mut orphans: Vec<_> = entries
+        .into_iter()
+        .filter_map(|e| {
+            let decoded = path::decode_path(&git.shadow_dir, &e.file_name().to_string_lossy());
+            (!config.files.contains_key(&decoded)).then(|| (decoded, e.path()))
+        })
+        .collect();
+    orphans.sort();
+    orphans
+}
+
 fn check_lock(git: &GitRepo, warnings: &mut Vec<String>) {
+    let timeout = Settings::load(&git.shadow_dir)
+        .and_then(|s| s.lock_timeout())
+        .unwrap_or(lock::DEFAULT_LOCK_TIMEOUT);
     if let Ok(status) = lock::check_lock(&git.shadow_dir) {
         match status {
             LockStatus::Stale(info) => {
                 warnings.push(format!(
-                    "stale lockfile detected (PID {}). Run `git-shadow restore`",
-                    info.pid
+                    "stale lockfile detected (PID {}, acquired {} ago). Run `git-shadow restore`",
+                    info.pid,
+                    lock::format_age(info.age())
+                ));
+            }
+            LockStatus::HeldByOther(info) if info.is_expired(timeout) => {
+                warnings.push(format!(
+                    "lockfile has been held by PID {} for {} (timeout {}); the PID may belong to an unrelated process. If no commit is running, run `git-shadow restore --force-lock`",
+                    info.pid,
+                    lock::format_age(info.age()),
+                    lock::format_age(timeout)
                 ));
             }
             LockStatus::HeldByOther(info) => {
                 warnings.push(format!(
-                    "lockfile is held by another process (PID {})",
-                    info.pid
+                    "lockfile is held by another process (PID {}, acquired {} ago)",
+                    info.pid,
+                    lock::format_age(info.age())
                 ));
             }
             _ => {}
@@ -233,6 +643,21 @@ mod tests {
         assert!(issues.iter().any(|i| i.contains("pre-commit")));
     }
 
+    #[test]
+    fn test_hook_missing_from_core_hooks_path() {
+        let (_dir, git) = make_test_repo();
+        crate::commands::install::install_hooks(&git, false).unwrap();
+        git.config_set("core.hooksPath", ".githooks").unwrap();
+        let mut issues = Vec::new();
+        let mut warnings = Vec::new();
+
+        super::check_hooks(&git, &mut issues, &mut warnings);
+
+        // Hooks in .git/hooks are not run once core.hooksPath is set
+        assert_eq!(issues.len(), super::HOOK_NAMES.len());
+        assert!(issues[0].contains(".githooks (core.hooksPath)"));
+    }
+
     #[test]
     fn test_hook_present_and_valid() {
         let (_dir, git) = make_test_repo();
@@ -262,6 +687,28 @@ mod tests {
         assert!(warnings.is_empty());
     }
 
+    #[test]
+    fn test_outdated_hook_warned() {
+        let (_dir, git) = make_test_repo();
+        crate::commands::install::install_hooks(&git, false).unwrap();
+        let hook = git.hooks_dir().join("post-merge");
+        let content = std::fs::read_to_string(&hook).unwrap();
+        let v1: String = content
+            .lines()
+            .filter(|line| !line.starts_with("# git-shadow hook v"))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        std::fs::write(&hook, v1).unwrap();
+
+        let mut issues = Vec::new();
+        let mut warnings = Vec::new();
+        super::check_hooks(&git, &mut issues, &mut warnings);
+
+        assert!(issues.is_empty());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("post-merge hook is outdated"));
+    }
+
     #[test]
     fn test_competing_hooks_detected() {
         let (_dir, git) = make_test_repo();
@@ -321,6 +768,51 @@ mod tests {
         assert!(issues.iter().any(|i| i.contains("baseline file for")));
     }
 
+    #[test]
+    fn test_baseline_content_matches_commit() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let commit = git.head_commit().unwrap();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+
+        let mut warnings = Vec::new();
+        super::check_baseline_content(&git, &config, &mut warnings);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn test_baseline_content_drift_detected() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let commit = git.head_commit().unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        let baseline_path = git
+            .shadow_dir
+            .join("baselines")
+            .join(crate::path::encode_path("CLAUDE.md"));
+
+        // Different length
+        fs_util::atomic_write(&baseline_path, b"# Team\nextra\n").unwrap();
+        let mut warnings = Vec::new();
+        super::check_baseline_content(&git, &config, &mut warnings);
+        assert!(warnings.iter().any(|w| w.contains("differs from commit")));
+
+        // Same length, different content
+        fs_util::atomic_write(&baseline_path, b"# Tean\n").unwrap();
+        let mut warnings = Vec::new();
+        super::check_baseline_content(&git, &config, &mut warnings);
+        assert!(warnings
+            .iter()
+            .any(|w| w.contains("git-shadow rebase CLAUDE.md")));
+    }
+
     #[test]
     fn test_stash_remnant_detected() {
         let (_dir, git) = make_test_repo();
@@ -334,6 +826,23 @@ mod tests {
         assert!(warnings.iter().any(|w| w.contains("stash")));
     }
 
+    #[test]
+    fn test_conflict_backup_detected() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), "abc".to_string())
+            .unwrap();
+
+        let mut warnings = Vec::new();
+        super::check_conflict_backups(&git, &config, &mut warnings);
+        assert!(warnings.is_empty());
+
+        std::fs::write(git.root.join("CLAUDE.md.orig"), "mine").unwrap();
+        super::check_conflict_backups(&git, &config, &mut warnings);
+        assert!(warnings.iter().any(|w| w.contains("CLAUDE.md.orig")));
+    }
+
     #[test]
     fn test_stale_lock_detected() {
         let (_dir, git) = make_test_repo();
@@ -350,6 +859,52 @@ mod tests {
 
         assert!(!warnings.is_empty());
         assert!(warnings.iter().any(|w| w.contains("stale lockfile")));
+        assert!(warnings.iter().any(|w| w.contains("ago")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_check_lock_warns_about_old_live_lock() {
+        let (_dir, git) = make_test_repo();
+
+        // PID 1 is always alive; a lock this old points at a reused PID
+        std::fs::write(
+            git.shadow_dir.join("lock"),
+            "pid=1\ntimestamp=2026-01-01T00:00:00+00:00",
+        )
+        .unwrap();
+
+        let mut warnings = Vec::new();
+        super::check_lock(&git, &mut warnings);
+
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("held by PID 1"));
+        assert!(warnings[0].contains("timeout 10m"));
+    }
+
+    #[test]
+    fn test_tracked_phantom_detected() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+        config
+            .add_phantom(
+                "local.md".to_string(),
+                crate::config::ExcludeMode::None,
+                false,
+            )
+            .unwrap();
+        assert!(super::tracked_phantom_warnings(&git, &config).is_empty());
+
+        std::process::Command::new("git")
+            .args(["add", "-f", "local.md"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        let warnings = super::tracked_phantom_warnings(&git, &config);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("git-shadow add --overlay local.md"));
+        assert!(warnings[0].contains("git rm --cached local.md"));
     }
 
     #[test]
@@ -402,6 +957,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_git_version_warnings() {
+        assert!(super::git_version_warnings((2, 43, 0)).is_empty());
+        assert!(super::git_version_warnings((2, 11, 0)).is_empty());
+
+        let warnings = super::git_version_warnings((2, 7, 4));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("--porcelain=v2"));
+        assert!(warnings[0].contains("2.11.0"));
+
+        assert_eq!(super::git_version_warnings((1, 7, 1)).len(), 3);
+    }
+
     #[test]
     fn test_all_healthy() {
         let (_dir, git) = make_test_repo();
@@ -436,4 +1004,130 @@ mod tests {
         assert!(issues.is_empty());
         assert!(warnings.is_empty());
     }
+
+    #[test]
+    fn test_fix_installs_hooks_and_removes_stale_lock() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        config.save(&git.shadow_dir).unwrap();
+        std::fs::write(
+            git.shadow_dir.join("lock"),
+            "pid=999999\ntimestamp=2026-01-01T00:00:00+00:00",
+        )
+        .unwrap();
+
+        let fixed = super::apply_fixes(&git, &mut config, true).unwrap();
+        assert!(fixed.iter().any(|f| f.contains("hooks")));
+        assert!(fixed.iter().any(|f| f.contains("stale lockfile")));
+
+        let (issues, warnings) = super::run_checks(&git, &config, false);
+        assert!(issues.is_empty(), "{:?}", issues);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn test_fix_restores_stash_and_baseline_with_yes() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let commit = git.head_commit().unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        config.save(&git.shadow_dir).unwrap();
+        std::fs::write(
+            git.shadow_dir
+                .join("stash")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            "# Mine\n",
+        )
+        .unwrap();
+
+        let fixed = super::apply_fixes(&git, &mut config, true).unwrap();
+        assert!(fixed.iter().any(|f| f.contains("regenerated baseline")));
+        assert!(fixed.iter().any(|f| f.contains("restored CLAUDE.md")));
+
+        assert_eq!(
+            std::fs::read_to_string(
+                git.shadow_dir
+                    .join("baselines")
+                    .join(crate::path::encode_path("CLAUDE.md"))
+            )
+            .unwrap(),
+            "# Team\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap(),
+            "# Mine\n"
+        );
+    }
+
+    #[test]
+    fn test_orphan_baseline_and_stash_detected() {
+        let (_dir, git) = make_test_repo();
+        let config = ShadowConfig::new();
+        for (dir, file) in [("baselines", "old.md"), ("stash", "gone.md")] {
+            fs_util::atomic_write(
+                &git.shadow_dir
+                    .join(dir)
+                    .join(crate::path::encode_path(file)),
+                b"x\n",
+            )
+            .unwrap();
+            crate::path::update_path_map(&git.shadow_dir, [file]).unwrap();
+        }
+
+        let mut warnings = Vec::new();
+        super::check_orphans(&git, &config, &mut warnings);
+
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("orphan baseline for old.md"));
+        assert!(warnings[1].contains("git-shadow restore gone.md"));
+    }
+
+    #[test]
+    fn test_temp_files_are_not_orphans_and_fix_removes_them() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        crate::commands::install::install_hooks(&git, false).unwrap();
+        let temp_file = git.shadow_dir.join("baselines").join(".tmpQ7r8S9");
+        std::fs::write(&temp_file, "partial").unwrap();
+        std::fs::write(git.shadow_dir.join("stash").join(".tmpT1u2V3"), "x").unwrap();
+
+        let (_, warnings) = super::run_checks(&git, &config, false);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings.iter().all(|w| w.starts_with("leftover temp file")));
+
+        let fixed = super::apply_fixes(&git, &mut config, true).unwrap();
+        assert_eq!(fixed.len(), 2);
+        assert!(!temp_file.exists());
+        assert!(super::run_checks(&git, &config, false).1.is_empty());
+    }
+
+    #[test]
+    fn test_fix_removes_orphan_baseline_and_restores_stash() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        crate::commands::install::install_hooks(&git, false).unwrap();
+        let baseline = git
+            .shadow_dir
+            .join("baselines")
+            .join(crate::path::encode_path("old.md"));
+        let stash = git
+            .shadow_dir
+            .join("stash")
+            .join(crate::path::encode_path("gone.md"));
+        fs_util::atomic_write(&baseline, b"x\n").unwrap();
+        fs_util::atomic_write(&stash, b"y\n").unwrap();
+        crate::path::update_path_map(&git.shadow_dir, ["old.md", "gone.md"]).unwrap();
+
+        let fixed = super::apply_fixes(&git, &mut config, true).unwrap();
+        assert!(fixed
+            .iter()
+            .any(|f| f == "removed orphan baseline for old.md"));
+        assert!(!baseline.exists());
+        // Orphan stash content is recovered by restore, never deleted
+        assert!(fixed.iter().any(|f| f == "restored gone.md from stash"));
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("gone.md")).unwrap(),
+            "y\n"
+        );
+    }
 }
@@ -0,0 +1,285 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::baseline;
+use crate::bundle::{Bundle, BundledFile, JsonBundle, OverlayPatch, PhantomContent};
+use crate::cli::ExportFormat;
+use crate::config::{FileEntry, FileType, ShadowConfig};
+use crate::diff_util;
+use crate::fs_util::{self, WalkEntry};
+use crate::git::GitRepo;
+
+pub fn run(output: &Path, format: ExportFormat) -> Result<()> {
+    let git = GitRepo::discover(&std::env::current_dir()?)?;
+    let config = ShadowConfig::load(&git.shadow_dir)?;
+
+    let bundle = build_bundle(&git, &config, format)?;
+    match format {
+        ExportFormat::Bundle => bundle.save(output)?,
+        ExportFormat::Json => JsonBundle::from_bundle(&bundle).save(output)?,
+    }
+
+    println!(
+        "exported {} overlay(s) and {} phantom(s) to {}",
+        bundle.overlays.len(),
+        bundle.phantoms.len(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Collect every exportable entry. Suspended, binary and missing files are
+/// skipped with a warning; the JSON format keeps binary phantom files.
+fn build_bundle(git: &GitRepo, config: &ShadowConfig, format: ExportFormat) -> Result<Bundle> {
+    let mut bundle = Bundle::new();
+
+    for (file_path, entry) in &config.files {
+        if entry.suspended {
+            warn_skip(file_path, "suspended; run `git-shadow resume` first");
+            continue;
+        }
+        if !git.root.join(file_path).exists() {
+            warn_skip(file_path, "missing from the working tree");
+            continue;
+        }
+        match entry.file_type {
+            FileType::Overlay => {
+                if let Some(overlay) = export_overlay(git, file_path, entry)? {
+                    bundle.overlays.push(overlay);
+                }
+            }
+            FileType::Phantom => {
+                if let Some(phantom) = export_phantom(git, file_path, entry, format)? {
+                    bundle.phantoms.push(phantom);
+                }
+            }
+        }
+    }
+
+    Ok(bundle)
+}
+
+fn export_overlay(
+    git: &GitRepo,
+    file_path: &str,
+    entry: &FileEntry,
+) -> Result<Option<OverlayPatch>> {
+    if entry.is_binary {
+        warn_skip(file_path, "binary overlays cannot be exported");
+        return Ok(None);
+    }
+
+    let baseline = baseline::read_to_string(git, file_path, entry.baseline_blob.as_deref())?;
+    let Some(content) = read_text(&git.root.join(file_path))? else {
+        warn_skip(file_path, "not valid UTF-8");
+        return Ok(None);
+    };
+
+    let patch = diff_util::git_patch(&baseline, &content, file_path);
+    Ok(Some(OverlayPatch {
+        path: file_path.to_string(),
+        baseline_commit: entry.baseline_commit.clone(),
+        baseline,
+        content,
+        patch,
+    }))
+}
+
+fn export_phantom(
+    git: &GitRepo,
+    file_path: &str,
+    entry: &FileEntry,
+    format: ExportFormat,
+) -> Result<Option<PhantomContent>> {
+    let source = git.root.join(file_path);
+    let mut files = Vec::new();
+
+    if entry.is_directory {
+        for walk in fs_util::walk_dir(&source)? {
+            let path = match walk {
+                WalkEntry::File { path, .. } => path,
+                WalkEntry::Symlink { path, .. } => {
+                    let relative = format!("{}/{}", file_path, path.to_string_lossy());
+                    warn_skip(&relative, "symlinks cannot be exported");
+                    continue;
+                }
+            };
+            let relative = format!(
+                "{}/{}",
+                file_path,
+                path.to_string_lossy().replace('\\', "/")
+            );
+            match read_phantom_file(&source.join(&path), format)? {
+                Some(content) => files.push(BundledFile {
+                    path: relative,
+                    content,
+                }),
+                None => warn_skip(&relative, NOT_TEXT),
+            }
+        }
+    } else {
+        match read_phantom_file(&source, format)? {
+            Some(content) => files.push(BundledFile {
+                path: file_path.to_string(),
+                content,
+            }),
+            None => {
+                warn_skip(file_path, NOT_TEXT);
+                return Ok(None);
+            }
+        }
+    }
+
+    Ok(Some(PhantomContent {
+        path: file_path.to_string(),
+        is_directory: entry.is_directory,
+        files,
+    }))
+}
+
+const NOT_TEXT: &str = "not valid UTF-8; use `--format json` to export it";
+
+/// Phantom file content, or None for non-UTF-8 content, which only the JSON
+/// format can carry
+fn read_phantom_file(file: &Path, format: ExportFormat) -> Result<Option<Vec<u8>>> {
+    let bytes =
+        std::fs::read(file).with_context(|| format!("failed to read {}", file.display()))?;
+    if format == ExportFormat::Json || std::str::from_utf8(&bytes).is_ok() {
+        Ok(Some(bytes))
+    } else {
+        Ok(None)
+    }
+}
+
+/// File content as text, or None for non-UTF-8 content
+fn read_text(file: &Path) -> Result<Option<String>> {
+    let bytes =
+        std::fs::read(file).with_context(|| format!("failed to read {}", file.display()))?;
+    Ok(String::from_utf8(bytes).ok())
+}
+
+fn warn_skip(file_path: &str, reason: &str) {
+    eprintln!(
+        "{}",
+        format!("warning: skipping {}: {}", file_path, reason).yellow()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ExcludeMode;
+    use crate::path;
+
+    fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.email", "t@t.com"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::fs::write(root.join("CLAUDE.md"), "# Team\n").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "CLAUDE.md"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+
+        let repo = GitRepo::discover(&root).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("baselines")).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("stash")).unwrap();
+        (dir, repo)
+    }
+
+    #[test]
+    fn test_bundle_contains_overlays_and_phantoms() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), git.head_commit().unwrap())
+            .unwrap();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# Mine\n").unwrap();
+
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+        std::fs::create_dir_all(git.root.join("notes")).unwrap();
+        std::fs::write(git.root.join("notes/a.md"), "a\n").unwrap();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+        config
+            .add_phantom("notes".to_string(), ExcludeMode::None, true)
+            .unwrap();
+
+        let bundle = build_bundle(&git, &config, ExportFormat::Bundle).unwrap();
+
+        assert_eq!(bundle.overlays.len(), 1);
+        let overlay = &bundle.overlays[0];
+        assert_eq!(overlay.baseline, "# Team\n");
+        assert_eq!(overlay.content, "# Team\n# Mine\n");
+        assert!(overlay.patch.contains("+# Mine"));
+
+        let paths: Vec<&str> = bundle
+            .phantoms
+            .iter()
+            .flat_map(|p| p.files.iter().map(|f| f.path.as_str()))
+            .collect();
+        assert_eq!(paths, vec!["local.md", "notes/a.md"]);
+    }
+
+    #[test]
+    fn test_bundle_skips_binary_and_missing() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay_with("CLAUDE.md".to_string(), git.head_commit().unwrap(), true)
+            .unwrap();
+        config
+            .add_phantom("gone.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+
+        let bundle = build_bundle(&git, &config, ExportFormat::Bundle).unwrap();
+        assert!(bundle.overlays.is_empty());
+        assert!(bundle.phantoms.is_empty());
+    }
+
+    #[test]
+    fn test_json_format_keeps_binary_phantoms() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        std::fs::write(git.root.join("logo.png"), b"\x89PNG\0\xff").unwrap();
+        config
+            .add_phantom("logo.png".to_string(), ExcludeMode::None, false)
+            .unwrap();
+
+        let bundle = build_bundle(&git, &config, ExportFormat::Bundle).unwrap();
+        assert!(bundle.phantoms.is_empty());
+
+        let bundle = build_bundle(&git, &config, ExportFormat::Json).unwrap();
+        assert_eq!(bundle.phantoms[0].files[0].content, b"\x89PNG\0\xff");
+    }
+}
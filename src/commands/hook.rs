@@ -1,15 +1,61 @@
 use anyhow::{bail, Result};
 
+use crate::error::ShadowError;
 use crate::git::GitRepo;
 use crate::hooks;
 
-pub fn run(hook_name: &str) -> Result<()> {
+/// Set to `1` by generated hook scripts, so `hook` can tell git running it
+/// from a user typing it
+pub const FROM_HOOK_ENV: &str = "GIT_SHADOW_FROM_HOOK";
+
+pub fn run(hook_name: &str, args: &[String]) -> Result<()> {
     let git = GitRepo::discover(&std::env::current_dir()?)?;
+    let from_hook = std::env::var(FROM_HOOK_ENV).is_ok_and(|v| v == "1");
+    if !should_run(&git, from_hook)? {
+        return Ok(());
+    }
 
     match hook_name {
         "pre-commit" => hooks::pre_commit::handle(&git),
         "post-commit" => hooks::post_commit::handle(&git),
         "post-merge" => hooks::post_merge::handle(&git),
+        "post-checkout" => hooks::post_checkout::handle(&git, args),
         _ => bail!("unknown hook name: {}", hook_name),
     }
 }
+
+/// Without `.git/shadow/` there is nothing to do. A hook left behind after
+/// the data was deleted must not block git, but a user running `hook` by
+/// hand is told to set git-shadow up.
+fn should_run(git: &GitRepo, from_hook: bool) -> Result<bool> {
+    if git.shadow_dir.exists() {
+        return Ok(true);
+    }
+    if from_hook {
+        return Ok(false);
+    }
+    Err(ShadowError::NotInitialized.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uninitialized_repo_only_errors_outside_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        let git = GitRepo::discover(dir.path()).unwrap();
+
+        let err = should_run(&git, false).unwrap_err();
+        assert!(err.to_string().contains("git-shadow install"));
+        assert!(!should_run(&git, true).unwrap());
+
+        std::fs::create_dir_all(&git.shadow_dir).unwrap();
+        assert!(should_run(&git, false).unwrap());
+    }
+}
@@ -0,0 +1,480 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+
+use crate::bundle::{Bundle, OverlayPatch, PhantomContent};
+use crate::commands::add::{self, AddOptions};
+use crate::config::{FileType, ShadowConfig};
+use crate::error::ShadowError;
+use crate::git::GitRepo;
+use crate::history::{self, Action};
+use crate::merge;
+use crate::path;
+use crate::settings::Settings;
+
+pub fn run(input: &Path) -> Result<()> {
+    let git = GitRepo::discover(&std::env::current_dir()?)?;
+    let mut config = ShadowConfig::load(&git.shadow_dir)?;
+    let bundle = Bundle::load(input)?;
+
+    let options = AddOptions {
+        max_file_size: Settings::load(&git.shadow_dir)?.max_file_size()?,
+        ..AddOptions::default()
+    };
+    let summary = import_bundle(&git, &mut config, &bundle, &options)?;
+
+    println!(
+        "summary: {} applied, {} unchanged, {} conflict(s), {} failed",
+        summary.applied, summary.unchanged, summary.conflicts, summary.failed
+    );
+    if summary.failed > 0 {
+        bail!("failed to import {} file(s)", summary.failed);
+    }
+    Ok(())
+}
+
+/// Outcome counts for an `import`
+#[derive(Debug, Default)]
+struct ImportSummary {
+    applied: usize,
+    unchanged: usize,
+    conflicts: usize,
+    failed: usize,
+}
+
+#[derive(Debug, PartialEq)]
+enum ImportOutcome {
+    Applied,
+    Unchanged,
+    Conflict,
+}
+
+/// Import every entry independently. A failure is reported and skipped; the
+/// config is saved after each entry so earlier imports survive a later error.
+fn import_bundle(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    bundle: &Bundle,
+    options: &AddOptions,
+) -> Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+
+    let overlays = bundle
+        .overlays
+        .iter()
+        .map(|o| (o.path.as_str(), Entry::Overlay(o)));
+    let phantoms = bundle
+        .phantoms
+        .iter()
+        .map(|p| (p.path.as_str(), Entry::Phantom(p)));
+
+    for (file_path, entry) in overlays.chain(phantoms) {
+        let result = path::normalize_path(file_path, "", &git.root).and_then(|normalized| {
+            let registered = config.get(&normalized).is_some();
+            let outcome = match entry {
+                Entry::Overlay(overlay) => {
+                    import_overlay(git, config, &normalized, overlay, options)
+                }
+                Entry::Phantom(phantom) => {
+                    import_phantom(git, config, &normalized, phantom, options)
+                }
+            }?;
+            Ok((normalized, registered, outcome))
+        });
+
+        match result {
+            Ok((normalized, registered, outcome)) => {
+                config.save(&git.shadow_dir)?;
+                if !registered {
+                    let commit = config
+                        .get(&normalized)
+                        .and_then(|e| e.baseline_commit.as_deref());
+                    history::record(&git.shadow_dir, Action::Add, Some(&normalized), commit);
+                }
+                match outcome {
+                    ImportOutcome::Applied => summary.applied += 1,
+                    ImportOutcome::Unchanged => summary.unchanged += 1,
+                    ImportOutcome::Conflict => summary.conflicts += 1,
+                }
+            }
+            Err(e) => {
+                eprintln!("{}", format!("error: {}: {}", file_path, e).red());
+                summary.failed += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+enum Entry<'a> {
+    Overlay(&'a OverlayPatch),
+    Phantom(&'a PhantomContent),
+}
+
+/// Apply an overlay's changes on top of the local content. Unregistered
+/// files are registered first (baseline = HEAD). The exported baseline is
+/// the merge base, so local shadow changes and a moved baseline are both
+/// kept; conflicts leave markers and a `.orig` backup.
+fn import_overlay(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    normalized: &str,
+    overlay: &OverlayPatch,
+    options: &AddOptions,
+) -> Result<ImportOutcome> {
+    match config.get(normalized) {
+        None => add::add_overlay(git, config, normalized, options)?,
+        Some(entry) if entry.file_type != FileType::Overlay => {
+            bail!("already registered as a phantom")
+        }
+        Some(entry) if entry.suspended => return Err(ShadowError::Suspended.into()),
+        Some(entry) if entry.is_binary => bail!("binary overlays cannot be merged"),
+        Some(_) => {}
+    }
+
+    let worktree_path = git.root.join(normalized);
+    let current = std::fs::read_to_string(&worktree_path)
+        .map_err(|_| ShadowError::FileMissing(normalized.to_string()))?;
+
+    if current == overlay.content {
+        println!("{}: already up to date", normalized);
+        return Ok(ImportOutcome::Unchanged);
+    }
+    if current == overlay.baseline {
+        std::fs::write(&worktree_path, &overlay.content)?;
+        println!(
+            "{}",
+            format!("applied shadow changes to {}", normalized).green()
+        );
+        return Ok(ImportOutcome::Applied);
+    }
+
+    let merge_result = merge::three_way_merge(
+        &overlay.baseline,
+        &current,
+        &overlay.content,
+        merge::ConflictStyle::resolve(git)?,
+        merge::MergeStrategy::Conflict,
+    );
+    std::fs::write(&worktree_path, &merge_result.content)?;
+
+    if merge_result.has_conflicts {
+        let backup = merge::write_orig(git, normalized, current.as_bytes())?;
+        eprintln!(
+            "{}",
+            format!(
+                "warning: conflicts detected in {}. Please resolve manually (your version is saved in {}; delete it when done)",
+                normalized, backup
+            )
+            .yellow()
+        );
+        Ok(ImportOutcome::Conflict)
+    } else {
+        println!(
+            "{}",
+            format!("merged shadow changes into {}", normalized).green()
+        );
+        Ok(ImportOutcome::Applied)
+    }
+}
+
+/// Write a phantom's files and register it. Existing files with different
+/// content are kept and reported as conflicts.
+fn import_phantom(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    normalized: &str,
+    phantom: &PhantomContent,
+    options: &AddOptions,
+) -> Result<ImportOutcome> {
+    match config.get(normalized) {
+        Some(entry) if entry.file_type != FileType::Phantom => {
+            bail!("already registered as an overlay")
+        }
+        Some(entry) if entry.suspended => return Err(ShadowError::Suspended.into()),
+        _ => {}
+    }
+
+    // Validate every path before writing anything
+    let mut targets = Vec::new();
+    for file in &phantom.files {
+        let file_path = path::normalize_path(&file.path, "", &git.root)?;
+        let inside = file_path == normalized
+            || (phantom.is_directory && file_path.starts_with(&format!("{}/", normalized)));
+        if !inside {
+            bail!(
+                "bundle file {} is outside phantom {}",
+                file.path,
+                normalized
+            );
+        }
+        targets.push((file_path, &file.content));
+    }
+
+    let mut written = 0;
+    let mut kept = Vec::new();
+    for (file_path, content) in targets {
+        let target = git.root.join(&file_path);
+        match std::fs::read(&target) {
+            Ok(existing) if existing == *content => continue,
+            Ok(_) => {
+                kept.push(file_path);
+                continue;
+            }
+            Err(_) if target.exists() => {
+                kept.push(file_path);
+                continue;
+            }
+            Err(_) => {}
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, content)
+            .with_context(|| format!("failed to write {}", file_path))?;
+        written += 1;
+    }
+    if phantom.is_directory {
+        std::fs::create_dir_all(git.root.join(normalized))?;
+    }
+
+    if config.get(normalized).is_none() {
+        add::add_phantom(git, config, normalized, options, false)?;
+    }
+
+    for file_path in &kept {
+        eprintln!(
+            "{}",
+            format!(
+                "warning: {} already exists with different content; kept the local version",
+                file_path
+            )
+            .yellow()
+        );
+    }
+
+    if !kept.is_empty() {
+        Ok(ImportOutcome::Conflict)
+    } else if written > 0 {
+        println!("{}", format!("imported phantom {}", normalized).green());
+        Ok(ImportOutcome::Applied)
+    } else {
+        println!("{}: already up to date", normalized);
+        Ok(ImportOutcome::Unchanged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::BundledFile;
+    use crate::fs_util;
+
+    fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.email", "t@t.com"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::fs::write(root.join("CLAUDE.md"), "# Team\n\nline2\n\nline3\n").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "CLAUDE.md"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+
+        let repo = GitRepo::discover(&root).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("baselines")).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("stash")).unwrap();
+        (dir, repo)
+    }
+
+    fn overlay_bundle(baseline: &str, content: &str) -> Bundle {
+        let mut bundle = Bundle::new();
+        bundle.overlays.push(OverlayPatch {
+            path: "CLAUDE.md".to_string(),
+            baseline_commit: None,
+            baseline: baseline.to_string(),
+            content: content.to_string(),
+            patch: String::new(),
+        });
+        bundle
+    }
+
+    fn options() -> AddOptions {
+        AddOptions {
+            exclude: crate::config::ExcludeMode::None,
+            ..AddOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_import_registers_and_applies_overlay() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let bundle = overlay_bundle(
+            "# Team\n\nline2\n\nline3\n",
+            "# Team\n# Mine\n\nline2\n\nline3\n",
+        );
+
+        let summary = import_bundle(&git, &mut config, &bundle, &options()).unwrap();
+        assert_eq!(summary.applied, 1);
+
+        assert!(config.get("CLAUDE.md").is_some());
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(content, "# Team\n# Mine\n\nline2\n\nline3\n");
+        // Baseline is the local HEAD, so the imported text is a shadow change
+        let baseline = std::fs::read_to_string(
+            git.shadow_dir
+                .join("baselines")
+                .join(path::encode_path("CLAUDE.md")),
+        )
+        .unwrap();
+        assert_eq!(baseline, "# Team\n\nline2\n\nline3\n");
+    }
+
+    #[test]
+    fn test_import_merges_with_local_changes() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), git.head_commit().unwrap())
+            .unwrap();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(path::encode_path("CLAUDE.md")),
+            b"# Team\n\nline2\n\nline3\n",
+        )
+        .unwrap();
+        std::fs::write(
+            git.root.join("CLAUDE.md"),
+            "# Team\n\nline2\n\nline3\n# Local\n",
+        )
+        .unwrap();
+
+        let bundle = overlay_bundle(
+            "# Team\n\nline2\n\nline3\n",
+            "# Team\n# Mine\n\nline2\n\nline3\n",
+        );
+        let summary = import_bundle(&git, &mut config, &bundle, &options()).unwrap();
+        assert_eq!(summary.applied, 1);
+
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(content, "# Team\n# Mine\n\nline2\n\nline3\n# Local\n");
+    }
+
+    #[test]
+    fn test_import_conflict_keeps_backup() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        std::fs::write(
+            git.root.join("CLAUDE.md"),
+            "# Local title\n\nline2\n\nline3\n",
+        )
+        .unwrap();
+
+        let bundle = overlay_bundle(
+            "# Team\n\nline2\n\nline3\n",
+            "# Their title\n\nline2\n\nline3\n",
+        );
+        let summary = import_bundle(&git, &mut config, &bundle, &options()).unwrap();
+        assert_eq!(summary.conflicts, 1);
+
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert!(content.contains("<<<<<<<"));
+        let backup = std::fs::read_to_string(git.root.join("CLAUDE.md.orig")).unwrap();
+        assert_eq!(backup, "# Local title\n\nline2\n\nline3\n");
+    }
+
+    #[test]
+    fn test_import_phantoms() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        std::fs::create_dir_all(git.root.join("notes")).unwrap();
+        std::fs::write(git.root.join("notes/b.md"), "mine\n").unwrap();
+
+        let mut bundle = Bundle::new();
+        bundle.phantoms.push(PhantomContent {
+            path: "local.md".to_string(),
+            is_directory: false,
+            files: vec![BundledFile {
+                path: "local.md".to_string(),
+                content: b"# Local\n".to_vec(),
+            }],
+        });
+        bundle.phantoms.push(PhantomContent {
+            path: "notes".to_string(),
+            is_directory: true,
+            files: vec![
+                BundledFile {
+                    path: "notes/a.md".to_string(),
+                    content: b"a\n".to_vec(),
+                },
+                BundledFile {
+                    path: "notes/b.md".to_string(),
+                    content: b"theirs\n".to_vec(),
+                },
+            ],
+        });
+
+        let summary = import_bundle(&git, &mut config, &bundle, &options()).unwrap();
+        assert_eq!(summary.applied, 1);
+        assert_eq!(summary.conflicts, 1);
+
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("local.md")).unwrap(),
+            "# Local\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("notes/a.md")).unwrap(),
+            "a\n"
+        );
+        // Existing files are never overwritten
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("notes/b.md")).unwrap(),
+            "mine\n"
+        );
+        assert!(config.get("notes").unwrap().is_directory);
+    }
+
+    #[test]
+    fn test_import_rejects_paths_outside_phantom() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+
+        let mut bundle = Bundle::new();
+        bundle.phantoms.push(PhantomContent {
+            path: "notes".to_string(),
+            is_directory: true,
+            files: vec![BundledFile {
+                path: "../escape.md".to_string(),
+                content: b"x\n".to_vec(),
+            }],
+        });
+
+        let summary = import_bundle(&git, &mut config, &bundle, &options()).unwrap();
+        assert_eq!(summary.failed, 1);
+        assert!(config.get("notes").is_none());
+        assert!(!git.root.join("notes").exists());
+    }
+}
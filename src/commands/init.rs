@@ -0,0 +1,97 @@
+use anyhow::{Context, Result};
+
+use crate::commands::add;
+use crate::config::ShadowConfig;
+use crate::git::{GitRepo, SHADOW_DIR_ENV};
+
+pub fn run() -> Result<()> {
+    let git = GitRepo::discover(&std::env::current_dir()?)?;
+    if init_shadow_dir(&git)? {
+        println!("initialized .git/shadow/");
+        // Only a fresh setup picks up the defaults on its own
+        let mut config = ShadowConfig::load(&git.shadow_dir)?;
+        let registered = add::add_defaults(&git, &mut config)?;
+        if registered > 0 {
+            println!("registered {} file(s) from defaults", registered);
+        }
+    } else {
+        println!(".git/shadow/ is already initialized");
+    }
+    note_shadow_dir_override(&git);
+    Ok(())
+}
+
+/// Point out where shadow data actually lives when `GIT_SHADOW_DIR` moved it:
+/// the hooks only find it if git runs them with the same variable set
+pub(crate) fn note_shadow_dir_override(git: &GitRepo) {
+    if std::env::var_os(SHADOW_DIR_ENV).is_some_and(|dir| !dir.is_empty()) {
+        println!(
+            "shadow data is stored in {} ({} is set); keep it set for git commands too, or the hooks will not find it",
+            git.shadow_dir.display(),
+            SHADOW_DIR_ENV
+        );
+    }
+}
+
+/// Create the shadow directory structure and an empty config. Existing
+/// directories and config are left untouched. Returns true if the config
+/// was created.
+pub(crate) fn init_shadow_dir(git: &GitRepo) -> Result<bool> {
+    let shadow_dir = &git.shadow_dir;
+    std::fs::create_dir_all(shadow_dir.join("baselines"))
+        .context("failed to create .git/shadow/baselines/")?;
+    std::fs::create_dir_all(shadow_dir.join("stash"))
+        .context("failed to create .git/shadow/stash/")?;
+
+    if ShadowConfig::exists(shadow_dir) {
+        return Ok(false);
+    }
+    ShadowConfig::new().save(shadow_dir)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        let repo = GitRepo::discover(&root).unwrap();
+        (dir, repo)
+    }
+
+    #[test]
+    fn test_init_creates_directories_and_config() {
+        let (_dir, git) = make_test_repo();
+        assert!(init_shadow_dir(&git).unwrap());
+
+        assert!(git.shadow_dir.join("baselines").is_dir());
+        assert!(git.shadow_dir.join("stash").is_dir());
+        let config = ShadowConfig::load(&git.shadow_dir).unwrap();
+        assert!(config.files.is_empty());
+        // No hooks are written
+        assert!(!git.missing_hooks().is_empty());
+    }
+
+    #[test]
+    fn test_init_is_idempotent_and_keeps_config() {
+        let (_dir, git) = make_test_repo();
+        init_shadow_dir(&git).unwrap();
+
+        let mut config = ShadowConfig::load(&git.shadow_dir).unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), "abc".to_string())
+            .unwrap();
+        config.save(&git.shadow_dir).unwrap();
+
+        assert!(!init_shadow_dir(&git).unwrap());
+        let config = ShadowConfig::load(&git.shadow_dir).unwrap();
+        assert!(config.get("CLAUDE.md").is_some());
+    }
+}
@@ -1,43 +1,230 @@
-use std::os::unix::fs::PermissionsExt;
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
 
-use anyhow::{Context, Result};
+use crate::commands::{add, hook, init};
+use crate::config::ShadowConfig;
+use crate::git::{GitRepo, HOOK_NAMES};
 
-use crate::git::GitRepo;
+/// Files that indicate another tool manages the hooks
+pub(crate) const COMPETING_HOOKS: &[&str] = &[".husky", ".pre-commit-config.yaml", "lefthook.yml"];
 
-const HOOK_NAMES: &[&str] = &["pre-commit", "post-commit", "post-merge"];
+/// Git alias that shows `git status` with shadow-managed files split out
+const STATUS_ALIAS: (&str, &str) = ("alias.st", "shadow status --git");
 
-fn generate_hook_script(hook_name: &str) -> String {
+/// Bumped whenever the generated hook script changes, so `install` can
+/// replace scripts written by older versions
+const HOOK_SCRIPT_VERSION: u32 = 3;
+
+/// Marker line carrying the script version
+const VERSION_MARKER: &str = "# git-shadow hook v";
+
+/// Comment present in every generated script, including v1
+const MANAGED_MARKER: &str = "# git-shadow managed hook";
+
+/// Shebang shell of generated hooks unless `install --shell` names another
+const DEFAULT_HOOK_SHELL: &str = "/bin/sh";
+
+/// The body sticks to POSIX sh syntax with LF line endings, so any
+/// sh-compatible shell (bash, dash, zsh, the bundled sh of Git for
+/// Windows...) runs it, including the `.pre-shadow` chain
+fn hook_script_for_shell(hook_name: &str, shell: &str) -> String {
+    format!(
+        "#!{}\n{}{}\n{}",
+        shell,
+        VERSION_MARKER,
+        HOOK_SCRIPT_VERSION,
+        hook_script_body(hook_name, HOOK_SCRIPT_VERSION)
+    )
+}
+
+/// Interpreter named by a script's shebang line
+fn script_shell(content: &str) -> Option<&str> {
+    content.lines().next()?.strip_prefix("#!").map(str::trim)
+}
+
+/// `install --shell` takes an absolute path, optionally with arguments
+/// (`/usr/bin/env bash`)
+fn validate_shell(shell: &str) -> Result<()> {
+    if !shell.starts_with('/') || shell.contains(['\n', '\r']) {
+        bail!(
+            "--shell must be an absolute path, e.g. /bin/bash or \"/usr/bin/env bash\" (got '{}')",
+            shell
+        );
+    }
+    Ok(())
+}
+
+/// Script body of a given version. v1 scripts are the v2 body without the
+/// version line; v3 tells `git-shadow hook` it runs from a hook.
+fn hook_script_body(hook_name: &str, version: u32) -> String {
+    let env = if version >= 3 {
+        format!("{}=1 ", hook::FROM_HOOK_ENV)
+    } else {
+        String::new()
+    };
     format!(
-        r#"#!/bin/sh
-# git-shadow managed hook
-git-shadow hook {hook_name}
+        r#"{MANAGED_MARKER}
+{env}git-shadow hook {hook_name} "$@"
 SHADOW_EXIT=$?
 if [ $SHADOW_EXIT -ne 0 ]; then
   exit $SHADOW_EXIT
 fi
 
 # Chain to existing hook
-if [ -x .git/hooks/{hook_name}.pre-shadow ]; then
-  .git/hooks/{hook_name}.pre-shadow "$@"
+HOOK_DIR=$(dirname "$0")
+if [ -x "$HOOK_DIR/{hook_name}.pre-shadow" ]; then
+  "$HOOK_DIR/{hook_name}.pre-shadow" "$@"
 fi
 "#,
         hook_name = hook_name
     )
 }
 
-pub fn run() -> Result<()> {
+/// Version of an installed git-shadow hook script (1 if it has no marker)
+fn hook_script_version(content: &str) -> u32 {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix(VERSION_MARKER))
+        .and_then(|version| version.trim().parse().ok())
+        .unwrap_or(1)
+}
+
+/// A script generated by an older git-shadow. Hand-written hooks that call
+/// `git-shadow hook` are never considered outdated.
+pub(crate) fn is_outdated_hook(content: &str) -> bool {
+    content.contains(MANAGED_MARKER) && hook_script_version(content) < HOOK_SCRIPT_VERSION
+}
+
+/// Lines of an installed hook that no generated version of the script
+/// contains, i.e. edits made by hand
+fn unknown_hook_lines(hook_name: &str, content: &str) -> Vec<String> {
+    let known: Vec<String> = (1..=HOOK_SCRIPT_VERSION)
+        .map(|version| hook_script_body(hook_name, version))
+        .collect();
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter(|line| !line.starts_with("#!") && !line.starts_with(VERSION_MARKER))
+        .filter(|line| !known.iter().any(|body| body.lines().any(|k| k == *line)))
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn run(
+    no_hooks: bool,
+    alias: bool,
+    force: bool,
+    apply_defaults: bool,
+    shell: Option<&str>,
+) -> Result<()> {
+    if let Some(shell) = shell {
+        validate_shell(shell)?;
+    }
     let git = GitRepo::discover(&std::env::current_dir()?)?;
+    // A fresh setup picks up the defaults like `init` does
+    let fresh = !ShadowConfig::exists(&git.shadow_dir);
+    if no_hooks {
+        init::init_shadow_dir(&git)?;
+        println!("initialized .git/shadow/ (hooks not written)");
+        print_hook_guide(&git);
+    } else {
+        install_hooks_with_shell(&git, force, shell)?;
+        match git.custom_hooks_dir() {
+            Some(dir) => println!(
+                "git-shadow hooks installed successfully in {} (core.hooksPath)",
+                dir.display()
+            ),
+            None => println!("git-shadow hooks installed successfully"),
+        }
+    }
+    init::note_shadow_dir_override(&git);
+
+    if apply_defaults || fresh {
+        let mut config = ShadowConfig::load(&git.shadow_dir)?;
+        match add::add_defaults(&git, &mut config)? {
+            0 if apply_defaults => println!("no default files to register"),
+            0 => {}
+            registered => println!("registered {} file(s) from defaults", registered),
+        }
+    }
+
+    let (key, value) = STATUS_ALIAS;
+    if alias {
+        if install_status_alias(&git)? {
+            println!("set {} = \"{}\"", key, value);
+        } else {
+            println!(
+                "{} is already set; left unchanged (wanted \"{}\")",
+                key, value
+            );
+        }
+    } else if git.config_get(key).is_none() {
+        println!(
+            "hint: run `git config {} \"{}\"` (or `git-shadow install --alias`) to see shadow-managed files in `git st`",
+            key, value
+        );
+    }
+    Ok(())
+}
+
+/// Set the `git st` alias unless the user already has one. Returns true if
+/// the alias now points at `status --git`.
+fn install_status_alias(git: &GitRepo) -> Result<bool> {
+    let (key, value) = STATUS_ALIAS;
+    match git.config_get(key) {
+        Some(existing) => Ok(existing == value),
+        None => {
+            git.config_set(key, value)?;
+            Ok(true)
+        }
+    }
+}
+
+/// Explain how to call git-shadow from hooks managed by another tool
+fn print_hook_guide(git: &GitRepo) {
+    for marker in detect_competing_hooks(git) {
+        println!("detected hook manager: {}", marker);
+    }
+    println!("call git-shadow from your own hooks:");
+    for hook_name in HOOK_NAMES {
+        println!(
+            "  {}: {}=1 git-shadow hook {} \"$@\"",
+            hook_name,
+            hook::FROM_HOOK_ENV,
+            hook_name
+        );
+    }
+}
 
-    // Create shadow directory structure
-    let shadow_dir = &git.shadow_dir;
-    std::fs::create_dir_all(shadow_dir.join("baselines"))
-        .context("failed to create .git/shadow/baselines/")?;
-    std::fs::create_dir_all(shadow_dir.join("stash"))
-        .context("failed to create .git/shadow/stash/")?;
+/// Competing hook manager markers present in the repository root
+pub(crate) fn detect_competing_hooks(git: &GitRepo) -> Vec<&'static str> {
+    COMPETING_HOOKS
+        .iter()
+        .copied()
+        .filter(|marker| git.root.join(marker).exists())
+        .collect()
+}
 
-    let hooks_dir = git.git_dir.join("hooks");
+/// Create the shadow directory structure and install (or repair) the hooks.
+/// Hooks written by an older git-shadow are updated in place; hooks with
+/// hand-made edits are only overwritten with `force`.
+pub(crate) fn install_hooks(git: &GitRepo, force: bool) -> Result<()> {
+    install_hooks_with_shell(git, force, None)
+}
+
+/// `install_hooks()` with the shebang shell of generated hooks. Without
+/// `shell`, new hooks use /bin/sh and existing ones keep their shell.
+pub(crate) fn install_hooks_with_shell(
+    git: &GitRepo,
+    force: bool,
+    shell: Option<&str>,
+) -> Result<()> {
+    init::init_shadow_dir(git)?;
+
+    let hooks_dir = git.hooks_dir();
     std::fs::create_dir_all(&hooks_dir).context("failed to create hooks directory")?;
 
+    let mut edited = Vec::new();
     for hook_name in HOOK_NAMES {
         let hook_path = hooks_dir.join(hook_name);
 
@@ -45,7 +232,42 @@ pub fn run() -> Result<()> {
         if hook_path.exists() {
             let content = std::fs::read_to_string(&hook_path)?;
             if content.contains("git-shadow hook") {
-                // Already installed, skip
+                let current_shell = script_shell(&content).unwrap_or(DEFAULT_HOOK_SHELL);
+                let wanted_shell = shell.unwrap_or(current_shell);
+                let outdated = is_outdated_hook(&content);
+                // Hand-written hooks keep whatever shell their owner chose
+                let reshell = content.contains(MANAGED_MARKER) && wanted_shell != current_shell;
+                if !outdated && !reshell {
+                    // Up to date or written by hand, only make sure it is executable
+                    set_executable(&hook_path)?;
+                    continue;
+                }
+                let unknown = unknown_hook_lines(hook_name, &content);
+                if !unknown.is_empty() && !force {
+                    eprintln!(
+                        "{}",
+                        format!(
+                            "warning: {} hook is outdated but has local edits:",
+                            hook_name
+                        )
+                        .yellow()
+                    );
+                    for line in &unknown {
+                        eprintln!("  {}", line);
+                    }
+                    set_executable(&hook_path)?;
+                    edited.push(*hook_name);
+                    continue;
+                }
+                // The `.pre-shadow` chain target is left alone
+                std::fs::write(&hook_path, hook_script_for_shell(hook_name, wanted_shell))
+                    .with_context(|| format!("failed to update {}", hook_name))?;
+                set_executable(&hook_path)?;
+                if outdated {
+                    println!("updated {} hook to v{}", hook_name, HOOK_SCRIPT_VERSION);
+                } else {
+                    println!("switched {} hook to {}", hook_name, wanted_shell);
+                }
                 continue;
             }
             // Existing hook from another tool - back it up
@@ -54,24 +276,121 @@ pub fn run() -> Result<()> {
                 .with_context(|| format!("failed to back up {}", hook_name))?;
         }
 
-        let script = generate_hook_script(hook_name);
+        let script = hook_script_for_shell(hook_name, shell.unwrap_or(DEFAULT_HOOK_SHELL));
         std::fs::write(&hook_path, &script)
             .with_context(|| format!("failed to write {}", hook_name))?;
 
-        // Set executable permission
-        let mut perms = std::fs::metadata(&hook_path)?.permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(&hook_path, perms)?;
+        set_executable(&hook_path)?;
     }
 
-    println!("git-shadow hooks installed successfully");
+    if !edited.is_empty() {
+        bail!(
+            "not updated: {} (re-run `git-shadow install --force` to overwrite local edits)",
+            edited.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// What uninstalling does with one hook
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum HookUninstall {
+    /// Generated script without a chained hook: delete it
+    Delete,
+    /// Generated script chaining to `<hook>.pre-shadow`: put the backup back
+    RestoreBackup,
+    /// Hand-written hook that calls git-shadow: left for the user to edit
+    KeepHandWritten,
+}
+
+/// Hooks that call git-shadow and how to uninstall each. Hooks that do not
+/// call git-shadow are not listed.
+pub(crate) fn plan_uninstall(git: &GitRepo) -> Vec<(&'static str, HookUninstall)> {
+    let hooks_dir = git.hooks_dir();
+    HOOK_NAMES
+        .iter()
+        .filter_map(|hook_name| {
+            let content = std::fs::read_to_string(hooks_dir.join(hook_name)).ok()?;
+            let action = if !content.contains(MANAGED_MARKER) {
+                if !content.contains("git-shadow hook") {
+                    return None;
+                }
+                HookUninstall::KeepHandWritten
+            } else if hooks_dir.join(format!("{}.pre-shadow", hook_name)).exists() {
+                HookUninstall::RestoreBackup
+            } else {
+                HookUninstall::Delete
+            };
+            Some((*hook_name, action))
+        })
+        .collect()
+}
+
+/// Remove the generated hooks in `plan` and move chained hooks back to
+/// their original names
+pub(crate) fn uninstall_hooks(git: &GitRepo, plan: &[(&'static str, HookUninstall)]) -> Result<()> {
+    let hooks_dir = git.hooks_dir();
+    for (hook_name, action) in plan {
+        let hook_path = hooks_dir.join(hook_name);
+        match action {
+            HookUninstall::Delete => std::fs::remove_file(&hook_path)
+                .with_context(|| format!("failed to delete {}", hook_name))?,
+            HookUninstall::RestoreBackup => {
+                std::fs::rename(
+                    hooks_dir.join(format!("{}.pre-shadow", hook_name)),
+                    &hook_path,
+                )
+                .with_context(|| format!("failed to restore {}.pre-shadow", hook_name))?;
+            }
+            HookUninstall::KeepHandWritten => {}
+        }
+    }
+    Ok(())
+}
+
+/// Tell the user which hooks need their `git-shadow hook` call removed by hand
+pub(crate) fn warn_hand_written(hooks: &[(&'static str, HookUninstall)]) {
+    for (hook_name, action) in hooks {
+        if *action == HookUninstall::KeepHandWritten {
+            eprintln!(
+                "{}",
+                format!(
+                    "warning: {} hook was not written by git-shadow; remove its `git-shadow hook` call by hand",
+                    hook_name
+                )
+                .yellow()
+            );
+        }
+    }
+}
+
+/// `git st` alias key, if it is the one `install --alias` set
+pub(crate) fn installed_status_alias(git: &GitRepo) -> Option<&'static str> {
+    let (key, value) = STATUS_ALIAS;
+    (git.config_get(key).as_deref() == Some(value)).then_some(key)
+}
+
+#[cfg(unix)]
+fn set_executable(hook_path: &std::path::Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut perms = std::fs::metadata(hook_path)?.permissions();
+    perms.set_mode(0o755);
+    std::fs::set_permissions(hook_path, perms)?;
+    Ok(())
+}
+
+/// Git for Windows runs hooks through its own sh regardless of permission
+/// bits, so there is nothing to set
+#[cfg(not(unix))]
+fn set_executable(_hook_path: &std::path::Path) -> Result<()> {
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::os::unix::fs::PermissionsExt;
 
     fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
         let dir = tempfile::tempdir().unwrap();
@@ -100,7 +419,7 @@ mod tests {
         std::fs::create_dir_all(shadow_dir.join("baselines")).unwrap();
         std::fs::create_dir_all(shadow_dir.join("stash")).unwrap();
 
-        let hooks_dir = git.git_dir.join("hooks");
+        let hooks_dir = git.hooks_dir();
         std::fs::create_dir_all(&hooks_dir).unwrap();
 
         for hook_name in HOOK_NAMES {
@@ -113,11 +432,9 @@ mod tests {
                 let backup = hooks_dir.join(format!("{}.pre-shadow", hook_name));
                 std::fs::rename(&hook_path, &backup).unwrap();
             }
-            let script = generate_hook_script(hook_name);
+            let script = hook_script_for_shell(hook_name, DEFAULT_HOOK_SHELL);
             std::fs::write(&hook_path, &script).unwrap();
-            let mut perms = std::fs::metadata(&hook_path).unwrap().permissions();
-            perms.set_mode(0o755);
-            std::fs::set_permissions(&hook_path, perms).unwrap();
+            set_executable(&hook_path).unwrap();
         }
     }
 
@@ -132,6 +449,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_install_writes_to_core_hooks_path() {
+        let (_dir, git) = make_test_repo();
+        git.config_set("core.hooksPath", ".githooks").unwrap();
+        super::install_hooks(&git, false).unwrap();
+
+        for name in HOOK_NAMES {
+            assert!(git.root.join(".githooks").join(name).exists());
+            assert!(!git.git_dir.join("hooks").join(name).exists());
+        }
+        assert!(git.missing_hooks().is_empty());
+    }
+
     #[test]
     fn test_hook_content_calls_git_shadow() {
         let (_dir, git) = make_test_repo();
@@ -149,7 +479,70 @@ mod tests {
     }
 
     #[test]
+    fn test_hook_script_is_portable_sh() {
+        for name in HOOK_NAMES {
+            let script = hook_script_for_shell(name, DEFAULT_HOOK_SHELL);
+            assert!(script.starts_with("#!/bin/sh\n"));
+            assert!(
+                !script.contains('\r'),
+                "{} should use LF line endings",
+                name
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_hook_script_parses_as_posix_sh() {
+        let dir = tempfile::tempdir().unwrap();
+        for shell in [DEFAULT_HOOK_SHELL, "/usr/bin/env bash"] {
+            let script = dir.path().join("pre-commit");
+            std::fs::write(&script, hook_script_for_shell("pre-commit", shell)).unwrap();
+            let output = std::process::Command::new("sh")
+                .arg("-n")
+                .arg(&script)
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+
+    #[test]
+    fn test_install_shell_rewrites_shebang_only() {
+        let (_dir, git) = make_test_repo();
+        let hooks_dir = git.hooks_dir();
+        std::fs::create_dir_all(&hooks_dir).unwrap();
+        let chained = hooks_dir.join("pre-commit.pre-shadow");
+        std::fs::write(&chained, "#!/bin/sh\necho existing\n").unwrap();
+        super::install_hooks(&git, false).unwrap();
+
+        super::install_hooks_with_shell(&git, false, Some("/usr/bin/env bash")).unwrap();
+        let hook = hooks_dir.join("pre-commit");
+        let content = std::fs::read_to_string(&hook).unwrap();
+        assert_eq!(
+            content,
+            hook_script_for_shell("pre-commit", "/usr/bin/env bash")
+        );
+        assert!(chained.exists());
+
+        // A plain install keeps the chosen shell
+        super::install_hooks(&git, false).unwrap();
+        assert_eq!(std::fs::read_to_string(&hook).unwrap(), content);
+        assert!(unknown_hook_lines("pre-commit", &content).is_empty());
+
+        assert!(validate_shell("/bin/bash").is_ok());
+        assert!(validate_shell("bash").is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
     fn test_hook_has_executable_permission() {
+        use std::os::unix::fs::PermissionsExt;
+
         let (_dir, git) = make_test_repo();
         install_hooks(&git);
 
@@ -203,16 +596,126 @@ mod tests {
             let hook = git.git_dir.join("hooks").join(name);
             let content = std::fs::read_to_string(&hook).unwrap();
             // Should not be double-wrapped
-            let count = content.matches("git-shadow hook").count();
+            let count = content
+                .matches(&format!("git-shadow hook {}", name))
+                .count();
             assert_eq!(count, 1, "{} should only have one git-shadow call", name);
         }
     }
 
+    #[test]
+    fn test_install_initializes_shadow_dir() {
+        let (_dir, git) = make_test_repo();
+        super::install_hooks(&git, false).unwrap();
+
+        assert!(git.shadow_dir.join("config.json").exists());
+        assert!(git.missing_hooks().is_empty());
+    }
+
+    #[test]
+    fn test_detect_competing_hooks() {
+        let (_dir, git) = make_test_repo();
+        assert!(detect_competing_hooks(&git).is_empty());
+
+        std::fs::write(git.root.join("lefthook.yml"), "").unwrap();
+        assert_eq!(detect_competing_hooks(&git), vec!["lefthook.yml"]);
+    }
+
     #[test]
     fn test_hooks_installed_returns_true_after_install() {
         let (_dir, git) = make_test_repo();
-        assert!(!git.hooks_installed());
+        assert!(!git.missing_hooks().is_empty());
         install_hooks(&git);
-        assert!(git.hooks_installed());
+        assert!(git.missing_hooks().is_empty());
+    }
+
+    #[test]
+    fn test_status_alias_does_not_overwrite_existing() {
+        let (_dir, git) = make_test_repo();
+        assert!(install_status_alias(&git).unwrap());
+        assert_eq!(git.config_get("alias.st").unwrap(), "shadow status --git");
+        // Running again is a no-op
+        assert!(install_status_alias(&git).unwrap());
+
+        git.config_set("alias.st", "status -sb").unwrap();
+        assert!(!install_status_alias(&git).unwrap());
+        assert_eq!(git.config_get("alias.st").unwrap(), "status -sb");
+    }
+
+    /// Hook script as written by git-shadow before version markers existed
+    fn v1_script(hook_name: &str) -> String {
+        format!("#!/bin/sh\n{}", hook_script_body(hook_name, 1))
+    }
+
+    #[test]
+    fn test_hook_script_version() {
+        assert_eq!(hook_script_version(&v1_script("pre-commit")), 1);
+        assert_eq!(
+            hook_script_version(&hook_script_for_shell("pre-commit", DEFAULT_HOOK_SHELL)),
+            HOOK_SCRIPT_VERSION
+        );
+        assert!(is_outdated_hook(&v1_script("pre-commit")));
+        assert!(!is_outdated_hook(&hook_script_for_shell(
+            "pre-commit",
+            DEFAULT_HOOK_SHELL
+        )));
+        // Hand-written hooks are left to their owner
+        assert!(!is_outdated_hook("#!/bin/sh\ngit-shadow hook pre-commit\n"));
+
+        // Lines of older versions are not mistaken for local edits
+        let v2 = format!(
+            "#!/bin/sh\n{}2\n{}",
+            VERSION_MARKER,
+            hook_script_body("pre-commit", 2)
+        );
+        assert!(is_outdated_hook(&v2));
+        assert!(unknown_hook_lines("pre-commit", &v2).is_empty());
+        assert!(hook_script_for_shell("pre-commit", DEFAULT_HOOK_SHELL)
+            .contains("GIT_SHADOW_FROM_HOOK=1 git-shadow hook pre-commit"));
+    }
+
+    #[test]
+    fn test_outdated_hooks_are_updated_and_chain_kept() {
+        let (_dir, git) = make_test_repo();
+        let hooks_dir = git.hooks_dir();
+        std::fs::create_dir_all(&hooks_dir).unwrap();
+        for name in HOOK_NAMES {
+            std::fs::write(hooks_dir.join(name), v1_script(name)).unwrap();
+        }
+        let chained = hooks_dir.join("pre-commit.pre-shadow");
+        std::fs::write(&chained, "#!/bin/sh\necho existing\n").unwrap();
+
+        super::install_hooks(&git, false).unwrap();
+
+        for name in HOOK_NAMES {
+            let content = std::fs::read_to_string(hooks_dir.join(name)).unwrap();
+            assert_eq!(content, hook_script_for_shell(name, DEFAULT_HOOK_SHELL));
+        }
+        assert_eq!(
+            std::fs::read_to_string(&chained).unwrap(),
+            "#!/bin/sh\necho existing\n"
+        );
+    }
+
+    #[test]
+    fn test_edited_outdated_hook_requires_force() {
+        let (_dir, git) = make_test_repo();
+        let hooks_dir = git.hooks_dir();
+        std::fs::create_dir_all(&hooks_dir).unwrap();
+        let hook = hooks_dir.join("pre-commit");
+        let edited = format!("{}echo custom\n", v1_script("pre-commit"));
+        std::fs::write(&hook, &edited).unwrap();
+
+        let err = super::install_hooks(&git, false).unwrap_err();
+        assert!(err.to_string().contains("pre-commit"));
+        assert_eq!(std::fs::read_to_string(&hook).unwrap(), edited);
+        // The other hooks were still installed
+        assert!(hooks_dir.join("post-commit").exists());
+
+        super::install_hooks(&git, true).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&hook).unwrap(),
+            hook_script_for_shell("pre-commit", DEFAULT_HOOK_SHELL)
+        );
     }
 }
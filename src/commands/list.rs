@@ -0,0 +1,90 @@
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::cli::FileTypeArg;
+use crate::config::{FileType, ShadowConfig};
+use crate::git::GitRepo;
+
+pub fn run(file_type: Option<FileTypeArg>, null: bool) -> Result<()> {
+    let git = GitRepo::discover(&std::env::current_dir()?)?;
+    // A missing config (shadow not initialized) loads as empty
+    let config = ShadowConfig::load(&git.shadow_dir)?;
+
+    let separator = if null { '\0' } else { '\n' };
+    let mut out = std::io::stdout().lock();
+    for path in list_paths(&config, file_type) {
+        write!(out, "{}{}", path, separator)?;
+    }
+    out.flush()?;
+
+    Ok(())
+}
+
+fn list_paths(config: &ShadowConfig, file_type: Option<FileTypeArg>) -> Vec<&str> {
+    config
+        .files
+        .iter()
+        .filter(|(_, entry)| match file_type {
+            Some(FileTypeArg::Overlay) => entry.file_type == FileType::Overlay,
+            Some(FileTypeArg::Phantom) => entry.file_type == FileType::Phantom,
+            None => true,
+        })
+        .map(|(path, _)| path.as_str())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ExcludeMode;
+
+    fn make_config() -> ShadowConfig {
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), "abc1234".to_string())
+            .unwrap();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+        config
+            .add_phantom(".claude".to_string(), ExcludeMode::GitInfoExclude, true)
+            .unwrap();
+        config
+    }
+
+    #[test]
+    fn test_list_all() {
+        let config = make_config();
+        assert_eq!(
+            list_paths(&config, None),
+            vec![".claude", "CLAUDE.md", "local.md"]
+        );
+    }
+
+    #[test]
+    fn test_list_overlay_only() {
+        let config = make_config();
+        assert_eq!(
+            list_paths(&config, Some(FileTypeArg::Overlay)),
+            vec!["CLAUDE.md"]
+        );
+    }
+
+    #[test]
+    fn test_list_phantom_only() {
+        let config = make_config();
+        assert_eq!(
+            list_paths(&config, Some(FileTypeArg::Phantom)),
+            vec![".claude", "local.md"]
+        );
+    }
+
+    #[test]
+    fn test_list_empty_config() {
+        let dir = tempfile::tempdir().unwrap();
+        // Uninitialized shadow dir loads as an empty config
+        let config = ShadowConfig::load(&dir.path().join("shadow")).unwrap();
+        assert!(list_paths(&config, None).is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::Result;
+
+use crate::git::{short_hash, GitRepo};
+use crate::history::{self, HistoryEntry};
+use crate::path;
+
+pub fn run(file: Option<&str>) -> Result<()> {
+    let git = GitRepo::discover(&std::env::current_dir()?)?;
+    let filter = file
+        .map(|f| path::normalize_path(f, &git.prefix, &git.root))
+        .transpose()?;
+
+    let entries = history::read(&git.shadow_dir)?;
+    let selected = select_entries(&entries, filter.as_deref());
+
+    if selected.is_empty() {
+        println!("no history");
+        return Ok(());
+    }
+
+    for entry in selected {
+        println!("{}", format_entry(entry));
+    }
+
+    Ok(())
+}
+
+/// Entries newest first, optionally limited to one path
+fn select_entries<'a>(entries: &'a [HistoryEntry], file: Option<&str>) -> Vec<&'a HistoryEntry> {
+    entries
+        .iter()
+        .rev()
+        .filter(|e| file.is_none() || e.path.as_deref() == file)
+        .collect()
+}
+
+fn format_entry(entry: &HistoryEntry) -> String {
+    let mut line = format!(
+        "{}  {:<8}",
+        entry.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        entry.action.to_string()
+    );
+    if let Some(path) = &entry.path {
+        line.push_str(&format!(" {}", path));
+    }
+    if let Some(commit) = &entry.commit {
+        line.push_str(&format!(" ({})", short_hash(commit)));
+    }
+    line.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::Action;
+    use chrono::TimeZone;
+
+    fn entry(action: Action, path: Option<&str>, commit: Option<&str>) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: chrono::Utc.with_ymd_and_hms(2026, 1, 2, 3, 4, 5).unwrap(),
+            action,
+            path: path.map(str::to_string),
+            commit: commit.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_select_entries_newest_first_and_filtered() {
+        let entries = vec![
+            entry(Action::Add, Some("a.md"), None),
+            entry(Action::Add, Some("b.md"), None),
+            entry(Action::Remove, Some("a.md"), None),
+        ];
+
+        let all = select_entries(&entries, None);
+        assert_eq!(all[0].action, Action::Remove);
+        assert_eq!(all.len(), 3);
+
+        let only_a = select_entries(&entries, Some("a.md"));
+        assert_eq!(only_a.len(), 2);
+        assert!(only_a.iter().all(|e| e.path.as_deref() == Some("a.md")));
+    }
+
+    #[test]
+    fn test_format_entry() {
+        let line = format_entry(&entry(
+            Action::Rebase,
+            Some("CLAUDE.md"),
+            Some("abc1234def5678"),
+        ));
+        assert_eq!(line, "2026-01-02 03:04:05  rebase   CLAUDE.md (abc1234)");
+
+        let line = format_entry(&entry(Action::Suspend, None, None));
+        assert_eq!(line, "2026-01-02 03:04:05  suspend");
+    }
+}
@@ -1,11 +1,21 @@
 pub mod add;
+pub mod clean;
 pub mod diff;
 pub mod doctor;
+pub mod export;
 pub mod hook;
+pub mod import;
+pub mod init;
 pub mod install;
+pub mod list;
+pub mod log;
+pub mod note;
 pub mod rebase;
 pub mod remove;
 pub mod restore;
 pub mod resume;
+pub mod snapshot;
 pub mod status;
 pub mod suspend;
+pub mod uninstall;
+pub mod verify;
@@ -0,0 +1,69 @@
+use anyhow::Result;
+
+use crate::config::ShadowConfig;
+use crate::error::ShadowError;
+use crate::git::GitRepo;
+use crate::path;
+
+pub fn run(file: &str, text: Option<&str>, clear: bool) -> Result<()> {
+    let git = GitRepo::discover(&std::env::current_dir()?)?;
+    let normalized = path::normalize_path(file, &git.prefix, &git.root)?;
+    let mut config = ShadowConfig::load(&git.shadow_dir)?;
+
+    if text.is_some() || clear {
+        set_note(&mut config, &normalized, text.filter(|_| !clear))?;
+        config.save(&git.shadow_dir)?;
+    }
+
+    let entry = config
+        .get(&normalized)
+        .ok_or_else(|| ShadowError::NotManaged(normalized.clone()))?;
+    match &entry.note {
+        Some(note) => println!("{}: {}", normalized, note),
+        None => println!("{}: no note", normalized),
+    }
+    Ok(())
+}
+
+/// Replace the note of a managed file; blank text removes it
+fn set_note(config: &mut ShadowConfig, file_path: &str, note: Option<&str>) -> Result<()> {
+    let entry = config
+        .files
+        .get_mut(file_path)
+        .ok_or_else(|| ShadowError::NotManaged(file_path.to_string()))?;
+    entry.note = note
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ExcludeMode;
+
+    #[test]
+    fn test_set_and_clear_note() {
+        let mut config = ShadowConfig::new();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+
+        set_note(&mut config, "local.md", Some("local API key")).unwrap();
+        assert_eq!(
+            config.get("local.md").unwrap().note.as_deref(),
+            Some("local API key")
+        );
+
+        set_note(&mut config, "local.md", Some("  ")).unwrap();
+        assert!(config.get("local.md").unwrap().note.is_none());
+    }
+
+    #[test]
+    fn test_set_note_on_unmanaged_file_fails() {
+        let mut config = ShadowConfig::new();
+        let err = set_note(&mut config, "other.md", Some("x")).unwrap_err();
+        assert!(err.to_string().contains("not managed"));
+    }
+}
@@ -1,21 +1,47 @@
-use anyhow::{bail, Result};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
+use is_terminal::IsTerminal;
 
-use crate::config::{FileType, ShadowConfig};
+use crate::baseline;
+use crate::config::{FileEntry, FileType, ShadowConfig};
+use crate::diff_util::diff_stats;
 use crate::error::ShadowError;
-use crate::fs_util;
-use crate::git::GitRepo;
+use crate::git::{short_hash, GitRepo};
+use crate::history::{self, Action};
 use crate::merge;
 use crate::path;
 
-pub fn run(file: Option<&str>) -> Result<()> {
+/// Result of rebasing a single overlay file
+#[derive(Debug, PartialEq)]
+pub(crate) enum RebaseOutcome {
+    Updated,
+    Unchanged,
+    Conflict,
+}
+
+/// Per-outcome counts for a multi-file rebase
+#[derive(Debug, Default)]
+struct RebaseSummary {
+    updated: usize,
+    unchanged: usize,
+    conflicted: usize,
+    failed: usize,
+}
+
+pub fn run(
+    file: Option<&str>,
+    all: bool,
+    set: Option<&str>,
+    adopt: bool,
+    yes: bool,
+    interactive: bool,
+    strategy: merge::MergeStrategy,
+) -> Result<()> {
     let git = GitRepo::discover(&std::env::current_dir()?)?;
     let mut config = ShadowConfig::load(&git.shadow_dir)?;
 
-    if config.suspended {
-        return Err(ShadowError::Suspended.into());
-    }
-
     let head = git.head_commit()?;
 
     if config.files.is_empty() {
@@ -23,55 +49,570 @@ pub fn run(file: Option<&str>) -> Result<()> {
         return Ok(());
     }
 
-    let mut found = false;
+    let targets: Vec<String> = match file {
+        Some(target) => {
+            let normalized = path::normalize_path(target, &git.prefix, &git.root)?;
+            match config.get(&normalized) {
+                Some(entry) if entry.suspended => return Err(ShadowError::Suspended.into()),
+                Some(entry) if entry.file_type == FileType::Overlay => vec![normalized],
+                _ => bail!("{} is not managed as overlay", target),
+            }
+        }
+        None => {
+            // Suspended overlays are skipped; rebase them after `resume`
+            let (suspended, overlays): (Vec<_>, Vec<_>) = config
+                .files
+                .iter()
+                .filter(|(_, e)| e.file_type == FileType::Overlay)
+                .partition(|(_, e)| e.suspended);
+            let overlays: Vec<String> = overlays.into_iter().map(|(p, _)| p.clone()).collect();
+            if overlays.is_empty() {
+                if !suspended.is_empty() {
+                    return Err(ShadowError::Suspended.into());
+                }
+                println!("no overlay files found");
+                return Ok(());
+            }
+            if !suspended.is_empty() {
+                eprintln!(
+                    "{}",
+                    format!("skipping {} suspended file(s)", suspended.len()).yellow()
+                );
+            }
+            if !all && !confirm_rebase_all(overlays.len())? {
+                println!("aborted");
+                return Ok(());
+            }
+            overlays
+        }
+    };
 
-    let file_paths: Vec<String> = config.files.keys().cloned().collect();
-    for file_path in &file_paths {
-        let entry = config.files.get(file_path).unwrap();
+    if let Some(reference) = set {
+        let commit = git
+            .resolve_commit(reference)
+            .with_context(|| format!("failed to resolve --set {}", reference))?;
+        let summary = set_baselines(&git, &mut config, &targets, &commit);
+        config.save(&git.shadow_dir)?;
+
+        if targets.len() > 1 {
+            println!(
+                "summary: {} updated, {} unchanged, {} failed",
+                summary.updated, summary.unchanged, summary.failed
+            );
+        }
+        println!("run `git-shadow status` to check the shadow changes against the new baseline");
+        if summary.failed > 0 {
+            bail!("failed to set baseline for {} file(s)", summary.failed);
+        }
+        return Ok(());
+    }
 
-        if entry.file_type != FileType::Overlay {
-            continue;
+    if adopt {
+        let changes = shadow_changes(&git, &config, &targets)?;
+        if !confirm_adopt(&changes, yes)? {
+            println!("aborted");
+            return Ok(());
         }
+        let summary = adopt_baselines(&git, &mut config, &targets, &head);
+        config.save(&git.shadow_dir)?;
 
-        if let Some(target) = file {
-            let normalized = path::normalize_path(target, &git.root)?;
-            if *file_path != normalized {
-                continue;
-            }
+        if targets.len() > 1 {
+            println!(
+                "summary: {} updated, {} unchanged, {} failed",
+                summary.updated, summary.unchanged, summary.failed
+            );
+        }
+        if summary.failed > 0 {
+            bail!("failed to adopt {} file(s)", summary.failed);
+        }
+        return Ok(());
+    }
+
+    let interactive = interactive && {
+        let terminal = std::io::stdin().is_terminal();
+        if !terminal {
+            eprintln!(
+                "{}",
+                "warning: --interactive is ignored in non-interactive mode".yellow()
+            );
         }
-        found = true;
+        terminal
+    };
+    let summary = rebase_files(&git, &mut config, &targets, &head, interactive, strategy);
+    config.save(&git.shadow_dir)?;
+
+    if targets.len() > 1 {
+        println!(
+            "summary: {} updated, {} unchanged, {} conflicted, {} failed",
+            summary.updated, summary.unchanged, summary.conflicted, summary.failed
+        );
+    }
+
+    if summary.failed > 0 {
+        bail!("failed to rebase {} file(s)", summary.failed);
+    }
+
+    Ok(())
+}
 
-        rebase_file(&git, &mut config, file_path, &head)?;
+/// Ask before rebasing every overlay when neither a file nor `--all` was given
+fn confirm_rebase_all(count: usize) -> Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        bail!("specify a file or use --all to rebase all overlay files");
     }
 
-    if !found {
-        if let Some(target) = file {
-            bail!("{} is not managed as overlay", target);
+    eprintln!("Rebase all {} overlay file(s)? [y/N]", count);
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let input = input.trim().to_lowercase();
+    Ok(input == "y" || input == "yes")
+}
+
+/// Rebase each file independently, continuing past errors. With
+/// `interactive`, each text conflict is resolved, skipped or rolled back on
+/// the spot; aborting stops before the remaining files.
+fn rebase_files(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    targets: &[String],
+    head: &str,
+    interactive: bool,
+    strategy: merge::MergeStrategy,
+) -> RebaseSummary {
+    let mut summary = RebaseSummary::default();
+
+    for (i, file_path) in targets.iter().enumerate() {
+        let snapshot = if interactive && !config.get(file_path).is_some_and(|e| e.is_binary) {
+            match Snapshot::take(git, config, file_path) {
+                Ok(snapshot) => Some(snapshot),
+                Err(e) => {
+                    eprintln!("{}", format!("error: {}: {}", file_path, e).red());
+                    summary.failed += 1;
+                    continue;
+                }
+            }
         } else {
-            println!("no overlay files found");
+            None
+        };
+
+        let outcome =
+            rebase_file(git, config, file_path, head, strategy).and_then(|outcome| {
+                match (&outcome, &snapshot) {
+                    (RebaseOutcome::Conflict, Some(snapshot)) => resolve_conflict(
+                        git,
+                        config,
+                        file_path,
+                        snapshot,
+                        prompt_conflict_action,
+                        |path| run_editor(git, path),
+                    ),
+                    _ => Ok(Resolution::Done(outcome)),
+                }
+            });
+        match outcome {
+            Ok(Resolution::Done(RebaseOutcome::Updated)) => {
+                history::record(&git.shadow_dir, Action::Rebase, Some(file_path), Some(head));
+                summary.updated += 1;
+            }
+            Ok(Resolution::Done(RebaseOutcome::Unchanged)) => summary.unchanged += 1,
+            Ok(Resolution::Done(RebaseOutcome::Conflict)) => {
+                history::record(&git.shadow_dir, Action::Rebase, Some(file_path), Some(head));
+                summary.conflicted += 1;
+            }
+            Ok(Resolution::Aborted) => {
+                let remaining = targets.len() - i - 1;
+                if remaining > 0 {
+                    eprintln!(
+                        "{}",
+                        format!("rebase aborted; {} file(s) not rebased", remaining).yellow()
+                    );
+                }
+                break;
+            }
+            Err(e) => {
+                eprintln!("{}", format!("error: {}: {}", file_path, e).red());
+                summary.failed += 1;
+            }
         }
     }
 
-    config.save(&git.shadow_dir)?;
+    summary
+}
+
+/// A file as it was before `rebase --interactive` touched it
+struct Snapshot {
+    entry: FileEntry,
+    baseline: Vec<u8>,
+    worktree: Vec<u8>,
+}
 
+impl Snapshot {
+    fn take(git: &GitRepo, config: &ShadowConfig, file_path: &str) -> Result<Self> {
+        let entry = config
+            .get(file_path)
+            .cloned()
+            .with_context(|| format!("{} is not managed", file_path))?;
+        let baseline = baseline::read(git, file_path, entry.baseline_blob.as_deref())?;
+        let worktree = std::fs::read(git.root.join(file_path))
+            .with_context(|| format!("failed to read {}", file_path))?;
+        Ok(Self {
+            entry,
+            baseline,
+            worktree,
+        })
+    }
+
+    /// Put the baseline, config entry and working tree back, and drop the
+    /// conflict backup
+    fn restore(&self, git: &GitRepo, config: &mut ShadowConfig, file_path: &str) -> Result<()> {
+        let blob = baseline::write(git, file_path, &self.baseline)?;
+        let mut entry = self.entry.clone();
+        entry.baseline_blob = blob;
+        config.files.insert(file_path.to_string(), entry);
+        std::fs::write(git.root.join(file_path), &self.worktree)
+            .with_context(|| format!("failed to restore {}", file_path))?;
+        let backup = git.root.join(merge::orig_path(file_path));
+        if backup.exists() {
+            std::fs::remove_file(&backup)?;
+        }
+        Ok(())
+    }
+}
+
+/// What the user chose for a conflicted file in `rebase --interactive`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ConflictAction {
+    Edit,
+    Skip,
+    Abort,
+}
+
+/// Result of a rebase step after any interactive resolution
+#[derive(Debug, PartialEq)]
+enum Resolution {
+    Done(RebaseOutcome),
+    /// The file was restored to its pre-rebase state and the rebase stops
+    Aborted,
+}
+
+/// Let the user resolve a conflict left by `rebase_file`: edit until no
+/// conflict markers remain, leave the markers for later, or restore
+/// `snapshot`. `choose` and `edit` are the prompt and the editor.
+fn resolve_conflict(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    file_path: &str,
+    snapshot: &Snapshot,
+    mut choose: impl FnMut(&str) -> Result<ConflictAction>,
+    mut edit: impl FnMut(&Path) -> Result<()>,
+) -> Result<Resolution> {
+    let worktree_path = git.root.join(file_path);
+    loop {
+        match choose(file_path)? {
+            ConflictAction::Edit => {
+                if let Err(e) = edit(&worktree_path) {
+                    eprintln!("{}", format!("warning: {}", e).yellow());
+                    continue;
+                }
+                let content = std::fs::read_to_string(&worktree_path)
+                    .with_context(|| format!("failed to read {}", file_path))?;
+                if merge::has_conflict_markers(&content) {
+                    eprintln!(
+                        "{}",
+                        format!("warning: {} still has conflict markers", file_path).yellow()
+                    );
+                    continue;
+                }
+                let backup = git.root.join(merge::orig_path(file_path));
+                if backup.exists() {
+                    std::fs::remove_file(&backup)?;
+                }
+                println!("{}", format!("resolved conflicts in {}", file_path).green());
+                return Ok(Resolution::Done(RebaseOutcome::Updated));
+            }
+            ConflictAction::Skip => return Ok(Resolution::Done(RebaseOutcome::Conflict)),
+            ConflictAction::Abort => {
+                snapshot.restore(git, config, file_path)?;
+                println!("{}: restored to its state before the rebase", file_path);
+                return Ok(Resolution::Aborted);
+            }
+        }
+    }
+}
+
+/// Ask what to do with a conflicted file. End of input skips it.
+fn prompt_conflict_action(file_path: &str) -> Result<ConflictAction> {
+    loop {
+        eprintln!(
+            "{}: conflict. [e]dit in editor / [s]kip / [a]bort?",
+            file_path
+        );
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input)? == 0 {
+            return Ok(ConflictAction::Skip);
+        }
+        match input.trim().to_lowercase().as_str() {
+            "e" | "edit" => return Ok(ConflictAction::Edit),
+            "s" | "skip" => return Ok(ConflictAction::Skip),
+            "a" | "abort" => return Ok(ConflictAction::Abort),
+            _ => {}
+        }
+    }
+}
+
+/// Open `path` in the editor the way git does, so editor settings with
+/// arguments (`code --wait`) work
+fn run_editor(git: &GitRepo, path: &Path) -> Result<()> {
+    let editor = git.editor();
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(&editor)
+        .arg(path)
+        .status()
+        .with_context(|| format!("failed to start editor '{}'", editor))?;
+    if !status.success() {
+        bail!("editor '{}' exited with {}", editor, status);
+    }
     Ok(())
 }
 
+/// `rebase --set`: replace each baseline with its content at `commit`
+fn set_baselines(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    targets: &[String],
+    commit: &str,
+) -> RebaseSummary {
+    let mut summary = RebaseSummary::default();
+
+    for file_path in targets {
+        match set_baseline(git, config, file_path, commit) {
+            Ok(RebaseOutcome::Unchanged) => summary.unchanged += 1,
+            Ok(_) => {
+                history::record(
+                    &git.shadow_dir,
+                    Action::Rebase,
+                    Some(file_path),
+                    Some(commit),
+                );
+                summary.updated += 1;
+            }
+            Err(e) => {
+                eprintln!("{}", format!("error: {}: {}", file_path, e).red());
+                summary.failed += 1;
+            }
+        }
+    }
+
+    summary
+}
+
+/// Overwrite the baseline with the file at `commit` without merging. The
+/// working tree is left alone, so its shadow changes are now measured
+/// against the new baseline.
+fn set_baseline(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    file_path: &str,
+    commit: &str,
+) -> Result<RebaseOutcome> {
+    let blob = config.baseline_blob(file_path);
+
+    let Ok(new_baseline) = git.show_file(commit, file_path) else {
+        bail!("{} does not exist in {}", file_path, short_hash(commit));
+    };
+    let old_baseline = baseline::read(git, file_path, blob.as_deref()).unwrap_or_default();
+
+    if let Some(entry) = config.files.get_mut(file_path) {
+        entry.baseline_commit = Some(commit.to_string());
+    }
+
+    if old_baseline == new_baseline {
+        println!(
+            "{}: baseline content unchanged (commit ref updated)",
+            file_path
+        );
+        return Ok(RebaseOutcome::Unchanged);
+    }
+
+    let blob = baseline::write(git, file_path, &new_baseline)?;
+    config.set_baseline_blob(file_path, blob);
+
+    let short = short_hash(commit);
+    let is_binary = config.get(file_path).is_some_and(|e| e.is_binary);
+    match std::fs::read(git.root.join(file_path)) {
+        Ok(current) if !is_binary => {
+            let (added, removed) = diff_stats(
+                &String::from_utf8_lossy(&new_baseline),
+                &String::from_utf8_lossy(&current),
+            );
+            println!(
+                "{}",
+                format!(
+                    "baseline for {} set to {} (shadow changes: +{} lines / -{} lines)",
+                    file_path, short, added, removed
+                )
+                .green()
+            );
+        }
+        _ => println!(
+            "{}",
+            format!("baseline for {} set to {}", file_path, short).green()
+        ),
+    }
+
+    Ok(RebaseOutcome::Updated)
+}
+
+/// Size of each target's shadow changes (None when there are none), for the
+/// `--adopt` confirmation
+fn shadow_changes(
+    git: &GitRepo,
+    config: &ShadowConfig,
+    targets: &[String],
+) -> Result<Vec<(String, Option<String>)>> {
+    let mut changes = Vec::new();
+    for file_path in targets {
+        let current = std::fs::read(git.root.join(file_path))
+            .with_context(|| format!("failed to read {}", file_path))?;
+        let blob = config.baseline_blob(file_path);
+        let old_baseline = baseline::read(git, file_path, blob.as_deref()).unwrap_or_default();
+        let change = if current == old_baseline {
+            None
+        } else if config.get(file_path).is_some_and(|e| e.is_binary) {
+            Some("binary content differs".to_string())
+        } else {
+            let (added, removed) = diff_stats(
+                &String::from_utf8_lossy(&old_baseline),
+                &String::from_utf8_lossy(&current),
+            );
+            Some(format!("+{} lines / -{} lines", added, removed))
+        };
+        changes.push((file_path.clone(), change));
+    }
+    Ok(changes)
+}
+
+/// Show the shadow changes `--adopt` resets and ask before going on. Nothing
+/// is asked when there are none.
+fn confirm_adopt(changes: &[(String, Option<String>)], yes: bool) -> Result<bool> {
+    let pending: Vec<_> = changes
+        .iter()
+        .filter_map(|(file_path, change)| change.as_ref().map(|c| (file_path, c)))
+        .collect();
+    if pending.is_empty() || yes {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        bail!("--yes is required in non-interactive mode");
+    }
+
+    eprintln!("These shadow changes become part of the baseline:");
+    for (file_path, change) in &pending {
+        eprintln!("  {} ({})", file_path, change);
+    }
+    eprintln!("Adopt the working tree as the new baseline? [y/N]");
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let input = input.trim().to_lowercase();
+    Ok(input == "y" || input == "yes")
+}
+
+/// `rebase --adopt`: store each working tree file as its baseline at `head`
+fn adopt_baselines(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    targets: &[String],
+    head: &str,
+) -> RebaseSummary {
+    let mut summary = RebaseSummary::default();
+
+    for file_path in targets {
+        match adopt_baseline(git, config, file_path, head) {
+            Ok(RebaseOutcome::Unchanged) => summary.unchanged += 1,
+            Ok(_) => {
+                history::record(&git.shadow_dir, Action::Rebase, Some(file_path), Some(head));
+                summary.updated += 1;
+            }
+            Err(e) => {
+                eprintln!("{}", format!("error: {}: {}", file_path, e).red());
+                summary.failed += 1;
+            }
+        }
+    }
+
+    summary
+}
+
+/// Save the working tree content as the baseline without merging, so the
+/// file has no shadow changes afterwards
+fn adopt_baseline(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    file_path: &str,
+    head: &str,
+) -> Result<RebaseOutcome> {
+    let current = std::fs::read(git.root.join(file_path))
+        .with_context(|| format!("failed to read {}", file_path))?;
+    let blob = config.baseline_blob(file_path);
+    let old_baseline = baseline::read(git, file_path, blob.as_deref()).unwrap_or_default();
+
+    if let Some(entry) = config.files.get_mut(file_path) {
+        entry.baseline_commit = Some(head.to_string());
+    }
+
+    if old_baseline == current {
+        println!("{}: no shadow changes (commit ref updated)", file_path);
+        return Ok(RebaseOutcome::Unchanged);
+    }
+
+    let blob = baseline::write(git, file_path, &current)?;
+    config.set_baseline_blob(file_path, blob);
+    println!(
+        "{}",
+        format!("adopted the working tree of {} as its baseline", file_path).green()
+    );
+    // The baseline is what pre-commit commits
+    if git
+        .show_file(head, file_path)
+        .map_or(true, |h| h != current)
+    {
+        eprintln!(
+            "{}",
+            format!(
+                "warning: {} differs from HEAD; the next commit that includes it commits this content",
+                file_path
+            )
+            .yellow()
+        );
+    }
+
+    Ok(RebaseOutcome::Updated)
+}
+
+/// 3-way merge the shadow changes of `file_path` onto HEAD. `strategy`
+/// settles conflicting regions (`--ours` / `--theirs`) instead of leaving
+/// markers.
 pub(crate) fn rebase_file(
     git: &GitRepo,
     config: &mut ShadowConfig,
     file_path: &str,
     new_head: &str,
-) -> Result<()> {
-    let encoded = path::encode_path(file_path);
-    let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
+    strategy: merge::MergeStrategy,
+) -> Result<RebaseOutcome> {
     let worktree_path = git.root.join(file_path);
 
+    if config.get(file_path).is_some_and(|e| e.is_binary) {
+        return rebase_binary_file(git, config, file_path, new_head, strategy);
+    }
+
     // 1. Read current content (baseline + shadow changes)
     let current_content = std::fs::read_to_string(&worktree_path)?;
 
     // 2. Read old baseline
-    let old_baseline = std::fs::read_to_string(&baseline_path)?;
+    let old_baseline =
+        baseline::read_to_string(git, file_path, config.baseline_blob(file_path).as_deref())?;
 
     // 3. Get new HEAD content
     let new_baseline = match git.show_file("HEAD", file_path) {
@@ -94,7 +635,7 @@ pub(crate) fn rebase_file(
             "{}: baseline content unchanged (commit ref updated)",
             file_path
         );
-        return Ok(());
+        return Ok(RebaseOutcome::Unchanged);
     }
 
     // 4. 3-way merge: old_baseline (base), current_content (ours), new_baseline (theirs)
@@ -102,14 +643,16 @@ pub(crate) fn rebase_file(
         &old_baseline,
         &current_content,
         &new_baseline,
-        &git.shadow_dir,
-    )?;
+        merge::ConflictStyle::resolve(git)?,
+        strategy,
+    );
 
     // 5. Write merged content to working tree
     std::fs::write(&worktree_path, &merge_result.content)?;
 
     // 6. Update baseline
-    fs_util::atomic_write(&baseline_path, new_baseline.as_bytes())?;
+    let blob = baseline::write(git, file_path, new_baseline.as_bytes())?;
+    config.set_baseline_blob(file_path, blob);
 
     // 7. Update config
     if let Some(entry) = config.files.get_mut(file_path) {
@@ -117,25 +660,100 @@ pub(crate) fn rebase_file(
     }
 
     if merge_result.has_conflicts {
+        let backup = merge::write_orig(git, file_path, current_content.as_bytes())?;
         eprintln!(
             "{}",
             format!(
-                "warning: conflicts detected in {}. Please resolve manually",
-                file_path
+                "warning: conflicts detected in {}. Please resolve manually (your version is saved in {}; delete it when done)",
+                file_path, backup
             )
             .yellow()
         );
+        Ok(RebaseOutcome::Conflict)
+    } else if merge_result.resolved > 0 {
+        println!(
+            "{}",
+            format!(
+                "baseline updated for {} ({} conflict(s) resolved with {})",
+                file_path,
+                merge_result.resolved,
+                strategy.side()
+            )
+            .green()
+        );
+        Ok(RebaseOutcome::Updated)
     } else {
         println!("{}", format!("baseline updated for {}", file_path).green());
+        Ok(RebaseOutcome::Updated)
     }
+}
 
-    Ok(())
+/// Binary overlays cannot be merged. When HEAD changed, the baseline moves to
+/// HEAD, the working tree keeps the shadow version, and the result is reported
+/// as a conflict for the user to resolve by hand. With `--ours` / `--theirs`
+/// the chosen version is kept without a conflict.
+fn rebase_binary_file(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    file_path: &str,
+    new_head: &str,
+    strategy: merge::MergeStrategy,
+) -> Result<RebaseOutcome> {
+    let blob = config.baseline_blob(file_path);
+    let old_baseline = baseline::read(git, file_path, blob.as_deref())?;
+    let Ok(new_baseline) = git.show_file("HEAD", file_path) else {
+        bail!(
+            "{} does not exist in HEAD. The file may have been deleted",
+            file_path
+        );
+    };
+
+    if let Some(entry) = config.files.get_mut(file_path) {
+        entry.baseline_commit = Some(new_head.to_string());
+    }
+
+    if old_baseline == new_baseline {
+        println!(
+            "{}: baseline content unchanged (commit ref updated)",
+            file_path
+        );
+        return Ok(RebaseOutcome::Unchanged);
+    }
+
+    let blob = baseline::write(git, file_path, &new_baseline)?;
+    config.set_baseline_blob(file_path, blob);
+    if strategy == merge::MergeStrategy::Theirs {
+        std::fs::write(git.root.join(file_path), &new_baseline)
+            .with_context(|| format!("failed to write {}", file_path))?;
+    }
+    if strategy != merge::MergeStrategy::Conflict {
+        println!(
+            "{}",
+            format!(
+                "baseline updated for {} (binary, kept {})",
+                file_path,
+                strategy.side()
+            )
+            .green()
+        );
+        return Ok(RebaseOutcome::Updated);
+    }
+    eprintln!(
+        "{}",
+        format!(
+            "warning: {} is a binary overlay and cannot be merged. Your version was kept; compare it with `git show HEAD:{}` and resolve manually",
+            file_path, file_path
+        )
+        .yellow()
+    );
+    Ok(RebaseOutcome::Conflict)
 }
 
 #[cfg(test)]
 mod tests {
     use crate::config::ShadowConfig;
     use crate::git::GitRepo;
+    use crate::merge::MergeStrategy;
     use crate::{fs_util, merge, path};
 
     fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
@@ -280,7 +898,13 @@ mod tests {
         // Upstream also changes the same line
         let theirs = "# Their Team\n";
 
-        let result = merge::three_way_merge(old_baseline, ours, theirs, &git.shadow_dir).unwrap();
+        let result = merge::three_way_merge(
+            old_baseline,
+            ours,
+            theirs,
+            merge::ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
         assert!(result.has_conflicts);
         assert!(result.content.contains("<<<<<<<"));
 
@@ -290,8 +914,6 @@ mod tests {
 
     #[test]
     fn test_rebase_preserves_shadow_changes() {
-        let (_dir, git) = make_test_repo();
-
         // Base: "line1\nline2\nline3\n"
         // Ours (shadow): "line1\nline2\nline3\nmy addition\n"
         // Theirs (new HEAD): "line1\nline2 updated\nline3\n"
@@ -301,7 +923,13 @@ mod tests {
         let ours = "line1\nline2\nline3\nmy addition\n";
         let theirs = "line1\nline2 updated\nline3\n";
 
-        let result = merge::three_way_merge(base, ours, theirs, &git.shadow_dir).unwrap();
+        let result = merge::three_way_merge(
+            base,
+            ours,
+            theirs,
+            merge::ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
         assert!(!result.has_conflicts);
         assert!(result.content.contains("line2 updated"));
         assert!(result.content.contains("my addition"));
@@ -350,7 +978,14 @@ mod tests {
         std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# My shadow\n").unwrap();
 
         // Rebase should detect content is unchanged but update baseline_commit
-        super::rebase_file(&git, &mut config, "CLAUDE.md", &new_head).unwrap();
+        super::rebase_file(
+            &git,
+            &mut config,
+            "CLAUDE.md",
+            &new_head,
+            MergeStrategy::Conflict,
+        )
+        .unwrap();
 
         // Verify baseline_commit was updated to new HEAD
         let entry = config.get("CLAUDE.md").unwrap();
@@ -385,9 +1020,9 @@ mod tests {
             &old_baseline,
             &current_content,
             &new_baseline,
-            &git.shadow_dir,
-        )
-        .unwrap();
+            merge::ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
 
         std::fs::write(&worktree_path, &merge_result.content).unwrap();
         fs_util::atomic_write(&baseline_path, new_baseline.as_bytes()).unwrap();
@@ -396,4 +1031,433 @@ mod tests {
             entry.baseline_commit = Some(new_head.to_string());
         }
     }
+
+    #[test]
+    fn test_rebase_files_continues_after_error() {
+        let (_dir, git) = make_test_repo();
+        let commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+
+        // CLAUDE.md: valid overlay with unchanged baseline
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), commit.clone())
+            .unwrap();
+
+        // gone.md: registered but missing from HEAD and worktree
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("gone.md")),
+            b"x\n",
+        )
+        .unwrap();
+        config
+            .add_overlay("gone.md".to_string(), commit.clone())
+            .unwrap();
+
+        let targets = vec!["gone.md".to_string(), "CLAUDE.md".to_string()];
+        let summary = super::rebase_files(
+            &git,
+            &mut config,
+            &targets,
+            &commit,
+            false,
+            MergeStrategy::Conflict,
+        );
+
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.unchanged, 1);
+        assert_eq!(summary.updated, 0);
+        assert_eq!(summary.conflicted, 0);
+    }
+
+    #[test]
+    fn test_rebase_file_reports_conflict() {
+        let (_dir, git) = make_test_repo();
+        let old_commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), old_commit)
+            .unwrap();
+
+        std::fs::write(git.root.join("CLAUDE.md"), "# Their Team\n").unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-am", "upstream"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        let new_head = git.head_commit().unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# My Team\n").unwrap();
+
+        let outcome = super::rebase_file(
+            &git,
+            &mut config,
+            "CLAUDE.md",
+            &new_head,
+            MergeStrategy::Conflict,
+        )
+        .unwrap();
+        assert_eq!(outcome, super::RebaseOutcome::Conflict);
+
+        // Pre-merge content is kept next to the file and excluded from git
+        let backup = std::fs::read_to_string(git.root.join("CLAUDE.md.orig")).unwrap();
+        assert_eq!(backup, "# My Team\n");
+        let entries = crate::exclude::ExcludeManager::new(&git.common_dir)
+            .list_entries()
+            .unwrap();
+        assert!(entries.contains(&"CLAUDE.md.orig".to_string()));
+    }
+
+    /// Register CLAUDE.md and make upstream and the shadow change its first
+    /// line differently. Returns the new HEAD.
+    fn setup_conflict(git: &GitRepo, config: &mut ShadowConfig) -> String {
+        let old_commit = git.head_commit().unwrap();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), old_commit)
+            .unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Their Team\n").unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-am", "upstream"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# My Team\n").unwrap();
+        git.head_commit().unwrap()
+    }
+
+    #[test]
+    fn test_strategy_resolves_conflicts_without_markers() {
+        for (strategy, expected) in [
+            (MergeStrategy::Ours, "# My Team\n"),
+            (MergeStrategy::Theirs, "# Their Team\n"),
+        ] {
+            let (_dir, git) = make_test_repo();
+            let mut config = ShadowConfig::new();
+            let new_head = setup_conflict(&git, &mut config);
+
+            let outcome =
+                super::rebase_file(&git, &mut config, "CLAUDE.md", &new_head, strategy).unwrap();
+            assert_eq!(outcome, super::RebaseOutcome::Updated);
+            assert_eq!(
+                std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap(),
+                expected
+            );
+            assert!(!git.root.join("CLAUDE.md.orig").exists());
+            let entry = config.get("CLAUDE.md").unwrap();
+            assert_eq!(entry.baseline_commit.as_deref(), Some(new_head.as_str()));
+            assert_eq!(
+                crate::baseline::read(&git, "CLAUDE.md", None).unwrap(),
+                b"# Their Team\n"
+            );
+        }
+    }
+
+    #[test]
+    fn test_interactive_edit_until_markers_are_gone() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let new_head = setup_conflict(&git, &mut config);
+        let snapshot = super::Snapshot::take(&git, &config, "CLAUDE.md").unwrap();
+        let outcome = super::rebase_file(
+            &git,
+            &mut config,
+            "CLAUDE.md",
+            &new_head,
+            MergeStrategy::Conflict,
+        )
+        .unwrap();
+        assert_eq!(outcome, super::RebaseOutcome::Conflict);
+
+        // The first edit leaves the markers, so the user is asked again
+        let mut edits = 0;
+        let resolution = super::resolve_conflict(
+            &git,
+            &mut config,
+            "CLAUDE.md",
+            &snapshot,
+            |_| Ok(super::ConflictAction::Edit),
+            |path| {
+                edits += 1;
+                if edits == 2 {
+                    std::fs::write(path, "# Our Team\n")?;
+                }
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(edits, 2);
+        assert_eq!(
+            resolution,
+            super::Resolution::Done(super::RebaseOutcome::Updated)
+        );
+        assert!(!git.root.join("CLAUDE.md.orig").exists());
+        assert_eq!(
+            config.get("CLAUDE.md").unwrap().baseline_commit.as_deref(),
+            Some(new_head.as_str())
+        );
+    }
+
+    #[test]
+    fn test_interactive_abort_restores_file_and_baseline() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let old_commit = git.head_commit().unwrap();
+        let new_head = setup_conflict(&git, &mut config);
+        let snapshot = super::Snapshot::take(&git, &config, "CLAUDE.md").unwrap();
+        super::rebase_file(
+            &git,
+            &mut config,
+            "CLAUDE.md",
+            &new_head,
+            MergeStrategy::Conflict,
+        )
+        .unwrap();
+
+        let resolution = super::resolve_conflict(
+            &git,
+            &mut config,
+            "CLAUDE.md",
+            &snapshot,
+            |_| Ok(super::ConflictAction::Abort),
+            |_| panic!("editor must not run"),
+        )
+        .unwrap();
+
+        assert_eq!(resolution, super::Resolution::Aborted);
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap(),
+            "# My Team\n"
+        );
+        assert_eq!(
+            crate::baseline::read(&git, "CLAUDE.md", None).unwrap(),
+            b"# Team\n"
+        );
+        assert_eq!(
+            config.get("CLAUDE.md").unwrap().baseline_commit.as_deref(),
+            Some(old_commit.as_str())
+        );
+        assert!(!git.root.join("CLAUDE.md.orig").exists());
+    }
+
+    #[test]
+    fn test_rebase_file_without_final_newline() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n\nrules").unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-am", "no newline"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        let old_commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n\nrules",
+        )
+        .unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), old_commit)
+            .unwrap();
+
+        // Upstream only adds the missing final newline
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n\nrules\n").unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-am", "upstream"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        let new_head = git.head_commit().unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# My Team\n\nrules").unwrap();
+
+        let outcome = super::rebase_file(
+            &git,
+            &mut config,
+            "CLAUDE.md",
+            &new_head,
+            MergeStrategy::Conflict,
+        )
+        .unwrap();
+        assert_eq!(outcome, super::RebaseOutcome::Updated);
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap(),
+            "# My Team\n\nrules\n"
+        );
+    }
+
+    #[test]
+    fn test_rebase_binary_overlay_keeps_shadow_and_reports_conflict() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join("app.db"), b"v1\x00").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "app.db"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "add db"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        let old_commit = git.head_commit().unwrap();
+
+        let mut config = ShadowConfig::new();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("app.db")),
+            b"v1\x00",
+        )
+        .unwrap();
+        config
+            .add_overlay_with("app.db".to_string(), old_commit, true)
+            .unwrap();
+
+        std::fs::write(git.root.join("app.db"), b"v2\x00").unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-am", "upstream"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        let new_head = git.head_commit().unwrap();
+        std::fs::write(git.root.join("app.db"), b"mine\x00").unwrap();
+
+        let outcome = super::rebase_file(
+            &git,
+            &mut config,
+            "app.db",
+            &new_head,
+            MergeStrategy::Conflict,
+        )
+        .unwrap();
+        assert_eq!(outcome, super::RebaseOutcome::Conflict);
+
+        // Working tree keeps the shadow version, baseline moves to HEAD
+        assert_eq!(std::fs::read(git.root.join("app.db")).unwrap(), b"mine\x00");
+        assert_eq!(
+            std::fs::read(
+                git.shadow_dir
+                    .join("baselines")
+                    .join(crate::path::encode_path("app.db"))
+            )
+            .unwrap(),
+            b"v2\x00"
+        );
+        assert_eq!(
+            config.get("app.db").unwrap().baseline_commit.as_deref(),
+            Some(new_head.as_str())
+        );
+    }
+
+    #[test]
+    fn test_set_baseline_replaces_baseline_without_touching_worktree() {
+        let (_dir, git) = make_test_repo();
+        let old_commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        let baseline_path = git
+            .shadow_dir
+            .join("baselines")
+            .join(crate::path::encode_path("CLAUDE.md"));
+        fs_util::atomic_write(&baseline_path, b"# Team\n").unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), old_commit)
+            .unwrap();
+
+        std::fs::write(git.root.join("CLAUDE.md"), "# Rewritten\n").unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-am", "upstream rewrite"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        let new_head = git.head_commit().unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# Mine\n").unwrap();
+
+        let targets = vec!["CLAUDE.md".to_string()];
+        let summary = super::set_baselines(&git, &mut config, &targets, &new_head);
+        assert_eq!(summary.updated, 1);
+        assert_eq!(summary.failed, 0);
+
+        assert_eq!(std::fs::read(&baseline_path).unwrap(), b"# Rewritten\n");
+        let worktree = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(worktree, "# Team\n# Mine\n");
+        assert_eq!(
+            config.get("CLAUDE.md").unwrap().baseline_commit.as_deref(),
+            Some(new_head.as_str())
+        );
+        assert!(!git.root.join("CLAUDE.md.orig").exists());
+    }
+
+    #[test]
+    fn test_adopt_baseline_resets_shadow_changes() {
+        let (_dir, git) = make_test_repo();
+        let old_commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        crate::baseline::write(&git, "CLAUDE.md", b"# Old team\n").unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), old_commit)
+            .unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# Mine\n").unwrap();
+
+        let targets = vec!["CLAUDE.md".to_string()];
+        let changes = super::shadow_changes(&git, &config, &targets).unwrap();
+        assert_eq!(changes[0].1.as_deref(), Some("+2 lines / -1 lines"));
+
+        std::process::Command::new("git")
+            .args(["commit", "--allow-empty", "-m", "later"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        let head = git.head_commit().unwrap();
+        let summary = super::adopt_baselines(&git, &mut config, &targets, &head);
+        assert_eq!(summary.updated, 1);
+
+        assert_eq!(
+            crate::baseline::read(&git, "CLAUDE.md", None).unwrap(),
+            b"# Team\n# Mine\n"
+        );
+        assert_eq!(
+            config.get("CLAUDE.md").unwrap().baseline_commit.as_deref(),
+            Some(head.as_str())
+        );
+        let changes = super::shadow_changes(&git, &config, &targets).unwrap();
+        assert_eq!(changes[0].1, None);
+        assert!(super::confirm_adopt(&changes, false).unwrap());
+    }
+
+    #[test]
+    fn test_set_baseline_fails_when_file_missing_at_ref() {
+        let (_dir, git) = make_test_repo();
+        let commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("gone.md".to_string(), commit.clone())
+            .unwrap();
+
+        let targets = vec!["gone.md".to_string()];
+        let summary = super::set_baselines(&git, &mut config, &targets, &commit);
+        assert_eq!(summary.failed, 1);
+    }
 }
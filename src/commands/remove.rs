@@ -2,47 +2,58 @@ use anyhow::{bail, Result};
 use colored::Colorize;
 use is_terminal::IsTerminal;
 
-use crate::config::{ExcludeMode, FileType, ShadowConfig};
+use crate::baseline;
+use crate::commands::suspend;
+use crate::config::{ExcludeMode, FileEntry, FileType, ShadowConfig};
 use crate::exclude::ExcludeManager;
 use crate::git::GitRepo;
+use crate::history::{self, Action};
 use crate::path;
 
-pub fn run(file: &str, force: bool) -> Result<()> {
+pub fn run(files: &[String], force: bool, keep_shadow: bool) -> Result<()> {
     let git = GitRepo::discover(&std::env::current_dir()?)?;
     let mut config = ShadowConfig::load(&git.shadow_dir)?;
-    let normalized = path::normalize_path(file, &git.root)?;
+    suspend::check_not_suspended(&config, force)?;
 
-    let entry = config
-        .get(&normalized)
-        .ok_or_else(|| anyhow::anyhow!("{} is not managed by git-shadow", normalized))?
-        .clone();
+    // Resolve every target before touching anything
+    let targets = resolve_targets(&config, files, &git.prefix, &git.root)?;
+    if targets.is_empty() {
+        println!("no managed files match");
+        return Ok(());
+    }
+    // Its shadow content is parked in suspended/, even with --force
+    if let Some((normalized, _)) = targets.iter().find(|(_, entry)| entry.suspended) {
+        bail!(
+            "{} is suspended. Run `git-shadow resume {}` first",
+            normalized,
+            normalized
+        );
+    }
 
-    // Confirmation prompt
+    // Confirmation prompt (once for all files)
     if !force {
         if !std::io::stdin().is_terminal() {
             bail!("--force is required in non-interactive mode");
         }
 
-        let prompt = match entry.file_type {
-            FileType::Overlay => {
-                format!(
-                    "Shadow changes for {} will be discarded. Continue? [y/N]",
-                    normalized
-                )
-            }
-            FileType::Phantom => {
-                if entry.is_directory {
-                    format!(
-                        "{} (directory) will be unregistered from shadow management. The directory and its contents will remain. Continue? [y/N]",
-                        normalized
-                    )
-                } else {
-                    format!(
-                        "{} will be unregistered from shadow management. The file itself will remain. Continue? [y/N]",
-                        normalized
-                    )
-                }
+        let prompt = if let [(normalized, entry)] = targets.as_slice() {
+            format!(
+                "{}. Continue? [y/N]",
+                describe_removal(normalized, entry, keep_shadow)
+            )
+        } else {
+            let mut prompt = String::new();
+            for (normalized, entry) in &targets {
+                prompt.push_str(&format!(
+                    "  {}\n",
+                    describe_removal(normalized, entry, keep_shadow)
+                ));
             }
+            prompt.push_str(&format!(
+                "Unregister {} files from shadow management? [y/N]",
+                targets.len()
+            ));
+            prompt
         };
 
         eprintln!("{}", prompt);
@@ -55,55 +66,154 @@ pub fn run(file: &str, force: bool) -> Result<()> {
         }
     }
 
+    for (normalized, entry) in &targets {
+        match entry.file_type {
+            FileType::Overlay => {
+                remove_overlay(
+                    &git,
+                    normalized,
+                    entry.baseline_blob.as_deref(),
+                    keep_shadow,
+                )?;
+            }
+            FileType::Phantom => {
+                remove_phantom(&git, normalized, &entry.exclude_mode, entry.is_directory)?;
+            }
+        }
+
+        config.remove(normalized)?;
+        config.save(&git.shadow_dir)?;
+        history::record(&git.shadow_dir, Action::Remove, Some(normalized), None);
+
+        println!(
+            "{}",
+            format!("unregistered {} from shadow management", normalized).green()
+        );
+    }
+
+    Ok(())
+}
+
+/// Expand the arguments into managed entries. An argument is a managed path,
+/// a directory prefix (`src/` selects every entry below it), or a glob
+/// (`src/**/CLAUDE.md`, where `*` stays within one path segment). A plain
+/// path that matches nothing is an error; a pattern that matches nothing is
+/// only reported.
+fn resolve_targets(
+    config: &ShadowConfig,
+    files: &[String],
+    cwd_prefix: &str,
+    repo_root: &std::path::Path,
+) -> Result<Vec<(String, FileEntry)>> {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+
+    let mut targets: Vec<(String, FileEntry)> = Vec::new();
+    for file in files {
+        let normalized = path::normalize_path(file, cwd_prefix, repo_root)?;
+        let matched: Vec<&String> = if file.contains(['*', '?', '[']) {
+            let pattern = glob::Pattern::new(&normalized)
+                .map_err(|e| anyhow::anyhow!("invalid pattern '{}': {}", file, e))?;
+            config
+                .files
+                .keys()
+                .filter(|p| pattern.matches_with(p, options))
+                .collect()
+        } else if let Some((managed, _)) = config.files.get_key_value(&normalized) {
+            vec![managed]
+        } else {
+            let prefix = format!("{}/", normalized);
+            let below: Vec<&String> = config
+                .files
+                .keys()
+                .filter(|p| normalized.is_empty() || p.starts_with(&prefix))
+                .collect();
+            if below.is_empty() {
+                bail!("{} is not managed by git-shadow", normalized);
+            }
+            below
+        };
+
+        if matched.is_empty() {
+            eprintln!(
+                "{}",
+                format!("warning: no managed files match {}", file).yellow()
+            );
+        }
+        for p in matched {
+            if !targets.iter().any(|(t, _)| t == p) {
+                targets.push((p.clone(), config.files[p].clone()));
+            }
+        }
+    }
+
+    Ok(targets)
+}
+
+/// One-line description of what removing an entry does, for the prompt
+fn describe_removal(normalized: &str, entry: &FileEntry, keep_shadow: bool) -> String {
     match entry.file_type {
         FileType::Overlay => {
-            remove_overlay(&git, &normalized)?;
+            if keep_shadow {
+                format!(
+                    "{} will be unregistered. The current content (with shadow changes) will remain",
+                    normalized
+                )
+            } else {
+                format!("Shadow changes for {} will be discarded", normalized)
+            }
         }
         FileType::Phantom => {
-            remove_phantom(&git, &normalized, &entry.exclude_mode, entry.is_directory)?;
+            if entry.is_directory {
+                format!(
+                    "{} (directory) will be unregistered from shadow management. The directory and its contents will remain",
+                    normalized
+                )
+            } else {
+                format!(
+                    "{} will be unregistered from shadow management. The file itself will remain",
+                    normalized
+                )
+            }
         }
     }
-
-    config.remove(&normalized)?;
-    config.save(&git.shadow_dir)?;
-
-    println!(
-        "{}",
-        format!("unregistered {} from shadow management", normalized).green()
-    );
-
-    Ok(())
 }
 
-fn remove_overlay(git: &GitRepo, file_path: &str) -> Result<()> {
-    let encoded = path::encode_path(file_path);
-    let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
+/// Drop the baseline of an overlay. Unless `keep_shadow` is set, the working
+/// tree is reset to the baseline content first.
+pub(crate) fn remove_overlay(
+    git: &GitRepo,
+    file_path: &str,
+    blob: Option<&str>,
+    keep_shadow: bool,
+) -> Result<()> {
     let worktree_path = git.root.join(file_path);
 
-    // Restore baseline content to working tree
-    if baseline_path.exists() {
-        let baseline = std::fs::read(&baseline_path)?;
-        std::fs::write(&worktree_path, &baseline)?;
-        std::fs::remove_file(&baseline_path)?;
+    if baseline::exists(git, file_path, blob) {
+        // Restore baseline content to working tree
+        if !keep_shadow {
+            let content = baseline::read(git, file_path, blob)?;
+            std::fs::write(&worktree_path, &content)?;
+        }
     }
-
-    Ok(())
+    baseline::remove(git, file_path)
 }
 
-fn remove_phantom(
+pub(crate) fn remove_phantom(
     git: &GitRepo,
     file_path: &str,
     exclude_mode: &ExcludeMode,
     is_directory: bool,
 ) -> Result<()> {
-    // Remove from .git/info/exclude if applicable
-    if *exclude_mode == ExcludeMode::GitInfoExclude {
+    // Remove from the exclude file if applicable
+    if let Some(manager) = ExcludeManager::for_mode(git, exclude_mode) {
         let exclude_path = if is_directory {
             format!("{}/", file_path)
         } else {
             file_path.to_string()
         };
-        let manager = ExcludeManager::new(&git.git_dir);
         manager.remove_entry(&exclude_path)?;
     }
 
@@ -184,6 +294,36 @@ mod tests {
         assert!(!git.shadow_dir.join("baselines").join(&encoded).exists());
     }
 
+    #[test]
+    fn test_remove_overlay_keep_shadow() {
+        let (_dir, git) = make_test_repo();
+        let encoded = path::encode_path("CLAUDE.md");
+        let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
+        fs_util::atomic_write(&baseline_path, b"# Team\n").unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# Mine\n").unwrap();
+
+        super::remove_overlay(&git, "CLAUDE.md", None, true).unwrap();
+
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(content, "# Team\n# Mine\n");
+        assert!(!baseline_path.exists());
+    }
+
+    #[test]
+    fn test_remove_overlay_discards_shadow_by_default() {
+        let (_dir, git) = make_test_repo();
+        let encoded = path::encode_path("CLAUDE.md");
+        let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
+        fs_util::atomic_write(&baseline_path, b"# Team\n").unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# Mine\n").unwrap();
+
+        super::remove_overlay(&git, "CLAUDE.md", None, false).unwrap();
+
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(content, "# Team\n");
+        assert!(!baseline_path.exists());
+    }
+
     #[test]
     fn test_remove_phantom_keeps_file() {
         let (_dir, git) = make_test_repo();
@@ -214,6 +354,22 @@ mod tests {
         assert!(!entries.contains(&"local.md".to_string()));
     }
 
+    #[test]
+    fn test_remove_phantom_gitignore_removes_entry() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+        let manager = ExcludeManager::gitignore(&git.root);
+        manager.add_entry("local.md").unwrap();
+        manager.add_entry("other.md").unwrap();
+
+        super::remove_phantom(&git, "local.md", &ExcludeMode::Gitignore, false).unwrap();
+
+        assert_eq!(
+            manager.list_entries().unwrap(),
+            vec!["other.md".to_string()]
+        );
+    }
+
     #[test]
     fn test_remove_phantom_no_exclude_skips_exclude() {
         let (_dir, git) = make_test_repo();
@@ -395,4 +551,60 @@ mod tests {
         let entries = manager.list_entries().unwrap();
         assert!(!entries.contains(&"local.md".to_string()));
     }
+
+    fn glob_config() -> ShadowConfig {
+        let mut config = ShadowConfig::new();
+        for overlay in [
+            "CLAUDE.md",
+            "src/CLAUDE.md",
+            "src/a/b/CLAUDE.md",
+            "src/lib.md",
+        ] {
+            config
+                .add_overlay(overlay.to_string(), "abc".to_string())
+                .unwrap();
+        }
+        config
+    }
+
+    fn target_paths(config: &ShadowConfig, args: &[&str]) -> Vec<String> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        super::resolve_targets(config, &args, "", std::path::Path::new("/repo"))
+            .unwrap()
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_targets_glob() {
+        let config = glob_config();
+        assert_eq!(
+            target_paths(&config, &["src/**/CLAUDE.md"]),
+            vec!["src/CLAUDE.md", "src/a/b/CLAUDE.md"]
+        );
+        // `*` does not cross directories
+        assert_eq!(
+            target_paths(&config, &["src/*.md"]),
+            vec!["src/CLAUDE.md", "src/lib.md"]
+        );
+        assert!(target_paths(&config, &["docs/*.md"]).is_empty());
+    }
+
+    #[test]
+    fn test_resolve_targets_directory_prefix() {
+        let config = glob_config();
+        assert_eq!(
+            target_paths(&config, &["src/", "src/lib.md"]),
+            vec!["src/CLAUDE.md", "src/a/b/CLAUDE.md", "src/lib.md"]
+        );
+        assert_eq!(target_paths(&config, &["CLAUDE.md"]), vec!["CLAUDE.md"]);
+    }
+
+    #[test]
+    fn test_resolve_targets_unmanaged_path_fails() {
+        let config = glob_config();
+        let args = vec!["docs".to_string()];
+        assert!(super::resolve_targets(&config, &args, "", std::path::Path::new("/repo")).is_err());
+    }
 }
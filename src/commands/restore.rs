@@ -1,57 +1,102 @@
-use anyhow::Result;
+use std::path::PathBuf;
 
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use crate::baseline;
+use crate::config::{FileType, ShadowConfig};
+use crate::fs_util;
 use crate::git::GitRepo;
-use crate::lock;
+use crate::lock::{self, LockInfo, LockStatus};
 use crate::path;
 
-pub fn run(file: Option<&str>) -> Result<()> {
+pub fn run(file: Option<&str>, list: bool, force_lock: bool, verify: bool) -> Result<()> {
     let git = GitRepo::discover(&std::env::current_dir()?)?;
-    let stash_dir = git.shadow_dir.join("stash");
-    let mut restored = Vec::new();
+    let file = file
+        .map(|f| path::normalize_path(f, &git.prefix, &git.root))
+        .transpose()?;
+    let file = file.as_deref();
 
-    if stash_dir.exists() {
-        let entries: Vec<_> = std::fs::read_dir(&stash_dir)?
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
-            .collect();
-
-        for entry in entries {
-            let filename = entry.file_name();
-            let encoded = filename.to_string_lossy().to_string();
-            let normalized = path::decode_path(&encoded);
-
-            // If a specific file is requested, skip others
-            if let Some(target) = file {
-                if normalized != target {
-                    continue;
-                }
-            }
+    if list {
+        return print_plan(&git, file, force_lock);
+    }
+    if !restore(&git, file, force_lock)? {
+        return Ok(());
+    }
 
-            let worktree_path = git.root.join(&normalized);
-            let stash_path = entry.path();
+    // Don't leave an inconsistency behind silently
+    let problems = match ShadowConfig::load(&git.shadow_dir) {
+        Ok(config) => check_consistency(&git, &config, verify),
+        Err(e) => vec![format!("failed to load the config: {:#}", e)],
+    };
+    for problem in &problems {
+        eprintln!("{}", format!("warning: {}", problem).yellow());
+    }
+    if problems.is_empty() {
+        if verify {
+            println!("verified: overlays are consistent with HEAD");
+        }
+    } else if verify {
+        bail!(
+            "{} problem(s) found after restore. Run `git-shadow doctor` for details",
+            problems.len()
+        );
+    } else {
+        eprintln!("run `git-shadow doctor` for details");
+    }
+    Ok(())
+}
 
-            // Ensure parent directory exists
-            if let Some(parent) = worktree_path.parent() {
-                std::fs::create_dir_all(parent)?;
-            }
+/// What `restore` does with the lockfile
+#[derive(Debug)]
+enum LockAction {
+    None,
+    Remove,
+    /// Held by a live process and `--force-lock` was not given
+    Keep(LockInfo),
+}
 
-            let content = std::fs::read(&stash_path)?;
-            std::fs::write(&worktree_path, &content)?;
-            std::fs::remove_file(&stash_path)?;
-            restored.push(normalized);
-        }
+fn lock_action(git: &GitRepo, force_lock: bool) -> LockAction {
+    match lock::check_lock(&git.shadow_dir) {
+        Ok(LockStatus::Free) => LockAction::None,
+        Ok(LockStatus::HeldByOther(info)) if !force_lock => LockAction::Keep(info),
+        // Stale, ours, forced, or unreadable
+        _ => LockAction::Remove,
     }
+}
 
-    // Remove stale lock
-    let lock_removed = if git.shadow_dir.join("lock").exists() {
+fn warn_held(info: &LockInfo) {
+    eprintln!(
+        "{}",
+        format!(
+            "warning: lockfile is held by another process (PID {}, acquired {} ago); leaving it and the stash in place. Use `--force-lock` to remove it",
+            info.pid,
+            lock::format_age(info.age())
+        )
+        .yellow()
+    );
+}
+
+/// Returns false if nothing was done because another process holds the lock
+fn restore(git: &GitRepo, file: Option<&str>, force_lock: bool) -> Result<bool> {
+    // A live commit owns the stash; restoring it now would race with its
+    // post-commit hook.
+    let action = lock_action(git, force_lock);
+    if let LockAction::Keep(info) = &action {
+        warn_held(info);
+        return Ok(false);
+    }
+
+    let restored = restore_stash(git, file)?;
+    let temp_files = path::remove_orphan_temp_files(&git.shadow_dir)?;
+
+    let lock_removed = matches!(action, LockAction::Remove);
+    if lock_removed {
         lock::release_lock(&git.shadow_dir)?;
-        true
-    } else {
-        false
-    };
+    }
 
     // Print summary
-    if restored.is_empty() && !lock_removed {
+    if restored.is_empty() && temp_files.is_empty() && !lock_removed {
         println!("nothing to restore");
     } else {
         if !restored.is_empty() {
@@ -60,14 +105,136 @@ pub fn run(file: Option<&str>) -> Result<()> {
                 println!("  {}", f);
             }
         }
+        if !temp_files.is_empty() {
+            println!("removed {} leftover temp file(s)", temp_files.len());
+        }
         if lock_removed {
             println!("lockfile removed");
         }
     }
 
+    Ok(true)
+}
+
+/// Problems a crashed commit can leave behind: an overlay whose working tree
+/// file or baseline is missing. `strict` (`--verify`) also reports baselines
+/// that differ from the file in HEAD.
+fn check_consistency(git: &GitRepo, config: &ShadowConfig, strict: bool) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (file_path, entry) in &config.files {
+        if entry.file_type != FileType::Overlay {
+            continue;
+        }
+        if !git.root.join(file_path).exists() {
+            problems.push(format!("{} does not exist in working tree", file_path));
+        }
+        let blob = entry.baseline_blob.as_deref();
+        if !baseline::exists(git, file_path, blob) {
+            problems.push(format!("baseline file for {} does not exist", file_path));
+            continue;
+        }
+        if !strict {
+            continue;
+        }
+        match git.show_file("HEAD", file_path) {
+            Ok(head) => {
+                if baseline::read(git, file_path, blob).is_ok_and(|content| content != head) {
+                    problems.push(format!(
+                        "baseline for {} differs from HEAD. Consider `git-shadow rebase {}`",
+                        file_path, file_path
+                    ));
+                }
+            }
+            Err(_) => problems.push(format!("{} does not exist in HEAD", file_path)),
+        }
+    }
+    problems
+}
+
+/// Dry run: show what `restore` would do without touching anything
+fn print_plan(git: &GitRepo, file: Option<&str>, force_lock: bool) -> Result<()> {
+    let action = lock_action(git, force_lock);
+    if let LockAction::Keep(info) = &action {
+        warn_held(info);
+        return Ok(());
+    }
+    let entries = stash_entries(git, file)?;
+    let temp_files = path::orphan_temp_files(&git.shadow_dir);
+    let lock_removed = matches!(action, LockAction::Remove);
+
+    if entries.is_empty() && temp_files.is_empty() && !lock_removed {
+        println!("nothing to restore");
+        return Ok(());
+    }
+
+    if !entries.is_empty() {
+        println!("files to restore:");
+        for (normalized, _) in &entries {
+            if git.root.join(normalized).exists() {
+                println!("  {} (overwrites working tree file)", normalized);
+            } else {
+                println!("  {} (new file)", normalized);
+            }
+        }
+    }
+    if !temp_files.is_empty() {
+        println!("leftover temp files to remove:");
+        for temp_file in &temp_files {
+            println!("  {}", temp_file.display());
+        }
+    }
+    if lock_removed {
+        println!("lockfile will be removed");
+    }
+
     Ok(())
 }
 
+/// Stashed files as (decoded path, stash path) sorted by path, optionally
+/// limited to one file
+fn stash_entries(git: &GitRepo, file: Option<&str>) -> Result<Vec<(String, PathBuf)>> {
+    let mut result = Vec::new();
+
+    for entry in path::stored_files(&git.shadow_dir.join("stash"))? {
+        let encoded = entry.file_name().to_string_lossy().to_string();
+        let normalized = path::decode_path(&git.shadow_dir, &encoded);
+
+        // If a specific file is requested, skip others
+        if let Some(target) = file {
+            if normalized != target {
+                continue;
+            }
+        }
+        result.push((normalized, entry.path()));
+    }
+    // Stored names start with a hash, so their order says nothing
+    result.sort();
+
+    Ok(result)
+}
+
+/// Move stashed files back into the working tree. Returns the restored paths.
+pub(crate) fn restore_stash(git: &GitRepo, file: Option<&str>) -> Result<Vec<String>> {
+    let mut restored = Vec::new();
+
+    for (normalized, stash_path) in stash_entries(git, file)? {
+        let worktree_path = git.root.join(&normalized);
+
+        // Ensure parent directory exists
+        if let Some(parent) = worktree_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let content = std::fs::read(&stash_path)?;
+        std::fs::write(&worktree_path, &content)?;
+        fs_util::copy_permissions(&stash_path, &worktree_path)?;
+        std::fs::remove_file(&stash_path)?;
+        restored.push(normalized);
+    }
+
+    Ok(restored)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -109,16 +276,19 @@ mod tests {
         (dir, repo)
     }
 
+    /// Stash `content` for `file_path` the way pre-commit does
+    fn write_stash(git: &GitRepo, file_path: &str, content: &[u8]) {
+        let encoded = path::encode_path(file_path);
+        fs_util::atomic_write(&git.shadow_dir.join("stash").join(encoded), content).unwrap();
+        path::update_path_map(&git.shadow_dir, [file_path]).unwrap();
+    }
+
     #[test]
     fn test_restores_stashed_files() {
         let (_dir, git) = make_test_repo();
 
         // Put file in stash
-        fs_util::atomic_write(
-            &git.shadow_dir.join("stash").join("CLAUDE.md"),
-            b"# Shadow content\n",
-        )
-        .unwrap();
+        write_stash(&git, "CLAUDE.md", b"# Shadow content\n");
 
         // Overwrite worktree with baseline
         std::fs::write(git.root.join("CLAUDE.md"), "# Team\n").unwrap();
@@ -127,27 +297,34 @@ mod tests {
 
         let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
         assert_eq!(content, "# Shadow content\n");
-        assert!(!git.shadow_dir.join("stash").join("CLAUDE.md").exists());
+        assert!(!git
+            .shadow_dir
+            .join("stash")
+            .join(crate::path::encode_path("CLAUDE.md"))
+            .exists());
     }
 
     #[test]
     fn test_restores_specific_file() {
         let (_dir, git) = make_test_repo();
 
-        fs_util::atomic_write(
-            &git.shadow_dir.join("stash").join("CLAUDE.md"),
-            b"# Shadow\n",
-        )
-        .unwrap();
-        fs_util::atomic_write(&git.shadow_dir.join("stash").join("other.md"), b"# Other\n")
-            .unwrap();
+        write_stash(&git, "CLAUDE.md", b"# Shadow\n");
+        write_stash(&git, "other.md", b"# Other\n");
 
         restore_for_test(&git, Some("CLAUDE.md"));
 
         // CLAUDE.md restored
-        assert!(!git.shadow_dir.join("stash").join("CLAUDE.md").exists());
+        assert!(!git
+            .shadow_dir
+            .join("stash")
+            .join(crate::path::encode_path("CLAUDE.md"))
+            .exists());
         // other.md still in stash
-        assert!(git.shadow_dir.join("stash").join("other.md").exists());
+        assert!(git
+            .shadow_dir
+            .join("stash")
+            .join(crate::path::encode_path("other.md"))
+            .exists());
     }
 
     #[test]
@@ -166,6 +343,70 @@ mod tests {
         assert!(!git.shadow_dir.join("lock").exists());
     }
 
+    /// Lock owned by a live process other than the test (PID 1 is init)
+    fn write_live_lock(git: &GitRepo) {
+        std::fs::write(
+            git.shadow_dir.join("lock"),
+            format!("pid=1\ntimestamp={}", chrono::Utc::now().to_rfc3339()),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_keeps_lock_held_by_other_process() {
+        let (_dir, git) = make_test_repo();
+        write_live_lock(&git);
+        write_stash(&git, "CLAUDE.md", b"# Shadow\n");
+
+        restore(&git, None, false).unwrap();
+
+        assert!(git.shadow_dir.join("lock").exists());
+        assert!(git
+            .shadow_dir
+            .join("stash")
+            .join(path::encode_path("CLAUDE.md"))
+            .exists());
+    }
+
+    #[test]
+    fn test_force_lock_removes_live_lock() {
+        let (_dir, git) = make_test_repo();
+        write_live_lock(&git);
+        write_stash(&git, "CLAUDE.md", b"# Shadow\n");
+
+        restore(&git, None, true).unwrap();
+
+        assert!(!git.shadow_dir.join("lock").exists());
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(content, "# Shadow\n");
+    }
+
+    #[test]
+    fn test_restore_removes_stale_lock() {
+        let (_dir, git) = make_test_repo();
+        std::fs::write(
+            git.shadow_dir.join("lock"),
+            "pid=999999\ntimestamp=2026-01-01T00:00:00+00:00",
+        )
+        .unwrap();
+
+        assert!(matches!(lock_action(&git, false), LockAction::Remove));
+        restore(&git, None, false).unwrap();
+        assert!(!git.shadow_dir.join("lock").exists());
+    }
+
+    #[test]
+    fn test_restore_removes_leftover_temp_files() {
+        let (_dir, git) = make_test_repo();
+        let temp_file = git.shadow_dir.join("stash").join(".tmpK4l5M6");
+        std::fs::write(&temp_file, "partial").unwrap();
+
+        assert!(stash_entries(&git, None).unwrap().is_empty());
+        restore(&git, None, false).unwrap();
+        assert!(!temp_file.exists());
+        assert!(!git.root.join(".tmpK4l5M6").exists());
+    }
+
     #[test]
     fn test_nothing_to_restore() {
         let (_dir, git) = make_test_repo();
@@ -177,12 +418,7 @@ mod tests {
     fn test_restores_nested_path() {
         let (_dir, git) = make_test_repo();
 
-        let encoded = path::encode_path("src/components/CLAUDE.md");
-        fs_util::atomic_write(
-            &git.shadow_dir.join("stash").join(&encoded),
-            b"# Component\n",
-        )
-        .unwrap();
+        write_stash(&git, "src/components/CLAUDE.md", b"# Component\n");
 
         restore_for_test(&git, None);
 
@@ -190,7 +426,96 @@ mod tests {
         assert_eq!(content, "# Component\n");
     }
 
+    #[test]
+    fn test_restores_legacy_stash_name() {
+        let (_dir, git) = make_test_repo();
+
+        // Stash written by an older version under a URL-encoded name
+        fs_util::atomic_write(
+            &git.shadow_dir.join("stash").join("src%2Fold.md"),
+            b"# Old\n",
+        )
+        .unwrap();
+
+        let restored = restore_stash(&git, None).unwrap();
+        assert_eq!(restored, vec!["src/old.md"]);
+        let content = std::fs::read_to_string(git.root.join("src/old.md")).unwrap();
+        assert_eq!(content, "# Old\n");
+    }
+
+    #[test]
+    fn test_stash_entries_lists_without_restoring() {
+        let (_dir, git) = make_test_repo();
+        let encoded = path::encode_path("src/new.md");
+        write_stash(&git, "src/new.md", b"# New\n");
+        write_stash(&git, "CLAUDE.md", b"# Shadow\n");
+
+        let entries = stash_entries(&git, None).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, vec!["CLAUDE.md", "src/new.md"]);
+
+        print_plan(&git, None, false).unwrap();
+
+        // Nothing was touched
+        assert!(git.shadow_dir.join("stash").join(&encoded).exists());
+        assert!(!git.root.join("src/new.md").exists());
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(content, "# Team\n");
+    }
+
+    #[test]
+    fn test_stash_entries_filters_by_file() {
+        let (_dir, git) = make_test_repo();
+        write_stash(&git, "CLAUDE.md", b"# Shadow\n");
+        write_stash(&git, "other.md", b"# Other\n");
+
+        let entries = stash_entries(&git, Some("other.md")).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "other.md");
+    }
+
     /// Helper that runs restore logic directly (bypassing cwd discovery)
+    #[test]
+    fn test_check_consistency_reports_missing_files() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), git.head_commit().unwrap())
+            .unwrap();
+        assert_eq!(
+            check_consistency(&git, &config, false),
+            ["baseline file for CLAUDE.md does not exist"]
+        );
+
+        baseline::write(&git, "CLAUDE.md", b"# Team\n").unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# Mine\n").unwrap();
+        assert!(check_consistency(&git, &config, false).is_empty());
+        assert!(check_consistency(&git, &config, true).is_empty());
+
+        std::fs::remove_file(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(
+            check_consistency(&git, &config, false),
+            ["CLAUDE.md does not exist in working tree"]
+        );
+    }
+
+    #[test]
+    fn test_check_consistency_strict_compares_with_head() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), git.head_commit().unwrap())
+            .unwrap();
+        // A crash can leave a baseline that no longer matches HEAD
+        baseline::write(&git, "CLAUDE.md", b"# Old team\n").unwrap();
+
+        assert!(check_consistency(&git, &config, false).is_empty());
+        assert_eq!(
+            check_consistency(&git, &config, true),
+            ["baseline for CLAUDE.md differs from HEAD. Consider `git-shadow rebase CLAUDE.md`"]
+        );
+    }
+
     fn restore_for_test(git: &GitRepo, file: Option<&str>) {
         let stash_dir = git.shadow_dir.join("stash");
         if stash_dir.exists() {
@@ -203,7 +528,7 @@ mod tests {
             for entry in entries {
                 let filename = entry.file_name();
                 let encoded = filename.to_string_lossy().to_string();
-                let normalized = path::decode_path(&encoded);
+                let normalized = path::decode_path(&git.shadow_dir, &encoded);
 
                 if let Some(target) = file {
                     if normalized != target {
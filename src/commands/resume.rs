@@ -1,62 +1,173 @@
-use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
 
+use crate::baseline;
 use crate::config::{FileType, ShadowConfig};
 use crate::error::ShadowError;
 use crate::fs_util;
 use crate::git::GitRepo;
+use crate::history::{self, Action};
 use crate::merge;
 use crate::path;
 
-pub fn run() -> Result<()> {
+pub fn run(files: &[String], discard_orphans: bool, strategy: merge::MergeStrategy) -> Result<()> {
     let git = GitRepo::discover(&std::env::current_dir()?)?;
     let mut config = ShadowConfig::load(&git.shadow_dir)?;
 
-    // Guard: not suspended
-    if !config.suspended {
+    // Guard: not suspended. Orphans can still be listed or discarded.
+    if !config.is_suspended() {
+        if files.is_empty() && !orphan_suspended(&git, &config)?.is_empty() {
+            return handle_orphans(&git, &config, discard_orphans);
+        }
         return Err(ShadowError::NotSuspended.into());
     }
 
+    let mut targets = Vec::new();
+    for file in files {
+        let normalized = path::normalize_path(file, &git.prefix, &git.root)?;
+        let entry = config
+            .get(&normalized)
+            .ok_or_else(|| ShadowError::NotManaged(normalized.clone()))?;
+        if !entry.suspended {
+            bail!("{} is not suspended", normalized);
+        }
+        if !targets.contains(&normalized) {
+            targets.push(normalized);
+        }
+    }
+    if targets.is_empty() {
+        targets = config.suspended_files();
+    }
+
+    let count = resume_files(&git, &mut config, &targets, strategy)?;
+
+    println!(
+        "{}",
+        format!("shadow changes resumed for {} file(s)", count).green()
+    );
+
+    handle_orphans(&git, &config, discard_orphans)
+}
+
+/// Files in `suspended/` that belong to no suspended entry of the config, as
+/// (decoded path, stored file). They are left behind when an entry is
+/// removed from the config by other means while suspended.
+pub(crate) fn orphan_suspended(
+    git: &GitRepo,
+    config: &ShadowConfig,
+) -> Result<Vec<(String, PathBuf)>> {
+    let mut orphans = Vec::new();
+    for entry in path::stored_files(&git.shadow_dir.join("suspended"))? {
+        let decoded = path::decode_path(&git.shadow_dir, &entry.file_name().to_string_lossy());
+        if !config.get(&decoded).is_some_and(|e| e.suspended) {
+            orphans.push((decoded, entry.path()));
+        }
+    }
+    Ok(orphans)
+}
+
+/// Warn about orphans in `suspended/`, or delete them with `--discard-orphans`
+fn handle_orphans(git: &GitRepo, config: &ShadowConfig, discard: bool) -> Result<()> {
+    let orphans = orphan_suspended(git, config)?;
+    if orphans.is_empty() {
+        return Ok(());
+    }
+
+    if !discard {
+        eprintln!(
+            "{}",
+            format!(
+                "warning: {} suspended file(s) are no longer managed and were kept in .git/shadow/suspended/:",
+                orphans.len()
+            )
+            .yellow()
+        );
+        for (file_path, _) in &orphans {
+            eprintln!("  {}", file_path);
+        }
+        eprintln!(
+            "Copy out what you need, then run `git-shadow resume --discard-orphans` to delete them"
+        );
+        return Ok(());
+    }
+
+    for (file_path, stored) in &orphans {
+        std::fs::remove_file(stored)
+            .with_context(|| format!("failed to delete suspended content for {}", file_path))?;
+        println!("discarded suspended content for {}", file_path);
+    }
     let suspended_dir = git.shadow_dir.join("suspended");
-    let head = git.head_commit()?;
-    let mut count = 0;
+    if !config.is_suspended() && suspended_dir.exists() {
+        std::fs::remove_dir_all(&suspended_dir)
+            .context("failed to clean up suspended directory")?;
+    }
+    Ok(())
+}
 
-    let file_paths: Vec<(String, FileType, bool)> = config
-        .files
-        .iter()
-        .map(|(p, e)| (p.clone(), e.file_type.clone(), e.is_directory))
-        .collect();
+/// Restore every suspended file onto the current HEAD. Returns the number of
+/// files resumed.
+pub(crate) fn resume_all(git: &GitRepo, config: &mut ShadowConfig) -> Result<usize> {
+    let targets = config.suspended_files();
+    resume_files(git, config, &targets, merge::MergeStrategy::Conflict)
+}
 
-    for (file_path, file_type, is_directory) in &file_paths {
+/// Restore the given suspended files onto the current HEAD and clear their
+/// suspended flag. The suspended directory is removed once nothing is left
+/// suspended. Returns the number of files resumed. `strategy` settles
+/// merge conflicts (`--ours` / `--theirs`).
+pub(crate) fn resume_files(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    targets: &[String],
+    strategy: merge::MergeStrategy,
+) -> Result<usize> {
+    let suspended_dir = git.shadow_dir.join("suspended");
+    let head = git.head_commit()?;
+
+    for file_path in targets {
+        let Some(file_type) = config.get(file_path).map(|e| e.file_type.clone()) else {
+            continue;
+        };
         match file_type {
             FileType::Overlay => {
-                resume_overlay(&git, &mut config, &suspended_dir, file_path, &head)?;
-                count += 1;
+                resume_overlay(git, config, &suspended_dir, file_path, &head, strategy)?;
             }
             FileType::Phantom => {
-                if !is_directory {
-                    resume_phantom(&git, &suspended_dir, file_path)?;
-                    count += 1;
-                }
+                resume_phantom(git, &suspended_dir, file_path)?;
             }
         }
+
+        // Drop the parked content and save after each file so a later
+        // failure keeps the flags accurate
+        let suspend_path = suspended_dir.join(path::encode_path(file_path));
+        if suspend_path.exists() {
+            std::fs::remove_file(&suspend_path).with_context(|| {
+                format!("failed to clean up suspended content for {}", file_path)
+            })?;
+        }
+        if let Some(entry) = config.files.get_mut(file_path) {
+            entry.suspended = false;
+        }
+        config.save(&git.shadow_dir)?;
+        history::record(
+            &git.shadow_dir,
+            Action::Resume,
+            Some(file_path),
+            Some(&head),
+        );
     }
 
-    // Clean up suspended directory
-    if suspended_dir.exists() {
+    // Clean up suspended directory once everything is resumed. Orphans keep
+    // it, so content nothing refers to is never deleted silently.
+    if !config.is_suspended() && suspended_dir.exists() && orphan_suspended(git, config)?.is_empty()
+    {
         std::fs::remove_dir_all(&suspended_dir)
             .context("failed to clean up suspended directory")?;
     }
 
-    config.suspended = false;
-    config.save(&git.shadow_dir)?;
-
-    println!(
-        "{}",
-        format!("shadow changes resumed for {} file(s)", count).green()
-    );
-
-    Ok(())
+    Ok(targets.len())
 }
 
 fn resume_overlay(
@@ -65,10 +176,10 @@ fn resume_overlay(
     suspended_dir: &std::path::Path,
     file_path: &str,
     new_head: &str,
+    strategy: merge::MergeStrategy,
 ) -> Result<()> {
     let encoded = path::encode_path(file_path);
     let suspend_path = suspended_dir.join(&encoded);
-    let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
     let worktree_path = git.root.join(file_path);
 
     // Ensure parent directory exists (may be missing after branch switch)
@@ -85,17 +196,43 @@ fn resume_overlay(
         return Ok(());
     }
 
-    let suspended_content = std::fs::read_to_string(&suspend_path)
+    let suspended_content = std::fs::read(&suspend_path)
         .with_context(|| format!("failed to read suspended content for {}", file_path))?;
-    let old_baseline = std::fs::read_to_string(&baseline_path)
-        .with_context(|| format!("failed to read baseline for {}", file_path))?;
+    let old_baseline = baseline::read(git, file_path, config.baseline_blob(file_path).as_deref())?;
+
+    apply_onto_head(
+        git,
+        config,
+        file_path,
+        &old_baseline,
+        &suspended_content,
+        new_head,
+        strategy,
+    )
+}
+
+/// Write shadow content (`ours`, based on `old_baseline`) onto the HEAD version
+/// of an overlay file. When HEAD differs from the old baseline, a 3-way merge
+/// is performed and the baseline is moved to `new_head`. Binary overlays are
+/// never merged: the shadow content is kept and a conflict is reported.
+/// `strategy` picks a side for conflicts, for binary overlays too.
+pub(crate) fn apply_onto_head(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    file_path: &str,
+    old_baseline: &[u8],
+    ours: &[u8],
+    new_head: &str,
+    strategy: merge::MergeStrategy,
+) -> Result<()> {
+    let worktree_path = git.root.join(file_path);
 
     // Get current HEAD content for this file
     let new_baseline = match git.show_file("HEAD", file_path) {
-        Ok(content) => String::from_utf8_lossy(&content).to_string(),
+        Ok(content) => content,
         Err(_) => {
-            // File deleted in new branch — just restore the suspended content
-            std::fs::write(&worktree_path, suspended_content.as_bytes())
+            // File deleted in new branch — just restore the shadow content
+            std::fs::write(&worktree_path, ours)
                 .with_context(|| format!("failed to restore {}", file_path))?;
             println!(
                 "{}: shadow changes restored (file absent from HEAD)",
@@ -106,39 +243,81 @@ fn resume_overlay(
     };
 
     if old_baseline == new_baseline {
-        // Baseline unchanged — restore suspended content directly
-        std::fs::write(&worktree_path, suspended_content.as_bytes())
+        // Baseline unchanged — restore shadow content directly
+        std::fs::write(&worktree_path, ours)
             .with_context(|| format!("failed to restore {}", file_path))?;
         println!("{}: shadow changes restored", file_path);
+    } else if config.get(file_path).is_some_and(|e| e.is_binary) {
+        // Binary overlay — keep the shadow version (HEAD with --theirs),
+        // move the baseline to HEAD
+        let kept = if strategy == merge::MergeStrategy::Theirs {
+            &new_baseline[..]
+        } else {
+            ours
+        };
+        std::fs::write(&worktree_path, kept)
+            .with_context(|| format!("failed to restore {}", file_path))?;
+        let blob = baseline::write(git, file_path, &new_baseline)
+            .with_context(|| format!("failed to update baseline for {}", file_path))?;
+        config.set_baseline_blob(file_path, blob);
+        if let Some(entry) = config.files.get_mut(file_path) {
+            entry.baseline_commit = Some(new_head.to_string());
+        }
+        if strategy != merge::MergeStrategy::Conflict {
+            println!(
+                "{}: baseline updated (binary, kept {})",
+                file_path,
+                strategy.side()
+            );
+            return Ok(());
+        }
+        eprintln!(
+            "{}",
+            format!(
+                "warning: {} is a binary overlay and cannot be merged. Your version was kept; compare it with `git show HEAD:{}` and resolve manually",
+                file_path, file_path
+            )
+            .yellow()
+        );
     } else {
         // Baseline changed — 3-way merge
         let merge_result = merge::three_way_merge(
-            &old_baseline,
-            &suspended_content,
-            &new_baseline,
-            &git.shadow_dir,
-        )?;
+            &String::from_utf8_lossy(old_baseline),
+            &String::from_utf8_lossy(ours),
+            &String::from_utf8_lossy(&new_baseline),
+            merge::ConflictStyle::resolve(git)?,
+            strategy,
+        );
 
         std::fs::write(&worktree_path, merge_result.content.as_bytes())
             .with_context(|| format!("failed to write merged content for {}", file_path))?;
 
         // Update baseline
-        fs_util::atomic_write(&baseline_path, new_baseline.as_bytes())
+        let blob = baseline::write(git, file_path, &new_baseline)
             .with_context(|| format!("failed to update baseline for {}", file_path))?;
+        config.set_baseline_blob(file_path, blob);
 
         if let Some(entry) = config.files.get_mut(file_path) {
             entry.baseline_commit = Some(new_head.to_string());
         }
 
         if merge_result.has_conflicts {
+            let backup = merge::write_orig(git, file_path, ours)?;
             eprintln!(
                 "{}",
                 format!(
-                    "warning: conflicts detected in {}. Please resolve manually",
-                    file_path
+                    "warning: conflicts detected in {}. Please resolve manually (your version is saved in {}; delete it when done)",
+                    file_path, backup
                 )
                 .yellow()
             );
+        } else if merge_result.resolved > 0 {
+            println!(
+                "{}: baseline updated and shadow changes merged ({} conflict(s) resolved with {})",
+                file_path,
+                merge_result.resolved,
+                strategy.side()
+            );
         } else {
             println!("{}: baseline updated and shadow changes merged", file_path);
         }
@@ -171,6 +350,8 @@ fn resume_phantom(git: &GitRepo, suspended_dir: &std::path::Path, file_path: &st
 
     std::fs::write(&worktree_path, &content)
         .with_context(|| format!("failed to restore {}", file_path))?;
+    fs_util::copy_permissions(&suspend_path, &worktree_path)
+        .with_context(|| format!("failed to restore permissions of {}", file_path))?;
 
     println!("{}: phantom file restored", file_path);
 
@@ -179,7 +360,7 @@ fn resume_phantom(git: &GitRepo, suspended_dir: &std::path::Path, file_path: &st
 
 #[cfg(test)]
 mod tests {
-    use crate::config::ShadowConfig;
+    use crate::config::{ExcludeMode, ShadowConfig};
     use crate::git::GitRepo;
     use crate::{fs_util, path};
 
@@ -246,7 +427,15 @@ mod tests {
         std::fs::write(git.root.join("CLAUDE.md"), "# Team\n").unwrap();
 
         // Resume
-        super::resume_overlay(&git, &mut config, &suspended_dir, "CLAUDE.md", &commit).unwrap();
+        super::resume_overlay(
+            &git,
+            &mut config,
+            &suspended_dir,
+            "CLAUDE.md",
+            &commit,
+            crate::merge::MergeStrategy::Conflict,
+        )
+        .unwrap();
 
         // Working tree should have shadow content
         let wt = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
@@ -317,7 +506,15 @@ mod tests {
         let new_head = git.head_commit().unwrap();
 
         // Resume — should 3-way merge
-        super::resume_overlay(&git, &mut config, &suspended_dir, "CLAUDE.md", &new_head).unwrap();
+        super::resume_overlay(
+            &git,
+            &mut config,
+            &suspended_dir,
+            "CLAUDE.md",
+            &new_head,
+            crate::merge::MergeStrategy::Conflict,
+        )
+        .unwrap();
 
         // Working tree should have merged content
         let wt = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
@@ -334,6 +531,40 @@ mod tests {
         assert_eq!(entry.baseline_commit.as_ref().unwrap(), &new_head);
     }
 
+    #[test]
+    fn test_apply_onto_head_conflict_writes_orig() {
+        let (_dir, git) = make_test_repo();
+        let old_commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), old_commit)
+            .unwrap();
+
+        std::fs::write(git.root.join("CLAUDE.md"), "# Their Team\n").unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-am", "upstream"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        let new_head = git.head_commit().unwrap();
+
+        super::apply_onto_head(
+            &git,
+            &mut config,
+            "CLAUDE.md",
+            b"# Team\n",
+            b"# My Team\n",
+            &new_head,
+            crate::merge::MergeStrategy::Conflict,
+        )
+        .unwrap();
+
+        let wt = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert!(wt.contains("<<<<<<<"));
+        let backup = std::fs::read_to_string(git.root.join("CLAUDE.md.orig")).unwrap();
+        assert_eq!(backup, "# My Team\n");
+    }
+
     #[test]
     fn test_resume_phantom_restores_file() {
         let (_dir, git) = make_test_repo();
@@ -352,25 +583,142 @@ mod tests {
         assert_eq!(content, "# Local\n");
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_resume_clears_suspended_flag() {
+    fn test_suspend_and_resume_keep_exec_bit() {
+        use std::os::unix::fs::PermissionsExt;
+
         let (_dir, git) = make_test_repo();
+        let script = git.root.join("run.sh");
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
         let mut config = ShadowConfig::new();
-        config.suspended = true;
+        config
+            .add_phantom("run.sh".to_string(), ExcludeMode::None, false)
+            .unwrap();
         config.save(&git.shadow_dir).unwrap();
 
-        // Simulate resume
-        config.suspended = false;
-        config.save(&git.shadow_dir).unwrap();
+        crate::commands::suspend::suspend_files(&git, &mut config, &["run.sh".to_string()])
+            .unwrap();
+        assert!(!script.exists());
+        super::resume_all(&git, &mut config).unwrap();
+
+        let mode = std::fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+
+    #[test]
+    fn test_resume_clears_suspended_flag() {
+        let (_dir, git) = make_test_repo();
+        let commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        config.files.get_mut("CLAUDE.md").unwrap().suspended = true;
+        let suspended_dir = git.shadow_dir.join("suspended");
+        std::fs::create_dir_all(&suspended_dir).unwrap();
+        fs_util::atomic_write(
+            &suspended_dir.join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n# Mine\n",
+        )
+        .unwrap();
+
+        super::resume_all(&git, &mut config).unwrap();
 
         let loaded = ShadowConfig::load(&git.shadow_dir).unwrap();
-        assert!(!loaded.suspended);
+        assert!(!loaded.is_suspended());
+        assert!(!suspended_dir.exists());
+    }
+
+    #[test]
+    fn test_resume_files_keeps_other_suspended_files() {
+        let (_dir, git) = make_test_repo();
+        let commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+        for entry in config.files.values_mut() {
+            entry.suspended = true;
+        }
+        let suspended_dir = git.shadow_dir.join("suspended");
+        std::fs::create_dir_all(&suspended_dir).unwrap();
+        fs_util::atomic_write(
+            &suspended_dir.join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n# Mine\n",
+        )
+        .unwrap();
+        fs_util::atomic_write(
+            &suspended_dir.join(crate::path::encode_path("local.md")),
+            b"# Local\n",
+        )
+        .unwrap();
+
+        let count = super::resume_files(
+            &git,
+            &mut config,
+            &["local.md".to_string()],
+            crate::merge::MergeStrategy::Conflict,
+        )
+        .unwrap();
+
+        assert_eq!(count, 1);
+        assert!(git.root.join("local.md").exists());
+        assert!(!config.get("local.md").unwrap().suspended);
+        assert!(config.get("CLAUDE.md").unwrap().suspended);
+        assert!(suspended_dir
+            .join(crate::path::encode_path("CLAUDE.md"))
+            .exists());
+        assert!(!suspended_dir
+            .join(crate::path::encode_path("local.md"))
+            .exists());
+    }
+
+    #[test]
+    fn test_resume_keeps_orphan_suspended_files() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+        config.files.get_mut("local.md").unwrap().suspended = true;
+        let suspended_dir = git.shadow_dir.join("suspended");
+        std::fs::create_dir_all(&suspended_dir).unwrap();
+        for file in ["local.md", "docs/gone.md"] {
+            fs_util::atomic_write(&suspended_dir.join(path::encode_path(file)), b"x\n").unwrap();
+        }
+        path::update_path_map(&git.shadow_dir, ["docs/gone.md"]).unwrap();
+        config.save(&git.shadow_dir).unwrap();
+
+        super::resume_all(&git, &mut config).unwrap();
+
+        let orphans = super::orphan_suspended(&git, &config).unwrap();
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].0, "docs/gone.md");
+        assert!(orphans[0].1.exists());
+
+        super::handle_orphans(&git, &config, true).unwrap();
+        assert!(!suspended_dir.exists());
     }
 
     #[test]
     fn test_resume_not_suspended_is_error() {
         let config = ShadowConfig::new();
-        assert!(!config.suspended);
+        assert!(!config.is_suspended());
     }
 
     #[test]
@@ -386,6 +734,14 @@ mod tests {
         std::fs::create_dir_all(&suspended_dir).unwrap();
 
         // Resume with no suspended file — should warn but not error
-        super::resume_overlay(&git, &mut config, &suspended_dir, "CLAUDE.md", &commit).unwrap();
+        super::resume_overlay(
+            &git,
+            &mut config,
+            &suspended_dir,
+            "CLAUDE.md",
+            &commit,
+            crate::merge::MergeStrategy::Conflict,
+        )
+        .unwrap();
     }
 }
@@ -0,0 +1,337 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use colored::Colorize;
+
+use crate::config::{FileType, ShadowConfig};
+use crate::fs_util::{self, WalkEntry};
+use crate::git::GitRepo;
+use crate::settings::Settings;
+
+pub fn run(restore: Option<&str>, list: bool) -> Result<()> {
+    let git = GitRepo::discover(&std::env::current_dir()?)?;
+
+    if list {
+        let snapshots = list_snapshots(&git.shadow_dir)?;
+        if snapshots.is_empty() {
+            println!("no snapshots");
+        }
+        for id in snapshots {
+            let files = fs_util::walk_dir(&snapshots_dir(&git.shadow_dir).join(&id))
+                .map(|entries| entries.len())
+                .unwrap_or(0);
+            println!("{}  {} file(s)", id, files);
+        }
+        return Ok(());
+    }
+
+    if let Some(id) = restore {
+        let restored = restore_snapshot(&git, id)?;
+        println!("restored from snapshot {}:", id);
+        for file in &restored {
+            println!("  {}", file);
+        }
+        return Ok(());
+    }
+
+    let config = ShadowConfig::load(&git.shadow_dir)?;
+    let settings = Settings::load(&git.shadow_dir)?;
+    let Some((id, saved)) = create_snapshot(&git, &config)? else {
+        println!("no phantom files to snapshot");
+        return Ok(());
+    };
+    println!("saved snapshot {} ({} file(s))", id, saved.len());
+
+    for removed in prune_snapshots(&git.shadow_dir, settings.snapshot_limit())? {
+        println!("removed old snapshot {}", removed);
+    }
+
+    Ok(())
+}
+
+fn snapshots_dir(shadow_dir: &Path) -> PathBuf {
+    shadow_dir.join("snapshots")
+}
+
+/// Snapshot ids, oldest first
+fn list_snapshots(shadow_dir: &Path) -> Result<Vec<String>> {
+    let dir = snapshots_dir(shadow_dir);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut ids: Vec<String> = std::fs::read_dir(&dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .collect();
+    ids.sort();
+    Ok(ids)
+}
+
+/// Newest snapshot holding a copy of the phantom file `file_path`: its id
+/// (the UTC timestamp it was taken at) and the saved copy
+pub(crate) fn latest_copy(shadow_dir: &Path, file_path: &str) -> Result<Option<(String, PathBuf)>> {
+    for id in list_snapshots(shadow_dir)?.into_iter().rev() {
+        let copy = snapshots_dir(shadow_dir).join(&id).join(file_path);
+        if copy.is_file() {
+            return Ok(Some((id, copy)));
+        }
+    }
+    Ok(None)
+}
+
+/// Copy every phantom (directories recursively) into a new snapshot.
+/// Returns the snapshot id and the saved paths, or None when there is
+/// nothing to save.
+fn create_snapshot(git: &GitRepo, config: &ShadowConfig) -> Result<Option<(String, Vec<String>)>> {
+    let phantoms: Vec<&String> = config
+        .files
+        .iter()
+        .filter(|(_, e)| e.file_type == FileType::Phantom)
+        .map(|(p, _)| p)
+        .filter(|p| git.root.join(p).exists())
+        .collect();
+    if phantoms.is_empty() {
+        return Ok(None);
+    }
+
+    let id = new_snapshot_id(&git.shadow_dir);
+    let target = snapshots_dir(&git.shadow_dir).join(&id);
+    std::fs::create_dir_all(&target).context("failed to create snapshot directory")?;
+
+    let mut saved = Vec::new();
+    for phantom in phantoms {
+        let source = git.root.join(phantom);
+        if source.is_dir() {
+            for entry in fs_util::walk_dir(&source)? {
+                match entry {
+                    WalkEntry::File { path, .. } => {
+                        let relative =
+                            format!("{}/{}", phantom, path.to_string_lossy().replace('\\', "/"));
+                        copy_file(&source.join(&path), &target.join(&relative))?;
+                        saved.push(relative);
+                    }
+                    WalkEntry::Symlink { path, .. } => eprintln!(
+                        "{}",
+                        format!(
+                            "warning: skipping symlink {}/{}",
+                            phantom,
+                            path.to_string_lossy()
+                        )
+                        .yellow()
+                    ),
+                }
+            }
+        } else {
+            copy_file(&source, &target.join(phantom))?;
+            saved.push(phantom.clone());
+        }
+    }
+
+    Ok(Some((id, saved)))
+}
+
+/// Timestamp id, suffixed when a snapshot was already taken this second
+fn new_snapshot_id(shadow_dir: &Path) -> String {
+    let base = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+    let dir = snapshots_dir(shadow_dir);
+    let mut id = base.clone();
+    let mut n = 2;
+    while dir.join(&id).exists() {
+        id = format!("{}-{}", base, n);
+        n += 1;
+    }
+    id
+}
+
+/// Write a snapshot back into the working tree, overwriting existing files.
+/// Returns the restored paths.
+fn restore_snapshot(git: &GitRepo, id: &str) -> Result<Vec<String>> {
+    if !list_snapshots(&git.shadow_dir)?.iter().any(|s| s == id) {
+        bail!(
+            "snapshot {} not found (see `git-shadow snapshot --list`)",
+            id
+        );
+    }
+    let source = snapshots_dir(&git.shadow_dir).join(id);
+
+    let mut restored = Vec::new();
+    for entry in fs_util::walk_dir(&source)? {
+        if let WalkEntry::File { path, .. } = entry {
+            copy_file(&source.join(&path), &git.root.join(&path))?;
+            restored.push(path.to_string_lossy().replace('\\', "/"));
+        }
+    }
+    Ok(restored)
+}
+
+/// Delete the oldest snapshots beyond `limit` (0 keeps all). Returns the
+/// removed ids.
+fn prune_snapshots(shadow_dir: &Path, limit: usize) -> Result<Vec<String>> {
+    let snapshots = list_snapshots(shadow_dir)?;
+    if limit == 0 || snapshots.len() <= limit {
+        return Ok(Vec::new());
+    }
+    let excess = snapshots.len() - limit;
+    let removed: Vec<String> = snapshots.into_iter().take(excess).collect();
+    for id in &removed {
+        std::fs::remove_dir_all(snapshots_dir(shadow_dir).join(id))
+            .with_context(|| format!("failed to remove snapshot {}", id))?;
+    }
+    Ok(removed)
+}
+
+fn copy_file(from: &Path, to: &Path) -> Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(from, to).with_context(|| format!("failed to copy {}", from.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ExcludeMode;
+
+    fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.email", "t@t.com"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::fs::write(root.join("CLAUDE.md"), "# Team\n").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "CLAUDE.md"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+
+        let repo = GitRepo::discover(&root).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("baselines")).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("stash")).unwrap();
+        (dir, repo)
+    }
+
+    fn phantom_config(git: &GitRepo) -> ShadowConfig {
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+        std::fs::create_dir_all(git.root.join("notes/sub")).unwrap();
+        std::fs::write(git.root.join("notes/a.md"), "a\n").unwrap();
+        std::fs::write(git.root.join("notes/sub/b.md"), "b\n").unwrap();
+
+        let mut config = ShadowConfig::new();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+        config
+            .add_phantom("notes".to_string(), ExcludeMode::None, true)
+            .unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), git.head_commit().unwrap())
+            .unwrap();
+        config
+    }
+
+    #[test]
+    fn test_snapshot_and_restore_phantoms() {
+        let (_dir, git) = make_test_repo();
+        let config = phantom_config(&git);
+
+        let (id, saved) = create_snapshot(&git, &config).unwrap().unwrap();
+        assert_eq!(saved, vec!["local.md", "notes/a.md", "notes/sub/b.md"]);
+
+        // Overlays are not part of a snapshot
+        let snapshot = git.shadow_dir.join("snapshots").join(&id);
+        assert!(!snapshot.join("CLAUDE.md").exists());
+
+        std::fs::remove_file(git.root.join("local.md")).unwrap();
+        std::fs::remove_dir_all(git.root.join("notes")).unwrap();
+
+        let restored = restore_snapshot(&git, &id).unwrap();
+        assert_eq!(restored.len(), 3);
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("local.md")).unwrap(),
+            "# Local\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("notes/sub/b.md")).unwrap(),
+            "b\n"
+        );
+    }
+
+    #[test]
+    fn test_no_phantoms_creates_nothing() {
+        let (_dir, git) = make_test_repo();
+        let config = ShadowConfig::new();
+        assert!(create_snapshot(&git, &config).unwrap().is_none());
+        assert!(list_snapshots(&git.shadow_dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_same_second_snapshots_get_distinct_ids() {
+        let (_dir, git) = make_test_repo();
+        let config = phantom_config(&git);
+        let (first, _) = create_snapshot(&git, &config).unwrap().unwrap();
+        let (second, _) = create_snapshot(&git, &config).unwrap().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(list_snapshots(&git.shadow_dir).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        for id in ["20260101-000000", "20260102-000000", "20260103-000000"] {
+            std::fs::create_dir_all(dir.path().join("snapshots").join(id)).unwrap();
+        }
+
+        let removed = prune_snapshots(dir.path(), 2).unwrap();
+        assert_eq!(removed, vec!["20260101-000000"]);
+        assert_eq!(
+            list_snapshots(dir.path()).unwrap(),
+            vec!["20260102-000000", "20260103-000000"]
+        );
+
+        // 0 keeps everything
+        assert!(prune_snapshots(dir.path(), 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_latest_copy_skips_snapshots_without_the_file() {
+        let (_dir, git) = make_test_repo();
+        assert!(latest_copy(&git.shadow_dir, "local.md").unwrap().is_none());
+
+        let config = phantom_config(&git);
+        let (first, _) = create_snapshot(&git, &config).unwrap().unwrap();
+        std::fs::remove_file(git.root.join("local.md")).unwrap();
+        create_snapshot(&git, &config).unwrap().unwrap();
+
+        let (id, copy) = latest_copy(&git.shadow_dir, "local.md").unwrap().unwrap();
+        assert_eq!(id, first);
+        assert_eq!(std::fs::read_to_string(copy).unwrap(), "# Local\n");
+    }
+
+    #[test]
+    fn test_restore_unknown_snapshot_fails() {
+        let (_dir, git) = make_test_repo();
+        let err = restore_snapshot(&git, "20000101-000000").unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+}
@@ -1,74 +1,397 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 use colored::Colorize;
+use serde::Serialize;
 
-use crate::config::{FileType, ShadowConfig};
-use crate::git::GitRepo;
+use crate::baseline;
+use crate::config::{FileEntry, FileType, ShadowConfig};
+use crate::diff_util::{diff_stats, normalize_whitespace};
+use crate::git::{short_hash, GitRepo};
 use crate::lock::{self, LockStatus};
+use crate::merge;
 use crate::path;
+use crate::settings::Settings;
+
+/// Machine-readable status for `status --json`
+#[derive(Debug, Serialize)]
+struct StatusReport {
+    suspended: bool,
+    stash_remaining: bool,
+    lock: &'static str,
+    files: Vec<FileReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct FileReport {
+    path: String,
+    #[serde(rename = "type")]
+    file_type: FileType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    baseline_commit: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    is_directory: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    is_binary: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    suspended: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    exists: bool,
+    added: usize,
+    removed: usize,
+    baseline_outdated: bool,
+    /// A merge left `<<<<<<<`/`=======`/`>>>>>>>` markers in the file
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    conflict_markers: bool,
+    /// How HEAD differs from the baseline, when the baseline is not HEAD's
+    #[serde(skip_serializing_if = "Option::is_none")]
+    upstream: Option<UpstreamChange>,
+}
+
+/// Change between an overlay's baseline and the file in HEAD
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+enum UpstreamChange {
+    /// HEAD changed the text by +added / -removed lines
+    Changed { added: usize, removed: usize },
+    /// HEAD has different binary content
+    BinaryChanged,
+    /// The file no longer exists in HEAD
+    Deleted,
+}
+
+/// `status --check` exit codes, one per kind of problem. 1 is left for
+/// errors and 2 for `verify`.
+pub const EXIT_STASH_REMAINING: i32 = 3;
+pub const EXIT_STALE_LOCK: i32 = 4;
+pub const EXIT_SUSPENDED: i32 = 5;
+pub const EXIT_OUTDATED: i32 = 6;
+
+/// Totals for the `Summary:` line
+#[derive(Debug, Default, PartialEq)]
+struct StatusSummary {
+    overlays: usize,
+    phantoms: usize,
+    needs_rebase: usize,
+    missing: usize,
+    conflicted: usize,
+    suspended: usize,
+    stash_remaining: bool,
+    stale_lock: bool,
+}
+
+impl StatusSummary {
+    fn from_report(report: &StatusReport) -> Self {
+        let mut summary = StatusSummary {
+            stash_remaining: report.stash_remaining,
+            stale_lock: report.lock == "stale",
+            ..Default::default()
+        };
+        for file in &report.files {
+            match file.file_type {
+                FileType::Overlay => summary.overlays += 1,
+                FileType::Phantom => summary.phantoms += 1,
+            }
+            if file.suspended {
+                summary.suspended += 1;
+                continue;
+            }
+            if !file.exists {
+                summary.missing += 1;
+            }
+            if file.baseline_outdated {
+                summary.needs_rebase += 1;
+            }
+            if file.conflict_markers {
+                summary.conflicted += 1;
+            }
+        }
+        summary
+    }
+
+    fn has_problems(&self) -> bool {
+        self.needs_rebase > 0
+            || self.missing > 0
+            || self.conflicted > 0
+            || self.stash_remaining
+            || self.stale_lock
+    }
+}
 
-pub fn run() -> Result<()> {
+/// Exit code for `status --check`. When several problems are present, the
+/// first in the order stash, stale lock, suspended, outdated baseline wins.
+fn check_exit_code(summary: &StatusSummary) -> i32 {
+    if summary.stash_remaining {
+        EXIT_STASH_REMAINING
+    } else if summary.stale_lock {
+        EXIT_STALE_LOCK
+    } else if summary.suspended > 0 {
+        EXIT_SUSPENDED
+    } else if summary.needs_rebase > 0 {
+        EXIT_OUTDATED
+    } else {
+        0
+    }
+}
+
+/// `Summary: 3 overlay, 2 phantom, 1 needs rebase`; zero counts and absent
+/// problems are left out
+fn format_summary(summary: &StatusSummary) -> String {
+    let mut parts = vec![
+        format!("{} overlay", summary.overlays),
+        format!("{} phantom", summary.phantoms),
+    ];
+    if summary.needs_rebase > 0 {
+        parts.push(format!("{} needs rebase", summary.needs_rebase));
+    }
+    if summary.missing > 0 {
+        parts.push(format!("{} missing", summary.missing));
+    }
+    if summary.conflicted > 0 {
+        parts.push(format!("{} conflicted", summary.conflicted));
+    }
+    if summary.suspended > 0 {
+        parts.push(format!("{} suspended", summary.suspended));
+    }
+    if summary.stash_remaining {
+        parts.push("stash remaining".to_string());
+    }
+    if summary.stale_lock {
+        parts.push("stale lock".to_string());
+    }
+    format!("Summary: {}", parts.join(", "))
+}
+
+/// `status --porcelain`: one `TYPE\tPATH\tSTATE\tADDED\tREMOVED` line per
+/// file, no header and no colors. The columns are fixed; new information
+/// only ever goes into new columns at the end. Like `git diff --numstat`,
+/// line counts are `-` where there are none (phantoms, binary overlays).
+fn format_porcelain(report: &StatusReport) -> String {
+    let mut output = String::new();
+    for file in &report.files {
+        let file_type = match file.file_type {
+            FileType::Overlay => "overlay",
+            FileType::Phantom => "phantom",
+        };
+        let state = if file.suspended {
+            "suspended"
+        } else if !file.exists {
+            "missing"
+        } else if file.conflict_markers {
+            "conflict"
+        } else if file.upstream == Some(UpstreamChange::Deleted) {
+            "deleted"
+        } else if file.baseline_outdated {
+            "outdated"
+        } else {
+            "ok"
+        };
+        let (added, removed) = if file.file_type == FileType::Overlay && !file.is_binary {
+            (file.added.to_string(), file.removed.to_string())
+        } else {
+            ("-".to_string(), "-".to_string())
+        };
+        output.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            file_type, file.path, state, added, removed
+        ));
+    }
+    output
+}
+
+fn print_summary(git: &GitRepo, config: &ShadowConfig, ignore_whitespace: bool) -> Result<()> {
+    let summary = StatusSummary::from_report(&build_report(git, config, ignore_whitespace)?);
+    let line = format_summary(&summary);
+    if summary.has_problems() {
+        println!("{}", line.yellow());
+    } else {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Print the status. With `check`, returns the `EXIT_*` code of the first
+/// problem found (see `check_exit_code`); otherwise 0.
+pub fn run(
+    json: bool,
+    porcelain: bool,
+    git_mode: bool,
+    summary_only: bool,
+    ignore_whitespace: bool,
+    check: bool,
+) -> Result<i32> {
     let git = GitRepo::discover(&std::env::current_dir()?)?;
     let config = ShadowConfig::load(&git.shadow_dir)?;
 
+    show(
+        &git,
+        &config,
+        json,
+        porcelain,
+        git_mode,
+        summary_only,
+        ignore_whitespace,
+    )?;
+
+    if !check {
+        return Ok(0);
+    }
+    let report = build_report(&git, &config, ignore_whitespace)?;
+    Ok(check_exit_code(&StatusSummary::from_report(&report)))
+}
+
+fn show(
+    git: &GitRepo,
+    config: &ShadowConfig,
+    json: bool,
+    porcelain: bool,
+    git_mode: bool,
+    summary_only: bool,
+    ignore_whitespace: bool,
+) -> Result<()> {
+    if git_mode {
+        let raw = git.status_porcelain()?;
+        print!(
+            "{}",
+            format_git_status(git, config, &raw, ignore_whitespace)
+        );
+        return Ok(());
+    }
+
+    if json {
+        let report = build_report(git, config, ignore_whitespace)?;
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if porcelain {
+        print!(
+            "{}",
+            format_porcelain(&build_report(git, config, ignore_whitespace)?)
+        );
+        return Ok(());
+    }
+
+    if summary_only {
+        return print_summary(git, config, ignore_whitespace);
+    }
+
     // Check for stash remnants
-    let stash_dir = git.shadow_dir.join("stash");
-    if stash_dir.exists() {
-        let stash_files: Vec<_> = std::fs::read_dir(&stash_dir)?
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
-            .collect();
-        if !stash_files.is_empty() {
-            println!(
-                "{}",
-                "  warning: stash has remaining files (a previous commit may have been interrupted)"
-                    .yellow()
-            );
-            println!("{}", "    -> Run `git-shadow restore`".yellow());
-            println!();
-        }
+    if has_stash_remnants(git)? {
+        println!(
+            "{}",
+            "  warning: stash has remaining files (a previous commit may have been interrupted)"
+                .yellow()
+        );
+        println!("{}", "    -> Run `git-shadow restore`".yellow());
+        println!();
     }
 
-    // Check for stale lock
-    if let LockStatus::Stale(info) = lock::check_lock(&git.shadow_dir)? {
+    // Check for temp files an interrupted write left behind
+    let temp_files = path::orphan_temp_files(&git.shadow_dir);
+    if !temp_files.is_empty() {
         println!(
             "{}",
             format!(
-                "  warning: stale lockfile detected (PID {} no longer exists)",
-                info.pid
+                "  warning: {} leftover temp file(s) in .git/shadow/ (a previous write may have been interrupted)",
+                temp_files.len()
             )
             .yellow()
         );
-        println!("{}", "    -> Run `git-shadow restore`".yellow());
+        println!(
+            "{}",
+            "    -> Run `git-shadow restore` to remove them".yellow()
+        );
         println!();
     }
 
+    // Check for stale lock
+    match lock::check_lock(&git.shadow_dir)? {
+        LockStatus::Stale(info) => {
+            println!(
+                "{}",
+                format!(
+                    "  warning: stale lockfile detected (PID {} no longer exists, acquired {} ago)",
+                    info.pid,
+                    lock::format_age(info.age())
+                )
+                .yellow()
+            );
+            println!("{}", "    -> Run `git-shadow restore`".yellow());
+            println!();
+        }
+        LockStatus::HeldByOther(info)
+            if info.is_expired(Settings::load(&git.shadow_dir)?.lock_timeout()?) =>
+        {
+            println!(
+                "{}",
+                format!(
+                    "  warning: lockfile has been held by PID {} for {} (the PID may have been reused)",
+                    info.pid,
+                    lock::format_age(info.age())
+                )
+                .yellow()
+            );
+            println!(
+                "{}",
+                "    -> If no commit is running, run `git-shadow restore --force-lock`".yellow()
+            );
+            println!();
+        }
+        _ => {}
+    }
+
     if config.files.is_empty() {
         println!("no managed files");
         return Ok(());
     }
 
-    if config.suspended {
+    let suspended_count = config.files.values().filter(|e| e.suspended).count();
+    if suspended_count > 0 {
         println!(
             "{}",
-            "  status: SUSPENDED (run `git-shadow resume` to restore shadow changes)".yellow()
+            format!(
+                "  status: SUSPENDED ({} file(s); run `git-shadow resume` to restore shadow changes)",
+                suspended_count
+            )
+            .yellow()
         );
         println!();
     }
 
+    let head = HeadTree::read(git, config);
+
     println!("managed files:");
     println!();
 
     for (file_path, entry) in &config.files {
+        if entry.suspended {
+            let kind = match entry.file_type {
+                FileType::Overlay => "overlay",
+                FileType::Phantom => "phantom",
+            };
+            println!("  {} ({})", file_path, kind);
+            print_note(entry);
+            println!("{}", "    SUSPENDED".yellow());
+            println!();
+            continue;
+        }
+
         match entry.file_type {
             FileType::Overlay => {
-                println!("  {} (overlay)", file_path);
+                if entry.is_binary {
+                    println!("  {} (overlay, binary)", file_path);
+                } else {
+                    println!("  {} (overlay)", file_path);
+                }
+                print_note(entry);
                 if let Some(ref commit) = entry.baseline_commit {
-                    println!("    baseline: {}", &commit[..7.min(commit.len())]);
+                    println!("    baseline: {}", short_hash(commit));
                 }
 
                 // Show diff stats
-                let encoded = path::encode_path(file_path);
-                let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
+                let blob = entry.baseline_blob.as_deref();
                 let worktree_path = git.root.join(file_path);
 
                 if !worktree_path.exists() {
@@ -76,45 +399,43 @@ pub fn run() -> Result<()> {
                         "{}",
                         "    warning: file does not exist in working tree".yellow()
                     );
-                } else if baseline_path.exists() {
-                    let baseline = std::fs::read_to_string(&baseline_path).unwrap_or_default();
-                    let current = std::fs::read_to_string(&worktree_path).unwrap_or_default();
-                    let (added, removed) = diff_stats(&baseline, &current);
-                    println!("    shadow changes: +{} lines / -{} lines", added, removed);
+                } else if baseline::exists(git, file_path, blob) {
+                    if entry.is_binary {
+                        let baseline = baseline::read(git, file_path, blob).unwrap_or_default();
+                        let current = std::fs::read(&worktree_path).unwrap_or_default();
+                        let state = if baseline == current {
+                            "unchanged"
+                        } else {
+                            "binary differs"
+                        };
+                        println!("    shadow changes: {}", state);
+                    } else {
+                        let baseline =
+                            baseline::read_to_string(git, file_path, blob).unwrap_or_default();
+                        let current = std::fs::read_to_string(&worktree_path).unwrap_or_default();
+                        let (added, removed) = shadow_stats(&baseline, &current, ignore_whitespace);
+                        println!("    shadow changes: +{} lines / -{} lines", added, removed);
+                        if merge::has_conflict_markers(&current) {
+                            println!(
+                                "{}",
+                                "    warning: unresolved conflict markers (commits are blocked)"
+                                    .yellow()
+                            );
+                            println!(
+                                "{}",
+                                format!(
+                                    "    -> Resolve the conflicts in {} and remove the markers",
+                                    file_path
+                                )
+                                .yellow()
+                            );
+                        }
+                    }
 
                     // Check baseline drift (hash mismatch + content comparison)
-                    if let Some(ref commit) = entry.baseline_commit {
-                        if let Ok(head) = git.head_commit() {
-                            if *commit != head {
-                                // Hash differs — check if file content actually changed
-                                let content_changed = git
-                                    .show_file("HEAD", file_path)
-                                    .ok()
-                                    .map(|head_content| {
-                                        let baseline_bytes =
-                                            std::fs::read(&baseline_path).unwrap_or_default();
-                                        baseline_bytes != head_content
-                                    })
-                                    .unwrap_or(false);
-
-                                if content_changed {
-                                    println!(
-                                        "{}",
-                                        format!(
-                                            "    warning: baseline is outdated ({} -> {})",
-                                            &commit[..7.min(commit.len())],
-                                            &head[..7.min(head.len())]
-                                        )
-                                        .yellow()
-                                    );
-                                    println!(
-                                        "{}",
-                                        format!("    -> Run `git-shadow rebase {}`", file_path)
-                                            .yellow()
-                                    );
-                                }
-                            }
-                        }
+                    if let (Some(commit), Some(head)) = (&entry.baseline_commit, &head) {
+                        let change = upstream_change(git, file_path, entry, commit, head);
+                        print_upstream_change(file_path, change, commit, &head.commit);
                     }
                 }
                 println!();
@@ -126,13 +447,20 @@ pub fn run() -> Result<()> {
                     "phantom"
                 };
                 println!("  {} ({})", file_path, label);
+                print_note(entry);
                 match entry.exclude_mode {
                     crate::config::ExcludeMode::GitInfoExclude => {
                         println!("    exclude: .git/info/exclude");
                     }
+                    crate::config::ExcludeMode::Gitignore => {
+                        println!("    exclude: gitignore");
+                    }
                     crate::config::ExcludeMode::None => {
                         println!("    exclude: none (hook protection only)");
                     }
+                    crate::config::ExcludeMode::AlreadyIgnored => {
+                        println!("    exclude: already gitignored (no entry added)");
+                    }
                 }
                 let worktree_path = git.root.join(file_path);
                 if entry.is_directory {
@@ -155,23 +483,338 @@ pub fn run() -> Result<()> {
         }
     }
 
-    Ok(())
+    print_summary(git, config, ignore_whitespace)
+}
+
+fn print_upstream_change(
+    file_path: &str,
+    change: Option<UpstreamChange>,
+    commit: &str,
+    head: &str,
+) {
+    let Some(change) = change else {
+        return;
+    };
+    let commit = short_hash(commit);
+    let head = short_hash(head);
+    if change == UpstreamChange::Deleted {
+        println!(
+            "{}",
+            format!(
+                "    warning: file was deleted upstream ({} -> {})",
+                commit, head
+            )
+            .yellow()
+        );
+        println!(
+            "{}",
+            format!(
+                "    -> Run `git-shadow remove {}` if you no longer need it",
+                file_path
+            )
+            .yellow()
+        );
+        return;
+    }
+
+    println!(
+        "{}",
+        format!("    warning: baseline is outdated ({} -> {})", commit, head).yellow()
+    );
+    match change {
+        UpstreamChange::Changed { added, removed } => println!(
+            "    upstream changes: +{} lines / -{} lines",
+            added, removed
+        ),
+        UpstreamChange::BinaryChanged => println!("    upstream changes: binary differs"),
+        UpstreamChange::Deleted => {}
+    }
+    println!(
+        "{}",
+        format!("    -> Run `git-shadow rebase {}`", file_path).yellow()
+    );
+}
+
+fn print_note(entry: &FileEntry) {
+    if let Some(note) = &entry.note {
+        println!("    note: {}", note);
+    }
+}
+
+/// One record of `git status --porcelain=v1 -z`
+#[derive(Debug, PartialEq)]
+struct PorcelainEntry {
+    code: String,
+    path: String,
+    orig_path: Option<String>,
+}
+
+/// Split porcelain output into the `## branch` header and file entries
+fn parse_porcelain(raw: &str) -> (Option<String>, Vec<PorcelainEntry>) {
+    let mut branch = None;
+    let mut entries = Vec::new();
+    let mut records = raw.split('\0').filter(|r| !r.is_empty());
+
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("## ") {
+            branch = Some(header.to_string());
+            continue;
+        }
+        if record.len() < 4 {
+            continue;
+        }
+        let code = record[..2].to_string();
+        let path = record[3..].to_string();
+        // Renames and copies are followed by a separate origPath record
+        let orig_path = if code.contains('R') || code.contains('C') {
+            records.next().map(str::to_string)
+        } else {
+            None
+        };
+        entries.push(PorcelainEntry {
+            code,
+            path,
+            orig_path,
+        });
+    }
+
+    (branch, entries)
+}
+
+/// Whether `path` (as printed by git) is, or lies inside, a managed file
+fn is_shadow_managed(config: &ShadowConfig, path: &str) -> bool {
+    let path = path.trim_end_matches('/');
+    config.files.keys().any(|managed| {
+        path == managed
+            || path
+                .strip_prefix(managed.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
 }
 
-fn diff_stats(old: &str, new: &str) -> (usize, usize) {
-    let diff = similar::TextDiff::from_lines(old, new);
-    let mut added = 0;
-    let mut removed = 0;
+/// Render `git status` in short form with shadow-managed entries moved out
+/// into their own section
+fn format_git_status(
+    git: &GitRepo,
+    config: &ShadowConfig,
+    raw: &str,
+    ignore_whitespace: bool,
+) -> String {
+    let (branch, entries) = parse_porcelain(raw);
+    let mut out = String::new();
 
-    for change in diff.iter_all_changes() {
-        match change.tag() {
-            similar::ChangeTag::Insert => added += 1,
-            similar::ChangeTag::Delete => removed += 1,
-            _ => {}
+    if let Some(branch) = branch {
+        out.push_str(&format!("## {}\n", branch));
+    }
+
+    let mut shown = 0;
+    for entry in &entries {
+        if is_shadow_managed(config, &entry.path) {
+            continue;
         }
+        let (index, worktree) = entry.code.split_at(1);
+        let path = match &entry.orig_path {
+            Some(orig) => format!("{} -> {}", orig, entry.path),
+            None => entry.path.clone(),
+        };
+        out.push_str(&format!("{}{} {}\n", index.green(), worktree.red(), path));
+        shown += 1;
+    }
+    if shown == 0 {
+        out.push_str("nothing to commit outside shadow-managed files\n");
+    }
+
+    if !config.files.is_empty() {
+        out.push_str(&format!("\n{}\n", "Shadow-managed (local only):".cyan()));
+        for (file_path, entry) in &config.files {
+            out.push_str(&format!(
+                "  {}\n",
+                shadow_line(git, file_path, entry, ignore_whitespace)
+            ));
+        }
+    }
+
+    out
+}
+
+/// `overlay: path (+N/-M)` / `phantom: path` line for the shadow section
+fn shadow_line(
+    git: &GitRepo,
+    file_path: &str,
+    entry: &FileEntry,
+    ignore_whitespace: bool,
+) -> String {
+    let kind = match entry.file_type {
+        FileType::Overlay => "overlay",
+        FileType::Phantom => "phantom",
+    };
+    let worktree_path = git.root.join(file_path);
+
+    let detail = if entry.suspended {
+        Some("suspended".to_string())
+    } else if !worktree_path.exists() {
+        Some("missing".to_string())
+    } else if entry.file_type == FileType::Overlay {
+        let blob = entry.baseline_blob.as_deref();
+        if entry.is_binary {
+            let baseline = baseline::read(git, file_path, blob).unwrap_or_default();
+            let current = std::fs::read(&worktree_path).unwrap_or_default();
+            Some(
+                if baseline == current {
+                    "binary, unchanged"
+                } else {
+                    "binary differs"
+                }
+                .to_string(),
+            )
+        } else {
+            let baseline = baseline::read_to_string(git, file_path, blob).unwrap_or_default();
+            let current = std::fs::read_to_string(&worktree_path).unwrap_or_default();
+            let (added, removed) = shadow_stats(&baseline, &current, ignore_whitespace);
+            Some(format!("+{}/-{}", added, removed))
+        }
+    } else {
+        None
+    };
+
+    match detail {
+        Some(detail) => format!("{}: {} ({})", kind, file_path, detail),
+        None => format!("{}: {}", kind, file_path),
+    }
+}
+
+fn build_report(
+    git: &GitRepo,
+    config: &ShadowConfig,
+    ignore_whitespace: bool,
+) -> Result<StatusReport> {
+    let lock = match lock::check_lock(&git.shadow_dir)? {
+        LockStatus::Free => "free",
+        LockStatus::Stale(_) => "stale",
+        LockStatus::HeldByUs | LockStatus::HeldByOther(_) => "held",
+    };
+    let head = HeadTree::read(git, config);
+
+    let mut files = Vec::new();
+    for (file_path, entry) in &config.files {
+        let worktree_path = git.root.join(file_path);
+        let mut report = FileReport {
+            path: file_path.clone(),
+            file_type: entry.file_type.clone(),
+            baseline_commit: entry.baseline_commit.clone(),
+            is_directory: entry.is_directory,
+            is_binary: entry.is_binary,
+            suspended: entry.suspended,
+            note: entry.note.clone(),
+            exists: worktree_path.exists(),
+            added: 0,
+            removed: 0,
+            baseline_outdated: false,
+            conflict_markers: false,
+            upstream: None,
+        };
+
+        if entry.file_type == FileType::Overlay && report.exists && !entry.suspended {
+            let blob = entry.baseline_blob.as_deref();
+            if baseline::exists(git, file_path, blob) && !entry.is_binary {
+                let baseline = baseline::read_to_string(git, file_path, blob).unwrap_or_default();
+                let current = std::fs::read_to_string(&worktree_path).unwrap_or_default();
+                (report.added, report.removed) =
+                    shadow_stats(&baseline, &current, ignore_whitespace);
+                report.conflict_markers = merge::has_conflict_markers(&current);
+            }
+            if let (Some(commit), Some(head)) = (&entry.baseline_commit, &head) {
+                report.upstream = upstream_change(git, file_path, entry, commit, head);
+                report.baseline_outdated = matches!(
+                    report.upstream,
+                    Some(UpstreamChange::Changed { .. } | UpstreamChange::BinaryChanged)
+                );
+            }
+        }
+
+        files.push(report);
+    }
+
+    Ok(StatusReport {
+        suspended: config.is_suspended(),
+        stash_remaining: has_stash_remnants(git)?,
+        lock,
+        files,
+    })
+}
+
+/// Lines added/removed by the shadow changes, optionally ignoring line
+/// endings and trailing whitespace
+fn shadow_stats(baseline: &str, current: &str, ignore_whitespace: bool) -> (usize, usize) {
+    diff_stats(
+        &normalize_whitespace(baseline, ignore_whitespace),
+        &normalize_whitespace(current, ignore_whitespace),
+    )
+}
+
+fn has_stash_remnants(git: &GitRepo) -> Result<bool> {
+    Ok(!path::stored_files(&git.shadow_dir.join("stash"))?.is_empty())
+}
+
+/// HEAD and the blob ids of its files, read once per `status` instead of
+/// running git for every overlay
+struct HeadTree {
+    commit: String,
+    blobs: HashMap<String, String>,
+}
+
+impl HeadTree {
+    /// None without a HEAD. The tree is only listed when some baseline was
+    /// taken at another commit.
+    fn read(git: &GitRepo, config: &ShadowConfig) -> Option<Self> {
+        let commit = git.head_commit().ok()?;
+        let moved = config.files.values().any(|entry| {
+            entry.file_type == FileType::Overlay
+                && !entry.suspended
+                && entry.baseline_commit.as_ref().is_some_and(|c| *c != commit)
+        });
+        let blobs = if moved {
+            git.tree_blobs(&commit).ok()?
+        } else {
+            HashMap::new()
+        };
+        Some(Self { commit, blobs })
     }
+}
 
-    (added, removed)
+/// Baseline is outdated when HEAD moved and the file content in HEAD differs
+/// from the saved baseline. None while the baseline matches HEAD. Blob ids
+/// are compared first; content is only read to count changed lines.
+fn upstream_change(
+    git: &GitRepo,
+    file_path: &str,
+    entry: &FileEntry,
+    commit: &str,
+    head: &HeadTree,
+) -> Option<UpstreamChange> {
+    if commit == head.commit {
+        return None;
+    }
+    let Some(head_oid) = head.blobs.get(file_path) else {
+        return Some(UpstreamChange::Deleted);
+    };
+    let blob = entry.baseline_blob.as_deref();
+    if baseline::oid(git, file_path, blob).is_ok_and(|oid| oid == *head_oid) {
+        return None;
+    }
+    if entry.is_binary {
+        return Some(UpstreamChange::BinaryChanged);
+    }
+    let head_content = git.read_blob(head_oid).ok()?;
+    let baseline = baseline::read(git, file_path, blob).unwrap_or_default();
+    if baseline == head_content {
+        return None;
+    }
+    let (added, removed) = diff_stats(
+        &String::from_utf8_lossy(&baseline),
+        &String::from_utf8_lossy(&head_content),
+    );
+    Some(UpstreamChange::Changed { added, removed })
 }
 
 fn format_size(bytes: u64) -> String {
@@ -230,4 +873,350 @@ mod tests {
     fn test_format_size_mb() {
         assert_eq!(format_size(1_572_864), "1.5 MB");
     }
+
+    fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.email", "t@t.com"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::fs::write(root.join("CLAUDE.md"), "# Team\n").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "CLAUDE.md"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+
+        let repo = GitRepo::discover(&root).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("baselines")).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("stash")).unwrap();
+        (dir, repo)
+    }
+
+    #[test]
+    fn test_build_report_json_shape() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let commit = git.head_commit().unwrap();
+        std::fs::write(
+            git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            "# Team\n",
+        )
+        .unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# Mine\n").unwrap();
+
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+        config
+            .add_phantom(
+                "local.md".to_string(),
+                crate::config::ExcludeMode::None,
+                false,
+            )
+            .unwrap();
+        config.files.get_mut("local.md").unwrap().note = Some("scratch".to_string());
+
+        let report = build_report(&git, &config, false).unwrap();
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["suspended"], false);
+        assert_eq!(json["stash_remaining"], false);
+        assert_eq!(json["lock"], "free");
+        let files = json["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["path"], "CLAUDE.md");
+        assert_eq!(files[0]["type"], "overlay");
+        assert_eq!(files[0]["added"], 1);
+        assert_eq!(files[0]["removed"], 0);
+        assert_eq!(files[0]["baseline_outdated"], false);
+        assert_eq!(files[1]["type"], "phantom");
+        assert!(files[1].get("baseline_commit").is_none());
+        assert!(files[0].get("note").is_none());
+        assert_eq!(files[1]["note"], "scratch");
+    }
+
+    #[test]
+    fn test_build_report_ignore_whitespace() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let commit = git.head_commit().unwrap();
+        std::fs::write(
+            git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            "# Team\n",
+        )
+        .unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team \r\n# Mine\r\n").unwrap();
+
+        let report = build_report(&git, &config, false).unwrap();
+        assert_eq!((report.files[0].added, report.files[0].removed), (2, 1));
+        let report = build_report(&git, &config, true).unwrap();
+        assert_eq!((report.files[0].added, report.files[0].removed), (1, 0));
+    }
+
+    #[test]
+    fn test_build_report_detects_stash_and_outdated_baseline() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let old_commit = git.head_commit().unwrap();
+        std::fs::write(
+            git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            "# Team\n",
+        )
+        .unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), old_commit)
+            .unwrap();
+
+        std::fs::write(git.root.join("CLAUDE.md"), "# Updated\n").unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-am", "update"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        std::fs::write(git.shadow_dir.join("stash").join("old.md"), "remnant").unwrap();
+
+        let report = build_report(&git, &config, false).unwrap();
+        assert!(report.stash_remaining);
+        assert!(report.files[0].baseline_outdated);
+        assert_eq!(
+            report.files[0].upstream,
+            Some(UpstreamChange::Changed {
+                added: 1,
+                removed: 1
+            })
+        );
+
+        config
+            .add_phantom(
+                "gone.md".to_string(),
+                crate::config::ExcludeMode::None,
+                false,
+            )
+            .unwrap();
+        let summary = StatusSummary::from_report(&build_report(&git, &config, false).unwrap());
+        assert!(summary.has_problems());
+        assert_eq!(
+            format_summary(&summary),
+            "Summary: 1 overlay, 1 phantom, 1 needs rebase, 1 missing, stash remaining"
+        );
+    }
+
+    #[test]
+    fn test_build_report_reports_file_deleted_upstream() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let old_commit = git.head_commit().unwrap();
+        std::fs::write(
+            git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            "# Team\n",
+        )
+        .unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), old_commit)
+            .unwrap();
+
+        std::process::Command::new("git")
+            .args(["rm", "-q", "--cached", "CLAUDE.md"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "drop CLAUDE.md"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+
+        let report = build_report(&git, &config, false).unwrap();
+        assert_eq!(report.files[0].upstream, Some(UpstreamChange::Deleted));
+        assert!(!report.files[0].baseline_outdated);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["files"][0]["upstream"]["state"], "deleted");
+        assert_eq!(
+            format_porcelain(&report),
+            "overlay\tCLAUDE.md\tdeleted\t0\t0\n"
+        );
+    }
+
+    #[test]
+    fn test_format_porcelain_states_and_counts() {
+        let file = |path: &str, file_type: FileType| FileReport {
+            path: path.to_string(),
+            file_type,
+            baseline_commit: None,
+            is_directory: false,
+            is_binary: false,
+            suspended: false,
+            note: None,
+            exists: true,
+            added: 0,
+            removed: 0,
+            baseline_outdated: false,
+            conflict_markers: false,
+            upstream: None,
+        };
+        let report = StatusReport {
+            suspended: false,
+            stash_remaining: false,
+            lock: "free",
+            files: vec![
+                FileReport {
+                    added: 3,
+                    removed: 1,
+                    baseline_outdated: true,
+                    ..file("CLAUDE.md", FileType::Overlay)
+                },
+                FileReport {
+                    is_binary: true,
+                    ..file("logo.png", FileType::Overlay)
+                },
+                FileReport {
+                    conflict_markers: true,
+                    baseline_outdated: true,
+                    ..file("merged.md", FileType::Overlay)
+                },
+                FileReport {
+                    exists: false,
+                    ..file("local.md", FileType::Phantom)
+                },
+                FileReport {
+                    suspended: true,
+                    exists: false,
+                    ..file("notes", FileType::Phantom)
+                },
+            ],
+        };
+        assert_eq!(
+            format_porcelain(&report),
+            "overlay\tCLAUDE.md\toutdated\t3\t1\n\
+             overlay\tlogo.png\tok\t-\t-\n\
+             overlay\tmerged.md\tconflict\t0\t0\n\
+             phantom\tlocal.md\tmissing\t-\t-\n\
+             phantom\tnotes\tsuspended\t-\t-\n"
+        );
+    }
+
+    #[test]
+    fn test_format_summary_omits_zero_counts() {
+        let summary = StatusSummary {
+            overlays: 3,
+            phantoms: 2,
+            suspended: 1,
+            ..Default::default()
+        };
+        assert!(!summary.has_problems());
+        assert_eq!(
+            format_summary(&summary),
+            "Summary: 3 overlay, 2 phantom, 1 suspended"
+        );
+    }
+
+    #[test]
+    fn test_check_exit_code_per_problem() {
+        let mut summary = StatusSummary {
+            overlays: 1,
+            conflicted: 1,
+            ..Default::default()
+        };
+        assert_eq!(check_exit_code(&summary), 0);
+
+        summary.needs_rebase = 1;
+        assert_eq!(check_exit_code(&summary), EXIT_OUTDATED);
+        summary.suspended = 1;
+        assert_eq!(check_exit_code(&summary), EXIT_SUSPENDED);
+        summary.stale_lock = true;
+        assert_eq!(check_exit_code(&summary), EXIT_STALE_LOCK);
+        summary.stash_remaining = true;
+        assert_eq!(check_exit_code(&summary), EXIT_STASH_REMAINING);
+    }
+
+    #[test]
+    fn test_parse_porcelain_branch_and_renames() {
+        let raw = "## main...origin/main\0 M src/lib.rs\0R  new.md\0old.md\0?? notes/\0";
+        let (branch, entries) = parse_porcelain(raw);
+        assert_eq!(branch.as_deref(), Some("main...origin/main"));
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].code, " M");
+        assert_eq!(entries[0].path, "src/lib.rs");
+        assert_eq!(entries[1].path, "new.md");
+        assert_eq!(entries[1].orig_path.as_deref(), Some("old.md"));
+        assert_eq!(entries[2].path, "notes/");
+    }
+
+    #[test]
+    fn test_is_shadow_managed_matches_files_and_directories() {
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), "abc".to_string())
+            .unwrap();
+        config
+            .add_phantom("local".to_string(), crate::config::ExcludeMode::None, true)
+            .unwrap();
+
+        assert!(is_shadow_managed(&config, "CLAUDE.md"));
+        assert!(is_shadow_managed(&config, "local/"));
+        assert!(is_shadow_managed(&config, "local/notes.md"));
+        assert!(!is_shadow_managed(&config, "localized.md"));
+        assert!(!is_shadow_managed(&config, "src/CLAUDE.md"));
+    }
+
+    #[test]
+    fn test_format_git_status_moves_managed_files_to_section() {
+        let (_dir, git) = make_test_repo();
+        let mut config = ShadowConfig::new();
+        let commit = git.head_commit().unwrap();
+        std::fs::write(
+            git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            "# Team\n",
+        )
+        .unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# Mine\n").unwrap();
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+        config
+            .add_phantom(
+                "local.md".to_string(),
+                crate::config::ExcludeMode::None,
+                false,
+            )
+            .unwrap();
+        std::fs::write(git.root.join("notes.txt"), "todo\n").unwrap();
+
+        let raw = git.status_porcelain().unwrap();
+        let out = format_git_status(&git, &config, &raw, false);
+
+        let (regular, shadow) = out.split_once("Shadow-managed (local only):").unwrap();
+        assert!(regular.contains("notes.txt"));
+        assert!(!regular.contains("CLAUDE.md"));
+        assert!(!regular.contains("local.md"));
+        assert!(shadow.contains("overlay: CLAUDE.md (+1/-0)"));
+        assert!(shadow.contains("phantom: local.md"));
+    }
 }
@@ -1,36 +1,27 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
 
+use crate::baseline;
 use crate::config::{FileType, ShadowConfig};
 use crate::error::ShadowError;
 use crate::fs_util;
 use crate::git::GitRepo;
+use crate::history::{self, Action};
 use crate::lock::{self, LockStatus};
 use crate::path;
 
-pub fn run() -> Result<()> {
+pub fn run(files: &[String]) -> Result<()> {
     let git = GitRepo::discover(&std::env::current_dir()?)?;
     let mut config = ShadowConfig::load(&git.shadow_dir)?;
 
-    // Guard: already suspended
-    if config.suspended {
-        return Err(ShadowError::AlreadySuspended.into());
-    }
-
     // Guard: lock exists (commit in progress)
     if !matches!(lock::check_lock(&git.shadow_dir)?, LockStatus::Free) {
         anyhow::bail!("cannot suspend while a commit is in progress");
     }
 
     // Guard: stash has remaining files
-    let stash_dir = git.shadow_dir.join("stash");
-    if stash_dir.exists() {
-        let has_files = std::fs::read_dir(&stash_dir)?
-            .filter_map(|e| e.ok())
-            .any(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false));
-        if has_files {
-            return Err(ShadowError::StashRemaining.into());
-        }
+    if !path::stored_files(&git.shadow_dir.join("stash"))?.is_empty() {
+        return Err(ShadowError::StashRemaining.into());
     }
 
     if config.files.is_empty() {
@@ -38,29 +29,13 @@ pub fn run() -> Result<()> {
         return Ok(());
     }
 
-    // Create suspended directory
-    let suspended_dir = git.shadow_dir.join("suspended");
-    std::fs::create_dir_all(&suspended_dir).context("failed to create suspended directory")?;
-
-    let mut count = 0;
-
-    for (file_path, entry) in &config.files {
-        match entry.file_type {
-            FileType::Overlay => {
-                suspend_overlay(&git, &suspended_dir, file_path)?;
-                count += 1;
-            }
-            FileType::Phantom => {
-                if !entry.is_directory {
-                    suspend_phantom(&git, &suspended_dir, file_path)?;
-                    count += 1;
-                }
-            }
-        }
-    }
+    let normalized = files
+        .iter()
+        .map(|f| path::normalize_path(f, &git.prefix, &git.root))
+        .collect::<Result<Vec<_>>>()?;
+    let targets = select_targets(&config, &normalized)?;
 
-    config.suspended = true;
-    config.save(&git.shadow_dir)?;
+    let count = suspend_files(&git, &mut config, &targets)?;
 
     println!(
         "{}",
@@ -71,21 +46,118 @@ pub fn run() -> Result<()> {
     Ok(())
 }
 
-fn suspend_overlay(git: &GitRepo, suspended_dir: &std::path::Path, file_path: &str) -> Result<()> {
+/// Files to suspend: the given paths, or every file that is not suspended yet.
+/// Phantom directories stay in the working tree and are never suspended.
+fn select_targets(config: &ShadowConfig, files: &[String]) -> Result<Vec<String>> {
+    if files.is_empty() {
+        let targets: Vec<String> = config
+            .files
+            .iter()
+            .filter(|(_, e)| !e.suspended && !e.is_directory)
+            .map(|(p, _)| p.clone())
+            .collect();
+        if targets.is_empty() && config.is_suspended() {
+            return Err(ShadowError::AlreadySuspended.into());
+        }
+        return Ok(targets);
+    }
+
+    let mut targets = Vec::new();
+    for file_path in files {
+        let entry = config
+            .get(file_path)
+            .ok_or_else(|| ShadowError::NotManaged(file_path.clone()))?;
+        if entry.is_directory {
+            bail!(
+                "{} is a phantom directory, which is never suspended",
+                file_path
+            );
+        }
+        if entry.suspended {
+            bail!("{} is already suspended", file_path);
+        }
+        if !targets.contains(file_path) {
+            targets.push(file_path.clone());
+        }
+    }
+    Ok(targets)
+}
+
+/// Park the shadow content of `targets` in `.git/shadow/suspended/` and mark
+/// them suspended. Returns the number of files suspended.
+/// Guard for commands that change what is managed (`add`, `remove`). While
+/// files are suspended the working tree holds baselines, which must not be
+/// mistaken for shadow changes; `force` continues with a warning.
+pub(crate) fn check_not_suspended(config: &ShadowConfig, force: bool) -> Result<()> {
+    let suspended = config.suspended_files();
+    if suspended.is_empty() {
+        return Ok(());
+    }
+    if !force {
+        bail!(
+            "shadow changes are suspended ({}). Run `git-shadow resume` first, or use --force to continue anyway",
+            suspended.join(", ")
+        );
+    }
+    eprintln!(
+        "{}",
+        format!(
+            "warning: shadow changes are suspended ({}); continuing because of --force",
+            suspended.join(", ")
+        )
+        .yellow()
+    );
+    Ok(())
+}
+
+pub(crate) fn suspend_files(
+    git: &GitRepo,
+    config: &mut ShadowConfig,
+    targets: &[String],
+) -> Result<usize> {
+    let suspended_dir = git.shadow_dir.join("suspended");
+    std::fs::create_dir_all(&suspended_dir).context("failed to create suspended directory")?;
+
+    for file_path in targets {
+        let Some(entry) = config.files.get_mut(file_path) else {
+            continue;
+        };
+        match entry.file_type {
+            FileType::Overlay => suspend_overlay(
+                git,
+                &suspended_dir,
+                file_path,
+                entry.baseline_blob.as_deref(),
+            )?,
+            FileType::Phantom => suspend_phantom(git, &suspended_dir, file_path)?,
+        }
+        entry.suspended = true;
+        // Save after each file so a later failure keeps the flags accurate
+        config.save(&git.shadow_dir)?;
+        history::record(&git.shadow_dir, Action::Suspend, Some(file_path), None);
+    }
+
+    Ok(targets.len())
+}
+
+fn suspend_overlay(
+    git: &GitRepo,
+    suspended_dir: &std::path::Path,
+    file_path: &str,
+    blob: Option<&str>,
+) -> Result<()> {
     let encoded = path::encode_path(file_path);
     let worktree_path = git.root.join(file_path);
-    let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
     let suspend_path = suspended_dir.join(&encoded);
 
     // Save current working tree content (with shadow changes) to suspended/
     let content =
         std::fs::read(&worktree_path).with_context(|| format!("failed to read {}", file_path))?;
-    fs_util::atomic_write(&suspend_path, &content)
+    fs_util::atomic_write_with_mode_of(&suspend_path, &content, &worktree_path)
         .with_context(|| format!("failed to save suspended content for {}", file_path))?;
 
     // Restore baseline content to working tree
-    let baseline = std::fs::read(&baseline_path)
-        .with_context(|| format!("failed to read baseline for {}", file_path))?;
+    let baseline = baseline::read(git, file_path, blob)?;
     std::fs::write(&worktree_path, &baseline)
         .with_context(|| format!("failed to restore baseline for {}", file_path))?;
 
@@ -104,7 +176,7 @@ fn suspend_phantom(git: &GitRepo, suspended_dir: &std::path::Path, file_path: &s
     // Save phantom content to suspended/
     let content =
         std::fs::read(&worktree_path).with_context(|| format!("failed to read {}", file_path))?;
-    fs_util::atomic_write(&suspend_path, &content)
+    fs_util::atomic_write_with_mode_of(&suspend_path, &content, &worktree_path)
         .with_context(|| format!("failed to save suspended content for {}", file_path))?;
 
     // Remove phantom from working tree
@@ -179,7 +251,7 @@ mod tests {
         // Suspend
         let suspended_dir = git.shadow_dir.join("suspended");
         std::fs::create_dir_all(&suspended_dir).unwrap();
-        super::suspend_overlay(&git, &suspended_dir, "CLAUDE.md").unwrap();
+        super::suspend_overlay(&git, &suspended_dir, "CLAUDE.md", None).unwrap();
 
         // Working tree should have baseline content
         let wt = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
@@ -234,30 +306,90 @@ mod tests {
         // Add shadow changes
         std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# My shadow\n").unwrap();
 
-        assert!(!config.suspended);
+        assert!(!config.is_suspended());
 
-        // Simulate suspend logic
-        let suspended_dir = git.shadow_dir.join("suspended");
-        std::fs::create_dir_all(&suspended_dir).unwrap();
-        super::suspend_overlay(&git, &suspended_dir, "CLAUDE.md").unwrap();
-        config.suspended = true;
-        config.save(&git.shadow_dir).unwrap();
+        let targets = vec!["CLAUDE.md".to_string()];
+        super::suspend_files(&git, &mut config, &targets).unwrap();
 
         // Reload and verify
         let loaded = ShadowConfig::load(&git.shadow_dir).unwrap();
-        assert!(loaded.suspended);
+        assert!(loaded.get("CLAUDE.md").unwrap().suspended);
+    }
+
+    #[test]
+    fn test_suspend_only_given_files() {
+        let (_dir, git) = make_test_repo();
+        let commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n# My shadow\n").unwrap();
+        std::fs::write(git.root.join("local.md"), "# Local\n").unwrap();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+
+        let targets = super::select_targets(&config, &["local.md".to_string()]).unwrap();
+        assert_eq!(targets, vec!["local.md".to_string()]);
+        super::suspend_files(&git, &mut config, &targets).unwrap();
+
+        assert!(config.get("local.md").unwrap().suspended);
+        assert!(!config.get("CLAUDE.md").unwrap().suspended);
+        assert!(!git.root.join("local.md").exists());
+        let wt = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(wt, "# Team\n# My shadow\n");
+
+        // Without arguments, only the remaining file is selected
+        let rest = super::select_targets(&config, &[]).unwrap();
+        assert_eq!(rest, vec!["CLAUDE.md".to_string()]);
     }
 
     #[test]
     fn test_suspend_blocks_when_already_suspended() {
         let (_dir, git) = make_test_repo();
+        let commit = git.head_commit().unwrap();
         let mut config = ShadowConfig::new();
-        config.suspended = true;
-        config.save(&git.shadow_dir).unwrap();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        config.files.get_mut("CLAUDE.md").unwrap().suspended = true;
+
+        let err = super::select_targets(&config, &[]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<crate::error::ShadowError>(),
+            Some(crate::error::ShadowError::AlreadySuspended)
+        ));
+        assert!(super::select_targets(&config, &["CLAUDE.md".to_string()]).is_err());
+    }
 
-        // Should detect already suspended via config
-        let loaded = ShadowConfig::load(&git.shadow_dir).unwrap();
-        assert!(loaded.suspended);
+    #[test]
+    fn test_check_not_suspended() {
+        let (_dir, git) = make_test_repo();
+        let commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+        super::check_not_suspended(&config, false).unwrap();
+
+        config.files.get_mut("CLAUDE.md").unwrap().suspended = true;
+        let err = super::check_not_suspended(&config, false).unwrap_err();
+        assert!(err.to_string().contains("suspended (CLAUDE.md)"));
+        super::check_not_suspended(&config, true).unwrap();
+    }
+
+    #[test]
+    fn test_suspend_rejects_unmanaged_and_directory() {
+        let mut config = ShadowConfig::new();
+        config
+            .add_phantom(".claude".to_string(), ExcludeMode::None, true)
+            .unwrap();
+
+        assert!(super::select_targets(&config, &["nope.md".to_string()]).is_err());
+        assert!(super::select_targets(&config, &[".claude".to_string()]).is_err());
+        assert!(super::select_targets(&config, &[]).unwrap().is_empty());
     }
 
     #[test]
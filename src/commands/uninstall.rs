@@ -0,0 +1,174 @@
+use anyhow::{bail, Context, Result};
+
+use crate::baseline;
+use crate::commands::clean;
+use crate::commands::install::{self, HookUninstall};
+use crate::config::ShadowConfig;
+use crate::git::GitRepo;
+
+pub fn run(purge: bool) -> Result<()> {
+    let git = GitRepo::discover(&std::env::current_dir()?)?;
+    if purge {
+        check_purgeable(&git)?;
+    }
+
+    let hooks = install::plan_uninstall(&git);
+    install::warn_hand_written(&hooks);
+    install::uninstall_hooks(&git, &hooks)?;
+    for (hook_name, action) in &hooks {
+        match action {
+            HookUninstall::Delete => println!("removed {} hook", hook_name),
+            HookUninstall::RestoreBackup => {
+                println!("restored {}.pre-shadow as {} hook", hook_name, hook_name)
+            }
+            HookUninstall::KeepHandWritten => {}
+        }
+    }
+    if hooks.is_empty() {
+        println!("no git-shadow hooks found in {}", git.hooks_dir().display());
+    }
+
+    if let Some(key) = install::installed_status_alias(&git) {
+        git.config_unset(key)?;
+        println!("unset {}", key);
+    }
+
+    if purge {
+        purge_shadow_data(&git)?;
+        println!("deleted .git/shadow/");
+    } else if git.shadow_dir.exists() {
+        println!("kept .git/shadow/ (use --purge to delete it, or `git-shadow install` to set the hooks up again)");
+    }
+    Ok(())
+}
+
+/// Purging deletes the baselines, so it is only allowed once no file is
+/// managed. `clean` is the command that also unregisters files.
+fn check_purgeable(git: &GitRepo) -> Result<()> {
+    let config = ShadowConfig::load(&git.shadow_dir)?;
+    clean::check_cleanable(git, &config)?;
+    if !config.files.is_empty() {
+        bail!(
+            "{} file(s) are still managed. Remove them first, or run `git-shadow clean` to remove everything",
+            config.files.len()
+        );
+    }
+    Ok(())
+}
+
+fn purge_shadow_data(git: &GitRepo) -> Result<()> {
+    for name in git.list_refs(baseline::REF_PREFIX)? {
+        git.delete_ref(&name)?;
+    }
+    if git.shadow_dir.exists() {
+        std::fs::remove_dir_all(&git.shadow_dir).context("failed to delete .git/shadow/")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.email", "t@t.com"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        let repo = GitRepo::discover(&root).unwrap();
+        (dir, repo)
+    }
+
+    fn write_hook(path: &std::path::Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_uninstall_restores_chained_hooks() {
+        let (_dir, git) = make_test_repo();
+        let existing = "#!/bin/sh\necho lint\n";
+        write_hook(&git.hooks_dir().join("pre-commit"), existing);
+        install::install_hooks(&git, false).unwrap();
+        assert!(git.hooks_dir().join("pre-commit.pre-shadow").exists());
+
+        let hooks = install::plan_uninstall(&git);
+        install::uninstall_hooks(&git, &hooks).unwrap();
+
+        let pre_commit = git.hooks_dir().join("pre-commit");
+        assert_eq!(std::fs::read_to_string(&pre_commit).unwrap(), existing);
+        assert!(!git.hooks_dir().join("pre-commit.pre-shadow").exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&pre_commit).unwrap().permissions().mode();
+            assert_ne!(mode & 0o111, 0);
+        }
+        for name in ["post-commit", "post-merge", "post-checkout"] {
+            assert!(!git.hooks_dir().join(name).exists());
+        }
+        // Shadow data is kept without --purge
+        assert!(git.shadow_dir.exists());
+    }
+
+    #[test]
+    fn test_uninstall_keeps_hand_written_and_foreign_hooks() {
+        let (_dir, git) = make_test_repo();
+        git.config_set("core.hooksPath", ".githooks").unwrap();
+        install::install_hooks(&git, false).unwrap();
+        let hooks_dir = git.root.join(".githooks");
+        write_hook(
+            &hooks_dir.join("pre-commit"),
+            "#!/bin/sh\nrun-lint\ngit-shadow hook pre-commit \"$@\"\n",
+        );
+        write_hook(&hooks_dir.join("post-merge"), "#!/bin/sh\necho merged\n");
+
+        let hooks = install::plan_uninstall(&git);
+        assert!(hooks.contains(&("pre-commit", HookUninstall::KeepHandWritten)));
+        assert!(!hooks.iter().any(|(name, _)| *name == "post-merge"));
+        install::uninstall_hooks(&git, &hooks).unwrap();
+
+        assert!(hooks_dir.join("pre-commit").exists());
+        assert!(hooks_dir.join("post-merge").exists());
+        assert!(!hooks_dir.join("post-commit").exists());
+    }
+
+    #[test]
+    fn test_purge_requires_no_managed_files() {
+        let (_dir, git) = make_test_repo();
+        install::install_hooks(&git, false).unwrap();
+        let mut config = ShadowConfig::new();
+        config
+            .add_phantom(
+                "local.md".to_string(),
+                crate::config::ExcludeMode::None,
+                false,
+            )
+            .unwrap();
+        config.save(&git.shadow_dir).unwrap();
+        assert!(check_purgeable(&git).is_err());
+
+        ShadowConfig::new().save(&git.shadow_dir).unwrap();
+        check_purgeable(&git).unwrap();
+        purge_shadow_data(&git).unwrap();
+        assert!(!git.shadow_dir.exists());
+    }
+}
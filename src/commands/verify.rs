@@ -0,0 +1,185 @@
+use anyhow::Result;
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::commands::doctor;
+use crate::config::ShadowConfig;
+use crate::git::GitRepo;
+
+/// Exit code when verification fails
+pub const EXIT_FAILED: i32 = 2;
+
+/// Machine-readable result for `verify --json`
+#[derive(Debug, Serialize)]
+struct VerifyReport {
+    ok: bool,
+    strict: bool,
+    issues: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Run the doctor checks and return the process exit code: 0 when clean
+/// (or warnings only), `EXIT_FAILED` on issues, or on warnings with `strict`.
+/// Stash remnants and suspended changes count as issues here.
+pub fn run(quiet: bool, json: bool, strict: bool) -> Result<i32> {
+    let git = GitRepo::discover(&std::env::current_dir()?)?;
+    let report = verify(&git, strict)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if !quiet {
+        print_report(&report);
+    }
+
+    Ok(if report.ok { 0 } else { EXIT_FAILED })
+}
+
+fn verify(git: &GitRepo, strict: bool) -> Result<VerifyReport> {
+    // Nothing is managed in a repository that never used git-shadow
+    let (issues, warnings) = if git.shadow_dir.exists() {
+        let config = ShadowConfig::load(&git.shadow_dir)?;
+        doctor::run_checks(git, &config, true)
+    } else {
+        (Vec::new(), Vec::new())
+    };
+
+    Ok(VerifyReport {
+        ok: issues.is_empty() && (!strict || warnings.is_empty()),
+        strict,
+        issues,
+        warnings,
+    })
+}
+
+fn print_report(report: &VerifyReport) {
+    for issue in &report.issues {
+        println!("{} {}", "issue:".red(), issue);
+    }
+    for warning in &report.warnings {
+        println!("{} {}", "warning:".yellow(), warning);
+    }
+    if report.ok {
+        println!("{}", "verify: ok".green());
+    } else {
+        println!("{}", "verify: failed".red());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::install;
+
+    fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.email", "t@t.com"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::fs::write(root.join("CLAUDE.md"), "# Team\n").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "CLAUDE.md"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+
+        let repo = GitRepo::discover(&root).unwrap();
+        (dir, repo)
+    }
+
+    #[test]
+    fn test_uninitialized_repo_passes() {
+        let (_dir, git) = make_test_repo();
+        let report = verify(&git, true).unwrap();
+        assert!(report.ok);
+        assert!(report.issues.is_empty());
+    }
+
+    #[test]
+    fn test_missing_hooks_fail() {
+        let (_dir, git) = make_test_repo();
+        std::fs::create_dir_all(git.shadow_dir.join("stash")).unwrap();
+
+        let report = verify(&git, false).unwrap();
+        assert!(!report.ok);
+        assert!(report
+            .issues
+            .iter()
+            .any(|i| i.contains("hook does not exist")));
+    }
+
+    #[test]
+    fn test_warnings_fail_only_when_strict() {
+        let (_dir, git) = make_test_repo();
+        install::install_hooks(&git, false).unwrap();
+        std::fs::create_dir_all(git.root.join(".husky")).unwrap();
+
+        let report = verify(&git, false).unwrap();
+        assert!(report.ok);
+        assert!(report.issues.is_empty());
+        assert!(report
+            .warnings
+            .iter()
+            .any(|w| w.contains("competing hook manager")));
+
+        let report = verify(&git, true).unwrap();
+        assert!(!report.ok);
+    }
+
+    #[test]
+    fn test_stash_remnants_fail_without_strict() {
+        let (_dir, git) = make_test_repo();
+        install::install_hooks(&git, false).unwrap();
+        std::fs::write(git.shadow_dir.join("stash").join("leftover"), "x").unwrap();
+
+        let report = verify(&git, false).unwrap();
+        assert!(!report.ok);
+        assert!(report.issues.iter().any(|i| i.contains("stash")));
+    }
+
+    #[test]
+    fn test_suspended_fails_without_strict() {
+        let (_dir, git) = make_test_repo();
+        install::install_hooks(&git, false).unwrap();
+        let mut config = ShadowConfig::load(&git.shadow_dir).unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), git.head_commit().unwrap())
+            .unwrap();
+        config.files.get_mut("CLAUDE.md").unwrap().suspended = true;
+        config.save(&git.shadow_dir).unwrap();
+
+        let report = verify(&git, false).unwrap();
+        assert!(!report.ok);
+        assert!(report.issues.iter().any(|i| i.contains("suspended")));
+    }
+
+    #[test]
+    fn test_json_shape() {
+        let (_dir, git) = make_test_repo();
+        install::install_hooks(&git, false).unwrap();
+
+        let report = verify(&git, false).unwrap();
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["ok"], true);
+        assert_eq!(json["strict"], false);
+        assert!(json["issues"].as_array().unwrap().is_empty());
+        assert!(json["warnings"].as_array().unwrap().is_empty());
+    }
+}
@@ -1,12 +1,90 @@
 use std::collections::BTreeMap;
 use std::path::Path;
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use crate::error::ShadowError;
-use crate::fs_util;
+use crate::lock::{self, LockStatus};
+use crate::{fs_util, path};
+
+/// Current config.json schema version
+pub const CONFIG_VERSION: u32 = 3;
+
+/// Environment variable choosing the format of a new config file
+pub const CONFIG_FORMAT_ENV: &str = "GIT_SHADOW_CONFIG_FORMAT";
+
+/// On-disk format of the config file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Json => "config.json",
+            Self::Toml => "config.toml",
+        }
+    }
+
+    /// Format of the existing config file; `config.toml` wins when both exist
+    pub fn detect(shadow_dir: &Path) -> Option<Self> {
+        [Self::Toml, Self::Json]
+            .into_iter()
+            .find(|format| shadow_dir.join(format.file_name()).exists())
+    }
+
+    /// Format for a new config file: `GIT_SHADOW_CONFIG_FORMAT`, else JSON
+    pub fn for_new_config() -> anyhow::Result<Self> {
+        Self::from_env(std::env::var(CONFIG_FORMAT_ENV).ok().as_deref())
+    }
+
+    fn from_env(env: Option<&str>) -> anyhow::Result<Self> {
+        match env.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("" | "json") => Ok(Self::Json),
+            Some("toml") => Ok(Self::Toml),
+            Some(other) => bail!(
+                "invalid {} '{}' (expected json or toml)",
+                CONFIG_FORMAT_ENV,
+                other
+            ),
+        }
+    }
+
+    /// Parse into a JSON value so both formats share `migrate`
+    fn parse(self, content: &str) -> anyhow::Result<serde_json::Value> {
+        match self {
+            Self::Json => Ok(serde_json::from_str(content)?),
+            Self::Toml => Ok(toml_to_json(toml::from_str(content)?)),
+        }
+    }
+
+    fn serialize(self, config: &ShadowConfig) -> anyhow::Result<String> {
+        match self {
+            Self::Json => Ok(serde_json::to_string_pretty(config)?),
+            Self::Toml => Ok(toml::to_string_pretty(config)?),
+        }
+    }
+}
+
+/// TOML datetimes (e.g. a hand-written `added_at`) become RFC 3339 strings
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => serde_json::json!(i),
+        toml::Value::Float(f) => serde_json::json!(f),
+        toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+        toml::Value::Datetime(d) => serde_json::Value::String(d.to_string()),
+        toml::Value::Array(items) => items.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => table
+            .into_iter()
+            .map(|(key, value)| (key, toml_to_json(value)))
+            .collect(),
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -19,7 +97,11 @@ pub enum FileType {
 #[serde(rename_all = "snake_case")]
 pub enum ExcludeMode {
     GitInfoExclude,
+    Gitignore,
     None,
+    /// An ignore rule already matched the path when it was added, so no
+    /// exclude entry was written
+    AlreadyIgnored,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,24 +114,43 @@ pub struct FileEntry {
     #[serde(default)]
     #[serde(skip_serializing_if = "std::ops::Not::not")]
     pub is_directory: bool,
+    /// Binary overlays are replace-only: their content is never merged
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_binary: bool,
+    /// Shadow content is parked in `.git/shadow/suspended/`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub suspended: bool,
+    /// Free-form reason for managing the file (`add --note`, `note`)
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Object id of the baseline blob when it is kept in git's object store
+    /// (`object_baselines`) instead of `baselines/`
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline_blob: Option<String>,
     pub added_at: DateTime<Utc>,
+    /// Files of a phantom directory when it was registered, as path within
+    /// the directory -> SHA-1 (`fs_util::dir_manifest`). Configs from before
+    /// it was recorded have none.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<BTreeMap<String, String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShadowConfig {
     pub version: u32,
     pub files: BTreeMap<String, FileEntry>,
-    #[serde(default)]
-    #[serde(skip_serializing_if = "std::ops::Not::not")]
-    pub suspended: bool,
 }
 
 impl Default for ShadowConfig {
     fn default() -> Self {
         Self {
-            version: 1,
+            version: CONFIG_VERSION,
             files: BTreeMap::new(),
-            suspended: false,
         }
     }
 }
@@ -59,27 +160,92 @@ impl ShadowConfig {
         Self::default()
     }
 
+    /// Whether `config.json` or `config.toml` exists
+    pub fn exists(shadow_dir: &Path) -> bool {
+        ConfigFormat::detect(shadow_dir).is_some()
+    }
+
     pub fn load(shadow_dir: &Path) -> anyhow::Result<Self> {
-        let config_path = shadow_dir.join("config.json");
-        if !config_path.exists() {
+        let Some(format) = ConfigFormat::detect(shadow_dir) else {
             return Ok(Self::new());
+        };
+        let name = format.file_name();
+        let content = std::fs::read_to_string(shadow_dir.join(name))
+            .with_context(|| format!("failed to read {}", name))?;
+        let value = format
+            .parse(&content)
+            .with_context(|| format!("failed to parse {}", name))?;
+        let legacy_storage = value
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .is_none_or(|v| v < 3);
+        let mut config = migrate(value)?;
+
+        if legacy_storage && !config.migrate_storage_locked(shadow_dir)? {
+            // Stays below v3 so a later load renames the files
+            config.version = 2;
         }
-        let content =
-            std::fs::read_to_string(&config_path).context("failed to read config.json")?;
-        let config: Self = serde_json::from_str(&content).context("failed to parse config.json")?;
         Ok(config)
     }
 
+    /// Rename legacy stored files to hashed names and save the config, under
+    /// the shadow lock so files are never renamed under a running hook.
+    /// Returns false, leaving everything untouched, while another process
+    /// holds the lock.
+    fn migrate_storage_locked(&self, shadow_dir: &Path) -> anyhow::Result<bool> {
+        // pre-commit loads the config after taking the lock itself
+        if matches!(lock::check_lock(shadow_dir)?, LockStatus::HeldByUs) {
+            return self.migrate_storage(shadow_dir).map(|()| true);
+        }
+        match lock::acquire_lock(shadow_dir) {
+            Ok(()) => {}
+            Err(ShadowError::LockHeld { .. } | ShadowError::StaleLock(_)) => return Ok(false),
+            Err(e) => return Err(e.into()),
+        }
+        let result = self.migrate_storage(shadow_dir);
+        lock::release_lock(shadow_dir)?;
+        result.map(|()| true)
+    }
+
+    /// Files are renamed on disk, so the new version is saved right away to
+    /// keep the rename from running twice
+    fn migrate_storage(&self, shadow_dir: &Path) -> anyhow::Result<()> {
+        path::migrate_legacy_storage(shadow_dir)?;
+        self.save(shadow_dir)
+    }
+
+    /// Save in the format of the existing file, or the one chosen by
+    /// `GIT_SHADOW_CONFIG_FORMAT` for a new file
     pub fn save(&self, shadow_dir: &Path) -> anyhow::Result<()> {
-        let config_path = shadow_dir.join("config.json");
-        let content =
-            serde_json::to_string_pretty(self).context("failed to serialize config.json")?;
-        fs_util::atomic_write(&config_path, content.as_bytes())
-            .context("failed to write config.json")?;
+        let format = match ConfigFormat::detect(shadow_dir) {
+            Some(format) => format,
+            None => ConfigFormat::for_new_config()?,
+        };
+        self.save_as(shadow_dir, format)
+    }
+
+    fn save_as(&self, shadow_dir: &Path, format: ConfigFormat) -> anyhow::Result<()> {
+        let name = format.file_name();
+        let content = format
+            .serialize(self)
+            .with_context(|| format!("failed to serialize {}", name))?;
+        fs_util::atomic_write(&shadow_dir.join(name), content.as_bytes())
+            .with_context(|| format!("failed to write {}", name))?;
+        path::update_path_map(shadow_dir, self.files.keys().map(String::as_str))?;
         Ok(())
     }
 
     pub fn add_overlay(&mut self, path: String, commit: String) -> Result<(), ShadowError> {
+        self.add_overlay_with(path, commit, false)
+    }
+
+    /// Register an overlay, optionally as a replace-only binary overlay
+    pub fn add_overlay_with(
+        &mut self,
+        path: String,
+        commit: String,
+        is_binary: bool,
+    ) -> Result<(), ShadowError> {
         if self.files.contains_key(&path) {
             return Err(ShadowError::AlreadyManaged(path));
         }
@@ -90,7 +256,12 @@ impl ShadowConfig {
                 baseline_commit: Some(commit),
                 exclude_mode: ExcludeMode::None,
                 is_directory: false,
+                is_binary,
+                suspended: false,
+                note: None,
+                baseline_blob: None,
                 added_at: Utc::now(),
+                manifest: None,
             },
         );
         Ok(())
@@ -112,7 +283,12 @@ impl ShadowConfig {
                 baseline_commit: None,
                 exclude_mode: exclude,
                 is_directory,
+                is_binary: false,
+                suspended: false,
+                note: None,
+                baseline_blob: None,
                 added_at: Utc::now(),
+                manifest: None,
             },
         );
         Ok(())
@@ -127,6 +303,104 @@ impl ShadowConfig {
     pub fn get(&self, path: &str) -> Option<&FileEntry> {
         self.files.get(path)
     }
+
+    /// `baseline_blob` of `path`, if any
+    pub fn baseline_blob(&self, path: &str) -> Option<String> {
+        self.files.get(path).and_then(|e| e.baseline_blob.clone())
+    }
+
+    /// Record where the baseline of `path` is stored (see `baseline::write`)
+    pub fn set_baseline_blob(&mut self, path: &str, blob: Option<String>) {
+        if let Some(entry) = self.files.get_mut(path) {
+            entry.baseline_blob = blob;
+        }
+    }
+
+    /// True when at least one file is suspended
+    pub fn is_suspended(&self) -> bool {
+        self.files.values().any(|e| e.suspended)
+    }
+
+    /// Paths of suspended files, in config order
+    pub fn suspended_files(&self) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|(_, e)| e.suspended)
+            .map(|(p, _)| p.clone())
+            .collect()
+    }
+}
+
+/// Upgrade a raw config.json value to the current schema.
+/// A missing `version` field is treated as version 0 (before versioning).
+pub fn migrate(mut value: serde_json::Value) -> anyhow::Result<ShadowConfig> {
+    let version = match value.get("version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .context("config.json has an invalid version field")?,
+    };
+
+    if version > u64::from(CONFIG_VERSION) {
+        return Err(ShadowError::UnsupportedConfigVersion {
+            found: version,
+            supported: CONFIG_VERSION,
+        }
+        .into());
+    }
+
+    for from in version..u64::from(CONFIG_VERSION) {
+        match from {
+            0 => migrate_v0_to_v1(&mut value)?,
+            1 => migrate_v1_to_v2(&mut value)?,
+            // v3 renamed stored files to hashed names (see `ShadowConfig::load`)
+            2 => {
+                value["version"] = serde_json::json!(3);
+            }
+            _ => bail!("no migration from config.json version {}", from),
+        }
+    }
+
+    serde_json::from_value(value).context("failed to parse config.json")
+}
+
+/// v0 configs predate the `version` field and may omit `files` when empty
+fn migrate_v0_to_v1(value: &mut serde_json::Value) -> anyhow::Result<()> {
+    let object = value
+        .as_object_mut()
+        .context("config.json is not a JSON object")?;
+    object
+        .entry("files")
+        .or_insert_with(|| serde_json::json!({}));
+    object.insert("version".to_string(), serde_json::json!(1));
+    Ok(())
+}
+
+/// v1 had a single top-level `suspended` flag; v2 tracks it per file.
+/// Phantom directories are never suspended.
+fn migrate_v1_to_v2(value: &mut serde_json::Value) -> anyhow::Result<()> {
+    let object = value
+        .as_object_mut()
+        .context("config.json is not a JSON object")?;
+    let suspended = object
+        .remove("suspended")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if suspended {
+        if let Some(files) = object.get_mut("files").and_then(|f| f.as_object_mut()) {
+            for entry in files.values_mut().filter_map(|e| e.as_object_mut()) {
+                let is_directory = entry
+                    .get("is_directory")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                if !is_directory {
+                    entry.insert("suspended".to_string(), serde_json::json!(true));
+                }
+            }
+        }
+    }
+    object.insert("version".to_string(), serde_json::json!(2));
+    Ok(())
 }
 
 #[cfg(test)]
@@ -136,7 +410,7 @@ mod tests {
     #[test]
     fn test_new_config() {
         let config = ShadowConfig::new();
-        assert_eq!(config.version, 1);
+        assert_eq!(config.version, CONFIG_VERSION);
         assert!(config.files.is_empty());
     }
 
@@ -153,6 +427,19 @@ mod tests {
         assert_eq!(entry.exclude_mode, ExcludeMode::None);
     }
 
+    #[test]
+    fn test_add_binary_overlay() {
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay_with("app.db".to_string(), "abc1234".to_string(), true)
+            .unwrap();
+
+        let entry = config.get("app.db").unwrap();
+        assert!(entry.is_binary);
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["files"]["app.db"]["is_binary"], true);
+    }
+
     #[test]
     fn test_add_phantom_with_exclude() {
         let mut config = ShadowConfig::new();
@@ -236,12 +523,13 @@ mod tests {
             .unwrap();
 
         let json = serde_json::to_value(&config).unwrap();
-        assert_eq!(json["version"], 1);
+        assert_eq!(json["version"], CONFIG_VERSION);
 
         let claude = &json["files"]["CLAUDE.md"];
         assert_eq!(claude["type"], "overlay");
         assert_eq!(claude["baseline_commit"], "abc1234def5678");
         assert_eq!(claude["exclude_mode"], "none");
+        assert!(claude.get("is_binary").is_none());
 
         let component = &json["files"]["src/components/CLAUDE.md"];
         assert_eq!(component["type"], "phantom");
@@ -322,6 +610,21 @@ mod tests {
         let config: ShadowConfig = serde_json::from_str(json).unwrap();
         let entry = config.get("local.md").unwrap();
         assert!(!entry.is_directory);
+        assert!(entry.note.is_none());
+    }
+
+    #[test]
+    fn test_serialize_note_only_when_set() {
+        let mut config = ShadowConfig::new();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+        let json = serde_json::to_value(&config).unwrap();
+        assert!(json["files"]["local.md"].get("note").is_none());
+
+        config.files.get_mut("local.md").unwrap().note = Some("local API key".to_string());
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["files"]["local.md"]["note"], "local API key");
     }
 
     #[test]
@@ -349,12 +652,79 @@ mod tests {
         config.save(&shadow_dir).unwrap();
 
         let loaded = ShadowConfig::load(&shadow_dir).unwrap();
-        assert_eq!(loaded.version, 1);
+        assert_eq!(loaded.version, CONFIG_VERSION);
         assert_eq!(loaded.files.len(), 1);
         let entry = loaded.get("CLAUDE.md").unwrap();
         assert_eq!(entry.file_type, FileType::Overlay);
     }
 
+    #[test]
+    fn test_json_and_toml_roundtrip() {
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("src/CLAUDE.md".to_string(), "abc1234".to_string())
+            .unwrap();
+        config
+            .add_phantom(".env.local".to_string(), ExcludeMode::GitInfoExclude, false)
+            .unwrap();
+        config.files.get_mut(".env.local").unwrap().note = Some("local \"secrets\"".to_string());
+        config.files.get_mut("src/CLAUDE.md").unwrap().suspended = true;
+        config.files.get_mut(".env.local").unwrap().manifest = Some(BTreeMap::from([(
+            "a/b.md".to_string(),
+            "0123abcd".to_string(),
+        )]));
+
+        for format in [ConfigFormat::Json, ConfigFormat::Toml] {
+            let dir = tempfile::tempdir().unwrap();
+            config.save_as(dir.path(), format).unwrap();
+            assert_eq!(ConfigFormat::detect(dir.path()), Some(format));
+
+            let loaded = ShadowConfig::load(dir.path()).unwrap();
+            assert_eq!(
+                serde_json::to_value(&loaded).unwrap(),
+                serde_json::to_value(&config).unwrap(),
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_save_keeps_existing_format() {
+        let dir = tempfile::tempdir().unwrap();
+        ShadowConfig::new()
+            .save_as(dir.path(), ConfigFormat::Toml)
+            .unwrap();
+
+        let mut config = ShadowConfig::load(dir.path()).unwrap();
+        config
+            .add_overlay("CLAUDE.md".to_string(), "abc1234".to_string())
+            .unwrap();
+        config.save(dir.path()).unwrap();
+
+        assert!(!dir.path().join("config.json").exists());
+        let content = std::fs::read_to_string(dir.path().join("config.toml")).unwrap();
+        assert!(content.contains("[files.\"CLAUDE.md\"]"));
+
+        // config.toml is read even when a config.json is left next to it
+        std::fs::write(
+            dir.path().join("config.json"),
+            r#"{"version": 3, "files": {}}"#,
+        )
+        .unwrap();
+        assert_eq!(ShadowConfig::load(dir.path()).unwrap().files.len(), 1);
+    }
+
+    #[test]
+    fn test_config_format_from_env() {
+        assert_eq!(ConfigFormat::from_env(None).unwrap(), ConfigFormat::Json);
+        assert_eq!(
+            ConfigFormat::from_env(Some(" TOML ")).unwrap(),
+            ConfigFormat::Toml
+        );
+        assert!(ConfigFormat::from_env(Some("yaml")).is_err());
+    }
+
     #[test]
     fn test_load_nonexistent_returns_new() {
         let dir = tempfile::tempdir().unwrap();
@@ -362,7 +732,176 @@ mod tests {
         std::fs::create_dir_all(&shadow_dir).unwrap();
 
         let config = ShadowConfig::load(&shadow_dir).unwrap();
-        assert_eq!(config.version, 1);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert!(config.files.is_empty());
+    }
+
+    #[test]
+    fn test_migrate_current_version() {
+        let value = serde_json::json!({
+            "version": 1,
+            "files": {
+                "CLAUDE.md": {
+                    "type": "overlay",
+                    "baseline_commit": "abc1234",
+                    "exclude_mode": "none",
+                    "added_at": "2026-01-01T00:00:00Z"
+                }
+            }
+        });
+        let config = migrate(value).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.files.len(), 1);
+    }
+
+    #[test]
+    fn test_migrate_v0_without_version() {
+        let value = serde_json::json!({
+            "files": {
+                "local.md": {
+                    "type": "phantom",
+                    "exclude_mode": "git_info_exclude",
+                    "added_at": "2026-01-01T00:00:00Z"
+                }
+            }
+        });
+        let config = migrate(value).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.get("local.md").unwrap().file_type, FileType::Phantom);
+    }
+
+    #[test]
+    fn test_migrate_v0_without_files() {
+        let config = migrate(serde_json::json!({})).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
         assert!(config.files.is_empty());
     }
+
+    #[test]
+    fn test_load_v2_renames_legacy_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("baselines")).unwrap();
+        std::fs::write(dir.path().join("baselines").join("src%2FCLAUDE.md"), "base").unwrap();
+        let value = serde_json::json!({
+            "version": 2,
+            "files": {
+                "src/CLAUDE.md": {
+                    "type": "overlay",
+                    "baseline_commit": "abc1234",
+                    "exclude_mode": "none",
+                    "added_at": "2026-01-01T00:00:00Z"
+                }
+            }
+        });
+        std::fs::write(dir.path().join("config.json"), value.to_string()).unwrap();
+
+        let config = ShadowConfig::load(dir.path()).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        let encoded = path::encode_path("src/CLAUDE.md");
+        assert!(dir.path().join("baselines").join(&encoded).exists());
+        assert!(!dir
+            .path()
+            .join("baselines")
+            .join("src%2FCLAUDE.md")
+            .exists());
+
+        // The new version is persisted, so loading again does not re-migrate
+        let saved = std::fs::read_to_string(dir.path().join("config.json")).unwrap();
+        assert!(saved.contains(&format!("\"version\": {}", CONFIG_VERSION)));
+        ShadowConfig::load(dir.path()).unwrap();
+        assert!(dir.path().join("baselines").join(&encoded).exists());
+    }
+
+    #[test]
+    fn test_load_v2_defers_storage_rename_while_locked() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("baselines")).unwrap();
+        std::fs::write(dir.path().join("baselines").join("src%2FCLAUDE.md"), "base").unwrap();
+        let value = serde_json::json!({ "version": 2, "files": {} });
+        std::fs::write(dir.path().join("config.json"), value.to_string()).unwrap();
+        // PID 1 is always alive
+        std::fs::write(
+            dir.path().join("lock"),
+            format!("pid=1\ntimestamp={}", Utc::now().to_rfc3339()),
+        )
+        .unwrap();
+
+        let config = ShadowConfig::load(dir.path()).unwrap();
+        assert_eq!(config.version, 2);
+        assert!(dir
+            .path()
+            .join("baselines")
+            .join("src%2FCLAUDE.md")
+            .exists());
+        let saved = std::fs::read_to_string(dir.path().join("config.json")).unwrap();
+        assert!(saved.contains("\"version\":2"));
+
+        // Once the lock is released, the next load migrates
+        std::fs::remove_file(dir.path().join("lock")).unwrap();
+        let config = ShadowConfig::load(dir.path()).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert!(!dir
+            .path()
+            .join("baselines")
+            .join("src%2FCLAUDE.md")
+            .exists());
+        assert!(!dir.path().join("lock").exists());
+    }
+
+    #[test]
+    fn test_migrate_v1_suspended_moves_to_entries() {
+        let value = serde_json::json!({
+            "version": 1,
+            "suspended": true,
+            "files": {
+                "CLAUDE.md": {
+                    "type": "overlay",
+                    "baseline_commit": "abc1234",
+                    "exclude_mode": "none",
+                    "added_at": "2026-01-01T00:00:00Z"
+                },
+                ".claude": {
+                    "type": "phantom",
+                    "exclude_mode": "git_info_exclude",
+                    "is_directory": true,
+                    "added_at": "2026-01-01T00:00:00Z"
+                }
+            }
+        });
+        let config = migrate(value).unwrap();
+        assert!(config.get("CLAUDE.md").unwrap().suspended);
+        assert!(!config.get(".claude").unwrap().suspended);
+        assert_eq!(config.suspended_files(), vec!["CLAUDE.md".to_string()]);
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert!(json.get("suspended").is_none());
+    }
+
+    #[test]
+    fn test_migrate_future_version_is_error() {
+        let value = serde_json::json!({ "version": CONFIG_VERSION + 1, "files": {} });
+        let err = migrate(value).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ShadowError>(),
+            Some(ShadowError::UnsupportedConfigVersion { .. })
+        ));
+        assert!(err.to_string().contains("Upgrade git-shadow"));
+    }
+
+    #[test]
+    fn test_migrate_invalid_version_is_error() {
+        let value = serde_json::json!({ "version": "one", "files": {} });
+        assert!(migrate(value).is_err());
+    }
+
+    #[test]
+    fn test_load_future_version_is_error() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("config.json"),
+            r#"{"version": 99, "files": {}}"#,
+        )
+        .unwrap();
+        assert!(ShadowConfig::load(dir.path()).is_err());
+    }
 }
@@ -0,0 +1,160 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+/// Global defaults shared by every repository, read from
+/// `~/.config/git-shadow/defaults.json`. A repository's `settings.json`
+/// takes precedence over anything here.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Defaults {
+    /// Paths or globs, relative to the repository root, that `init` and
+    /// `install --apply-defaults` register
+    #[serde(default)]
+    pub add: Vec<String>,
+}
+
+impl Defaults {
+    /// Load the defaults at `path`; a missing file means no defaults
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    /// Load the global defaults file, if there is a home directory
+    pub fn load_global() -> anyhow::Result<Self> {
+        match global_path() {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/git-shadow/defaults.json`, falling back to
+/// `~/.config/git-shadow/defaults.json`
+pub fn global_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("defaults.json"))
+}
+
+/// `$XDG_CONFIG_HOME/git-shadow`, falling back to `~/.config/git-shadow`
+pub fn config_dir() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_home.join("git-shadow"))
+}
+
+/// Patterns to register in this repository: `default_add` in settings.json
+/// if set (an empty list turns the defaults off), else the global `add`.
+/// The global file is only read when the repository does not decide.
+pub fn patterns(settings: &Settings) -> anyhow::Result<Vec<String>> {
+    match &settings.default_add {
+        Some(patterns) => Ok(patterns.clone()),
+        None => Ok(Defaults::load_global()?.add),
+    }
+}
+
+/// Existing paths below `root` matching `patterns`, relative to the root and
+/// sorted. A plain path that does not exist is skipped; `.git/` never matches.
+pub fn expand(root: &Path, patterns: &[String]) -> anyhow::Result<Vec<String>> {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+
+    let mut found = Vec::new();
+    for pattern in patterns {
+        let pattern = pattern.trim().trim_end_matches('/');
+        if pattern.is_empty() {
+            continue;
+        }
+        if !pattern.contains(['*', '?', '[']) {
+            if root.join(pattern).exists() {
+                found.push(pattern.replace('\\', "/"));
+            }
+            continue;
+        }
+
+        let full = root.join(pattern);
+        let matches = glob::glob_with(&full.to_string_lossy(), options)
+            .with_context(|| format!("invalid default pattern '{}'", pattern))?;
+        for path in matches.filter_map(Result::ok) {
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            if relative == ".git" || relative.starts_with(".git/") {
+                continue;
+            }
+            found.push(relative);
+        }
+    }
+
+    found.sort();
+    found.dedup();
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_and_parse() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("defaults.json");
+        assert_eq!(Defaults::load(&path).unwrap(), Defaults::default());
+
+        std::fs::write(&path, r#"{"add": ["CLAUDE.md", ".claude/"]}"#).unwrap();
+        assert_eq!(
+            Defaults::load(&path).unwrap().add,
+            ["CLAUDE.md", ".claude/"]
+        );
+    }
+
+    #[test]
+    fn test_repository_settings_take_precedence() {
+        let local = Settings {
+            default_add: Some(vec![".env.local".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(patterns(&local).unwrap(), [".env.local"]);
+
+        let disabled = Settings {
+            default_add: Some(Vec::new()),
+            ..Default::default()
+        };
+        assert!(patterns(&disabled).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_expand_skips_missing_and_git_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join(".claude")).unwrap();
+        std::fs::write(root.join("CLAUDE.md"), "").unwrap();
+        std::fs::write(root.join("docs/CLAUDE.md"), "").unwrap();
+        std::fs::write(root.join(".git/CLAUDE.md"), "").unwrap();
+
+        let patterns = vec![
+            "CLAUDE.md".to_string(),
+            "**/CLAUDE.md".to_string(),
+            ".claude/".to_string(),
+            "missing.md".to_string(),
+        ];
+        assert_eq!(
+            expand(root, &patterns).unwrap(),
+            [".claude", "CLAUDE.md", "docs/CLAUDE.md"]
+        );
+    }
+}
@@ -1,5 +1,22 @@
+use std::borrow::Cow;
+use std::io::Write;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
 
+use crate::cli::ColorWhen;
+
+/// Decide whether to emit ANSI colors. `auto` colors only a terminal and
+/// honors `NO_COLOR` (any non-empty value disables color).
+pub fn should_color(when: ColorWhen, no_color: Option<&std::ffi::OsStr>, is_tty: bool) -> bool {
+    match when {
+        ColorWhen::Always => true,
+        ColorWhen::Never => false,
+        ColorWhen::Auto => is_tty && no_color.is_none_or(|value| value.is_empty()),
+    }
+}
+
 /// Generate unified diff output between old and new text
 pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
     let diff = similar::TextDiff::from_lines(old, new);
@@ -15,6 +32,101 @@ pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> S
     output
 }
 
+/// Patch from old to new content of `path` that `git apply` accepts:
+/// `diff --git` and `a/`/`b/` headers, and a `\ No newline at end of file`
+/// line after a last line without one. Empty when the contents are equal.
+pub fn git_patch(old: &str, new: &str, path: &str) -> String {
+    let diff = similar::TextDiff::from_lines(old, new);
+    let mut output = String::new();
+
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        if output.is_empty() {
+            output.push_str(&format!("diff --git a/{} b/{}\n", path, path));
+            output.push_str(&format!("--- a/{}\n", path));
+            output.push_str(&format!("+++ b/{}\n", path));
+        }
+        output.push_str(&format!("{}\n", hunk.header()));
+        for change in hunk.iter_changes() {
+            output.push_str(&format!("{}{}", change.tag(), change.value()));
+            if change.missing_newline() {
+                output.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+
+    output
+}
+
+/// Apply a patch made by `git_patch` to `old`, the content it was made
+/// from. Fails if any context or removed line does not match.
+pub fn apply_git_patch(old: &str, patch: &str) -> Result<String> {
+    let old_lines: Vec<&str> = old.split_inclusive('\n').collect();
+    let mut output = String::new();
+    let mut next = 0; // index of the first old line not yet copied
+    let mut in_hunk = false;
+    let mut last_added = false;
+
+    for line in patch.split_inclusive('\n') {
+        if let Some(header) = line.strip_prefix("@@ -") {
+            let start = parse_hunk_start(header)
+                .with_context(|| format!("invalid hunk header: {}", line.trim_end()))?;
+            if start < next || start > old_lines.len() {
+                bail!("patch does not apply: hunk starts outside the content");
+            }
+            output.extend(old_lines[next..start].iter().copied());
+            next = start;
+            in_hunk = true;
+            continue;
+        }
+        if !in_hunk {
+            // `diff --git`, `---` and `+++` headers
+            continue;
+        }
+
+        let (tag, value) = line.split_at(1.min(line.len()));
+        match tag {
+            " " | "-" => {
+                let Some(current) = old_lines.get(next) else {
+                    bail!("patch does not apply: content ends early");
+                };
+                if current.trim_end_matches('\n') != value.trim_end_matches('\n') {
+                    bail!("patch does not apply: line {} differs", next + 1);
+                }
+                if tag == " " {
+                    output.push_str(current);
+                }
+                next += 1;
+            }
+            "+" => output.push_str(value),
+            "\\" => {
+                if last_added {
+                    output.pop();
+                }
+            }
+            _ => bail!("invalid patch line: {}", line.trim_end()),
+        }
+        last_added = tag == "+";
+    }
+
+    output.extend(old_lines[next..].iter().copied());
+    Ok(output)
+}
+
+/// 0-based index of the first old line a hunk covers, from the text after
+/// `@@ -`. An empty range (`-3,0`) inserts after line 3.
+fn parse_hunk_start(header: &str) -> Option<usize> {
+    let range = header.split_whitespace().next()?;
+    let (start, count) = match range.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+        None => (range.parse::<usize>().ok()?, 1),
+    };
+    Some(if count == 0 {
+        start
+    } else {
+        start.saturating_sub(1)
+    })
+}
+
 /// Print unified diff with colors to stdout
 pub fn print_colored_diff(old: &str, new: &str, old_label: &str, new_label: &str) {
     let diff = similar::TextDiff::from_lines(old, new);
@@ -37,6 +149,213 @@ pub fn print_colored_diff(old: &str, new: &str, old_label: &str, new_label: &str
     }
 }
 
+/// Unified diff with changed words highlighted inside changed lines.
+/// With `color`, deleted words get a red background and added words a
+/// green one; without it they are marked `[-deleted-]` and `{+added+}`.
+pub fn word_diff(old: &str, new: &str, old_label: &str, new_label: &str, color: bool) -> String {
+    let diff = similar::TextDiff::from_lines(old, new);
+    let mut output = String::new();
+
+    output.push_str(&format!(
+        "{}\n",
+        paint(&format!("--- {}", old_label), color, |s| s.red())
+    ));
+    output.push_str(&format!(
+        "{}\n",
+        paint(&format!("+++ {}", new_label), color, |s| s.green())
+    ));
+
+    for hunk in diff.unified_diff().context_radius(3).iter_hunks() {
+        output.push_str(&format!(
+            "{}\n",
+            paint(&hunk.header().to_string(), color, |s| s.cyan())
+        ));
+        for op in hunk.ops() {
+            for change in diff.iter_inline_changes(op) {
+                output.push_str(&format_inline_change(&change, color));
+                output.push('\n');
+            }
+        }
+    }
+
+    output
+}
+
+/// One `word_diff` line: sign followed by the line, with consecutive
+/// emphasized words merged into a single highlighted run
+fn format_inline_change(change: &similar::InlineChange<'_, str>, color: bool) -> String {
+    let tag = change.tag();
+    let sign = match tag {
+        similar::ChangeTag::Delete => "-",
+        similar::ChangeTag::Insert => "+",
+        similar::ChangeTag::Equal => " ",
+    };
+
+    let mut runs: Vec<(bool, String)> = Vec::new();
+    for (emphasized, value) in change.iter_strings_lossy() {
+        let value = value.trim_end_matches(['\n', '\r']);
+        match runs.last_mut() {
+            Some((last, text)) if *last == emphasized => text.push_str(value),
+            _ => runs.push((emphasized, value.to_string())),
+        }
+    }
+
+    let mut line = paint(sign, color, |s| line_color(tag, s));
+    for (emphasized, text) in runs {
+        let segment = match (tag, emphasized, color) {
+            (similar::ChangeTag::Delete, true, true) => text.on_red().to_string(),
+            (similar::ChangeTag::Insert, true, true) => text.on_green().to_string(),
+            (similar::ChangeTag::Delete, true, false) => format!("[-{}-]", text),
+            (similar::ChangeTag::Insert, true, false) => format!("{{+{}+}}", text),
+            _ => paint(&text, color, |s| line_color(tag, s)),
+        };
+        line.push_str(&segment);
+    }
+    line
+}
+
+fn line_color(tag: similar::ChangeTag, text: &str) -> colored::ColoredString {
+    match tag {
+        similar::ChangeTag::Delete => text.red(),
+        similar::ChangeTag::Insert => text.green(),
+        similar::ChangeTag::Equal => text.normal(),
+    }
+}
+
+fn paint(text: &str, color: bool, style: impl Fn(&str) -> colored::ColoredString) -> String {
+    if color {
+        style(text).to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+/// Text as compared under `--ignore-whitespace`: CRLF becomes LF and
+/// trailing whitespace is dropped from every line. Only for comparing;
+/// files are never rewritten with it.
+pub fn normalize_whitespace(text: &str, ignore_whitespace: bool) -> Cow<'_, str> {
+    if !ignore_whitespace {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let newline = line.ends_with('\n');
+        out.push_str(line.trim_end_matches('\n').trim_end());
+        if newline {
+            out.push('\n');
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Count inserted and deleted lines between old and new text
+pub fn diff_stats(old: &str, new: &str) -> (usize, usize) {
+    let diff = similar::TextDiff::from_lines(old, new);
+    let mut added = 0;
+    let mut removed = 0;
+
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            similar::ChangeTag::Insert => added += 1,
+            similar::ChangeTag::Delete => removed += 1,
+            _ => {}
+        }
+    }
+
+    (added, removed)
+}
+
+/// One line of `diff --stat` output
+pub struct StatEntry {
+    pub path: String,
+    pub added: usize,
+    pub removed: usize,
+    /// Phantom files have no baseline: every line counts as added
+    pub new_file: bool,
+    /// Binary overlays have no line counts
+    pub binary: bool,
+}
+
+/// Format a single stat line: `path | +N -M`, `path | +N (new file)` or
+/// `path | binary differs`
+pub fn format_stat_line(entry: &StatEntry, width: usize) -> String {
+    if entry.binary {
+        format!(" {:<width$} | binary differs", entry.path, width = width)
+    } else if entry.new_file {
+        format!(
+            " {:<width$} | {} (new file)",
+            entry.path,
+            format!("+{}", entry.added).green(),
+            width = width
+        )
+    } else {
+        format!(
+            " {:<width$} | {} {}",
+            entry.path,
+            format!("+{}", entry.added).green(),
+            format!("-{}", entry.removed).red(),
+            width = width
+        )
+    }
+}
+
+/// Print stat lines for all entries followed by a total line
+pub fn print_stat(entries: &[StatEntry]) {
+    let width = entries.iter().map(|e| e.path.len()).max().unwrap_or(0);
+    for entry in entries {
+        println!("{}", format_stat_line(entry, width));
+    }
+
+    let added: usize = entries.iter().map(|e| e.added).sum();
+    let removed: usize = entries.iter().map(|e| e.removed).sum();
+    println!(
+        " {} file(s) changed, {} insertion(s)(+), {} deletion(s)(-)",
+        entries.len(),
+        added,
+        removed
+    );
+}
+
+/// Open old/new content in an external diff tool.
+///
+/// `command` is run through `sh -c` with `$LOCAL` (old) and `$REMOTE` (new)
+/// pointing at temporary files, the same variables `git difftool` provides.
+/// The temporary files are removed once the tool exits.
+pub fn run_difftool(command: &str, old: &[u8], new: &[u8], file_name: &str) -> Result<()> {
+    let local = write_difftool_temp("shadow-baseline-", file_name, old)?;
+    let remote = write_difftool_temp("shadow-current-", file_name, new)?;
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("LOCAL", local.path())
+        .env("REMOTE", remote.path())
+        .status()
+        .with_context(|| format!("failed to run diff tool: {}", command))?;
+
+    if !status.success() {
+        bail!("diff tool exited with {}: {}", status, command);
+    }
+    Ok(())
+}
+
+fn write_difftool_temp(
+    prefix: &str,
+    file_name: &str,
+    content: &[u8],
+) -> Result<tempfile::NamedTempFile> {
+    // Keep the original file name as suffix so tools can pick syntax highlighting
+    let suffix = format!("-{}", file_name.rsplit('/').next().unwrap_or(file_name));
+    let mut tmp = tempfile::Builder::new()
+        .prefix(prefix)
+        .suffix(&suffix)
+        .tempfile()
+        .context("failed to create temp file for diff tool")?;
+    tmp.write_all(content)?;
+    tmp.flush()?;
+    Ok(tmp)
+}
+
 /// Print full file content as a "new file" diff
 pub fn print_new_file_diff(content: &str, file_path: &str) {
     println!("{}", "--- /dev/null".red());
@@ -54,6 +373,20 @@ pub fn print_new_file_diff(content: &str, file_path: &str) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_normalize_whitespace() {
+        let text = "a  \r\nb\t\nc \r";
+        assert_eq!(normalize_whitespace(text, false), text);
+        assert_eq!(normalize_whitespace(text, true), "a\nb\nc");
+        assert_eq!(
+            diff_stats(
+                &normalize_whitespace("x\r\ny\n", true),
+                &normalize_whitespace("x \ny\n", true)
+            ),
+            (0, 0)
+        );
+    }
+
     #[test]
     fn test_unified_diff_no_change() {
         let result = unified_diff("hello\n", "hello\n", "a/file", "b/file");
@@ -83,6 +416,170 @@ mod tests {
         assert!(result.contains("+new"));
     }
 
+    #[test]
+    fn test_word_diff_marks_changed_words() {
+        let result = word_diff(
+            "keep\nhello world\n",
+            "keep\nhello there\n",
+            "a/file",
+            "b/file",
+            false,
+        );
+        assert!(result.contains("@@"));
+        assert!(result.contains(" keep\n"));
+        assert!(result.contains("-hello [-world-]\n"));
+        assert!(result.contains("+hello {+there+}\n"));
+    }
+
+    #[test]
+    fn test_word_diff_merges_adjacent_words() {
+        let result = word_diff("a b\n", "a x y b\n", "a/file", "b/file", false);
+        assert!(result.contains("+a {+x y +}b"));
+    }
+
+    #[test]
+    fn test_should_color() {
+        let set = Some(std::ffi::OsStr::new("1"));
+        let empty = Some(std::ffi::OsStr::new(""));
+        assert!(should_color(ColorWhen::Auto, None, true));
+        assert!(should_color(ColorWhen::Auto, empty, true));
+        assert!(!should_color(ColorWhen::Auto, None, false));
+        assert!(!should_color(ColorWhen::Auto, set, true));
+        assert!(should_color(ColorWhen::Always, set, false));
+        assert!(!should_color(ColorWhen::Never, None, true));
+    }
+
+    #[test]
+    fn test_diff_stats_counts_lines() {
+        assert_eq!(diff_stats("a\nb\n", "a\nc\nd\n"), (2, 1));
+        assert_eq!(diff_stats("same\n", "same\n"), (0, 0));
+    }
+
+    #[test]
+    fn test_format_stat_line_overlay() {
+        colored::control::set_override(false);
+        let entry = StatEntry {
+            path: "CLAUDE.md".to_string(),
+            added: 3,
+            removed: 1,
+            new_file: false,
+            binary: false,
+        };
+        assert_eq!(format_stat_line(&entry, 12), " CLAUDE.md    | +3 -1");
+    }
+
+    #[test]
+    fn test_format_stat_line_binary() {
+        let entry = StatEntry {
+            path: "app.db".to_string(),
+            added: 0,
+            removed: 0,
+            new_file: false,
+            binary: true,
+        };
+        assert_eq!(format_stat_line(&entry, 6), " app.db | binary differs");
+    }
+
+    #[test]
+    fn test_format_stat_line_new_file() {
+        colored::control::set_override(false);
+        let entry = StatEntry {
+            path: "local.md".to_string(),
+            added: 5,
+            removed: 0,
+            new_file: true,
+            binary: false,
+        };
+        assert_eq!(format_stat_line(&entry, 8), " local.md | +5 (new file)");
+    }
+
+    #[test]
+    fn test_run_difftool_receives_both_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.txt");
+        let command = format!("cat \"$LOCAL\" \"$REMOTE\" > '{}'", out.display());
+
+        run_difftool(&command, b"old\n", b"new\n", "docs/CLAUDE.md").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "old\nnew\n");
+    }
+
+    #[test]
+    fn test_run_difftool_removes_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("paths.txt");
+        let command = format!("echo \"$LOCAL\" > '{}'", out.display());
+
+        run_difftool(&command, b"old\n", b"new\n", "CLAUDE.md").unwrap();
+
+        let local = std::fs::read_to_string(&out).unwrap();
+        assert!(local.trim().ends_with("-CLAUDE.md"));
+        assert!(!std::path::Path::new(local.trim()).exists());
+    }
+
+    #[test]
+    fn test_run_difftool_failure() {
+        assert!(run_difftool("exit 3", b"", b"", "f").is_err());
+    }
+
+    /// Check `patch` with `git apply --check`, then apply it and return the result
+    fn apply_patch(old: &str, patch: &str) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("CLAUDE.md"), old).unwrap();
+        std::fs::write(dir.path().join("shadow.patch"), patch).unwrap();
+        for args in [
+            &["apply", "--check", "shadow.patch"][..],
+            &["apply", "shadow.patch"],
+        ] {
+            let output = Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        let applied = std::fs::read_to_string(dir.path().join("CLAUDE.md")).unwrap();
+        assert_eq!(apply_git_patch(old, patch).unwrap(), applied);
+        applied
+    }
+
+    #[test]
+    fn test_git_patch_applies() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        let patch = git_patch(old, new, "CLAUDE.md");
+        assert!(patch.starts_with(
+            "diff --git a/CLAUDE.md b/CLAUDE.md\n--- a/CLAUDE.md\n+++ b/CLAUDE.md\n@@ "
+        ));
+        assert_eq!(apply_patch(old, &patch), new);
+        assert_eq!(git_patch(old, old, "CLAUDE.md"), "");
+    }
+
+    #[test]
+    fn test_git_patch_missing_final_newline() {
+        for (old, new) in [
+            ("a\nb", "a\nc\n"),
+            ("a\nb\n", "a\nb"),
+            ("a\nb", "a\nc"),
+            ("", "new"),
+        ] {
+            let patch = git_patch(old, new, "CLAUDE.md");
+            assert!(patch.contains("\\ No newline at end of file\n"));
+            assert_eq!(apply_patch(old, &patch), new);
+        }
+    }
+
+    #[test]
+    fn test_apply_git_patch_rejects_other_content() {
+        let patch = git_patch("a\nb\nc\n", "a\nB\nc\n", "CLAUDE.md");
+        assert!(apply_git_patch("a\nx\nc\n", &patch).is_err());
+        assert_eq!(apply_git_patch("same\n", "").unwrap(), "same\n");
+    }
+
     #[test]
     fn test_unified_diff_empty_to_content() {
         let result = unified_diff("", "new content\n", "a/file", "b/file");
@@ -5,21 +5,35 @@ pub enum ShadowError {
     #[error("not a Git repository")]
     NotAGitRepo,
 
+    #[error(
+        "git-shadow cannot be used in a bare repository (set GIT_WORK_TREE or use a working tree)"
+    )]
+    BareRepo,
+
+    #[error("not inside a working tree. Run git-shadow from the repository's working tree")]
+    NotInWorkTree,
+
     #[error("shadow directory not initialized. Run `git-shadow install`")]
     NotInitialized,
 
     #[error("file '{0}' is not tracked by Git")]
     FileNotTracked(String),
 
+    #[error("file '{0}' does not exist at {1}")]
+    FileNotInRef(String, String),
+
     #[error("file '{0}' is already managed by git-shadow")]
     AlreadyManaged(String),
 
     #[error("file '{0}' is not managed by git-shadow")]
     NotManaged(String),
 
-    #[error("file '{0}' is a binary file")]
+    #[error("file '{0}' is a binary file. Use --binary to register it as a replace-only overlay")]
     BinaryFile(String),
 
+    #[error("file '{0}' has a clean/smudge filter ({1}) in .gitattributes; its committed content differs from the working tree, so it cannot be an overlay")]
+    FilteredFile(String, String),
+
     #[error("file '{0}' exceeds size limit ({1} bytes > {2} bytes). Use --force to override")]
     FileTooLarge(String, u64, u64),
 
@@ -41,6 +55,9 @@ pub enum ShadowError {
     #[error("file '{0}' does not exist in the working tree")]
     FileMissing(String),
 
+    #[error("file '{0}' has unresolved conflict markers. Resolve them before committing")]
+    ConflictMarkers(String),
+
     #[error("failed to unstage phantom file '{0}'. Run `git reset -- {0}` manually")]
     UnstageFailure(String),
 
@@ -62,6 +79,9 @@ pub enum ShadowError {
     #[error("cannot run in non-interactive mode without --force")]
     NonInteractiveWithoutForce,
 
+    #[error("config.json version {found} is newer than supported version {supported}. Upgrade git-shadow")]
+    UnsupportedConfigVersion { found: u64, supported: u32 },
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
@@ -1,6 +1,8 @@
 use std::path::{Path, PathBuf};
 
+use crate::config::ExcludeMode;
 use crate::fs_util;
+use crate::git::GitRepo;
 
 const SECTION_START: &str = "# >>> git-shadow managed (DO NOT EDIT) >>>";
 const SECTION_END: &str = "# <<< git-shadow managed <<<";
@@ -16,6 +18,36 @@ impl ExcludeManager {
         }
     }
 
+    /// Manager for the `.gitignore` at the repository root
+    pub fn gitignore(root: &Path) -> Self {
+        Self {
+            path: root.join(".gitignore"),
+        }
+    }
+
+    /// Manager for the file an exclude mode writes to (None for
+    /// `ExcludeMode::None` and `ExcludeMode::AlreadyIgnored`)
+    pub fn for_mode(git: &GitRepo, mode: &ExcludeMode) -> Option<Self> {
+        match mode {
+            ExcludeMode::GitInfoExclude => Some(Self::new(&git.common_dir)),
+            ExcludeMode::Gitignore => Some(Self::gitignore(&git.root)),
+            ExcludeMode::None | ExcludeMode::AlreadyIgnored => None,
+        }
+    }
+
+    /// Human-readable name of the managed file
+    pub fn display_name(&self) -> &'static str {
+        if self
+            .path
+            .file_name()
+            .is_some_and(|name| name == ".gitignore")
+        {
+            ".gitignore"
+        } else {
+            ".git/info/exclude"
+        }
+    }
+
     /// Add a path to the managed section (idempotent)
     pub fn add_entry(&self, entry_path: &str) -> anyhow::Result<()> {
         let content = std::fs::read_to_string(&self.path).unwrap_or_default();
@@ -27,7 +59,7 @@ impl ExcludeManager {
         entries.push(entry_path.to_string());
 
         let new_content = self.rebuild_content(&content, &entries);
-        fs_util::atomic_write(&self.path, new_content.as_bytes())?;
+        fs_util::atomic_write_with_mode_of(&self.path, new_content.as_bytes(), &self.path)?;
         Ok(())
     }
 
@@ -39,7 +71,7 @@ impl ExcludeManager {
         entries.retain(|e| e != entry_path);
 
         let new_content = self.rebuild_content(&content, &entries);
-        fs_util::atomic_write(&self.path, new_content.as_bytes())?;
+        fs_util::atomic_write_with_mode_of(&self.path, new_content.as_bytes(), &self.path)?;
         Ok(())
     }
 
@@ -55,6 +87,7 @@ impl ExcludeManager {
         let mut entries = Vec::new();
 
         for line in content.lines() {
+            let line = line.trim_end_matches('\r');
             if line == SECTION_START {
                 in_section = true;
                 continue;
@@ -73,19 +106,23 @@ impl ExcludeManager {
         entries
     }
 
-    /// Rebuild file content: preserve everything outside the section, replace section
+    /// Rebuild file content: preserve everything outside the section byte for
+    /// byte, replace the section. The section uses the file's line ending and
+    /// the trailing newline state of the original is kept.
     fn rebuild_content(&self, original: &str, entries: &[String]) -> String {
-        let mut before = Vec::new();
-        let mut after = Vec::new();
+        let eol = detect_line_ending(original);
+        let mut before = String::new();
+        let mut after = String::new();
         let mut in_section = false;
         let mut past_section = false;
 
-        for line in original.lines() {
-            if line == SECTION_START {
+        for line in original.split_inclusive('\n') {
+            let bare = line.trim_end_matches('\n').trim_end_matches('\r');
+            if bare == SECTION_START {
                 in_section = true;
                 continue;
             }
-            if line == SECTION_END {
+            if bare == SECTION_END {
                 in_section = false;
                 past_section = true;
                 continue;
@@ -94,52 +131,58 @@ impl ExcludeManager {
                 continue;
             }
             if past_section {
-                after.push(line.to_string());
+                after.push_str(line);
             } else {
-                before.push(line.to_string());
+                before.push_str(line);
             }
         }
 
-        let mut result = before.join("\n");
+        let mut result = before;
 
-        if entries.is_empty() {
-            // No entries: don't add section at all
-            if !after.is_empty() {
-                if !result.is_empty() {
-                    result.push('\n');
-                }
-                result.push_str(&after.join("\n"));
-            }
+        // No entries: don't add section at all
+        if !entries.is_empty() {
             if !result.is_empty() && !result.ends_with('\n') {
-                result.push('\n');
+                result.push_str(eol);
             }
-            return result;
+            result.push_str(SECTION_START);
+            result.push_str(eol);
+            for entry in entries {
+                result.push_str(entry);
+                result.push_str(eol);
+            }
+            result.push_str(SECTION_END);
+            result.push_str(eol);
         }
 
-        // Add section with entries
-        if !result.is_empty() && !result.ends_with('\n') {
-            result.push('\n');
-        }
-        result.push_str(SECTION_START);
-        result.push('\n');
-        for entry in entries {
-            result.push_str(entry);
-            result.push('\n');
+        if !after.is_empty() {
+            if !result.is_empty() && !result.ends_with('\n') {
+                result.push_str(eol);
+            }
+            result.push_str(&after);
         }
-        result.push_str(SECTION_END);
-        result.push('\n');
 
-        if !after.is_empty() {
-            result.push_str(&after.join("\n"));
-            if !result.ends_with('\n') {
-                result.push('\n');
+        // Match the original's trailing newline (new files end with one)
+        let wants_trailing_newline = original.is_empty() || original.ends_with('\n');
+        if wants_trailing_newline {
+            if !result.is_empty() && !result.ends_with('\n') {
+                result.push_str(eol);
             }
+        } else if let Some(stripped) = result.strip_suffix(eol) {
+            result.truncate(stripped.len());
         }
 
         result
     }
 }
 
+/// Line ending of the first terminated line ("\n" if there is none)
+fn detect_line_ending(content: &str) -> &'static str {
+    match content.find('\n') {
+        Some(pos) if pos > 0 && content.as_bytes()[pos - 1] == b'\r' => "\r\n",
+        _ => "\n",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,10 +280,103 @@ mod tests {
         assert!(entries.is_empty());
     }
 
+    #[test]
+    fn test_gitignore_manager_uses_root_gitignore() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        let manager = ExcludeManager::gitignore(dir.path());
+        manager.add_entry("local.md").unwrap();
+
+        let content = std::fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        assert_eq!(
+            content,
+            format!("target/\n{}\nlocal.md\n{}\n", SECTION_START, SECTION_END)
+        );
+        assert_eq!(manager.display_name(), ".gitignore");
+    }
+
     #[test]
     fn test_remove_nonexistent_entry_is_ok() {
         let (_dir, manager) = setup();
         manager.add_entry("a.md").unwrap();
         assert!(manager.remove_entry("nonexistent.md").is_ok());
     }
+
+    #[test]
+    fn test_crlf_roundtrip_preserves_line_endings() {
+        let (_dir, manager) = setup();
+        let original = "*.log\r\ntmp/\r\n";
+        std::fs::write(&manager.path, original).unwrap();
+
+        manager.add_entry("CLAUDE.md").unwrap();
+        let content = std::fs::read_to_string(&manager.path).unwrap();
+        assert_eq!(
+            content,
+            format!(
+                "*.log\r\ntmp/\r\n{}\r\nCLAUDE.md\r\n{}\r\n",
+                SECTION_START, SECTION_END
+            )
+        );
+        assert_eq!(
+            manager.list_entries().unwrap(),
+            vec!["CLAUDE.md".to_string()]
+        );
+
+        manager.remove_entry("CLAUDE.md").unwrap();
+        let content = std::fs::read_to_string(&manager.path).unwrap();
+        assert_eq!(content, original);
+    }
+
+    #[test]
+    fn test_crlf_preserves_lines_after_section() {
+        let (_dir, manager) = setup();
+        std::fs::write(
+            &manager.path,
+            format!(
+                "a\r\n{}\r\nold.md\r\n{}\r\nb\r\n",
+                SECTION_START, SECTION_END
+            ),
+        )
+        .unwrap();
+
+        manager.add_entry("new.md").unwrap();
+        let content = std::fs::read_to_string(&manager.path).unwrap();
+        assert_eq!(
+            content,
+            format!(
+                "a\r\n{}\r\nold.md\r\nnew.md\r\n{}\r\nb\r\n",
+                SECTION_START, SECTION_END
+            )
+        );
+    }
+
+    #[test]
+    fn test_mixed_line_endings_outside_section_untouched() {
+        let (_dir, manager) = setup();
+        let original = "a\r\nb\nc\r\n";
+        std::fs::write(&manager.path, original).unwrap();
+
+        manager.add_entry("x.md").unwrap();
+        manager.remove_entry("x.md").unwrap();
+
+        let content = std::fs::read_to_string(&manager.path).unwrap();
+        assert_eq!(content, original);
+    }
+
+    #[test]
+    fn test_missing_trailing_newline_is_preserved() {
+        let (_dir, manager) = setup();
+        std::fs::write(&manager.path, "*.log").unwrap();
+
+        manager.add_entry("x.md").unwrap();
+        let content = std::fs::read_to_string(&manager.path).unwrap();
+        assert_eq!(
+            content,
+            format!("*.log\n{}\nx.md\n{}", SECTION_START, SECTION_END)
+        );
+
+        manager.remove_entry("x.md").unwrap();
+        let content = std::fs::read_to_string(&manager.path).unwrap();
+        assert_eq!(content, "*.log");
+    }
 }
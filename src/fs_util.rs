@@ -1,11 +1,18 @@
+use std::collections::BTreeMap;
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use sha1::{Digest, Sha1};
 
 use crate::error::ShadowError;
 
-pub const SIZE_LIMIT: u64 = 1_048_576; // 1 MB
+/// Overlay size limit when neither settings.json nor GIT_SHADOW_MAX_SIZE set one
+pub const DEFAULT_SIZE_LIMIT: u64 = 1_048_576; // 1 MB
 const BINARY_CHECK_BYTES: usize = 8192;
 
+/// Name prefix of the temp files `atomic_write` creates next to its target
+pub const TEMP_PREFIX: &str = ".tmp";
+
 /// Check if file appears to be binary (contains null bytes in first 8KB)
 pub fn is_binary(path: &Path) -> anyhow::Result<bool> {
     let mut file = std::fs::File::open(path)?;
@@ -14,15 +21,15 @@ pub fn is_binary(path: &Path) -> anyhow::Result<bool> {
     Ok(buf[..n].contains(&0))
 }
 
-/// Check if file exceeds size limit. Returns error if over limit and force is false.
-pub fn check_size(path: &Path, force: bool) -> Result<(), ShadowError> {
+/// Check if file exceeds `limit` bytes. Returns error if over limit and force is false.
+pub fn check_size(path: &Path, force: bool, limit: u64) -> Result<(), ShadowError> {
     let metadata = std::fs::metadata(path)?;
     let size = metadata.len();
-    if size > SIZE_LIMIT && !force {
+    if size > limit && !force {
         return Err(ShadowError::FileTooLarge(
             path.display().to_string(),
             size,
-            SIZE_LIMIT,
+            limit,
         ));
     }
     Ok(())
@@ -30,16 +37,121 @@ pub fn check_size(path: &Path, force: bool) -> Result<(), ShadowError> {
 
 /// Atomic write: write to temp file in same directory, then rename
 pub fn atomic_write(target: &Path, content: &[u8]) -> anyhow::Result<()> {
+    write_and_persist(target, content, None)
+}
+
+/// `atomic_write` that gives `target` the permissions of `mode_of` (when it
+/// exists) instead of the temp file's 0600. Pass `target` itself to keep its
+/// mode, or the working tree file being stashed so the copy carries its
+/// exec bit.
+pub fn atomic_write_with_mode_of(
+    target: &Path,
+    content: &[u8],
+    mode_of: &Path,
+) -> anyhow::Result<()> {
+    let permissions = std::fs::metadata(mode_of).ok().map(|m| m.permissions());
+    write_and_persist(target, content, permissions)
+}
+
+fn write_and_persist(
+    target: &Path,
+    content: &[u8],
+    permissions: Option<std::fs::Permissions>,
+) -> anyhow::Result<()> {
     let parent = target
         .parent()
         .ok_or_else(|| anyhow::anyhow!("target path has no parent directory"))?;
 
-    let mut tmp = tempfile::NamedTempFile::new_in(parent)?;
+    let mut tmp = tempfile::Builder::new()
+        .prefix(TEMP_PREFIX)
+        .tempfile_in(parent)?;
     tmp.write_all(content)?;
+    if let Some(permissions) = permissions {
+        tmp.as_file().set_permissions(permissions)?;
+    }
     tmp.persist(target)?;
     Ok(())
 }
 
+/// Give `to` the permissions of `from`, e.g. after writing stashed content
+/// back to a working tree file that had been removed. A missing `from`
+/// leaves `to` unchanged.
+pub fn copy_permissions(from: &Path, to: &Path) -> std::io::Result<()> {
+    match std::fs::metadata(from) {
+        Ok(metadata) => std::fs::set_permissions(to, metadata.permissions()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether a file name looks like an `atomic_write` temp file. One that
+/// outlives its write was left behind by an interrupted process.
+pub fn is_temp_file(name: &std::ffi::OsStr) -> bool {
+    name.to_string_lossy().starts_with(TEMP_PREFIX)
+}
+
+/// A file or symlink found by `walk_dir`
+#[derive(Debug, PartialEq)]
+pub enum WalkEntry {
+    File { path: PathBuf, size: u64 },
+    Symlink { path: PathBuf, target: PathBuf },
+}
+
+/// Every file under `dir` as path relative to `dir` (with `/`) -> SHA-1 of
+/// its content, or of its target for a symlink
+pub fn dir_manifest(dir: &Path) -> std::io::Result<BTreeMap<String, String>> {
+    let mut manifest = BTreeMap::new();
+    for entry in walk_dir(dir)? {
+        let (path, digest) = match entry {
+            WalkEntry::File { path, .. } => {
+                let digest = Sha1::digest(std::fs::read(dir.join(&path))?);
+                (path, digest)
+            }
+            WalkEntry::Symlink { path, target } => {
+                let link = format!("symlink:{}", target.to_string_lossy());
+                (path, Sha1::digest(link.as_bytes()))
+            }
+        };
+        let hex = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        manifest.insert(path.to_string_lossy().replace('\\', "/"), hex);
+    }
+    Ok(manifest)
+}
+
+/// Recursively list files under `dir`, sorted by path (relative to `dir`).
+/// Symlinks are reported but never followed.
+pub fn walk_dir(dir: &Path) -> std::io::Result<Vec<WalkEntry>> {
+    let mut entries = Vec::new();
+    walk_dir_inner(dir, Path::new(""), &mut entries)?;
+    Ok(entries)
+}
+
+fn walk_dir_inner(dir: &Path, prefix: &Path, out: &mut Vec<WalkEntry>) -> std::io::Result<()> {
+    let mut children: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    children.sort_by_key(|entry| entry.file_name());
+
+    for child in children {
+        let relative = prefix.join(child.file_name());
+        let file_type = child.file_type()?;
+        if file_type.is_symlink() {
+            let target = std::fs::read_link(child.path())?;
+            out.push(WalkEntry::Symlink {
+                path: relative,
+                target,
+            });
+        } else if file_type.is_dir() {
+            walk_dir_inner(&child.path(), &relative, out)?;
+        } else {
+            let size = child.metadata()?.len();
+            out.push(WalkEntry::File {
+                path: relative,
+                size,
+            });
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,17 +195,17 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("small.txt");
         std::fs::write(&path, "small content").unwrap();
-        assert!(check_size(&path, false).is_ok());
+        assert!(check_size(&path, false, DEFAULT_SIZE_LIMIT).is_ok());
     }
 
     #[test]
     fn test_check_size_over_limit() {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("large.bin");
-        let content = vec![0x41u8; (SIZE_LIMIT + 1) as usize];
+        let content = vec![0x41u8; (DEFAULT_SIZE_LIMIT + 1) as usize];
         std::fs::write(&path, &content).unwrap();
 
-        let result = check_size(&path, false);
+        let result = check_size(&path, false, DEFAULT_SIZE_LIMIT);
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
@@ -101,14 +213,26 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_check_size_custom_limit_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("medium.txt");
+        std::fs::write(&path, vec![0x41u8; 200]).unwrap();
+
+        assert!(check_size(&path, false, 1024).is_ok());
+        let err = check_size(&path, false, 100).unwrap_err();
+        assert!(matches!(err, ShadowError::FileTooLarge(_, 200, 100)));
+        assert!(err.to_string().contains("200 bytes > 100 bytes"));
+    }
+
     #[test]
     fn test_check_size_over_limit_with_force() {
         let dir = tempfile::tempdir().unwrap();
         let path = dir.path().join("large.bin");
-        let content = vec![0x41u8; (SIZE_LIMIT + 1) as usize];
+        let content = vec![0x41u8; (DEFAULT_SIZE_LIMIT + 1) as usize];
         std::fs::write(&path, &content).unwrap();
 
-        assert!(check_size(&path, true).is_ok());
+        assert!(check_size(&path, true, DEFAULT_SIZE_LIMIT).is_ok());
     }
 
     #[test]
@@ -128,10 +252,97 @@ mod tests {
         assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
     }
 
+    #[test]
+    fn test_atomic_write_leaves_no_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        atomic_write(&dir.path().join("output.txt"), b"content").unwrap();
+        let names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["output.txt"]);
+        assert!(is_temp_file(std::ffi::OsStr::new(".tmpAb12Cd")));
+        assert!(!is_temp_file(names[0].as_os_str()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_atomic_write_with_mode_of_keeps_exec_bit() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("run.sh");
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        // Rewriting keeps the mode; plain atomic_write does not
+        atomic_write_with_mode_of(&script, b"#!/bin/sh\necho hi\n", &script).unwrap();
+        assert_eq!(mode(&script), 0o755);
+
+        // A stash copy carries it, and copying it back restores it
+        let stash = dir.path().join("stash");
+        atomic_write_with_mode_of(&stash, b"#!/bin/sh\n", &script).unwrap();
+        assert_eq!(mode(&stash), 0o755);
+        atomic_write(&script, b"#!/bin/sh\n").unwrap();
+        assert_eq!(mode(&script), 0o600);
+        copy_permissions(&stash, &script).unwrap();
+        assert_eq!(mode(&script), 0o755);
+
+        copy_permissions(&dir.path().join("missing"), &script).unwrap();
+        assert_eq!(mode(&script), 0o755);
+    }
+
     #[test]
     fn test_atomic_write_no_partial_on_dir_missing() {
         let path = Path::new("/nonexistent/dir/file.txt");
         assert!(atomic_write(path, b"content").is_err());
         assert!(!path.exists());
     }
+
+    #[test]
+    fn test_walk_dir_recursive_sorted() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sub/deep")).unwrap();
+        std::fs::write(dir.path().join("b.txt"), "bb").unwrap();
+        std::fs::write(dir.path().join("a.txt"), "a").unwrap();
+        std::fs::write(dir.path().join("sub/deep/c.txt"), "ccc").unwrap();
+
+        let entries = walk_dir(dir.path()).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                WalkEntry::File {
+                    path: PathBuf::from("a.txt"),
+                    size: 1
+                },
+                WalkEntry::File {
+                    path: PathBuf::from("b.txt"),
+                    size: 2
+                },
+                WalkEntry::File {
+                    path: PathBuf::from("sub/deep/c.txt"),
+                    size: 3
+                },
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_walk_dir_does_not_follow_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), "x").unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+
+        let entries = walk_dir(dir.path()).unwrap();
+        assert_eq!(
+            entries,
+            vec![WalkEntry::Symlink {
+                path: PathBuf::from("link"),
+                target: outside.path().to_path_buf(),
+            }]
+        );
+    }
 }
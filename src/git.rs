@@ -1,46 +1,140 @@
-use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
 
 use anyhow::{bail, Context};
+use sha1::{Digest, Sha1};
 
 use crate::error::ShadowError;
 
+/// Maximum number of paths passed to a single `git add`
+const ADD_BATCH_SIZE: usize = 100;
+
+/// Environment variable that moves shadow data out of `.git/shadow/`
+pub const SHADOW_DIR_ENV: &str = "GIT_SHADOW_DIR";
+
+/// Hooks git-shadow installs
+pub const HOOK_NAMES: &[&str] = &["pre-commit", "post-commit", "post-merge", "post-checkout"];
+
+/// First 7 characters of a commit hash for display. Shorter input is
+/// returned whole, and a multi-byte character is never split.
+pub fn short_hash(hash: &str) -> &str {
+    hash.char_indices()
+        .nth(7)
+        .map_or(hash, |(end, _)| &hash[..end])
+}
+
+/// SHA-1 object id of a blob with `content`, computed without running git.
+/// Repositories using SHA-256 object ids never match it.
+pub fn blob_oid(content: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("blob {}\0", content.len()));
+    hasher.update(content);
+    format!("{:x}", hasher.finalize())
+}
+
+/// A git hook as seen by git-shadow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookState {
+    Missing,
+    /// Exists but does not call git-shadow
+    Foreign,
+    Installed,
+}
+
 pub struct GitRepo {
     pub root: PathBuf,
+    /// Git directory of this working tree (`.git/worktrees/<name>` in a linked worktree)
     pub git_dir: PathBuf,
+    /// Git directory shared by all working trees (hooks, info/exclude, shadow data)
+    pub common_dir: PathBuf,
     pub shadow_dir: PathBuf,
+    /// Directory `discover` started from, relative to `root` with a trailing
+    /// `/` (empty at the root); relative paths given by the user are taken
+    /// from here
+    pub prefix: String,
 }
 
 impl GitRepo {
-    /// Discover git repo from current or given directory
+    /// Discover git repo from current or given directory. `GIT_DIR` and
+    /// `GIT_WORK_TREE` are honored because git itself resolves them.
     pub fn discover(start: &Path) -> anyhow::Result<Self> {
-        let output = Command::new("git")
-            .args(["rev-parse", "--show-toplevel"])
-            .current_dir(start)
-            .output()
-            .context("failed to run git command")?;
+        let output = rev_parse(
+            start,
+            &["--is-bare-repository", "--git-dir", "--git-common-dir"],
+        )?
+        .ok_or(ShadowError::NotAGitRepo)?;
 
-        if !output.status.success() {
+        let mut lines = output.lines();
+        let (Some(is_bare), Some(git_dir), Some(common_dir)) =
+            (lines.next(), lines.next(), lines.next())
+        else {
             return Err(ShadowError::NotAGitRepo.into());
+        };
+        if is_bare == "true" {
+            return Err(ShadowError::BareRepo.into());
         }
 
-        let root = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
-        let git_dir = root.join(".git");
-        let shadow_dir = git_dir.join("shadow");
+        // Fails inside `.git` itself, where there is no working tree
+        let output = rev_parse(start, &["--show-toplevel", "--show-prefix"])?
+            .ok_or(ShadowError::NotInWorkTree)?;
+        let mut lines = output.lines();
+        let root = PathBuf::from(lines.next().unwrap_or_default());
+        let prefix = lines.next().unwrap_or_default().to_string();
+        let git_dir = resolve_git_path(start, git_dir);
+        let common_dir = resolve_git_path(start, common_dir);
+        let shadow_dir = shadow_dir_with_env(
+            &root,
+            &common_dir,
+            std::env::var_os(SHADOW_DIR_ENV).as_deref(),
+        );
 
         Ok(Self {
             root,
             git_dir,
+            common_dir,
             shadow_dir,
+            prefix,
         })
     }
 
+    /// Directory git runs hooks from: `core.hooksPath` if set, else `hooks/`
+    /// in the common git dir (shared across worktrees)
+    pub fn hooks_dir(&self) -> PathBuf {
+        self.custom_hooks_dir()
+            .unwrap_or_else(|| self.common_dir.join("hooks"))
+    }
+
+    /// `core.hooksPath` resolved like git does: `~` is expanded and a
+    /// relative path is taken from the working tree root
+    pub fn custom_hooks_dir(&self) -> Option<PathBuf> {
+        let value = self
+            .run_git(&["config", "--type=path", "--get", "core.hooksPath"])
+            .ok()?;
+        let value = value.trim_end_matches(['\n', '\r']);
+        if value.is_empty() {
+            return None;
+        }
+        Some(self.root.join(value))
+    }
+
     /// Get current HEAD commit hash (full)
     pub fn head_commit(&self) -> anyhow::Result<String> {
         let output = self.run_git(&["rev-parse", "HEAD"])?;
         Ok(output.trim().to_string())
     }
 
+    /// Resolve a ref (branch, tag, short hash...) to a full commit hash
+    pub fn resolve_commit(&self, reference: &str) -> anyhow::Result<String> {
+        let spec = format!("{}^{{commit}}", reference);
+        let output = self
+            .run_git(&["rev-parse", "--verify", "--quiet", &spec])
+            .map_err(|_| anyhow::anyhow!("'{}' is not a valid commit", reference))?;
+        Ok(output.trim().to_string())
+    }
+
     /// Read file content from a specific ref (e.g. "HEAD")
     pub fn show_file(&self, reference: &str, path: &str) -> anyhow::Result<Vec<u8>> {
         let spec = format!("{}:{}", reference, path);
@@ -61,10 +155,122 @@ impl GitRepo {
         Ok(output.stdout)
     }
 
+    /// Size in bytes of a file at a specific ref, without reading its content
+    pub fn blob_size(&self, reference: &str, path: &str) -> anyhow::Result<u64> {
+        let spec = format!("{}:{}", reference, path);
+        let output = self.run_git(&["cat-file", "-s", &spec])?;
+        output
+            .trim()
+            .parse()
+            .with_context(|| format!("failed to parse size of {}", spec))
+    }
+
+    /// Object id of a file at a specific ref
+    pub fn blob_id(&self, reference: &str, path: &str) -> anyhow::Result<String> {
+        let spec = format!("{}:{}", reference, path);
+        let output = self.run_git(&["rev-parse", &spec])?;
+        Ok(output.trim().to_string())
+    }
+
+    /// Object ids of every file in the tree of `reference`, keyed by path
+    /// (`git ls-tree -r`). Submodules are left out.
+    pub fn tree_blobs(&self, reference: &str) -> anyhow::Result<HashMap<String, String>> {
+        let output = self.run_git(&["ls-tree", "-r", "-z", "--full-tree", reference])?;
+        Ok(output
+            .split('\0')
+            .filter_map(|line| {
+                let (info, path) = line.split_once('\t')?;
+                let mut fields = info.split(' ');
+                let (_mode, kind, oid) = (fields.next()?, fields.next()?, fields.next()?);
+                (kind == "blob").then(|| (path.to_string(), oid.to_string()))
+            })
+            .collect())
+    }
+
+    /// Object id git would assign to a file on disk (`git hash-object`)
+    pub fn hash_object(&self, file: &Path) -> anyhow::Result<String> {
+        let file = file.to_string_lossy();
+        let output = self.run_git(&["hash-object", "--no-filters", "--", &file])?;
+        Ok(output.trim().to_string())
+    }
+
+    /// Store `content` as a blob in the object database (`git hash-object -w`)
+    pub fn write_blob(&self, content: &[u8]) -> anyhow::Result<String> {
+        let mut child = Command::new("git")
+            .args(["hash-object", "-w", "--no-filters", "--stdin"])
+            .current_dir(&self.root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("failed to run git hash-object")?;
+        child
+            .stdin
+            .take()
+            .context("failed to open git hash-object stdin")?
+            .write_all(content)
+            .context("failed to write to git hash-object")?;
+        let output = child
+            .wait_with_output()
+            .context("failed to run git hash-object")?;
+
+        if !output.status.success() {
+            bail!(
+                "git hash-object failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Read a blob by object id (`git cat-file blob`)
+    pub fn read_blob(&self, oid: &str) -> anyhow::Result<Vec<u8>> {
+        let output = Command::new("git")
+            .args(["cat-file", "blob", oid])
+            .current_dir(&self.root)
+            .output()
+            .context("failed to run git cat-file")?;
+
+        if !output.status.success() {
+            bail!(
+                "git cat-file blob {} failed: {}",
+                oid,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(output.stdout)
+    }
+
+    /// Point `name` at `oid`, creating the ref if needed
+    pub fn update_ref(&self, name: &str, oid: &str) -> anyhow::Result<()> {
+        self.run_git(&["update-ref", name, oid])?;
+        Ok(())
+    }
+
+    /// Delete `name`; a missing ref is not an error
+    pub fn delete_ref(&self, name: &str) -> anyhow::Result<()> {
+        if self.ref_exists(name) {
+            self.run_git(&["update-ref", "-d", name])?;
+        }
+        Ok(())
+    }
+
+    /// Full names of the refs under `prefix` (e.g. `refs/shadow/`)
+    pub fn list_refs(&self, prefix: &str) -> anyhow::Result<Vec<String>> {
+        let output = self.run_git(&["for-each-ref", "--format=%(refname)", prefix])?;
+        Ok(output.lines().map(str::to_string).collect())
+    }
+
+    /// True if `name` resolves to an object
+    pub fn ref_exists(&self, name: &str) -> bool {
+        self.run_git(&["rev-parse", "--verify", "--quiet", name])
+            .is_ok()
+    }
+
     /// Check if a file is tracked by git
     pub fn is_tracked(&self, path: &str) -> anyhow::Result<bool> {
-        let output = Command::new("git")
-            .args(["ls-files", "--error-unmatch", path])
+        let output = literal_pathspecs(&mut Command::new("git"))
+            .args(["ls-files", "--error-unmatch", "--", path])
             .current_dir(&self.root)
             .output()
             .context("failed to run git ls-files")?;
@@ -72,43 +278,106 @@ impl GitRepo {
         Ok(output.status.success())
     }
 
+    /// Whether an ignore rule (`.gitignore`, `.git/info/exclude` or
+    /// `core.excludesFile`) already matches an untracked path
+    pub fn is_ignored(&self, path: &str) -> anyhow::Result<bool> {
+        // check-ignore takes plain paths and rejects GIT_LITERAL_PATHSPECS
+        let output = Command::new("git")
+            .args(["check-ignore", "-q", "--", path])
+            .current_dir(&self.root)
+            .output()
+            .context("failed to run git check-ignore")?;
+        match output.status.code() {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => bail!(
+                "git check-ignore failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        }
+    }
+
+    /// Whether git treats the file as binary. `.gitattributes` decides first:
+    /// `binary` or `-text` make it binary, `text` makes it text even with
+    /// null bytes. Otherwise the content is checked (`fs_util::is_binary`).
+    pub fn is_binary(&self, path: &str) -> anyhow::Result<bool> {
+        let output = self.run_git(&["check-attr", "-z", "binary", "text", "--", path])?;
+        let fields: Vec<&str> = output.split('\0').collect();
+        let mut binary = false;
+        let mut text = None;
+        for attr in fields.chunks_exact(3) {
+            match (attr[1], attr[2]) {
+                ("binary", "set") => binary = true,
+                ("text", "unset") => text = Some(false),
+                ("text", "set") => text = Some(true),
+                _ => {}
+            }
+        }
+        if binary || text == Some(false) {
+            return Ok(true);
+        }
+        if text == Some(true) {
+            return Ok(false);
+        }
+        crate::fs_util::is_binary(&self.root.join(path))
+    }
+
+    /// Name of the clean/smudge filter `.gitattributes` sets for `path`
+    /// (e.g. `lfs`); None when no filter applies
+    pub fn filter_attr(&self, path: &str) -> anyhow::Result<Option<String>> {
+        let output = self.run_git(&["check-attr", "-z", "filter", "--", path])?;
+        let value = output.split('\0').nth(2).unwrap_or("");
+        Ok(match value {
+            "" | "unspecified" | "unset" => None,
+            name => Some(name.to_string()),
+        })
+    }
+
     /// Check staging status for partial staging detection
     /// Returns (index_differs_from_head, worktree_differs_from_index)
     pub fn staging_status(&self, path: &str) -> anyhow::Result<(bool, bool)> {
-        let output = Command::new("git")
-            .args(["status", "--porcelain=v2", "--", path])
+        let output = literal_pathspecs(&mut Command::new("git"))
+            .args(["status", "--porcelain=v2", "-z", "--", path])
             .current_dir(&self.root)
             .output()
             .context("failed to run git status")?;
 
         let stdout = String::from_utf8_lossy(&output.stdout);
+        Ok(parse_staging_status(&stdout, path))
+    }
 
-        for line in stdout.lines() {
-            if !line.starts_with('1') && !line.starts_with('2') {
-                continue;
-            }
-            // Format: "1 XY sub mH mI mW hH hI path"
-            let parts: Vec<&str> = line.splitn(9, ' ').collect();
-            if parts.len() < 2 {
-                continue;
-            }
-            let xy = parts[1];
-            let x = xy.chars().next().unwrap_or('.');
-            let y = xy.chars().nth(1).unwrap_or('.');
-
-            let index_changed = x != '.';
-            let worktree_changed = y != '.';
+    /// Stage a file (git add)
+    pub fn add(&self, path: &str) -> anyhow::Result<()> {
+        self.run_git(&["add", "--", path])?;
+        Ok(())
+    }
 
-            return Ok((index_changed, worktree_changed));
+    /// Stage several files with as few `git add` calls as possible
+    /// (batched to stay under command-line length limits)
+    pub fn add_paths(&self, paths: &[&str]) -> anyhow::Result<()> {
+        for chunk in paths.chunks(ADD_BATCH_SIZE) {
+            let mut args = vec!["add", "--"];
+            args.extend_from_slice(chunk);
+            self.run_git(&args)?;
         }
+        Ok(())
+    }
 
-        // File not in status output = clean
-        Ok((false, false))
+    /// Mode and object id of `path` in the index (`git ls-files --stage`);
+    /// None when it is not in the index
+    pub fn index_entry(&self, path: &str) -> anyhow::Result<Option<(String, String)>> {
+        let output = self.run_git(&["ls-files", "--stage", "-z", "--", path])?;
+        Ok(output.split('\0').find_map(|line| {
+            let (info, _) = line.split_once('\t')?;
+            let mut fields = info.split(' ');
+            Some((fields.next()?.to_string(), fields.next()?.to_string()))
+        }))
     }
 
-    /// Stage a file (git add)
-    pub fn add(&self, path: &str) -> anyhow::Result<()> {
-        self.run_git(&["add", path])?;
+    /// Point the index entry of `path` at blob `oid` without touching the
+    /// working tree (`git update-index --cacheinfo`)
+    pub fn set_index_entry(&self, path: &str, mode: &str, oid: &str) -> anyhow::Result<()> {
+        self.run_git(&["update-index", "--add", "--cacheinfo", mode, oid, path])?;
         Ok(())
     }
 
@@ -116,14 +385,14 @@ impl GitRepo {
     pub fn unstage_phantom(&self, path: &str) -> Result<(), ShadowError> {
         // Strategy 1: git rm --cached --ignore-unmatch
         if self
-            .run_git(&["rm", "--cached", "--ignore-unmatch", path])
+            .run_git(&["rm", "--cached", "--ignore-unmatch", "--", path])
             .is_ok()
         {
             return Ok(());
         }
 
         // Strategy 2: git restore --staged
-        if self.run_git(&["restore", "--staged", path]).is_ok() {
+        if self.run_git(&["restore", "--staged", "--", path]).is_ok() {
             return Ok(());
         }
 
@@ -135,24 +404,99 @@ impl GitRepo {
         Err(ShadowError::UnstageFailure(path.to_string()))
     }
 
-    /// Check if hooks are installed
-    pub fn hooks_installed(&self) -> bool {
-        let hooks_dir = self.git_dir.join("hooks");
-        ["pre-commit", "post-commit", "post-merge"]
-            .iter()
-            .all(|name| {
-                let hook = hooks_dir.join(name);
-                if let Ok(content) = std::fs::read_to_string(&hook) {
-                    content.contains("git-shadow hook")
-                } else {
-                    false
-                }
+    /// Read a git config value (`git config --get <key>`). Returns None if unset.
+    pub fn config_get(&self, key: &str) -> Option<String> {
+        self.run_git(&["config", "--get", key])
+            .ok()
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    }
+
+    /// Set a git config value in the repository's local config
+    pub fn config_set(&self, key: &str, value: &str) -> anyhow::Result<()> {
+        self.run_git(&["config", key, value])?;
+        Ok(())
+    }
+
+    /// Remove a key from the repository's local config
+    pub fn config_unset(&self, key: &str) -> anyhow::Result<()> {
+        self.run_git(&["config", "--unset", key])?;
+        Ok(())
+    }
+
+    /// Editor command for interactive edits: `$EDITOR`, else git's own choice
+    /// (`git var GIT_EDITOR`), else `vi`
+    pub fn editor(&self) -> String {
+        std::env::var("EDITOR")
+            .ok()
+            .filter(|editor| !editor.trim().is_empty())
+            .or_else(|| {
+                self.run_git(&["var", "GIT_EDITOR"])
+                    .ok()
+                    .map(|editor| editor.trim().to_string())
+                    .filter(|editor| !editor.is_empty())
             })
+            .unwrap_or_else(|| "vi".to_string())
+    }
+
+    /// Raw `git status --porcelain=v1 -z --branch` output
+    pub fn status_porcelain(&self) -> anyhow::Result<String> {
+        Ok(self.run_git(&["status", "--porcelain=v1", "-z", "--branch"])?)
+    }
+
+    /// Paths that differ between HEAD and the index (`git diff --cached`).
+    /// Inside a hook this reads the index git is about to commit.
+    pub fn staged_paths(&self) -> anyhow::Result<Vec<String>> {
+        let output = self.run_git(&["diff", "--cached", "--name-only", "-z"])?;
+        Ok(output
+            .split('\0')
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    /// Submodule paths in the index (gitlink entries, mode 160000). Files
+    /// below them belong to the submodule's own repository.
+    pub fn submodule_paths(&self) -> anyhow::Result<Vec<String>> {
+        let output = self.run_git(&["ls-files", "--stage", "-z"])?;
+        Ok(output
+            .split('\0')
+            .filter_map(|line| line.split_once('\t'))
+            .filter(|(info, _)| info.starts_with("160000 "))
+            .map(|(_, path)| path.to_string())
+            .collect())
     }
 
-    /// Run a git command and return stdout
+    /// Whether the hook `name` in `hooks_dir()` calls git-shadow
+    pub fn hook_state(&self, name: &str) -> HookState {
+        let hook = self.hooks_dir().join(name);
+        if !hook.exists() {
+            return HookState::Missing;
+        }
+        match std::fs::read_to_string(&hook) {
+            Ok(content)
+                if content.contains("git-shadow hook") || content.contains("git shadow hook") =>
+            {
+                HookState::Installed
+            }
+            _ => HookState::Foreign,
+        }
+    }
+
+    /// Hooks from `HOOK_NAMES` that do not call git-shadow; empty when all
+    /// are installed
+    pub fn missing_hooks(&self) -> Vec<&'static str> {
+        HOOK_NAMES
+            .iter()
+            .copied()
+            .filter(|name| self.hook_state(name) != HookState::Installed)
+            .collect()
+    }
+
+    /// Run a git command and return stdout. Paths are never pathspec
+    /// patterns (see `literal_pathspecs`).
     fn run_git(&self, args: &[&str]) -> Result<String, ShadowError> {
-        let output = Command::new("git")
+        let output = literal_pathspecs(&mut Command::new("git"))
             .args(args)
             .current_dir(&self.root)
             .output()
@@ -172,6 +516,132 @@ impl GitRepo {
     }
 }
 
+/// Make git take every path argument literally, so names containing `*`,
+/// `?`, `[` or a leading `:` match only themselves. Settings that would
+/// conflict with it are dropped from the environment.
+fn literal_pathspecs(command: &mut Command) -> &mut Command {
+    command
+        .env("GIT_LITERAL_PATHSPECS", "1")
+        .env_remove("GIT_GLOB_PATHSPECS")
+        .env_remove("GIT_NOGLOB_PATHSPECS")
+        .env_remove("GIT_ICASE_PATHSPECS")
+}
+
+/// `git rev-parse <args>` run from `start`; None if git rejects it
+fn rev_parse(start: &Path, args: &[&str]) -> anyhow::Result<Option<String>> {
+    let output = Command::new("git")
+        .arg("rev-parse")
+        .args(args)
+        .current_dir(start)
+        .output()
+        .context("failed to run git command")?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// Output of `git --version`, trimmed
+pub fn version_output() -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .arg("--version")
+        .output()
+        .context("failed to run git command")?;
+    if !output.status.success() {
+        bail!("`git --version` failed");
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// (major, minor, patch) from `git --version` output. Vendor builds such as
+/// `2.39.3 (Apple Git-145)`, `2.45.1.windows.1` or `2.44.0.rc1` parse by
+/// their leading numbers; a missing minor or patch counts as 0.
+pub fn parse_version(output: &str) -> Option<(u32, u32, u32)> {
+    let text = output.trim();
+    let text = text.strip_prefix("git version").unwrap_or(text);
+    let token = text.split_whitespace().next()?;
+
+    let mut numbers = token.split('.').map(|part| {
+        let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+        digits.parse::<u32>().ok()
+    });
+    let major = numbers.next().flatten()?;
+    let minor = numbers.next().flatten().unwrap_or(0);
+    let patch = numbers.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+/// `GIT_SHADOW_DIR` (relative to the repo root) if set, else the shared `<common dir>/shadow/`
+fn shadow_dir_with_env(root: &Path, common_dir: &Path, env: Option<&OsStr>) -> PathBuf {
+    match env.filter(|dir| !dir.is_empty()) {
+        Some(dir) => root.join(dir),
+        None => common_dir.join("shadow"),
+    }
+}
+
+/// Resolve a path printed by `git rev-parse` (relative to `start`) to an
+/// absolute path without `.`/`..` components
+fn resolve_git_path(start: &Path, raw: &str) -> PathBuf {
+    let joined = start.join(raw);
+    let mut resolved = PathBuf::new();
+    for component in joined.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            other => resolved.push(other.as_os_str()),
+        }
+    }
+    resolved
+}
+
+/// Find `path` in `git status --porcelain=v2 -z` output and return
+/// (index_differs_from_head, worktree_differs_from_index).
+///
+/// - Ordinary entries: `1 XY sub mH mI mW hH hI path`
+/// - Renames/copies: `2 XY sub mH mI mW hH hI Xscore path`, followed by
+///   `origPath` as a record of its own
+///
+/// With `-z` paths are never quoted, so non-ASCII names compare as-is. When
+/// `path` is the source of a rename it no longer exists in the index, so only
+/// the index side is reported as changed.
+fn parse_staging_status(stdout: &str, path: &str) -> (bool, bool) {
+    let mut records = stdout.split('\0');
+    while let Some(record) = records.next() {
+        let (xy, entry_path, orig_path) = if let Some(rest) = record.strip_prefix("1 ") {
+            let parts: Vec<&str> = rest.splitn(8, ' ').collect();
+            if parts.len() < 8 {
+                continue;
+            }
+            (parts[0], parts[7], None)
+        } else if let Some(rest) = record.strip_prefix("2 ") {
+            let orig_path = records.next();
+            let parts: Vec<&str> = rest.splitn(9, ' ').collect();
+            if parts.len() < 9 {
+                continue;
+            }
+            (parts[0], parts[8], orig_path)
+        } else {
+            continue;
+        };
+
+        let x = xy.chars().next().unwrap_or('.');
+        let y = xy.chars().nth(1).unwrap_or('.');
+
+        if entry_path == path {
+            return (x != '.', y != '.');
+        }
+        if orig_path == Some(path) {
+            return (true, false);
+        }
+    }
+
+    // File not in status output = clean
+    (false, false)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,6 +692,117 @@ mod tests {
         std::fs::create_dir_all(&sub).unwrap();
         let found = GitRepo::discover(&sub).unwrap();
         assert_eq!(found.root, repo.root);
+        assert_eq!(found.prefix, "subdir/");
+        assert_eq!(repo.prefix, "");
+    }
+
+    #[test]
+    fn test_discover_from_subdir_resolves_git_dirs() {
+        let (_dir, repo) = make_test_repo();
+        let sub = repo.root.join("a").join("b");
+        std::fs::create_dir_all(&sub).unwrap();
+        let found = GitRepo::discover(&sub).unwrap();
+        assert_eq!(found.git_dir, repo.root.join(".git"));
+        assert_eq!(found.common_dir, repo.root.join(".git"));
+        assert_eq!(found.shadow_dir, repo.root.join(".git").join("shadow"));
+    }
+
+    #[test]
+    fn test_discover_in_linked_worktree() {
+        let (dir, repo) = make_test_repo();
+        let wt = dir.path().join("wt");
+        run_cmd(
+            &repo.root,
+            "git",
+            &["worktree", "add", "-q", wt.to_str().unwrap()],
+        );
+
+        let found = GitRepo::discover(&wt).unwrap();
+        assert_eq!(found.root, wt);
+        assert_eq!(found.common_dir, repo.git_dir);
+        assert_eq!(found.shadow_dir, repo.shadow_dir);
+        assert_eq!(found.hooks_dir(), repo.git_dir.join("hooks"));
+        assert_ne!(found.git_dir, repo.git_dir);
+    }
+
+    #[test]
+    fn test_discover_in_nested_submodules() {
+        let (_dir, repo) = make_test_repo();
+        // repo <- mid <- inner
+        let sources = tempfile::tempdir().unwrap();
+        for name in ["inner", "mid"] {
+            let source = sources.path().join(name);
+            std::fs::create_dir_all(&source).unwrap();
+            run_cmd(&source, "git", &["init", "-q"]);
+            run_cmd(&source, "git", &["config", "user.name", "Test"]);
+            run_cmd(&source, "git", &["config", "user.email", "t@t.com"]);
+            std::fs::write(source.join("CLAUDE.md"), name).unwrap();
+            run_cmd(&source, "git", &["add", "CLAUDE.md"]);
+            run_cmd(&source, "git", &["commit", "-q", "-m", "init"]);
+        }
+        let add_submodule = |cwd: &Path, name: &str| {
+            let url = sources.path().join(name);
+            run_cmd(
+                cwd,
+                "git",
+                &[
+                    "-c",
+                    "protocol.file.allow=always",
+                    "submodule",
+                    "add",
+                    "-q",
+                    url.to_str().unwrap(),
+                    name,
+                ],
+            );
+            run_cmd(cwd, "git", &["commit", "-q", "-m", "add submodule"]);
+        };
+        add_submodule(&sources.path().join("mid"), "inner");
+        add_submodule(&repo.root, "mid");
+        run_cmd(
+            &repo.root,
+            "git",
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "update",
+                "-q",
+                "--init",
+                "--recursive",
+            ],
+        );
+
+        // The nested submodule's git dir lives in the superproject's modules/
+        let nested = repo.root.join("mid").join("inner");
+        let module_dir = repo.git_dir.join("modules/mid/modules/inner");
+        let found = GitRepo::discover(&nested).unwrap();
+        assert_eq!(found.root, nested);
+        assert_eq!(found.git_dir, module_dir);
+        assert_eq!(found.common_dir, module_dir);
+        assert_eq!(found.shadow_dir, module_dir.join("shadow"));
+        assert_eq!(found.hooks_dir(), module_dir.join("hooks"));
+
+        assert_eq!(repo.submodule_paths().unwrap(), ["mid"]);
+        let mid = GitRepo::discover(&repo.root.join("mid")).unwrap();
+        assert_eq!(mid.submodule_paths().unwrap(), ["inner"]);
+    }
+
+    #[test]
+    fn test_resolve_git_path() {
+        let start = Path::new("/repo/sub");
+        assert_eq!(
+            resolve_git_path(start, "../.git"),
+            PathBuf::from("/repo/.git")
+        );
+        assert_eq!(
+            resolve_git_path(start, "/abs/.git"),
+            PathBuf::from("/abs/.git")
+        );
+        assert_eq!(
+            resolve_git_path(start, ".git"),
+            PathBuf::from("/repo/sub/.git")
+        );
     }
 
     #[test]
@@ -231,6 +812,31 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_discover_bare_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        run_cmd(dir.path(), "git", &["init", "--bare", "repo.git"]);
+
+        let err = GitRepo::discover(&dir.path().join("repo.git"))
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ShadowError>(),
+            Some(ShadowError::BareRepo)
+        ));
+    }
+
+    #[test]
+    fn test_discover_inside_git_dir() {
+        let (_dir, repo) = make_test_repo();
+
+        let err = GitRepo::discover(&repo.git_dir).err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<ShadowError>(),
+            Some(ShadowError::NotInWorkTree)
+        ));
+    }
+
     #[test]
     fn test_head_commit() {
         let (_dir, repo) = make_test_repo();
@@ -291,6 +897,49 @@ mod tests {
         assert!(wt); // worktree differs from index
     }
 
+    #[test]
+    fn test_parse_staging_status_rename_entry() {
+        let output = "2 RM N... 100644 100644 100644 aaaa aaaa R100 docs/NEW.md\0CLAUDE.md\0";
+        // Destination of the rename: staged rename plus a worktree change
+        assert_eq!(parse_staging_status(output, "docs/NEW.md"), (true, true));
+        // Source of the rename: gone from the index
+        assert_eq!(parse_staging_status(output, "CLAUDE.md"), (true, false));
+        assert_eq!(parse_staging_status(output, "other.md"), (false, false));
+    }
+
+    #[test]
+    fn test_parse_staging_status_matches_path_with_spaces() {
+        let output = "1 .M N... 100644 100644 100644 aaaa aaaa dir/a b.md\0\
+                      1 M. N... 100644 100644 100644 aaaa bbbb dir/c.md\0";
+        assert_eq!(parse_staging_status(output, "dir/c.md"), (true, false));
+        assert_eq!(parse_staging_status(output, "dir/a b.md"), (false, true));
+    }
+
+    #[test]
+    fn test_staging_status_partial_non_ascii_path() {
+        let (_dir, repo) = make_test_repo();
+        let name = "メモ.md";
+        std::fs::write(repo.root.join(name), "# Memo\n").unwrap();
+        run_cmd(&repo.root, "git", &["add", name]);
+        run_cmd(&repo.root, "git", &["commit", "-m", "memo"]);
+
+        std::fs::write(repo.root.join(name), "# Staged\n").unwrap();
+        run_cmd(&repo.root, "git", &["add", name]);
+        std::fs::write(repo.root.join(name), "# Partial\n").unwrap();
+
+        assert_eq!(repo.staging_status(name).unwrap(), (true, true));
+    }
+
+    #[test]
+    fn test_staging_status_renamed_overlay() {
+        let (_dir, repo) = make_test_repo();
+        run_cmd(&repo.root, "git", &["mv", "CLAUDE.md", "NEW.md"]);
+
+        let (idx, wt) = repo.staging_status("CLAUDE.md").unwrap();
+        assert!(idx);
+        assert!(!wt);
+    }
+
     #[test]
     fn test_add_stages_file() {
         let (_dir, repo) = make_test_repo();
@@ -307,8 +956,185 @@ mod tests {
     }
 
     #[test]
-    fn test_hooks_installed_false() {
+    fn test_blob_size_and_hash() {
+        let (_dir, repo) = make_test_repo();
+        std::fs::write(repo.root.join("copy.md"), "# Test\n").unwrap();
+
+        assert_eq!(repo.blob_size("HEAD", "CLAUDE.md").unwrap(), 7);
+        assert_eq!(
+            repo.blob_id("HEAD", "CLAUDE.md").unwrap(),
+            repo.hash_object(&repo.root.join("copy.md")).unwrap()
+        );
+        assert_eq!(
+            blob_oid(b"# Test\n"),
+            repo.blob_id("HEAD", "CLAUDE.md").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tree_blobs() {
+        let (_dir, repo) = make_test_repo();
+        std::fs::create_dir(repo.root.join("sub dir")).unwrap();
+        std::fs::write(repo.root.join("sub dir/a.txt"), "a\n").unwrap();
+        repo.add("sub dir/a.txt").unwrap();
+        Command::new("git")
+            .args(["commit", "-q", "-m", "sub"])
+            .current_dir(&repo.root)
+            .output()
+            .unwrap();
+
+        let blobs = repo.tree_blobs("HEAD").unwrap();
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(
+            blobs["CLAUDE.md"],
+            repo.blob_id("HEAD", "CLAUDE.md").unwrap()
+        );
+        assert_eq!(
+            blobs["sub dir/a.txt"],
+            repo.blob_id("HEAD", "sub dir/a.txt").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_resolve_commit() {
+        let (_dir, repo) = make_test_repo();
+        let head = repo.head_commit().unwrap();
+        assert_eq!(repo.resolve_commit("HEAD").unwrap(), head);
+        assert_eq!(repo.resolve_commit(&head[..7]).unwrap(), head);
+        assert!(repo.resolve_commit("no-such-ref").is_err());
+    }
+
+    #[test]
+    fn test_config_get() {
         let (_dir, repo) = make_test_repo();
-        assert!(!repo.hooks_installed());
+        assert_eq!(repo.config_get("user.name").as_deref(), Some("Test"));
+        assert_eq!(repo.config_get("shadow.nonexistent"), None);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("git version 2.43.0\n"), Some((2, 43, 0)));
+        assert_eq!(
+            parse_version("git version 2.39.3 (Apple Git-145)"),
+            Some((2, 39, 3))
+        );
+        assert_eq!(
+            parse_version("git version 2.45.1.windows.1"),
+            Some((2, 45, 1))
+        );
+        assert_eq!(parse_version("git version 2.44.0.rc1"), Some((2, 44, 0)));
+        assert_eq!(parse_version("git version 2.47.0-rc0"), Some((2, 47, 0)));
+        assert_eq!(parse_version("git version 3"), Some((3, 0, 0)));
+        assert_eq!(parse_version("git version custom-build"), None);
+        assert_eq!(parse_version(""), None);
+        assert!(parse_version(&version_output().unwrap()).is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_paths_with_pathspec_characters_are_literal() {
+        let (_dir, repo) = make_test_repo();
+        // Each name, read as a pattern, would also match its decoy
+        for (name, decoy) in [
+            ("a[1].md", "a1.md"),
+            ("b*.md", "bx.md"),
+            ("c?.md", "cc.md"),
+            (":(glob)d.md", "d.md"),
+        ] {
+            std::fs::write(repo.root.join(name), "x\n").unwrap();
+            std::fs::write(repo.root.join(decoy), "x\n").unwrap();
+
+            repo.add(name).unwrap();
+            assert!(repo.is_tracked(name).unwrap(), "{}", name);
+            assert!(!repo.is_tracked(decoy).unwrap(), "{}", decoy);
+            assert_eq!(repo.staging_status(name).unwrap(), (true, false));
+
+            run_cmd(&repo.root, "git", &["add", "--", decoy]);
+            repo.unstage_phantom(name).unwrap();
+            assert!(!repo.is_tracked(name).unwrap(), "{}", name);
+            assert!(repo.is_tracked(decoy).unwrap(), "{}", decoy);
+        }
+    }
+
+    #[test]
+    fn test_short_hash() {
+        assert_eq!(short_hash("0123456789abcdef"), "0123456");
+        assert_eq!(short_hash("abc"), "abc");
+        assert_eq!(short_hash(""), "");
+        assert_eq!(short_hash("ああああああああ"), "あああああああ");
+    }
+
+    #[test]
+    fn test_shadow_dir_override() {
+        let root = Path::new("/repo");
+        let common_dir = Path::new("/repo/.git");
+        assert_eq!(
+            shadow_dir_with_env(root, common_dir, None),
+            Path::new("/repo/.git/shadow")
+        );
+        assert_eq!(
+            shadow_dir_with_env(root, common_dir, Some(OsStr::new(""))),
+            Path::new("/repo/.git/shadow")
+        );
+        assert_eq!(
+            shadow_dir_with_env(root, common_dir, Some(OsStr::new("tmp/shadow"))),
+            Path::new("/repo/tmp/shadow")
+        );
+        assert_eq!(
+            shadow_dir_with_env(root, common_dir, Some(OsStr::new("/ci/shadow"))),
+            Path::new("/ci/shadow")
+        );
+    }
+
+    #[test]
+    fn test_missing_hooks() {
+        let (_dir, repo) = make_test_repo();
+        assert_eq!(repo.missing_hooks(), HOOK_NAMES);
+
+        let hooks_dir = repo.hooks_dir();
+        std::fs::create_dir_all(&hooks_dir).unwrap();
+        for name in ["pre-commit", "post-commit", "post-checkout"] {
+            std::fs::write(hooks_dir.join(name), format!("git-shadow hook {}\n", name)).unwrap();
+        }
+        std::fs::write(hooks_dir.join("post-merge"), "echo merged\n").unwrap();
+        assert_eq!(repo.hook_state("post-merge"), HookState::Foreign);
+        assert_eq!(repo.missing_hooks(), ["post-merge"]);
+    }
+
+    #[test]
+    fn test_is_binary_respects_gitattributes() {
+        let (_dir, repo) = make_test_repo();
+        std::fs::write(repo.root.join("plain.txt"), "hello\n").unwrap();
+        std::fs::write(repo.root.join("data.bin"), b"a\0b").unwrap();
+        std::fs::write(repo.root.join("locked.txt"), "hello\n").unwrap();
+        std::fs::write(repo.root.join("raw.txt"), "hello\n").unwrap();
+        std::fs::write(repo.root.join("forced.dat"), b"a\0b").unwrap();
+        assert!(!repo.is_binary("plain.txt").unwrap());
+        assert!(repo.is_binary("data.bin").unwrap());
+
+        std::fs::write(
+            repo.root.join(".gitattributes"),
+            "locked.txt binary\nraw.txt -text\nforced.dat text\n",
+        )
+        .unwrap();
+        assert!(repo.is_binary("locked.txt").unwrap());
+        assert!(repo.is_binary("raw.txt").unwrap());
+        assert!(!repo.is_binary("forced.dat").unwrap());
+        assert!(!repo.is_binary("plain.txt").unwrap());
+    }
+
+    #[test]
+    fn test_hooks_dir_follows_core_hooks_path() {
+        let (_dir, repo) = make_test_repo();
+        assert_eq!(repo.hooks_dir(), repo.common_dir.join("hooks"));
+        assert!(repo.custom_hooks_dir().is_none());
+
+        repo.config_set("core.hooksPath", ".githooks").unwrap();
+        assert_eq!(repo.hooks_dir(), repo.root.join(".githooks"));
+
+        let elsewhere = tempfile::tempdir().unwrap();
+        let absolute = elsewhere.path().to_str().unwrap();
+        repo.config_set("core.hooksPath", absolute).unwrap();
+        assert_eq!(repo.hooks_dir(), elsewhere.path());
     }
 }
@@ -0,0 +1,180 @@
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+/// history.log is rotated to history.log.1 once it reaches this size
+pub const HISTORY_SIZE_LIMIT: u64 = 1_048_576; // 1 MB
+
+const HISTORY_FILE: &str = "history.log";
+const ROTATED_FILE: &str = "history.log.1";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Add,
+    Remove,
+    Rebase,
+    Suspend,
+    Resume,
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Action::Add => "add",
+            Action::Remove => "remove",
+            Action::Rebase => "rebase",
+            Action::Suspend => "suspend",
+            Action::Resume => "resume",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One line of history.log (JSON Lines)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub action: Action,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+}
+
+/// Record an operation. Best-effort: a failure only prints a warning so the
+/// operation itself is never aborted by logging.
+pub fn record(shadow_dir: &Path, action: Action, path: Option<&str>, commit: Option<&str>) {
+    let entry = HistoryEntry {
+        timestamp: Utc::now(),
+        action,
+        path: path.map(str::to_string),
+        commit: commit.map(str::to_string),
+    };
+    if let Err(e) = append(shadow_dir, &entry, HISTORY_SIZE_LIMIT) {
+        eprintln!(
+            "{}",
+            format!("warning: failed to write history: {}", e).yellow()
+        );
+    }
+}
+
+/// Append an entry, rotating the log first if it has reached `limit` bytes
+pub fn append(shadow_dir: &Path, entry: &HistoryEntry, limit: u64) -> anyhow::Result<()> {
+    let log_path = shadow_dir.join(HISTORY_FILE);
+
+    if std::fs::metadata(&log_path).is_ok_and(|m| m.len() >= limit) {
+        std::fs::rename(&log_path, shadow_dir.join(ROTATED_FILE))
+            .context("failed to rotate history.log")?;
+    }
+
+    let mut line = serde_json::to_string(entry).context("failed to serialize history entry")?;
+    line.push('\n');
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .context("failed to open history.log")?;
+    file.write_all(line.as_bytes())
+        .context("failed to write history.log")?;
+    Ok(())
+}
+
+/// Read all entries, oldest first (rotated log, then current log).
+/// Lines that cannot be parsed are skipped.
+pub fn read(shadow_dir: &Path) -> anyhow::Result<Vec<HistoryEntry>> {
+    let mut entries = Vec::new();
+
+    for name in [ROTATED_FILE, HISTORY_FILE] {
+        let log_path = shadow_dir.join(name);
+        if !log_path.exists() {
+            continue;
+        }
+        let file =
+            std::fs::File::open(&log_path).with_context(|| format!("failed to open {}", name))?;
+        for line in std::io::BufReader::new(file).lines() {
+            let line = line?;
+            if let Ok(entry) = serde_json::from_str(&line) {
+                entries.push(entry);
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(action: Action, path: Option<&str>) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: Utc::now(),
+            action,
+            path: path.map(str::to_string),
+            commit: None,
+        }
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        append(dir.path(), &entry(Action::Add, Some("CLAUDE.md")), 1024).unwrap();
+        append(dir.path(), &entry(Action::Suspend, None), 1024).unwrap();
+
+        let entries = read(dir.path()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, Action::Add);
+        assert_eq!(entries[0].path.as_deref(), Some("CLAUDE.md"));
+        assert_eq!(entries[1].action, Action::Suspend);
+    }
+
+    #[test]
+    fn test_json_lines_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut e = entry(Action::Rebase, Some("CLAUDE.md"));
+        e.commit = Some("abc1234".to_string());
+        append(dir.path(), &e, 1024).unwrap();
+
+        let content = std::fs::read_to_string(dir.path().join(HISTORY_FILE)).unwrap();
+        assert_eq!(content.lines().count(), 1);
+        let value: serde_json::Value = serde_json::from_str(content.trim()).unwrap();
+        assert_eq!(value["action"], "rebase");
+        assert_eq!(value["path"], "CLAUDE.md");
+        assert_eq!(value["commit"], "abc1234");
+    }
+
+    #[test]
+    fn test_rotation_keeps_previous_log() {
+        let dir = tempfile::tempdir().unwrap();
+        append(dir.path(), &entry(Action::Add, Some("a.md")), 1).unwrap();
+        // Log is now over the limit, so the next append rotates it
+        append(dir.path(), &entry(Action::Add, Some("b.md")), 1).unwrap();
+
+        assert!(dir.path().join(ROTATED_FILE).exists());
+        let entries = read(dir.path()).unwrap();
+        let paths: Vec<_> = entries.iter().filter_map(|e| e.path.as_deref()).collect();
+        assert_eq!(paths, vec!["a.md", "b.md"]);
+    }
+
+    #[test]
+    fn test_read_skips_invalid_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(HISTORY_FILE), "not json\n").unwrap();
+        append(dir.path(), &entry(Action::Resume, None), 1024).unwrap();
+
+        let entries = read(dir.path()).unwrap();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn test_read_missing_log() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read(dir.path()).unwrap().is_empty());
+    }
+}
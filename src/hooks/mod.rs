@@ -1,3 +1,4 @@
+pub mod post_checkout;
 pub mod post_commit;
 pub mod post_merge;
 pub mod pre_commit;
@@ -0,0 +1,234 @@
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use crate::baseline;
+use crate::commands::resume;
+use crate::config::{FileType, ShadowConfig};
+use crate::git::GitRepo;
+use crate::merge;
+
+/// Handle `post-checkout <prev-head> <new-head> <branch-flag>`.
+/// Only branch checkouts that move HEAD are handled; file checkouts are ignored.
+pub fn handle(git: &GitRepo, args: &[String]) -> Result<()> {
+    let [prev_head, new_head, branch_flag] = args else {
+        bail!("post-checkout expects <prev-head> <new-head> <branch-flag>");
+    };
+
+    if branch_flag != "1" || prev_head == new_head {
+        return Ok(());
+    }
+
+    // `git rebase` checks out commits as it goes; leave overlays alone until it finishes
+    if git.git_dir.join("rebase-merge").exists() || git.git_dir.join("rebase-apply").exists() {
+        return Ok(());
+    }
+
+    let mut config = ShadowConfig::load(&git.shadow_dir)?;
+
+    // Resume suspended files first; the rest are re-applied below
+    if config.is_suspended() {
+        let count = resume::resume_all(git, &mut config)?;
+        println!(
+            "{}",
+            format!("git-shadow: resumed shadow changes for {} file(s)", count).green()
+        );
+    }
+
+    let overlays: Vec<(String, Option<String>)> = config
+        .files
+        .iter()
+        .filter(|(_, entry)| entry.file_type == FileType::Overlay)
+        .map(|(file_path, entry)| (file_path.clone(), entry.baseline_blob.clone()))
+        .collect();
+
+    let mut changed = false;
+    for (file_path, blob) in &overlays {
+        let Ok(old_baseline) = baseline::read(git, file_path, blob.as_deref()) else {
+            continue;
+        };
+        let Ok(head_content) = git.show_file("HEAD", file_path) else {
+            continue;
+        };
+        if old_baseline == head_content {
+            continue;
+        }
+
+        let current = match std::fs::read(git.root.join(file_path)) {
+            Ok(content) => content,
+            Err(_) => {
+                eprintln!(
+                    "{}",
+                    format!("warning: {} does not exist in working tree", file_path).yellow()
+                );
+                continue;
+            }
+        };
+
+        resume::apply_onto_head(
+            git,
+            &mut config,
+            file_path,
+            &old_baseline,
+            &current,
+            new_head,
+            merge::MergeStrategy::Conflict,
+        )?;
+        changed = true;
+    }
+
+    if changed {
+        config.save(&git.shadow_dir)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs_util;
+
+    fn make_test_repo() -> (tempfile::TempDir, GitRepo) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        std::process::Command::new("git")
+            .args(["init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.name", "Test"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["config", "user.email", "t@t.com"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::fs::write(root.join("CLAUDE.md"), "# Team\n").unwrap();
+        std::process::Command::new("git")
+            .args(["add", "CLAUDE.md"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "init"])
+            .current_dir(&root)
+            .output()
+            .unwrap();
+
+        let repo = GitRepo::discover(&root).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("baselines")).unwrap();
+        std::fs::create_dir_all(repo.shadow_dir.join("stash")).unwrap();
+        (dir, repo)
+    }
+
+    fn commit_file(git: &GitRepo, content: &str) -> String {
+        std::fs::write(git.root.join("CLAUDE.md"), content).unwrap();
+        std::process::Command::new("git")
+            .args(["add", "CLAUDE.md"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "update"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        git.head_commit().unwrap()
+    }
+
+    fn register_overlay(git: &GitRepo) -> (ShadowConfig, String) {
+        let commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), commit.clone())
+            .unwrap();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            b"# Team\n",
+        )
+        .unwrap();
+        config.save(&git.shadow_dir).unwrap();
+        (config, commit)
+    }
+
+    fn args(prev: &str, new: &str, flag: &str) -> Vec<String> {
+        vec![prev.to_string(), new.to_string(), flag.to_string()]
+    }
+
+    #[test]
+    fn test_file_checkout_is_ignored() {
+        let (_dir, git) = make_test_repo();
+        let (_config, old_head) = register_overlay(&git);
+        let new_head = commit_file(&git, "# Team\n# Updated\n");
+
+        handle(&git, &args(&old_head, &new_head, "0")).unwrap();
+
+        let baseline = std::fs::read_to_string(
+            git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+        )
+        .unwrap();
+        assert_eq!(baseline, "# Team\n");
+    }
+
+    #[test]
+    fn test_branch_checkout_merges_drifted_overlay() {
+        let (_dir, git) = make_test_repo();
+        let (_config, old_head) = register_overlay(&git);
+        let new_head = commit_file(&git, "# Team\n# Updated\n");
+        std::fs::write(git.root.join("CLAUDE.md"), "# Mine\n# Team\n").unwrap();
+
+        handle(&git, &args(&old_head, &new_head, "1")).unwrap();
+
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(content, "# Mine\n# Team\n# Updated\n");
+        let baseline = std::fs::read_to_string(
+            git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+        )
+        .unwrap();
+        assert_eq!(baseline, "# Team\n# Updated\n");
+        let config = ShadowConfig::load(&git.shadow_dir).unwrap();
+        assert_eq!(
+            config.get("CLAUDE.md").unwrap().baseline_commit.as_deref(),
+            Some(new_head.as_str())
+        );
+    }
+
+    #[test]
+    fn test_branch_checkout_resumes_when_suspended() {
+        let (_dir, git) = make_test_repo();
+        let (mut config, old_head) = register_overlay(&git);
+        config.files.get_mut("CLAUDE.md").unwrap().suspended = true;
+        config.save(&git.shadow_dir).unwrap();
+        let suspended_dir = git.shadow_dir.join("suspended");
+        std::fs::create_dir_all(&suspended_dir).unwrap();
+        std::fs::write(
+            suspended_dir.join(crate::path::encode_path("CLAUDE.md")),
+            "# Mine\n# Team\n",
+        )
+        .unwrap();
+        let new_head = commit_file(&git, "# Team\n# Updated\n");
+
+        handle(&git, &args(&old_head, &new_head, "1")).unwrap();
+
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(content, "# Mine\n# Team\n# Updated\n");
+        let config = ShadowConfig::load(&git.shadow_dir).unwrap();
+        assert!(!config.is_suspended());
+        assert!(!suspended_dir.exists());
+    }
+
+    #[test]
+    fn test_missing_args_is_error() {
+        let (_dir, git) = make_test_repo();
+        assert!(handle(&git, &[]).is_err());
+    }
+}
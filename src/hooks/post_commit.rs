@@ -2,6 +2,7 @@ use anyhow::Result;
 use colored::Colorize;
 
 use crate::config::ShadowConfig;
+use crate::fs_util;
 use crate::git::GitRepo;
 use crate::lock;
 use crate::path;
@@ -15,10 +16,7 @@ pub fn handle(git: &GitRepo) -> Result<()> {
         return Ok(());
     }
 
-    let stash_files: Vec<_> = std::fs::read_dir(&stash_dir)?
-        .filter_map(|e| e.ok())
-        .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
-        .collect();
+    let stash_files = path::stored_files(&stash_dir)?;
 
     if stash_files.is_empty() {
         lock::release_lock(&git.shadow_dir)?;
@@ -30,14 +28,16 @@ pub fn handle(git: &GitRepo) -> Result<()> {
     for entry in &stash_files {
         let filename = entry.file_name();
         let encoded = filename.to_string_lossy();
-        let normalized = path::decode_path(&encoded);
+        let normalized = path::decode_path(&git.shadow_dir, &encoded);
 
         let worktree_path = git.root.join(&normalized);
         let stash_path = entry.path();
 
         // Best-effort restore
         match std::fs::read(&stash_path) {
-            Ok(content) => match std::fs::write(&worktree_path, &content) {
+            Ok(content) => match std::fs::write(&worktree_path, &content)
+                .and_then(|_| fs_util::copy_permissions(&stash_path, &worktree_path))
+            {
                 Ok(_) => {
                     // Successfully restored, remove stash entry
                     let _ = std::fs::remove_file(&stash_path);
@@ -118,17 +118,20 @@ mod tests {
         (dir, repo)
     }
 
+    /// Stash `content` for `file_path` the way pre-commit does
+    fn write_stash(git: &GitRepo, file_path: &str, content: &[u8]) {
+        let encoded = path::encode_path(file_path);
+        fs_util::atomic_write(&git.shadow_dir.join("stash").join(encoded), content).unwrap();
+        path::update_path_map(&git.shadow_dir, [file_path]).unwrap();
+    }
+
     #[test]
     fn test_restores_stashed_overlay() {
         let (_dir, git) = make_test_repo();
 
         // Simulate post pre-commit state: baseline in worktree, shadow in stash
         std::fs::write(git.root.join("CLAUDE.md"), "# Team\n").unwrap();
-        fs_util::atomic_write(
-            &git.shadow_dir.join("stash").join("CLAUDE.md"),
-            b"# Team\n# My shadow\n",
-        )
-        .unwrap();
+        write_stash(&git, "CLAUDE.md", b"# Team\n# My shadow\n");
         lock::acquire_lock(&git.shadow_dir).unwrap();
 
         handle(&git).unwrap();
@@ -138,7 +141,11 @@ mod tests {
         assert_eq!(content, "# Team\n# My shadow\n");
 
         // Stash should be cleaned
-        assert!(!git.shadow_dir.join("stash").join("CLAUDE.md").exists());
+        assert!(!git
+            .shadow_dir
+            .join("stash")
+            .join(crate::path::encode_path("CLAUDE.md"))
+            .exists());
 
         // Lock should be released
         assert!(matches!(
@@ -152,8 +159,7 @@ mod tests {
         let (_dir, git) = make_test_repo();
 
         // Create phantom stash
-        fs_util::atomic_write(&git.shadow_dir.join("stash").join("local.md"), b"# Local\n")
-            .unwrap();
+        write_stash(&git, "local.md", b"# Local\n");
         lock::acquire_lock(&git.shadow_dir).unwrap();
 
         handle(&git).unwrap();
@@ -188,17 +194,11 @@ mod tests {
     }
 
     #[test]
-    fn test_decodes_url_encoded_stash_path() {
+    fn test_decodes_nested_stash_path() {
         let (_dir, git) = make_test_repo();
 
-        // Create stash with URL-encoded filename
-        let encoded = path::encode_path("src/components/CLAUDE.md");
         std::fs::create_dir_all(git.root.join("src/components")).unwrap();
-        fs_util::atomic_write(
-            &git.shadow_dir.join("stash").join(&encoded),
-            b"# Component\n",
-        )
-        .unwrap();
+        write_stash(&git, "src/components/CLAUDE.md", b"# Component\n");
         lock::acquire_lock(&git.shadow_dir).unwrap();
 
         handle(&git).unwrap();
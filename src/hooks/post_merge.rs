@@ -1,45 +1,114 @@
 use anyhow::Result;
 use colored::Colorize;
 
+use crate::baseline;
+use crate::commands::rebase::{self, RebaseOutcome};
 use crate::config::{FileType, ShadowConfig};
 use crate::git::GitRepo;
-use crate::path;
+use crate::history::{self, Action};
+use crate::merge;
+use crate::settings::Settings;
 
 pub fn handle(git: &GitRepo) -> Result<()> {
-    let config = ShadowConfig::load(&git.shadow_dir)?;
+    let mut config = ShadowConfig::load(&git.shadow_dir)?;
     let head = git.head_commit()?;
 
+    let drifted = drifted_overlays(git, &config, &head);
+    if drifted.is_empty() {
+        return Ok(());
+    }
+
+    let auto_rebase = match Settings::load(&git.shadow_dir) {
+        Ok(settings) => settings.auto_rebase_on_merge,
+        Err(e) => {
+            eprintln!("{}", format!("warning: {:#}", e).yellow());
+            false
+        }
+    };
+
+    // Suspended overlays hold the baseline in the working tree; `resume`
+    // merges them instead.
+    let (rebase_now, warn_only): (Vec<_>, Vec<_>) = drifted
+        .into_iter()
+        .partition(|f| auto_rebase && !config.get(f).is_some_and(|e| e.suspended));
+    for file_path in &warn_only {
+        warn_outdated(file_path);
+    }
+    if !rebase_now.is_empty() {
+        auto_rebase_files(git, &mut config, &rebase_now, &head);
+    }
+
+    Ok(())
+}
+
+/// Overlays whose baseline content differs from HEAD
+fn drifted_overlays(git: &GitRepo, config: &ShadowConfig, head: &str) -> Vec<String> {
+    let mut drifted = Vec::new();
+
     for (file_path, entry) in &config.files {
         if entry.file_type != FileType::Overlay {
             continue;
         }
 
         if let Some(ref baseline_commit) = entry.baseline_commit {
-            if *baseline_commit == head {
+            if baseline_commit == head {
                 continue;
             }
 
             // Check if file content actually changed
-            let encoded = path::encode_path(file_path);
-            let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
-            if let Ok(baseline_content) = std::fs::read(&baseline_path) {
+            let blob = entry.baseline_blob.as_deref();
+            if let Ok(baseline_content) = baseline::read(git, file_path, blob) {
                 if let Ok(head_content) = git.show_file("HEAD", file_path) {
                     if baseline_content != head_content {
-                        eprintln!(
-                            "{}",
-                            format!(
-                                "warning: baseline for {} is outdated.\n  Run `git-shadow rebase {}`",
-                                file_path, file_path
-                            )
-                            .yellow()
-                        );
+                        drifted.push(file_path.clone());
                     }
                 }
             }
         }
     }
 
-    Ok(())
+    drifted
+}
+
+fn warn_outdated(file_path: &str) {
+    eprintln!(
+        "{}",
+        format!(
+            "warning: baseline for {} is outdated.\n  Run `git-shadow rebase {}`",
+            file_path, file_path
+        )
+        .yellow()
+    );
+}
+
+/// `auto_rebase_on_merge`: 3-way merge each drifted overlay onto HEAD.
+/// Failures are only reported so the merge itself is never failed.
+fn auto_rebase_files(git: &GitRepo, config: &mut ShadowConfig, targets: &[String], head: &str) {
+    for file_path in targets {
+        match rebase::rebase_file(git, config, file_path, head, merge::MergeStrategy::Conflict) {
+            Ok(RebaseOutcome::Unchanged) => {}
+            Ok(RebaseOutcome::Updated | RebaseOutcome::Conflict) => {
+                history::record(&git.shadow_dir, Action::Rebase, Some(file_path), Some(head));
+            }
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "warning: auto-rebase of {} failed: {:#}\n  Run `git-shadow rebase {}`",
+                        file_path, e, file_path
+                    )
+                    .yellow()
+                );
+            }
+        }
+    }
+
+    if let Err(e) = config.save(&git.shadow_dir) {
+        eprintln!(
+            "{}",
+            format!("warning: failed to save config after auto-rebase: {:#}", e).yellow()
+        );
+    }
 }
 
 #[cfg(test)]
@@ -94,7 +163,9 @@ mod tests {
         // Save baseline
         let content = git.show_file("HEAD", "CLAUDE.md").unwrap();
         fs_util::atomic_write(
-            &git.shadow_dir.join("baselines").join("CLAUDE.md"),
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
             &content,
         )
         .unwrap();
@@ -117,7 +188,9 @@ mod tests {
         // Save old baseline
         let content = git.show_file("HEAD", "CLAUDE.md").unwrap();
         fs_util::atomic_write(
-            &git.shadow_dir.join("baselines").join("CLAUDE.md"),
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
             &content,
         )
         .unwrap();
@@ -140,4 +213,91 @@ mod tests {
         // Should not error (warnings go to stderr)
         handle(&git).unwrap();
     }
+
+    /// Overlay CLAUDE.md with `shadow` in the working tree, then commit
+    /// `upstream` so the baseline drifts
+    fn setup_drift(git: &GitRepo, shadow: &str, upstream: &str) {
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), git.head_commit().unwrap())
+            .unwrap();
+        let content = git.show_file("HEAD", "CLAUDE.md").unwrap();
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            &content,
+        )
+        .unwrap();
+        config.save(&git.shadow_dir).unwrap();
+
+        std::fs::write(git.root.join("CLAUDE.md"), upstream).unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-am", "upstream"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), shadow).unwrap();
+    }
+
+    fn enable_auto_rebase(git: &GitRepo) {
+        Settings {
+            auto_rebase_on_merge: true,
+            ..Default::default()
+        }
+        .save(&git.shadow_dir)
+        .unwrap();
+    }
+
+    #[test]
+    fn test_warns_only_without_auto_rebase() {
+        let (_dir, git) = make_test_repo();
+        setup_drift(&git, "# Team\n# Mine\n", "# Team\n## Rules\n");
+
+        handle(&git).unwrap();
+
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(content, "# Team\n# Mine\n");
+    }
+
+    #[test]
+    fn test_auto_rebase_merges_drifted_overlay() {
+        let (_dir, git) = make_test_repo();
+        setup_drift(&git, "# Mine\n\n# Team\n", "# Team\n\n## Rules\n");
+        enable_auto_rebase(&git);
+
+        handle(&git).unwrap();
+
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert_eq!(content, "# Mine\n\n# Team\n\n## Rules\n");
+        let config = ShadowConfig::load(&git.shadow_dir).unwrap();
+        assert_eq!(
+            config.get("CLAUDE.md").unwrap().baseline_commit.as_deref(),
+            Some(git.head_commit().unwrap().as_str())
+        );
+    }
+
+    #[test]
+    fn test_auto_rebase_conflict_keeps_orig() {
+        let (_dir, git) = make_test_repo();
+        setup_drift(&git, "# Mine\n", "# Theirs\n");
+        enable_auto_rebase(&git);
+
+        handle(&git).unwrap();
+
+        let content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
+        assert!(content.contains("<<<<<<<"));
+        let orig = std::fs::read_to_string(git.root.join("CLAUDE.md.orig")).unwrap();
+        assert_eq!(orig, "# Mine\n");
+    }
+
+    #[test]
+    fn test_auto_rebase_failure_does_not_fail_hook() {
+        let (_dir, git) = make_test_repo();
+        setup_drift(&git, "# Mine\n", "# Theirs\n");
+        enable_auto_rebase(&git);
+        std::fs::remove_file(git.root.join("CLAUDE.md")).unwrap();
+
+        handle(&git).unwrap();
+    }
 }
@@ -1,65 +1,128 @@
-use anyhow::{Context, Result};
+use std::ffi::OsStr;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
+use rayon::prelude::*;
 
+use crate::baseline;
+use crate::commands::{doctor, restore};
 use crate::config::{FileEntry, FileType, ShadowConfig};
 use crate::error::ShadowError;
 use crate::git::GitRepo;
-use crate::lock;
-use crate::{fs_util, path};
+use crate::lock::{self, StalePolicy};
+use crate::settings::Settings;
+use crate::{fs_util, merge, path};
+
+/// Index entry (mode, object id) of an overlay before `index_only_swap`
+/// replaced it; None when the path was not in the index
+type IndexEntry = Option<(String, String)>;
 
-/// Tracks stashed files for rollback capability
+/// Tracks stashed files for rollback capability. Files are processed in
+/// parallel, so every record goes through a mutex.
 struct PreCommitTransaction {
-    stashed_overlays: Vec<String>, // normalized paths of overlay files stashed
-    stashed_phantoms: Vec<String>, // normalized paths of phantom files stashed
-    overwritten: Vec<String>,      // overlay files where baseline was restored
+    stashed_overlays: Mutex<Vec<String>>, // normalized paths of overlay files stashed
+    stashed_phantoms: Mutex<Vec<String>>, // normalized paths of phantom files stashed
+    overwritten: Mutex<Vec<String>>,      // overlay files where baseline was restored
+    index_swapped: Mutex<Vec<(String, IndexEntry)>>, // overlays staged by `index_only_swap`
 }
 
 impl PreCommitTransaction {
     fn new() -> Self {
         Self {
-            stashed_overlays: Vec::new(),
-            stashed_phantoms: Vec::new(),
-            overwritten: Vec::new(),
+            stashed_overlays: Mutex::new(Vec::new()),
+            stashed_phantoms: Mutex::new(Vec::new()),
+            overwritten: Mutex::new(Vec::new()),
+            index_swapped: Mutex::new(Vec::new()),
         }
     }
 
+    fn record(list: &Mutex<Vec<String>>, file_path: &str) {
+        // A poisoned lock still holds valid records; keep them for rollback
+        list.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(file_path.to_string());
+    }
+
+    fn take(list: &Mutex<Vec<String>>) -> Vec<String> {
+        std::mem::take(&mut *list.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
     /// Best-effort rollback: restore stashed files to working tree
     fn rollback(&self, git: &GitRepo) {
-        for file_path in self
-            .stashed_overlays
-            .iter()
-            .chain(self.stashed_phantoms.iter())
-        {
-            let encoded = path::encode_path(file_path);
+        let overwritten = Self::take(&self.overwritten);
+        let stashed = Self::take(&self.stashed_overlays)
+            .into_iter()
+            .chain(Self::take(&self.stashed_phantoms));
+        for file_path in stashed {
+            let encoded = path::encode_path(&file_path);
             let stash_path = git.shadow_dir.join("stash").join(&encoded);
             let worktree_path = git.root.join(file_path);
 
             if stash_path.exists() {
                 if let Ok(content) = std::fs::read(&stash_path) {
                     let _ = std::fs::write(&worktree_path, &content);
+                    let _ = fs_util::copy_permissions(&stash_path, &worktree_path);
                     let _ = std::fs::remove_file(&stash_path);
                 }
             }
         }
 
         // Re-stage overlay files that were overwritten with baseline
-        for file_path in &self.overwritten {
+        for file_path in &overwritten {
             let _ = git.add(file_path);
         }
+
+        // Put back the index entries `index_only_swap` replaced
+        let swapped =
+            std::mem::take(&mut *self.index_swapped.lock().unwrap_or_else(|e| e.into_inner()));
+        for (file_path, original) in swapped {
+            let _ = match original {
+                Some((mode, oid)) => git.set_index_entry(&file_path, &mode, &oid),
+                None => git.unstage_phantom(&file_path).map_err(Into::into),
+            };
+        }
     }
 }
 
 pub fn handle(git: &GitRepo) -> Result<()> {
     // 0. Acquire lock
-    lock::acquire_lock(&git.shadow_dir).map_err(|e| {
+    let policy = if auto_restore_enabled(git) {
+        StalePolicy::Recover
+    } else {
+        StalePolicy::Fail
+    };
+    let recovered = lock::acquire_lock_with(&git.shadow_dir, policy).map_err(|e| {
         // Convert StaleLock to anyhow with context
         anyhow::anyhow!("{}", e)
     })?;
 
+    // A stale lock means a previous commit was interrupted; bring back its stash
+    if let Some(pid) = recovered {
+        eprintln!(
+            "{}",
+            format!("warning: recovered stale lock (PID {})", pid).yellow()
+        );
+        match restore::restore_stash(git, None) {
+            Ok(restored) if !restored.is_empty() => {
+                eprintln!(
+                    "{}",
+                    format!("warning: restored {} file(s) from stash", restored.len()).yellow()
+                );
+            }
+            Ok(_) => {}
+            Err(e) => {
+                lock::release_lock(&git.shadow_dir).ok();
+                return Err(e);
+            }
+        }
+    }
+
     let config = ShadowConfig::load(&git.shadow_dir)?;
 
     // Block commits while suspended
-    if config.suspended {
+    if config.is_suspended() {
         lock::release_lock(&git.shadow_dir)?;
         return Err(ShadowError::Suspended.into());
     }
@@ -70,11 +133,15 @@ pub fn handle(git: &GitRepo) -> Result<()> {
     }
 
     // 1. Integrity checks
-    if let Err(e) = run_hard_checks(git, &config) {
+    let settings = Settings::load(&git.shadow_dir).unwrap_or_default();
+    if let Err(e) = run_hard_checks(git, &config, &settings) {
         lock::release_lock(&git.shadow_dir).ok();
         return Err(e);
     }
-    run_soft_checks(git, &config);
+    // Soft checks only warn; `status` and `doctor` report the same problems
+    if !settings.suppress_warnings() {
+        run_soft_checks(git, &config, &settings);
+    }
 
     // 2. Partial staging detection
     if let Err(e) = detect_partial_staging(git, &config) {
@@ -83,8 +150,16 @@ pub fn handle(git: &GitRepo) -> Result<()> {
     }
 
     // 3-4. Process files with rollback
-    let mut tx = PreCommitTransaction::new();
-    if let Err(e) = process_files(git, &config, &mut tx) {
+    let config = match partial_commit_paths(git) {
+        Ok(Some(paths)) => scope_to_commit(&config, &paths),
+        Ok(None) => config,
+        Err(e) => {
+            lock::release_lock(&git.shadow_dir).ok();
+            return Err(e);
+        }
+    };
+    let tx = PreCommitTransaction::new();
+    if let Err(e) = process_files(git, &config, &tx, settings.index_only_swap) {
         tx.rollback(git);
         lock::release_lock(&git.shadow_dir).ok();
         return Err(e);
@@ -94,16 +169,50 @@ pub fn handle(git: &GitRepo) -> Result<()> {
     Ok(())
 }
 
-fn run_hard_checks(git: &GitRepo, config: &ShadowConfig) -> Result<()> {
+/// `git commit <paths>` commits a temporary index (`next-index-*.lock`)
+/// instead of the real one. Returns the paths that commit contains, or None
+/// for a regular commit.
+fn partial_commit_paths(git: &GitRepo) -> Result<Option<Vec<String>>> {
+    let index_file = std::env::var_os("GIT_INDEX_FILE");
+    if !is_partial_commit(index_file.as_deref()) {
+        return Ok(None);
+    }
+    Ok(Some(git.staged_paths()?))
+}
+
+fn is_partial_commit(index_file: Option<&OsStr>) -> bool {
+    index_file
+        .and_then(|f| Path::new(f).file_name())
+        .is_some_and(|name| name.to_string_lossy().starts_with("next-index-"))
+}
+
+/// Drop overlays that are not part of a partial commit. Swapping them would
+/// stage their baseline into a commit the user never asked for. Phantoms are
+/// kept: unstaging them is always safe.
+fn scope_to_commit(config: &ShadowConfig, paths: &[String]) -> ShadowConfig {
+    let mut scoped = config.clone();
+    scoped.files.retain(|file_path, entry| {
+        entry.file_type != FileType::Overlay || paths.contains(file_path)
+    });
+    scoped
+}
+
+/// Stale lock recovery is enabled by `GIT_SHADOW_AUTO_RESTORE=1`
+/// or `git config shadow.autoRestore true`
+fn auto_restore_enabled(git: &GitRepo) -> bool {
+    if std::env::var("GIT_SHADOW_AUTO_RESTORE").is_ok_and(|v| v == "1") {
+        return true;
+    }
+    matches!(
+        git.config_get("shadow.autoRestore").as_deref(),
+        Some("true" | "1" | "yes" | "on")
+    )
+}
+
+fn run_hard_checks(git: &GitRepo, config: &ShadowConfig, settings: &Settings) -> Result<()> {
     // Check stash remnants
-    let stash_dir = git.shadow_dir.join("stash");
-    if stash_dir.exists() {
-        let has_files = std::fs::read_dir(&stash_dir)?
-            .filter_map(|e| e.ok())
-            .any(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false));
-        if has_files {
-            return Err(ShadowError::StashRemaining.into());
-        }
+    if !path::stored_files(&git.shadow_dir.join("stash"))?.is_empty() {
+        return Err(ShadowError::StashRemaining.into());
     }
 
     for (file_path, entry) in &config.files {
@@ -114,20 +223,62 @@ fn run_hard_checks(git: &GitRepo, config: &ShadowConfig) -> Result<()> {
                     return Err(ShadowError::FileMissing(file_path.clone()).into());
                 }
                 // Check baseline exists
-                let encoded = path::encode_path(file_path);
-                let baseline = git.shadow_dir.join("baselines").join(&encoded);
-                if !baseline.exists() {
+                if !baseline::exists(git, file_path, entry.baseline_blob.as_deref()) {
                     return Err(ShadowError::BaselineMissing(file_path.clone()).into());
                 }
+                // A conflicting rebase/resume left markers behind; stop before
+                // they end up in the stash and, after post-commit, stay unnoticed
+                if !entry.is_binary {
+                    let content = std::fs::read_to_string(git.root.join(file_path));
+                    if content.is_ok_and(|c| merge::has_conflict_markers(&c)) {
+                        return Err(ShadowError::ConflictMarkers(file_path.clone()).into());
+                    }
+                }
             }
             FileType::Phantom => {}
         }
     }
 
+    if settings.strict_overlay_checks {
+        let problems = overlay_content_problems(git, config, settings.max_file_size()?);
+        if !problems.is_empty() {
+            bail!("{} (strict_overlay_checks is on)", problems.join("; "));
+        }
+    }
+
     Ok(())
 }
 
-fn run_soft_checks(git: &GitRepo, config: &ShadowConfig) {
+/// Overlays that outgrew the size limit or turned binary after `add`. The
+/// size comes from metadata alone; only files within the limit are read.
+fn overlay_content_problems(git: &GitRepo, config: &ShadowConfig, limit: u64) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (file_path, entry) in &config.files {
+        if entry.file_type != FileType::Overlay {
+            continue;
+        }
+        let worktree_path = git.root.join(file_path);
+        let Ok(metadata) = std::fs::metadata(&worktree_path) else {
+            continue;
+        };
+        if metadata.len() > limit {
+            problems.push(format!(
+                "{} has grown to {} bytes, over the {} byte limit",
+                file_path,
+                metadata.len(),
+                limit
+            ));
+        } else if !entry.is_binary && fs_util::is_binary(&worktree_path).unwrap_or(false) {
+            problems.push(format!(
+                "{} has become binary, so its shadow changes can no longer be merged",
+                file_path
+            ));
+        }
+    }
+    problems
+}
+
+fn run_soft_checks(git: &GitRepo, config: &ShadowConfig, settings: &Settings) {
     let head = git.head_commit().ok();
 
     for (file_path, entry) in &config.files {
@@ -137,13 +288,13 @@ fn run_soft_checks(git: &GitRepo, config: &ShadowConfig) {
             {
                 if baseline_commit != current_head {
                     // Hash differs — check if file content actually changed
-                    let encoded = path::encode_path(file_path);
-                    let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
                     let content_changed = git
                         .show_file("HEAD", file_path)
                         .ok()
                         .map(|head_content| {
-                            let baseline_bytes = std::fs::read(&baseline_path).unwrap_or_default();
+                            let baseline_bytes =
+                                baseline::read(git, file_path, entry.baseline_blob.as_deref())
+                                    .unwrap_or_default();
                             baseline_bytes != head_content
                         })
                         .unwrap_or(false);
@@ -162,6 +313,19 @@ fn run_soft_checks(git: &GitRepo, config: &ShadowConfig) {
             }
         }
     }
+
+    for warning in doctor::tracked_phantom_warnings(git, config) {
+        eprintln!("{}", format!("warning: {}", warning).yellow());
+    }
+
+    // With strict_overlay_checks the hard checks have already failed on these
+    if !settings.strict_overlay_checks {
+        if let Ok(limit) = settings.max_file_size() {
+            for problem in overlay_content_problems(git, config, limit) {
+                eprintln!("{}", format!("warning: {}", problem).yellow());
+            }
+        }
+    }
 }
 
 fn detect_partial_staging(git: &GitRepo, config: &ShadowConfig) -> Result<()> {
@@ -176,61 +340,117 @@ fn detect_partial_staging(git: &GitRepo, config: &ShadowConfig) -> Result<()> {
     Ok(())
 }
 
+/// Stash and swap files in parallel, then touch the index sequentially:
+/// concurrent `git add`/`git rm` calls would race for `.git/index.lock`.
+/// With `index_only`, overlays are never stashed or overwritten; their
+/// baseline goes straight into the index.
 fn process_files(
     git: &GitRepo,
     config: &ShadowConfig,
-    tx: &mut PreCommitTransaction,
+    tx: &PreCommitTransaction,
+    index_only: bool,
 ) -> Result<()> {
-    for (file_path, entry) in &config.files {
-        match entry.file_type {
-            FileType::Overlay => {
-                process_overlay(git, file_path, tx)?;
-            }
-            FileType::Phantom => {
-                process_phantom(git, file_path, entry, tx)?;
+    // 1. File I/O per file (independent of each other)
+    config
+        .files
+        .par_iter()
+        .try_for_each(|(file_path, entry)| match entry.file_type {
+            FileType::Overlay if index_only => Ok(()),
+            FileType::Overlay => process_overlay(git, file_path, entry, tx),
+            FileType::Phantom => process_phantom(git, file_path, entry, tx),
+        })?;
+
+    // 2. Stage the baseline content of every overlay
+    if index_only {
+        for (file_path, entry) in &config.files {
+            if entry.file_type == FileType::Overlay {
+                stage_baseline(git, file_path, entry, tx)
+                    .with_context(|| format!("failed to stage baseline for {}", file_path))?;
             }
         }
+    } else {
+        let overlays: Vec<&str> = config
+            .files
+            .iter()
+            .filter(|(_, entry)| entry.file_type == FileType::Overlay)
+            .map(|(file_path, _)| file_path.as_str())
+            .collect();
+        git.add_paths(&overlays)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .context("failed to stage overlay baselines")?;
+    }
+
+    // 3. Unstage phantoms
+    for (file_path, entry) in &config.files {
+        if entry.file_type == FileType::Phantom {
+            git.unstage_phantom(file_path)?;
+        }
     }
     Ok(())
 }
 
-fn process_overlay(git: &GitRepo, file_path: &str, tx: &mut PreCommitTransaction) -> Result<()> {
+fn process_overlay(
+    git: &GitRepo,
+    file_path: &str,
+    entry: &FileEntry,
+    tx: &PreCommitTransaction,
+) -> Result<()> {
     let encoded = path::encode_path(file_path);
     let worktree_path = git.root.join(file_path);
     let stash_path = git.shadow_dir.join("stash").join(&encoded);
-    let baseline_path = git.shadow_dir.join("baselines").join(&encoded);
 
     // a. Stash current content
     let content =
         std::fs::read(&worktree_path).with_context(|| format!("failed to read {}", file_path))?;
-    fs_util::atomic_write(&stash_path, &content)
+    fs_util::atomic_write_with_mode_of(&stash_path, &content, &worktree_path)
         .with_context(|| format!("failed to stash {}", file_path))?;
-    tx.stashed_overlays.push(file_path.to_string());
+    PreCommitTransaction::record(&tx.stashed_overlays, file_path);
 
     // b. Restore baseline
-    let baseline = std::fs::read(&baseline_path)
-        .with_context(|| format!("failed to read baseline for {}", file_path))?;
+    let baseline = baseline::read(git, file_path, entry.baseline_blob.as_deref())?;
     std::fs::write(&worktree_path, &baseline)
         .with_context(|| format!("failed to restore baseline for {}", file_path))?;
-    tx.overwritten.push(file_path.to_string());
-
-    // c. Stage the baseline content
-    git.add(file_path)
-        .map_err(|e| anyhow::anyhow!("{}", e))
-        .with_context(|| format!("failed to stage {}", file_path))?;
+    PreCommitTransaction::record(&tx.overwritten, file_path);
 
+    // c. Staging happens afterwards in one batch (see process_files)
     Ok(())
 }
 
+/// `index_only_swap`: point the index entry at the baseline blob. The
+/// working tree keeps the shadow content throughout the commit, so tools
+/// watching it never see the baseline. The index mode is kept, so is the
+/// executable bit.
+fn stage_baseline(
+    git: &GitRepo,
+    file_path: &str,
+    entry: &FileEntry,
+    tx: &PreCommitTransaction,
+) -> Result<()> {
+    let original = git.index_entry(file_path)?;
+    let oid = match &entry.baseline_blob {
+        Some(oid) => oid.clone(),
+        None => git.write_blob(&baseline::read(git, file_path, None)?)?,
+    };
+    let mode = original
+        .as_ref()
+        .map_or("100644", |(mode, _)| mode.as_str())
+        .to_string();
+
+    tx.index_swapped
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push((file_path.to_string(), original));
+    git.set_index_entry(file_path, &mode, &oid)
+}
+
 fn process_phantom(
     git: &GitRepo,
     file_path: &str,
     entry: &FileEntry,
-    tx: &mut PreCommitTransaction,
+    tx: &PreCommitTransaction,
 ) -> Result<()> {
     if entry.is_directory {
-        // Directory phantoms: no stash needed, just unstage
-        git.unstage_phantom(file_path)?;
+        // Directory phantoms: no stash needed, only unstaged (see process_files)
         return Ok(());
     }
 
@@ -242,14 +462,12 @@ fn process_phantom(
     if worktree_path.exists() {
         let content = std::fs::read(&worktree_path)
             .with_context(|| format!("failed to read {}", file_path))?;
-        fs_util::atomic_write(&stash_path, &content)
+        fs_util::atomic_write_with_mode_of(&stash_path, &content, &worktree_path)
             .with_context(|| format!("failed to stash {}", file_path))?;
-        tx.stashed_phantoms.push(file_path.to_string());
+        PreCommitTransaction::record(&tx.stashed_phantoms, file_path);
     }
 
-    // b. Unstage from index
-    git.unstage_phantom(file_path)?;
-
+    // b. Unstaging happens afterwards, sequentially (see process_files)
     Ok(())
 }
 
@@ -332,8 +550,12 @@ mod tests {
         assert_eq!(wt, "# Team\n");
 
         // Stash should have shadow content
-        let stash =
-            std::fs::read_to_string(git.shadow_dir.join("stash").join("CLAUDE.md")).unwrap();
+        let stash = std::fs::read_to_string(
+            git.shadow_dir
+                .join("stash")
+                .join(crate::path::encode_path("CLAUDE.md")),
+        )
+        .unwrap();
         assert_eq!(stash, "# Team\n# My additions\n");
 
         // Cleanup for test
@@ -362,7 +584,12 @@ mod tests {
         handle(&git).unwrap();
 
         // Stash should have phantom content
-        let stash = std::fs::read_to_string(git.shadow_dir.join("stash").join("local.md")).unwrap();
+        let stash = std::fs::read_to_string(
+            git.shadow_dir
+                .join("stash")
+                .join(crate::path::encode_path("local.md")),
+        )
+        .unwrap();
         assert_eq!(stash, "# Local\n");
 
         lock::release_lock(&git.shadow_dir).unwrap();
@@ -427,6 +654,39 @@ mod tests {
         assert!(err_msg.contains("does not exist in the working tree"));
     }
 
+    #[test]
+    fn test_overlay_content_rechecked_at_commit() {
+        let (_dir, git) = make_test_repo();
+        let config = setup_overlay(&git);
+
+        // "# Team\n# My additions\n" is over a 16 byte limit
+        assert_eq!(
+            overlay_content_problems(&git, &config, 1024),
+            Vec::<String>::new()
+        );
+        let problems = overlay_content_problems(&git, &config, 16);
+        assert!(problems[0].contains("over the 16 byte limit"));
+
+        std::fs::write(git.root.join("CLAUDE.md"), b"# Team\0").unwrap();
+        let problems = overlay_content_problems(&git, &config, 1024);
+        assert!(problems[0].contains("has become binary"));
+
+        // Only a warning by default
+        handle(&git).unwrap();
+        restore::restore_stash(&git, None).unwrap();
+        lock::release_lock(&git.shadow_dir).unwrap();
+
+        let settings = Settings {
+            strict_overlay_checks: true,
+            ..Default::default()
+        };
+        settings.save(&git.shadow_dir).unwrap();
+        let err = handle(&git).unwrap_err();
+        assert!(err.to_string().contains("strict_overlay_checks"));
+        let status = lock::check_lock(&git.shadow_dir).unwrap();
+        assert!(matches!(status, LockStatus::Free));
+    }
+
     #[test]
     fn test_missing_baseline_blocks_commit() {
         let (_dir, git) = make_test_repo();
@@ -442,6 +702,181 @@ mod tests {
         assert!(err_msg.contains("baseline missing"));
     }
 
+    #[test]
+    fn test_conflict_markers_block_commit() {
+        let (_dir, git) = make_test_repo();
+        let _config = setup_overlay(&git);
+        let conflicted = "<<<<<<< ours\n# Mine\n=======\n# Theirs\n>>>>>>> theirs\n";
+        std::fs::write(git.root.join("CLAUDE.md"), conflicted).unwrap();
+
+        let err = handle(&git).unwrap_err();
+        assert!(err.to_string().contains("unresolved conflict markers"));
+        // Nothing was swapped
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap(),
+            conflicted
+        );
+        let status = lock::check_lock(&git.shadow_dir).unwrap();
+        assert!(matches!(status, LockStatus::Free));
+    }
+
+    /// Commit `count` files and register each as an overlay with shadow changes
+    fn setup_many_overlays(git: &GitRepo, count: usize) -> ShadowConfig {
+        for i in 0..count {
+            std::fs::write(git.root.join(format!("f{}.md", i)), format!("base {}\n", i)).unwrap();
+        }
+        std::process::Command::new("git")
+            .args(["add", "."])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        std::process::Command::new("git")
+            .args(["commit", "-m", "many"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+
+        let commit = git.head_commit().unwrap();
+        let mut config = ShadowConfig::new();
+        for i in 0..count {
+            let file_path = format!("f{}.md", i);
+            fs_util::atomic_write(
+                &git.shadow_dir
+                    .join("baselines")
+                    .join(path::encode_path(&file_path)),
+                format!("base {}\n", i).as_bytes(),
+            )
+            .unwrap();
+            std::fs::write(git.root.join(&file_path), format!("shadow {}\n", i)).unwrap();
+            config.add_overlay(file_path, commit.clone()).unwrap();
+        }
+        config.save(&git.shadow_dir).unwrap();
+        config
+    }
+
+    #[test]
+    fn test_many_overlays_are_all_swapped_and_staged() {
+        let (_dir, git) = make_test_repo();
+        setup_many_overlays(&git, 20);
+
+        handle(&git).unwrap();
+
+        for i in 0..20 {
+            let file_path = format!("f{}.md", i);
+            let wt = std::fs::read_to_string(git.root.join(&file_path)).unwrap();
+            assert_eq!(wt, format!("base {}\n", i));
+            let stash = std::fs::read_to_string(
+                git.shadow_dir
+                    .join("stash")
+                    .join(path::encode_path(&file_path)),
+            )
+            .unwrap();
+            assert_eq!(stash, format!("shadow {}\n", i));
+            assert_eq!(git.staging_status(&file_path).unwrap(), (false, false));
+        }
+
+        lock::release_lock(&git.shadow_dir).unwrap();
+    }
+
+    #[test]
+    fn test_parallel_failure_rolls_back_every_file() {
+        let (_dir, git) = make_test_repo();
+        setup_many_overlays(&git, 20);
+
+        // An unreadable baseline (a directory) passes the existence check but
+        // fails while the other files are being processed
+        let broken = git
+            .shadow_dir
+            .join("baselines")
+            .join(crate::path::encode_path("f7.md"));
+        std::fs::remove_file(&broken).unwrap();
+        std::fs::create_dir(&broken).unwrap();
+
+        assert!(handle(&git).is_err());
+
+        for i in 0..20 {
+            let wt = std::fs::read_to_string(git.root.join(format!("f{}.md", i))).unwrap();
+            assert_eq!(
+                wt,
+                format!("shadow {}\n", i),
+                "f{}.md should be restored",
+                i
+            );
+        }
+        let stash_files = std::fs::read_dir(git.shadow_dir.join("stash"))
+            .unwrap()
+            .count();
+        assert_eq!(stash_files, 0);
+        let status = lock::check_lock(&git.shadow_dir).unwrap();
+        assert!(matches!(status, LockStatus::Free));
+    }
+
+    #[test]
+    fn test_index_only_swap_leaves_worktree_alone() {
+        let (_dir, git) = make_test_repo();
+        setup_many_overlays(&git, 3);
+        let settings = Settings {
+            index_only_swap: true,
+            ..Default::default()
+        };
+        settings.save(&git.shadow_dir).unwrap();
+        // The shadow change is staged, as `git commit -a` would do
+        std::process::Command::new("git")
+            .args(["add", "f1.md"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+
+        handle(&git).unwrap();
+
+        assert!(path::stored_files(&git.shadow_dir.join("stash"))
+            .unwrap()
+            .is_empty());
+        for i in 0..3 {
+            let file_path = format!("f{}.md", i);
+            let wt = std::fs::read_to_string(git.root.join(&file_path)).unwrap();
+            assert_eq!(wt, format!("shadow {}\n", i));
+            let staged = git.show_file("", &file_path).unwrap();
+            assert_eq!(staged, format!("base {}\n", i).as_bytes());
+        }
+
+        std::process::Command::new("git")
+            .args(["commit", "--allow-empty", "-m", "work", "--no-verify"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        crate::hooks::post_commit::handle(&git).unwrap();
+        assert_eq!(git.show_file("HEAD", "f1.md").unwrap(), b"base 1\n");
+        assert_eq!(
+            std::fs::read_to_string(git.root.join("f1.md")).unwrap(),
+            "shadow 1\n"
+        );
+        assert!(matches!(
+            lock::check_lock(&git.shadow_dir).unwrap(),
+            LockStatus::Free
+        ));
+    }
+
+    #[test]
+    fn test_index_only_swap_rollback_restores_index() {
+        let (_dir, git) = make_test_repo();
+        let config = setup_many_overlays(&git, 2);
+        std::process::Command::new("git")
+            .args(["add", "f0.md"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+        let before = git.index_entry("f0.md").unwrap();
+
+        let tx = PreCommitTransaction::new();
+        process_files(&git, &config, &tx, true).unwrap();
+        assert_ne!(git.index_entry("f0.md").unwrap(), before);
+
+        tx.rollback(&git);
+        assert_eq!(git.index_entry("f0.md").unwrap(), before);
+        assert_eq!(git.show_file("", "f0.md").unwrap(), b"shadow 0\n");
+    }
+
     #[test]
     fn test_phantom_directory_skips_stash() {
         let (_dir, git) = make_test_repo();
@@ -498,4 +933,85 @@ mod tests {
         let status = lock::check_lock(&git.shadow_dir).unwrap();
         assert!(matches!(status, LockStatus::Free));
     }
+
+    #[test]
+    fn test_stale_lock_blocks_commit_by_default() {
+        let (_dir, git) = make_test_repo();
+        let _config = setup_overlay(&git);
+        std::fs::write(
+            git.shadow_dir.join("lock"),
+            "pid=999999\ntimestamp=2026-01-01T00:00:00+00:00",
+        )
+        .unwrap();
+
+        let result = handle(&git);
+        assert!(format!("{}", result.unwrap_err()).contains("stale lock"));
+    }
+
+    #[test]
+    fn test_auto_restore_recovers_stale_lock_and_stash() {
+        let (_dir, git) = make_test_repo();
+        let _config = setup_overlay(&git);
+        std::process::Command::new("git")
+            .args(["config", "shadow.autoRestore", "true"])
+            .current_dir(&git.root)
+            .output()
+            .unwrap();
+
+        // Interrupted commit: stale lock + stashed shadow content + baseline in worktree
+        std::fs::write(
+            git.shadow_dir.join("lock"),
+            "pid=999999\ntimestamp=2026-01-01T00:00:00+00:00",
+        )
+        .unwrap();
+        let shadow = std::fs::read(git.root.join("CLAUDE.md")).unwrap();
+        std::fs::write(
+            git.shadow_dir
+                .join("stash")
+                .join(crate::path::encode_path("CLAUDE.md")),
+            &shadow,
+        )
+        .unwrap();
+        std::fs::write(git.root.join("CLAUDE.md"), "# Team\n").unwrap();
+
+        handle(&git).unwrap();
+
+        // Commit proceeds: shadow content stashed again, baseline in worktree
+        let stashed = std::fs::read(
+            git.shadow_dir
+                .join("stash")
+                .join(crate::path::encode_path("CLAUDE.md")),
+        )
+        .unwrap();
+        assert_eq!(stashed, shadow);
+        let status = lock::check_lock(&git.shadow_dir).unwrap();
+        assert!(matches!(status, LockStatus::HeldByUs));
+    }
+
+    #[test]
+    fn test_partial_commit_detection_and_scope() {
+        assert!(is_partial_commit(Some(OsStr::new(
+            "/repo/.git/next-index-1234.lock"
+        ))));
+        assert!(!is_partial_commit(Some(OsStr::new(
+            "/repo/.git/index.lock"
+        ))));
+        assert!(!is_partial_commit(Some(OsStr::new(".git/index"))));
+        assert!(!is_partial_commit(None));
+
+        let mut config = ShadowConfig::new();
+        config
+            .add_overlay("CLAUDE.md".to_string(), "abc".to_string())
+            .unwrap();
+        config
+            .add_overlay("NOTES.md".to_string(), "abc".to_string())
+            .unwrap();
+        config
+            .add_phantom("local.md".to_string(), ExcludeMode::None, false)
+            .unwrap();
+
+        let scoped = scope_to_commit(&config, &["NOTES.md".to_string()]);
+        let kept: Vec<&str> = scoped.files.keys().map(String::as_str).collect();
+        assert_eq!(kept, vec!["NOTES.md", "local.md"]);
+    }
 }
@@ -1,12 +1,18 @@
+pub mod baseline;
+pub mod bundle;
 pub mod cli;
 pub mod commands;
 pub mod config;
+pub mod defaults;
 pub mod diff_util;
 pub mod error;
 pub mod exclude;
 pub mod fs_util;
 pub mod git;
+pub mod history;
 pub mod hooks;
 pub mod lock;
 pub mod merge;
 pub mod path;
+pub mod settings;
+pub mod templates;
@@ -1,9 +1,16 @@
+use std::io::Write;
 use std::path::Path;
+use std::time::Duration;
 
 use anyhow::Context;
 use chrono::{DateTime, Utc};
 
 use crate::error::ShadowError;
+use crate::fs_util;
+
+/// Lock age after which a holder is considered suspicious when
+/// `lock_timeout` is unset
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
 
 #[derive(Debug)]
 pub struct LockInfo {
@@ -11,6 +18,31 @@ pub struct LockInfo {
     pub timestamp: DateTime<Utc>,
 }
 
+impl LockInfo {
+    /// Time since the lock was taken (zero if the timestamp is in the future)
+    pub fn age(&self) -> Duration {
+        (Utc::now() - self.timestamp).to_std().unwrap_or_default()
+    }
+
+    /// Held longer than `timeout`. A commit never takes that long, so a
+    /// "live" holder this old is most likely an unrelated process that
+    /// reused the PID.
+    pub fn is_expired(&self, timeout: Duration) -> bool {
+        self.age() > timeout
+    }
+}
+
+/// Short human-readable duration, e.g. `45s`, `12m`, `2h 5m`, `3d 4h`
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
 #[derive(Debug)]
 pub enum LockStatus {
     Free,
@@ -41,16 +73,53 @@ pub fn check_lock(shadow_dir: &Path) -> anyhow::Result<LockStatus> {
     }
 }
 
+/// How `acquire_lock_with` treats a lock left behind by a dead process
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StalePolicy {
+    /// Fail with `ShadowError::StaleLock`
+    Fail,
+    /// Take over the lock
+    Recover,
+}
+
 /// Acquire lock (write PID + timestamp). Fails if locked by another live process.
 pub fn acquire_lock(shadow_dir: &Path) -> Result<(), ShadowError> {
+    acquire_lock_with(shadow_dir, StalePolicy::Fail).map(|_| ())
+}
+
+/// Attempts at creating the lockfile before giving up. Each failed attempt
+/// means another process changed the lock in between.
+const ACQUIRE_ATTEMPTS: usize = 3;
+
+/// Acquire lock with the given stale lock policy. Returns the PID of the stale
+/// lock that was taken over, if any. A lock held by a live process always fails.
+///
+/// The lockfile is created in one step with its content, so of two processes
+/// racing for a free lock exactly one wins; the other sees a live holder.
+pub fn acquire_lock_with(
+    shadow_dir: &Path,
+    policy: StalePolicy,
+) -> Result<Option<u32>, ShadowError> {
     let lock_path = shadow_dir.join("lock");
+    let mut recovered = None;
 
-    if lock_path.exists() {
-        let content = std::fs::read_to_string(&lock_path)?;
+    for _ in 0..ACQUIRE_ATTEMPTS {
+        match create_lock(shadow_dir, &lock_path) {
+            Ok(()) => return Ok(recovered),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let content = match std::fs::read_to_string(&lock_path) {
+            Ok(content) => content,
+            // Released in between
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        // An unreadable lockfile is not from a finished write; replace it
         if let Ok(info) = parse_lock(&content) {
-            let my_pid = std::process::id();
-            if info.pid == my_pid {
-                return Ok(()); // Already held by us
+            if info.pid == std::process::id() {
+                return Ok(None); // Already held by us
             }
             if is_process_alive(info.pid) {
                 return Err(ShadowError::LockHeld {
@@ -59,19 +128,59 @@ pub fn acquire_lock(shadow_dir: &Path) -> Result<(), ShadowError> {
                 });
             }
             // Stale lock
-            return Err(ShadowError::StaleLock(info.pid));
+            if policy == StalePolicy::Fail {
+                return Err(ShadowError::StaleLock(info.pid));
+            }
+            recovered = Some(info.pid);
         }
+        remove_lock_if_unchanged(shadow_dir, &lock_path, &content)?;
     }
 
-    let content = format!(
+    Err(std::io::Error::other("the lockfile kept changing while acquiring it; try again").into())
+}
+
+/// Write our PID and timestamp to a temp file and move it to `lock_path`
+/// unless that exists. Fails with `AlreadyExists` when the lock is taken.
+fn create_lock(shadow_dir: &Path, lock_path: &Path) -> std::io::Result<()> {
+    let mut tmp = tempfile::Builder::new()
+        .prefix(fs_util::TEMP_PREFIX)
+        .tempfile_in(shadow_dir)?;
+    write!(
+        tmp,
         "pid={}\ntimestamp={}",
         std::process::id(),
         Utc::now().to_rfc3339()
-    );
-    std::fs::write(&lock_path, content)?;
+    )?;
+    tmp.persist_noclobber(lock_path).map_err(|e| e.error)?;
     Ok(())
 }
 
+/// Remove a stale lockfile whose content was `seen`. It is first moved aside,
+/// so two processes cannot both remove it; if another process replaced it
+/// with its own lock meanwhile, that lock is put back.
+fn remove_lock_if_unchanged(
+    shadow_dir: &Path,
+    lock_path: &Path,
+    seen: &str,
+) -> std::io::Result<()> {
+    let aside = shadow_dir.join(format!(
+        "{}lock-{}",
+        fs_util::TEMP_PREFIX,
+        std::process::id()
+    ));
+    match std::fs::rename(lock_path, &aside) {
+        Ok(()) => {}
+        // Another process took it over first
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    }
+    if std::fs::read_to_string(&aside)? == seen {
+        std::fs::remove_file(&aside)
+    } else {
+        std::fs::rename(&aside, lock_path)
+    }
+}
+
 /// Release lock (remove file)
 pub fn release_lock(shadow_dir: &Path) -> anyhow::Result<()> {
     let lock_path = shadow_dir.join("lock");
@@ -82,10 +191,26 @@ pub fn release_lock(shadow_dir: &Path) -> anyhow::Result<()> {
 }
 
 /// Check if a process with the given PID is alive
+#[cfg(unix)]
 fn is_process_alive(pid: u32) -> bool {
     unsafe { libc::kill(pid as i32, 0) == 0 }
 }
 
+/// Check if a process with the given PID is alive. If `tasklist` cannot be
+/// run, the process is assumed alive so a live lock is never taken over.
+#[cfg(not(unix))]
+fn is_process_alive(pid: u32) -> bool {
+    let output = std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"])
+        .output();
+    match output {
+        Ok(out) if out.status.success() => {
+            String::from_utf8_lossy(&out.stdout).contains(&format!("\"{}\"", pid))
+        }
+        _ => true,
+    }
+}
+
 /// Parse lock file content
 fn parse_lock(content: &str) -> anyhow::Result<LockInfo> {
     let mut pid: Option<u32> = None;
@@ -167,6 +292,35 @@ mod tests {
         assert!(content.contains("timestamp="));
     }
 
+    #[test]
+    fn test_lock_age_and_expiry() {
+        let info = LockInfo {
+            pid: 1,
+            timestamp: Utc::now() - chrono::Duration::minutes(30),
+        };
+        assert!(info.age() >= Duration::from_secs(30 * 60));
+        assert!(info.is_expired(DEFAULT_LOCK_TIMEOUT));
+        assert!(!info.is_expired(Duration::from_secs(60 * 60)));
+
+        // A timestamp from the future is never expired
+        let future = LockInfo {
+            pid: 1,
+            timestamp: Utc::now() + chrono::Duration::minutes(5),
+        };
+        assert_eq!(future.age(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::from_secs(45)), "45s");
+        assert_eq!(format_age(Duration::from_secs(12 * 60 + 5)), "12m");
+        assert_eq!(format_age(Duration::from_secs(2 * 3600 + 5 * 60)), "2h 5m");
+        assert_eq!(
+            format_age(Duration::from_secs(3 * 86400 + 4 * 3600)),
+            "3d 4h"
+        );
+    }
+
     #[test]
     fn test_parse_lock_content() {
         let content = "pid=12345\ntimestamp=2026-02-07T12:00:00+00:00";
@@ -191,4 +345,114 @@ mod tests {
         let result = acquire_lock(&shadow_dir);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_acquire_lock_with_recovers_stale_lock() {
+        let (_dir, shadow_dir) = make_shadow_dir();
+        let lock_path = shadow_dir.join("lock");
+        std::fs::write(
+            &lock_path,
+            "pid=999999\ntimestamp=2026-01-01T00:00:00+00:00",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            acquire_lock_with(&shadow_dir, StalePolicy::Fail),
+            Err(ShadowError::StaleLock(999999))
+        ));
+        let recovered = acquire_lock_with(&shadow_dir, StalePolicy::Recover).unwrap();
+        assert_eq!(recovered, Some(999999));
+        assert!(matches!(
+            check_lock(&shadow_dir).unwrap(),
+            LockStatus::HeldByUs
+        ));
+    }
+
+    #[test]
+    fn test_acquire_lock_replaces_unreadable_lock() {
+        let (_dir, shadow_dir) = make_shadow_dir();
+        std::fs::write(shadow_dir.join("lock"), "garbage").unwrap();
+
+        assert_eq!(
+            acquire_lock_with(&shadow_dir, StalePolicy::Fail).unwrap(),
+            None
+        );
+        assert!(matches!(
+            check_lock(&shadow_dir).unwrap(),
+            LockStatus::HeldByUs
+        ));
+        // Nothing is left next to the lock
+        let names: Vec<_> = std::fs::read_dir(&shadow_dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["lock"]);
+    }
+
+    /// Set for the child processes of `test_concurrent_acquire_has_one_winner`
+    const CHILD_DIR_ENV: &str = "GIT_SHADOW_LOCK_TEST_DIR";
+
+    /// Runs only as a child process: waits for the start signal, tries to
+    /// take the lock, reports the outcome and keeps its PID alive briefly
+    #[test]
+    #[ignore]
+    fn lock_race_child() {
+        let Some(dir) = std::env::var_os(CHILD_DIR_ENV) else {
+            return;
+        };
+        let shadow_dir = std::path::PathBuf::from(dir);
+        while !shadow_dir.join("go").exists() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        match acquire_lock(&shadow_dir) {
+            Ok(()) => println!("RESULT=acquired"),
+            Err(e) => println!("RESULT=failed: {}", e),
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_concurrent_acquire_has_one_winner() {
+        let (_dir, shadow_dir) = make_shadow_dir();
+        let exe = std::env::current_exe().unwrap();
+        let children: Vec<_> = (0..2)
+            .map(|_| {
+                std::process::Command::new(&exe)
+                    .args(["lock::tests::lock_race_child", "--exact", "--ignored"])
+                    .args(["--nocapture", "--test-threads=1"])
+                    .env(CHILD_DIR_ENV, &shadow_dir)
+                    .stdout(std::process::Stdio::piped())
+                    .spawn()
+                    .unwrap()
+            })
+            .collect();
+        std::fs::write(shadow_dir.join("go"), "").unwrap();
+
+        let results: Vec<String> = children
+            .into_iter()
+            .map(|child| {
+                let output = child.wait_with_output().unwrap();
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .find_map(|line| line.split_once("RESULT=").map(|(_, r)| r.to_string()))
+                    .unwrap()
+            })
+            .collect();
+
+        assert_eq!(results.iter().filter(|r| *r == "acquired").count(), 1);
+        assert!(results
+            .iter()
+            .any(|r| r.starts_with("failed: lock held by process")));
+    }
+
+    #[test]
+    fn test_acquire_lock_with_recover_still_fails_on_live_process() {
+        let (_dir, shadow_dir) = make_shadow_dir();
+        let lock_path = shadow_dir.join("lock");
+        let content = format!("pid=1\ntimestamp={}", Utc::now().to_rfc3339());
+        std::fs::write(&lock_path, content).unwrap();
+
+        let result = acquire_lock_with(&shadow_dir, StalePolicy::Recover);
+        assert!(matches!(result, Err(ShadowError::LockHeld { pid: 1, .. })));
+    }
 }
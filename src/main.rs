@@ -3,27 +3,168 @@ use clap::Parser;
 
 use git_shadow::cli::{Cli, Commands};
 use git_shadow::commands;
+use git_shadow::merge::MergeStrategy;
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Install => commands::install::run()?,
+        Commands::Init => commands::init::run()?,
+        Commands::Install {
+            no_hooks,
+            alias,
+            force,
+            apply_defaults,
+            shell,
+        } => commands::install::run(no_hooks, alias, force, apply_defaults, shell.as_deref())?,
+        Commands::Uninstall { purge } => commands::uninstall::run(purge)?,
         Commands::Add {
-            file,
+            files,
             phantom,
+            overlay,
             no_exclude,
+            exclude_target,
+            expand,
+            expand_exclude,
+            baseline,
+            baseline_from,
+            binary,
+            force,
+            yes,
+            dry_run,
+            note,
+            glob,
+            template,
+        } => commands::add::run(
+            &files,
+            commands::add::AddArgs {
+                phantom,
+                overlay,
+                no_exclude,
+                exclude_target,
+                expand: expand.then_some(expand_exclude),
+                baseline,
+                baseline_from,
+                binary,
+                force,
+                yes,
+                dry_run,
+                note,
+                glob,
+                template,
+            },
+        )?,
+        Commands::Remove {
+            files,
+            force,
+            keep_shadow,
+        } => commands::remove::run(&files, force, keep_shadow)?,
+        Commands::Status {
+            json,
+            porcelain,
+            git,
+            summary_only,
+            ignore_whitespace,
+            check,
+        } => {
+            let code = commands::status::run(
+                json,
+                porcelain,
+                git,
+                summary_only,
+                ignore_whitespace,
+                check,
+            )?;
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+        Commands::List { file_type, null } => commands::list::run(file_type, null)?,
+        Commands::Diff {
+            file,
+            stat,
+            tool,
+            word_diff,
+            since_snapshot,
+            exit_code,
+            quiet,
+            ignore_whitespace,
+            color,
+        } => {
+            let code = commands::diff::run(
+                file.as_deref(),
+                commands::diff::DiffArgs {
+                    stat,
+                    tool: tool.as_deref(),
+                    word_diff,
+                    since_snapshot,
+                    exit_code,
+                    quiet,
+                    ignore_whitespace,
+                },
+                color,
+            )?;
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+        Commands::Rebase {
+            file,
+            all,
+            set,
+            adopt,
+            yes,
+            interactive,
+            ours,
+            theirs,
+        } => commands::rebase::run(
+            file.as_deref(),
+            all,
+            set.as_deref(),
+            adopt,
+            yes,
+            interactive,
+            MergeStrategy::from_flags(ours, theirs),
+        )?,
+        Commands::Restore {
+            file,
+            list,
+            force_lock,
+            verify,
+        } => commands::restore::run(file.as_deref(), list, force_lock, verify)?,
+        Commands::Suspend { files } => commands::suspend::run(&files)?,
+        Commands::Resume {
+            files,
+            discard_orphans,
+            ours,
+            theirs,
+        } => commands::resume::run(
+            &files,
+            discard_orphans,
+            MergeStrategy::from_flags(ours, theirs),
+        )?,
+        Commands::Log { file } => commands::log::run(file.as_deref())?,
+        Commands::Doctor { fix, yes } => commands::doctor::run(fix, yes)?,
+        Commands::Snapshot { restore, list } => commands::snapshot::run(restore.as_deref(), list)?,
+        Commands::Export { output, format } => commands::export::run(&output, format)?,
+        Commands::Import { input } => commands::import::run(&input)?,
+        Commands::Note { file, text, clear } => commands::note::run(&file, text.as_deref(), clear)?,
+        Commands::Clean {
             force,
-        } => commands::add::run(&file, phantom, no_exclude, force)?,
-        Commands::Remove { file, force } => commands::remove::run(&file, force)?,
-        Commands::Status => commands::status::run()?,
-        Commands::Diff { file } => commands::diff::run(file.as_deref())?,
-        Commands::Rebase { file } => commands::rebase::run(file.as_deref())?,
-        Commands::Restore { file } => commands::restore::run(file.as_deref())?,
-        Commands::Suspend => commands::suspend::run()?,
-        Commands::Resume => commands::resume::run()?,
-        Commands::Doctor => commands::doctor::run()?,
-        Commands::Hook { hook_name } => commands::hook::run(&hook_name)?,
+            keep_shadow,
+            dry_run,
+        } => commands::clean::run(force, keep_shadow, dry_run)?,
+        Commands::Verify {
+            quiet,
+            json,
+            strict,
+        } => {
+            let code = commands::verify::run(quiet, json, strict)?;
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+        Commands::Hook { hook_name, args } => commands::hook::run(&hook_name, &args)?,
     }
 
     Ok(())
@@ -1,6 +1,12 @@
-use std::path::Path;
-
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::exclude::ExcludeManager;
+use crate::git::GitRepo;
+use crate::settings::Settings;
+
+/// Suffix of the backup written next to a file when a merge conflicts
+pub const ORIG_SUFFIX: &str = ".orig";
 
 /// Result of a 3-way merge
 pub struct MergeResult {
@@ -8,61 +14,289 @@ pub struct MergeResult {
     pub content: String,
     /// Whether there were conflicts
     pub has_conflicts: bool,
+    /// Conflicting regions settled by a `MergeStrategy` other than `Conflict`
+    pub resolved: usize,
+}
+
+/// How `three_way_merge` settles a region both sides changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Write conflict markers
+    #[default]
+    Conflict,
+    /// Take our side (the shadow changes)
+    Ours,
+    /// Take their side (HEAD)
+    Theirs,
+}
+
+impl MergeStrategy {
+    /// `--ours` / `--theirs` flags as a strategy
+    pub fn from_flags(ours: bool, theirs: bool) -> Self {
+        match (ours, theirs) {
+            (true, _) => Self::Ours,
+            (_, true) => Self::Theirs,
+            _ => Self::Conflict,
+        }
+    }
+
+    /// Which side a resolved region came from, for messages
+    pub fn side(self) -> &'static str {
+        match self {
+            Self::Conflict => "none",
+            Self::Ours => "ours (shadow changes)",
+            Self::Theirs => "theirs (HEAD)",
+        }
+    }
+}
+
+/// Conflict marker style, matching git's `merge.conflictStyle`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStyle {
+    /// `<<<<<<<` / `=======` / `>>>>>>>` only
+    Merge,
+    /// Also show the base content (`|||||||`)
+    #[default]
+    Diff3,
+    /// Like diff3, with common lines moved out of the conflict
+    Zdiff3,
+}
+
+impl ConflictStyle {
+    /// Parse a `merge.conflictStyle` value
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "merge" => Some(Self::Merge),
+            "diff3" => Some(Self::Diff3),
+            "zdiff3" => Some(Self::Zdiff3),
+            _ => None,
+        }
+    }
+
+    /// Style to use in this repository: `conflict_style` in settings.json,
+    /// then `git config merge.conflictStyle`, then diff3
+    pub fn resolve(git: &GitRepo) -> Result<Self> {
+        if let Some(style) = Settings::load(&git.shadow_dir)?.conflict_style {
+            return Ok(style);
+        }
+        Ok(git
+            .config_get("merge.conflictStyle")
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or_default())
+    }
 }
 
-/// Perform a 3-way merge using `git merge-file`
+/// Perform a line-based 3-way merge (diff3)
 ///
 /// - base: the common ancestor (old baseline)
 /// - ours: the version with our changes (current working tree content)
 /// - theirs: the version from the other side (new HEAD content = new baseline)
 ///
-/// Returns merged content with conflict markers if applicable
+/// Returns merged content with conflict markers if applicable. Lines are
+/// matched with `similar`; a region changed on both sides in different ways
+/// is a conflict, including edits on adjacent lines, as in `git merge-file`.
+///
+/// Every input is merged newline-terminated and a missing final newline is
+/// restored afterwards (see `restore_final_newline()`).
+///
+/// `strategy` other than `Conflict` takes that side of every conflicting
+/// region instead of writing markers; `resolved` counts them.
 pub fn three_way_merge(
     base: &str,
     ours: &str,
     theirs: &str,
-    work_dir: &Path,
-) -> Result<MergeResult> {
-    let base_file = tempfile::Builder::new()
-        .prefix("shadow-base-")
-        .tempfile_in(work_dir)
-        .context("failed to create temp file")?;
-    let ours_file = tempfile::Builder::new()
-        .prefix("shadow-ours-")
-        .tempfile_in(work_dir)
-        .context("failed to create temp file")?;
-    let theirs_file = tempfile::Builder::new()
-        .prefix("shadow-theirs-")
-        .tempfile_in(work_dir)
-        .context("failed to create temp file")?;
-
-    std::fs::write(base_file.path(), base)?;
-    std::fs::write(ours_file.path(), ours)?;
-    std::fs::write(theirs_file.path(), theirs)?;
-
-    // git merge-file modifies ours_file in place and returns:
-    // 0: clean merge
-    // >0: number of conflicts
-    // <0: error
-    let output = std::process::Command::new("git")
-        .args([
-            "merge-file",
-            "-p",      // print to stdout instead of modifying file
-            "--diff3", // show base content in conflict markers
-        ])
-        .arg(ours_file.path())
-        .arg(base_file.path())
-        .arg(theirs_file.path())
-        .output()
-        .context("failed to run git merge-file")?;
-
-    let content = String::from_utf8_lossy(&output.stdout).to_string();
-    let has_conflicts = output.status.code().unwrap_or(-1) > 0;
-
-    Ok(MergeResult {
+    style: ConflictStyle,
+    strategy: MergeStrategy,
+) -> MergeResult {
+    let base_text = with_final_newline(base);
+    let ours_text = with_final_newline(ours);
+    let theirs_text = with_final_newline(theirs);
+    let base_lines: Vec<&str> = base_text.split_inclusive('\n').collect();
+    let ours_lines: Vec<&str> = ours_text.split_inclusive('\n').collect();
+    let theirs_lines: Vec<&str> = theirs_text.split_inclusive('\n').collect();
+
+    let ours_map = matching_lines(&base_lines, &ours_lines);
+    let theirs_map = matching_lines(&base_lines, &theirs_lines);
+
+    let mut content = String::new();
+    let mut conflicts = 0;
+    let mut resolved = 0;
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    while i < base_lines.len() || j < ours_lines.len() || k < theirs_lines.len() {
+        // Next base line both sides kept, or the end of all three
+        let (next_i, next_j, next_k) = (i..base_lines.len())
+            .find_map(|n| Some((n, ours_map[n]?, theirs_map[n]?)))
+            .unwrap_or((base_lines.len(), ours_lines.len(), theirs_lines.len()));
+
+        if (next_i, next_j, next_k) == (i, j, k) {
+            content.push_str(base_lines[i]);
+            (i, j, k) = (i + 1, j + 1, k + 1);
+            continue;
+        }
+
+        let chunk_base = &base_lines[i..next_i];
+        let chunk_ours = &ours_lines[j..next_j];
+        let chunk_theirs = &theirs_lines[k..next_k];
+        if chunk_ours == chunk_base || chunk_ours == chunk_theirs {
+            content.extend(chunk_theirs.iter().copied());
+        } else if chunk_theirs == chunk_base {
+            content.extend(chunk_ours.iter().copied());
+        } else {
+            match strategy {
+                MergeStrategy::Conflict => {
+                    write_conflict(&mut content, chunk_base, chunk_ours, chunk_theirs, style);
+                    conflicts += 1;
+                }
+                MergeStrategy::Ours => {
+                    content.extend(chunk_ours.iter().copied());
+                    resolved += 1;
+                }
+                MergeStrategy::Theirs => {
+                    content.extend(chunk_theirs.iter().copied());
+                    resolved += 1;
+                }
+            }
+        }
+        (i, j, k) = (next_i, next_j, next_k);
+    }
+
+    restore_final_newline(&mut content, base, ours, theirs);
+    MergeResult {
         content,
-        has_conflicts,
-    })
+        has_conflicts: conflicts > 0,
+        resolved,
+    }
+}
+
+/// For each line of `base`, the index of the line in `other` it is matched
+/// with, or None if it was changed or deleted
+fn matching_lines(base: &[&str], other: &[&str]) -> Vec<Option<usize>> {
+    let mut map = vec![None; base.len()];
+    for op in similar::capture_diff_slices(similar::Algorithm::Myers, base, other) {
+        if let similar::DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for offset in 0..len {
+                map[old_index + offset] = Some(new_index + offset);
+            }
+        }
+    }
+    map
+}
+
+/// Write one conflict. `merge` and `zdiff3` move lines both sides share at
+/// the start and end of the region out of the markers; `diff3` keeps the
+/// region whole so it lines up with the base.
+fn write_conflict(
+    content: &mut String,
+    base: &[&str],
+    ours: &[&str],
+    theirs: &[&str],
+    style: ConflictStyle,
+) {
+    let (prefix, suffix) = if style == ConflictStyle::Diff3 {
+        (0, 0)
+    } else {
+        let prefix = ours.iter().zip(theirs).take_while(|(a, b)| a == b).count();
+        let suffix = ours[prefix..]
+            .iter()
+            .rev()
+            .zip(theirs[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        (prefix, suffix)
+    };
+
+    content.extend(ours[..prefix].iter().copied());
+    content.push_str("<<<<<<< ours\n");
+    content.extend(ours[prefix..ours.len() - suffix].iter().copied());
+    if style != ConflictStyle::Merge {
+        content.push_str("||||||| base\n");
+        content.extend(base.iter().copied());
+    }
+    content.push_str("=======\n");
+    content.extend(theirs[prefix..theirs.len() - suffix].iter().copied());
+    content.push_str(">>>>>>> theirs\n");
+    content.extend(ours[ours.len() - suffix..].iter().copied());
+}
+
+/// Whether `content` ends with a line that has no newline
+fn lacks_final_newline(content: &str) -> bool {
+    !content.is_empty() && !content.ends_with('\n')
+}
+
+fn with_final_newline(content: &str) -> std::borrow::Cow<'_, str> {
+    if lacks_final_newline(content) {
+        format!("{}\n", content).into()
+    } else {
+        content.into()
+    }
+}
+
+/// Merge the "no newline at end of file" state like a line of its own: ours
+/// wins if it changed it, otherwise theirs. A conflict marker as the last
+/// line always keeps its newline.
+fn restore_final_newline(merged: &mut String, base: &str, ours: &str, theirs: &str) {
+    let base_lacks = lacks_final_newline(base);
+    let ours_lacks = lacks_final_newline(ours);
+    let lacks = if ours_lacks != base_lacks {
+        ours_lacks
+    } else {
+        lacks_final_newline(theirs)
+    };
+    if !lacks || !merged.ends_with('\n') {
+        return;
+    }
+    let last_line = merged[..merged.len() - 1]
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default();
+    if !last_line.starts_with(">>>>>>>") {
+        merged.pop();
+    }
+}
+
+/// Whether `content` still has a conflict left by a merge: a `<<<<<<<` line,
+/// then a `=======` line, then a `>>>>>>>` line. A lone `=======` (a Markdown
+/// heading underline) does not count.
+pub fn has_conflict_markers(content: &str) -> bool {
+    let is_marker = |line: &str, marker: &str| {
+        line.strip_prefix(marker)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+    };
+    let mut expected = ["<<<<<<<", "=======", ">>>>>>>"].into_iter().peekable();
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        let Some(&marker) = expected.peek() else {
+            break;
+        };
+        if is_marker(line, marker) {
+            expected.next();
+        }
+    }
+    expected.peek().is_none()
+}
+
+/// Path of the conflict backup for `file_path` (e.g. `CLAUDE.md.orig`)
+pub fn orig_path(file_path: &str) -> String {
+    format!("{}{}", file_path, ORIG_SUFFIX)
+}
+
+/// Save the pre-merge shadow content as `<file>.orig` so it can be recovered
+/// after a conflicting merge, and keep the backup out of `git status` via
+/// `.git/info/exclude`. Returns the backup path relative to the repo root.
+pub fn write_orig(git: &GitRepo, file_path: &str, ours: &[u8]) -> Result<String> {
+    let backup = orig_path(file_path);
+    std::fs::write(git.root.join(&backup), ours)
+        .with_context(|| format!("failed to write {}", backup))?;
+    ExcludeManager::new(&git.common_dir)
+        .add_entry(&backup)
+        .with_context(|| format!("failed to exclude {}", backup))?;
+    Ok(backup)
 }
 
 #[cfg(test)]
@@ -71,12 +305,17 @@ mod tests {
 
     #[test]
     fn test_clean_merge() {
-        let dir = tempfile::tempdir().unwrap();
         let base = "line1\nline2\nline3\n";
         let ours = "line1\nline2 modified\nline3\n";
         let theirs = "line1\nline2\nline3\nline4\n";
 
-        let result = three_way_merge(base, ours, theirs, dir.path()).unwrap();
+        let result = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
         assert!(!result.has_conflicts);
         assert!(result.content.contains("line2 modified"));
         assert!(result.content.contains("line4"));
@@ -84,48 +323,318 @@ mod tests {
 
     #[test]
     fn test_conflict_merge() {
-        let dir = tempfile::tempdir().unwrap();
         let base = "line1\n";
         let ours = "ours change\n";
         let theirs = "theirs change\n";
 
-        let result = three_way_merge(base, ours, theirs, dir.path()).unwrap();
+        let result = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
         assert!(result.has_conflicts);
         assert!(result.content.contains("<<<<<<<"));
         assert!(result.content.contains(">>>>>>>"));
     }
 
+    #[test]
+    fn test_strategy_takes_one_side_of_conflicts() {
+        let base = "a\nb\nc\n";
+        let ours = "a ours\nb\nc\n";
+        let theirs = "a theirs\nb\nc theirs\n";
+
+        let result = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Diff3,
+            MergeStrategy::Ours,
+        );
+        assert!(!result.has_conflicts);
+        assert_eq!(result.resolved, 1);
+        assert_eq!(result.content, "a ours\nb\nc theirs\n");
+
+        let result = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Diff3,
+            MergeStrategy::Theirs,
+        );
+        assert_eq!(result.resolved, 1);
+        assert_eq!(result.content, "a theirs\nb\nc theirs\n");
+
+        let result = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
+        assert!(result.has_conflicts);
+        assert_eq!(result.resolved, 0);
+    }
+
     #[test]
     fn test_no_changes() {
-        let dir = tempfile::tempdir().unwrap();
         let content = "unchanged\n";
 
-        let result = three_way_merge(content, content, content, dir.path()).unwrap();
+        let result = three_way_merge(
+            content,
+            content,
+            content,
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
         assert!(!result.has_conflicts);
         assert_eq!(result.content, "unchanged\n");
     }
 
     #[test]
     fn test_only_ours_changed() {
-        let dir = tempfile::tempdir().unwrap();
         let base = "original\n";
         let ours = "original\nour addition\n";
         let theirs = "original\n";
 
-        let result = three_way_merge(base, ours, theirs, dir.path()).unwrap();
+        let result = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
         assert!(!result.has_conflicts);
         assert!(result.content.contains("our addition"));
     }
 
     #[test]
-    fn test_only_theirs_changed() {
+    fn test_conflict_styles() {
+        let base = "line1\n";
+        let ours = "ours change\n";
+        let theirs = "theirs change\n";
+
+        let merge = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Merge,
+            MergeStrategy::Conflict,
+        );
+        assert!(merge.has_conflicts);
+        assert!(!merge.content.contains("|||||||"));
+
+        let diff3 = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
+        assert!(diff3.content.contains("|||||||"));
+
+        let zdiff3 = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Zdiff3,
+            MergeStrategy::Conflict,
+        );
+        assert!(zdiff3.has_conflicts);
+        assert!(zdiff3.content.contains("|||||||"));
+    }
+
+    #[test]
+    fn test_zdiff3_moves_common_lines_out() {
+        let base = "a\nb\nc\n";
+        let ours = "a\nsame\nours\nend\nc\n";
+        let theirs = "a\nsame\ntheirs\nend\nc\n";
+
+        let result = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Zdiff3,
+            MergeStrategy::Conflict,
+        );
+        assert_eq!(
+            result.content,
+            "a\nsame\n<<<<<<< ours\nours\n||||||| base\nb\n=======\ntheirs\n>>>>>>> theirs\nend\nc\n"
+        );
+
+        let result = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
+        assert!(result
+            .content
+            .starts_with("a\n<<<<<<< ours\nsame\nours\nend\n||||||| base\nb\n"));
+    }
+
+    #[test]
+    fn test_matches_git_merge_file() {
+        let cases = [
+            ("a\nb\nc\nd\ne\n", "a\nB\nc\nd\ne\n", "a\nb\nc\nD\ne\n"),
+            ("a\nb\nc\n", "a\nB\nc\n", "a\nb\nC\n"),
+            ("a\nb\nc\n", "x\na\nb\nc\n", "a\nb\nc\ny\n"),
+            ("a\nb\nc\n", "a\nours\nc\n", "a\ntheirs\nc\n"),
+            ("a\nb\nc\n", "a\nc\n", "a\nb\nc\nd\n"),
+            ("a\nb\nc\n", "a\nsame\nc\n", "a\nsame\nc\n"),
+            ("", "ours\n", "theirs\n"),
+        ];
         let dir = tempfile::tempdir().unwrap();
+        for (base, ours, theirs) in cases {
+            let files = [("ours", ours), ("base", base), ("theirs", theirs)];
+            for (name, content) in files {
+                std::fs::write(dir.path().join(name), content).unwrap();
+            }
+            let output = std::process::Command::new("git")
+                .args(["merge-file", "-p", "--diff3", "ours", "base", "theirs"])
+                .current_dir(dir.path())
+                .output()
+                .unwrap();
+
+            let result = three_way_merge(
+                base,
+                ours,
+                theirs,
+                ConflictStyle::Diff3,
+                MergeStrategy::Conflict,
+            );
+            assert_eq!(result.has_conflicts, output.status.code() != Some(0));
+            assert_eq!(
+                result.content,
+                String::from_utf8_lossy(&output.stdout),
+                "base {:?}, ours {:?}, theirs {:?}",
+                base,
+                ours,
+                theirs
+            );
+        }
+    }
+
+    #[test]
+    fn test_conflict_style_parse() {
+        assert_eq!(ConflictStyle::parse("zdiff3"), Some(ConflictStyle::Zdiff3));
+        assert_eq!(ConflictStyle::parse("Diff3"), Some(ConflictStyle::Diff3));
+        assert_eq!(ConflictStyle::parse("merge"), Some(ConflictStyle::Merge));
+        assert_eq!(ConflictStyle::parse("fancy"), None);
+    }
+
+    #[test]
+    fn test_only_theirs_changed() {
         let base = "original\n";
         let ours = "original\n";
         let theirs = "original\ntheir addition\n";
 
-        let result = three_way_merge(base, ours, theirs, dir.path()).unwrap();
+        let result = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
         assert!(!result.has_conflicts);
         assert!(result.content.contains("their addition"));
     }
+
+    #[test]
+    fn test_merge_without_final_newline_round_trip() {
+        let base = "line1\nline2\nline3";
+        let ours = "line1 ours\nline2\nline3";
+        let theirs = "line1\nline2\nline3 theirs";
+
+        let result = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
+        assert!(!result.has_conflicts);
+        assert_eq!(result.content, "line1 ours\nline2\nline3 theirs");
+
+        // Nothing changed: the file comes back byte for byte
+        let result = three_way_merge(
+            base,
+            base,
+            base,
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
+        assert_eq!(result.content, base);
+    }
+
+    #[test]
+    fn test_merge_final_newline_change_is_merged() {
+        // Theirs adds the final newline, ours appends nothing at the end
+        let result = three_way_merge(
+            "a\nb",
+            "a ours\nb",
+            "a\nb\n",
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
+        assert_eq!(result.content, "a ours\nb\n");
+
+        // Ours appends a line without a newline after a terminated base
+        let result = three_way_merge(
+            "a\nb\n",
+            "a\nb\nc",
+            "a theirs\nb\n",
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
+        assert_eq!(result.content, "a theirs\nb\nc");
+    }
+
+    #[test]
+    fn test_has_conflict_markers() {
+        let merged = three_way_merge(
+            "a\n",
+            "b\n",
+            "c\n",
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
+        assert!(merged.has_conflicts);
+        assert!(has_conflict_markers(&merged.content));
+        assert!(!has_conflict_markers("Title\n=======\n\ntext\n"));
+        assert!(!has_conflict_markers(
+            "<<<<<<<< not a marker\n=======\n>>>>>>>\n"
+        ));
+        assert!(has_conflict_markers(
+            "<<<<<<<\r\nx\r\n=======\r\ny\r\n>>>>>>>\r\n"
+        ));
+    }
+
+    #[test]
+    fn test_conflict_markers_without_final_newline() {
+        let base = "keep\nlast";
+        let ours = "keep\nours last";
+        let theirs = "keep\ntheirs last";
+
+        let result = three_way_merge(
+            base,
+            ours,
+            theirs,
+            ConflictStyle::Diff3,
+            MergeStrategy::Conflict,
+        );
+        assert!(result.has_conflicts);
+        let lines: Vec<&str> = result.content.lines().collect();
+        assert_eq!(lines[0], "keep");
+        assert!(lines[1].starts_with("<<<<<<<"));
+        assert_eq!(lines[2], "ours last");
+        assert!(lines[3].starts_with("|||||||"));
+        assert_eq!(lines[4], "last");
+        assert_eq!(lines[5], "=======");
+        assert_eq!(lines[6], "theirs last");
+        assert!(lines[7].starts_with(">>>>>>>"));
+        assert!(result.content.ends_with('\n'));
+    }
 }
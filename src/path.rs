@@ -1,11 +1,18 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+
+use crate::fs_util;
 
 /// Normalize a user-provided path to repository-relative format:
-/// - Convert to repo-relative path (using / separator)
-/// - Strip leading ./
-pub fn normalize_path(input: &str, repo_root: &Path) -> Result<String> {
+/// - Convert to repo-relative path (using / separator); a relative path is
+///   taken from `cwd_prefix` (`GitRepo::prefix`, empty for the root)
+/// - Resolve `.` and `..` segments; escaping the repository is an error
+/// - Resolve symlinked parent directories; symlinks leading outside the
+///   repository are an error
+pub fn normalize_path(input: &str, cwd_prefix: &str, repo_root: &Path) -> Result<String> {
     // Convert backslashes to forward slashes
     let input = input.replace('\\', "/");
 
@@ -13,7 +20,12 @@ pub fn normalize_path(input: &str, repo_root: &Path) -> Result<String> {
     let relative = if input.starts_with('/') {
         let root_str = repo_root.to_string_lossy().replace('\\', "/");
         let root_str = root_str.trim_end_matches('/');
-        if let Some(stripped) = input.strip_prefix(root_str) {
+        // The root must end at a component boundary: `/repo-other` is not
+        // inside `/repo`
+        let stripped = input
+            .strip_prefix(root_str)
+            .filter(|rest| rest.is_empty() || rest.starts_with('/'));
+        if let Some(stripped) = stripped {
             stripped.trim_start_matches('/').to_string()
         } else {
             bail!(
@@ -23,35 +35,237 @@ pub fn normalize_path(input: &str, repo_root: &Path) -> Result<String> {
             );
         }
     } else {
-        input.to_string()
+        in_cwd(&input, cwd_prefix)
     };
 
-    // Strip leading ./ (possibly repeated)
-    let mut result = relative.as_str();
-    while let Some(stripped) = result.strip_prefix("./") {
-        result = stripped;
+    // Resolve `.`/`..` logically; empty segments cover `//` and a trailing /
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in relative.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                if segments.pop().is_none() {
+                    bail!("path '{}' is outside the repository", input);
+                }
+            }
+            _ => segments.push(segment),
+        }
     }
 
-    // Strip trailing / (directory indicator)
-    let result = result.trim_end_matches('/');
+    resolve_symlinks(&segments, repo_root, &input)
+}
 
-    Ok(result.to_string())
+/// `input` as seen from the repository root when it was given relative to
+/// `cwd_prefix`. Absolute paths are returned as they are; nothing else is
+/// resolved (see `normalize_path`).
+pub fn in_cwd(input: &str, cwd_prefix: &str) -> String {
+    if input.starts_with(['/', '\\']) {
+        input.to_string()
+    } else {
+        format!("{}{}", cwd_prefix, input)
+    }
 }
 
-/// URL-encode a normalized path for use as filename in baselines/ and stash/:
-/// 1. % -> %25 (escape the escape char first)
-/// 2. / -> %2F
+/// Replace symlinked parent directories inside the repository with their
+/// real location, and reject paths that reach outside the repository through
+/// a symlink. The last segment is kept as-is so a symlink can itself be
+/// managed, as long as it points into the repository.
+fn resolve_symlinks(segments: &[&str], repo_root: &Path, input: &str) -> Result<String> {
+    let Ok(root) = repo_root.canonicalize() else {
+        return Ok(segments.join("/"));
+    };
+
+    let mut resolved = String::new();
+    for (index, segment) in segments.iter().enumerate() {
+        let candidate = if resolved.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{}/{}", resolved, segment)
+        };
+        let full = repo_root.join(&candidate);
+        let is_symlink = std::fs::symlink_metadata(&full)
+            .map(|m| m.file_type().is_symlink())
+            .unwrap_or(false);
+        // Dangling symlinks have nothing to resolve
+        let target = match full.canonicalize() {
+            Ok(target) if is_symlink => target,
+            _ => {
+                resolved = candidate;
+                continue;
+            }
+        };
+
+        let Ok(inside) = target.strip_prefix(&root) else {
+            bail!(
+                "path '{}' points outside the repository through symlink '{}'",
+                input,
+                candidate
+            );
+        };
+        resolved = if index + 1 == segments.len() {
+            candidate
+        } else {
+            inside.to_string_lossy().replace('\\', "/")
+        };
+    }
+
+    Ok(resolved)
+}
+
+/// Reverse mapping from storage names to paths, kept next to the config
+const PATH_MAP_FILE: &str = "paths.json";
+
+/// Directories whose files are named by `encode_path`
+pub const STORAGE_DIRS: &[&str] = &["baselines", "stash", "suspended"];
+
+/// Hex digits of the path hash kept in a storage name
+const HASH_LEN: usize = 16;
+
+/// Maximum length in bytes of the readable part of a storage name
+const SUFFIX_MAX_LEN: usize = 64;
+
+/// Storage filename for a normalized path in baselines/, stash/ and
+/// suspended/: a short SHA-256 of the full path followed by its file name,
+/// e.g. `1f0c6a2b9e4d7c35-CLAUDE.md`. The hash keeps names unique on
+/// case-insensitive filesystems, and the suffix is truncated so deep
+/// paths stay under filename length limits.
 pub fn encode_path(normalized: &str) -> String {
+    let hash = path_hash(normalized);
+    let file_name = normalized.rsplit('/').next().unwrap_or(normalized);
+    let mut end = file_name.len().min(SUFFIX_MAX_LEN);
+    while !file_name.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}-{}", hash, &file_name[..end])
+}
+
+/// First 16 hex digits of the SHA-256 of a normalized path; the prefix of
+/// `encode_path` and the name of its baseline ref
+pub fn path_hash(normalized: &str) -> String {
+    let digest = Sha256::digest(normalized.as_bytes());
+    digest
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>()[..HASH_LEN]
+        .to_string()
+}
+
+/// Storage filename used before hashed names (`%`→`%25`, `/`→`%2F`)
+pub fn legacy_encode_path(normalized: &str) -> String {
     normalized.replace('%', "%25").replace('/', "%2F")
 }
 
-/// Decode a URL-encoded filename back to a normalized path:
-/// 1. %2F -> /
-/// 2. %25 -> %
-pub fn decode_path(encoded: &str) -> String {
+/// Reverse of `legacy_encode_path`
+pub fn legacy_decode_path(encoded: &str) -> String {
     encoded.replace("%2F", "/").replace("%25", "%")
 }
 
+/// Map a storage filename back to its path using `paths.json`. Names that
+/// are not in the map are treated as legacy URL-encoded names.
+pub fn decode_path(shadow_dir: &Path, encoded: &str) -> String {
+    load_path_map(shadow_dir)
+        .remove(encoded)
+        .unwrap_or_else(|| legacy_decode_path(encoded))
+}
+
+/// Files stored in a storage directory, sorted by name. A missing directory
+/// has none, and leftover temp files are not stored files.
+pub fn stored_files(dir: &Path) -> std::io::Result<Vec<std::fs::DirEntry>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .filter(|e| !fs_util::is_temp_file(&e.file_name()))
+        .collect();
+    files.sort_by_key(|e| e.file_name());
+    Ok(files)
+}
+
+/// Temp files an interrupted `atomic_write` left in the shadow directory or
+/// its storage directories, sorted
+pub fn orphan_temp_files(shadow_dir: &Path) -> Vec<PathBuf> {
+    let dirs = std::iter::once(shadow_dir.to_path_buf())
+        .chain(STORAGE_DIRS.iter().map(|dir| shadow_dir.join(dir)));
+    let mut found: Vec<PathBuf> = dirs
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .filter(|e| fs_util::is_temp_file(&e.file_name()))
+        .map(|e| e.path())
+        .collect();
+    found.sort();
+    found
+}
+
+/// Delete `orphan_temp_files`. Only call this while holding the lock or once
+/// no other process can be writing. Returns the removed files.
+pub fn remove_orphan_temp_files(shadow_dir: &Path) -> Result<Vec<PathBuf>> {
+    let found = orphan_temp_files(shadow_dir);
+    for file in &found {
+        std::fs::remove_file(file)
+            .with_context(|| format!("failed to remove {}", file.display()))?;
+    }
+    Ok(found)
+}
+
+fn load_path_map(shadow_dir: &Path) -> BTreeMap<String, String> {
+    std::fs::read_to_string(shadow_dir.join(PATH_MAP_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Record the storage names of `paths` in `paths.json`. Entries for other
+/// paths are kept while a stored file still uses them, so stash remnants
+/// of unregistered files can still be restored.
+pub fn update_path_map<'a>(
+    shadow_dir: &Path,
+    paths: impl IntoIterator<Item = &'a str>,
+) -> Result<()> {
+    let mut map: BTreeMap<String, String> = load_path_map(shadow_dir)
+        .into_iter()
+        .filter(|(encoded, _)| {
+            STORAGE_DIRS
+                .iter()
+                .any(|dir| shadow_dir.join(dir).join(encoded).exists())
+        })
+        .collect();
+    for normalized in paths {
+        map.insert(encode_path(normalized), normalized.to_string());
+    }
+
+    let content = serde_json::to_string_pretty(&map).context("failed to serialize paths.json")?;
+    fs_util::atomic_write(&shadow_dir.join(PATH_MAP_FILE), content.as_bytes())
+        .context("failed to write paths.json")?;
+    Ok(())
+}
+
+/// Rename files stored under legacy URL-encoded names to hashed names and
+/// record them in `paths.json`. Returns the paths that were migrated.
+pub fn migrate_legacy_storage(shadow_dir: &Path) -> Result<Vec<String>> {
+    let mut migrated = Vec::new();
+    for dir in STORAGE_DIRS {
+        let dir = shadow_dir.join(dir);
+        for entry in stored_files(&dir)? {
+            let legacy = entry.file_name().to_string_lossy().to_string();
+            let normalized = legacy_decode_path(&legacy);
+            let encoded = encode_path(&normalized);
+            if encoded != legacy {
+                std::fs::rename(entry.path(), dir.join(&encoded))
+                    .with_context(|| format!("failed to migrate {}", entry.path().display()))?;
+            }
+            migrated.push(normalized);
+        }
+    }
+    update_path_map(shadow_dir, migrated.iter().map(String::as_str))?;
+    Ok(migrated)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -60,78 +274,126 @@ mod tests {
     // --- encode_path tests ---
 
     #[test]
-    fn test_encode_simple_filename() {
-        assert_eq!(encode_path("CLAUDE.md"), "CLAUDE.md");
+    fn test_encode_keeps_file_name_readable() {
+        let encoded = encode_path("src/components/CLAUDE.md");
+        assert!(encoded.ends_with("-CLAUDE.md"));
+        assert_eq!(encoded.len(), HASH_LEN + 1 + "CLAUDE.md".len());
+        assert!(!encoded.contains('/'));
     }
 
     #[test]
-    fn test_encode_path_with_slashes() {
-        assert_eq!(
-            encode_path("src/components/CLAUDE.md"),
-            "src%2Fcomponents%2FCLAUDE.md"
-        );
+    fn test_encode_is_stable() {
+        assert_eq!(encode_path("CLAUDE.md"), encode_path("CLAUDE.md"));
     }
 
     #[test]
-    fn test_encode_path_with_percent() {
-        assert_eq!(encode_path("docs/100%done.md"), "docs%2F100%25done.md");
+    fn test_encode_distinguishes_case() {
+        // Would collide on case-insensitive filesystems if only case differed
+        let upper = encode_path("Foo/bar.md");
+        let lower = encode_path("foo/bar.md");
+        assert_ne!(upper.to_lowercase(), lower.to_lowercase());
     }
 
     #[test]
-    fn test_encode_path_with_percent_and_slash() {
-        assert_eq!(encode_path("a%b/c"), "a%25b%2Fc");
+    fn test_encode_bounds_long_paths() {
+        let deep = format!("{}/{}", "dir/".repeat(100), "x".repeat(300));
+        assert!(encode_path(&deep).len() <= HASH_LEN + 1 + SUFFIX_MAX_LEN);
+
+        // Truncation respects UTF-8 boundaries
+        let multibyte = "あ".repeat(40);
+        let encoded = encode_path(&multibyte);
+        assert!(encoded.len() <= HASH_LEN + 1 + SUFFIX_MAX_LEN);
+        assert!(encoded.ends_with('あ'));
     }
 
-    // --- decode_path tests ---
-
-    #[test]
-    fn test_decode_simple_filename() {
-        assert_eq!(decode_path("CLAUDE.md"), "CLAUDE.md");
-    }
+    // --- legacy encoding tests ---
 
     #[test]
-    fn test_decode_path_with_slashes() {
-        assert_eq!(
-            decode_path("src%2Fcomponents%2FCLAUDE.md"),
-            "src/components/CLAUDE.md"
-        );
+    fn test_legacy_encode_path_with_percent_and_slash() {
+        assert_eq!(legacy_encode_path("a%b/c"), "a%25b%2Fc");
     }
 
     #[test]
-    fn test_decode_path_with_percent() {
-        assert_eq!(decode_path("docs%2F100%25done.md"), "docs/100%done.md");
+    fn test_legacy_roundtrip() {
+        for path in [
+            "CLAUDE.md",
+            "src/components/CLAUDE.md",
+            "a%b/c%d/e",
+            "%%/%%",
+        ] {
+            assert_eq!(legacy_decode_path(&legacy_encode_path(path)), path);
+        }
     }
 
-    // --- roundtrip tests ---
+    // --- path map tests ---
 
     #[test]
-    fn test_roundtrip_simple() {
-        let path = "CLAUDE.md";
-        assert_eq!(decode_path(&encode_path(path)), path);
+    fn test_decode_path_uses_path_map() {
+        let dir = tempfile::tempdir().unwrap();
+        update_path_map(dir.path(), ["src/CLAUDE.md"]).unwrap();
+        assert_eq!(
+            decode_path(dir.path(), &encode_path("src/CLAUDE.md")),
+            "src/CLAUDE.md"
+        );
+        // Unknown names fall back to the legacy decoding
+        assert_eq!(decode_path(dir.path(), "docs%2Fa.md"), "docs/a.md");
     }
 
     #[test]
-    fn test_roundtrip_nested() {
-        let path = "src/components/CLAUDE.md";
-        assert_eq!(decode_path(&encode_path(path)), path);
+    fn test_update_path_map_keeps_names_with_stored_files() {
+        let dir = tempfile::tempdir().unwrap();
+        update_path_map(dir.path(), ["kept.md", "dropped.md"]).unwrap();
+        std::fs::create_dir_all(dir.path().join("stash")).unwrap();
+        std::fs::write(dir.path().join("stash").join(encode_path("kept.md")), "x").unwrap();
+
+        update_path_map(dir.path(), ["other.md"]).unwrap();
+        let map = load_path_map(dir.path());
+        assert_eq!(map.get(&encode_path("kept.md")).unwrap(), "kept.md");
+        assert!(!map.contains_key(&encode_path("dropped.md")));
+        assert!(map.contains_key(&encode_path("other.md")));
     }
 
     #[test]
-    fn test_roundtrip_with_percent() {
-        let path = "docs/100%done.md";
-        assert_eq!(decode_path(&encode_path(path)), path);
-    }
+    fn test_migrate_legacy_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("baselines")).unwrap();
+        std::fs::create_dir_all(dir.path().join("stash")).unwrap();
+        std::fs::write(dir.path().join("baselines").join("src%2FCLAUDE.md"), "base").unwrap();
+        std::fs::write(dir.path().join("stash").join("src%2FCLAUDE.md"), "stash").unwrap();
 
-    #[test]
-    fn test_roundtrip_complex() {
-        let path = "a%b/c%d/e";
-        assert_eq!(decode_path(&encode_path(path)), path);
+        let migrated = migrate_legacy_storage(dir.path()).unwrap();
+        assert_eq!(migrated.len(), 2);
+
+        let encoded = encode_path("src/CLAUDE.md");
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("baselines").join(&encoded)).unwrap(),
+            "base"
+        );
+        assert!(!dir.path().join("stash").join("src%2FCLAUDE.md").exists());
+        assert_eq!(decode_path(dir.path(), &encoded), "src/CLAUDE.md");
     }
 
     #[test]
-    fn test_roundtrip_double_percent() {
-        let path = "%%/%%";
-        assert_eq!(decode_path(&encode_path(path)), path);
+    fn test_temp_files_are_not_stored_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let baselines = dir.path().join("baselines");
+        std::fs::create_dir_all(&baselines).unwrap();
+        std::fs::write(baselines.join(encode_path("CLAUDE.md")), "base").unwrap();
+        std::fs::write(baselines.join(".tmpX1y2Z3"), "partial").unwrap();
+        std::fs::write(dir.path().join(".tmpA1b2C3"), "{").unwrap();
+
+        let stored = stored_files(&baselines).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].file_name(), encode_path("CLAUDE.md").as_str());
+        assert!(stored_files(&dir.path().join("stash")).unwrap().is_empty());
+
+        assert_eq!(
+            orphan_temp_files(dir.path()),
+            [dir.path().join(".tmpA1b2C3"), baselines.join(".tmpX1y2Z3")]
+        );
+        assert_eq!(remove_orphan_temp_files(dir.path()).unwrap().len(), 2);
+        assert!(orphan_temp_files(dir.path()).is_empty());
+        assert!(baselines.join(encode_path("CLAUDE.md")).exists());
     }
 
     // --- normalize_path tests ---
@@ -139,20 +401,23 @@ mod tests {
     #[test]
     fn test_normalize_strips_leading_dot_slash() {
         let repo = PathBuf::from("/repo");
-        assert_eq!(normalize_path("./CLAUDE.md", &repo).unwrap(), "CLAUDE.md");
+        assert_eq!(
+            normalize_path("./CLAUDE.md", "", &repo).unwrap(),
+            "CLAUDE.md"
+        );
     }
 
     #[test]
     fn test_normalize_already_relative() {
         let repo = PathBuf::from("/repo");
-        assert_eq!(normalize_path("CLAUDE.md", &repo).unwrap(), "CLAUDE.md");
+        assert_eq!(normalize_path("CLAUDE.md", "", &repo).unwrap(), "CLAUDE.md");
     }
 
     #[test]
     fn test_normalize_nested_path() {
         let repo = PathBuf::from("/repo");
         assert_eq!(
-            normalize_path("src/components/CLAUDE.md", &repo).unwrap(),
+            normalize_path("src/components/CLAUDE.md", "", &repo).unwrap(),
             "src/components/CLAUDE.md"
         );
     }
@@ -161,7 +426,7 @@ mod tests {
     fn test_normalize_backslash_to_forward_slash() {
         let repo = PathBuf::from("/repo");
         assert_eq!(
-            normalize_path("src\\components\\CLAUDE.md", &repo).unwrap(),
+            normalize_path("src\\components\\CLAUDE.md", "", &repo).unwrap(),
             "src/components/CLAUDE.md"
         );
     }
@@ -170,7 +435,7 @@ mod tests {
     fn test_normalize_absolute_path_within_repo() {
         let repo = PathBuf::from("/repo");
         assert_eq!(
-            normalize_path("/repo/src/CLAUDE.md", &repo).unwrap(),
+            normalize_path("/repo/src/CLAUDE.md", "", &repo).unwrap(),
             "src/CLAUDE.md"
         );
     }
@@ -178,14 +443,14 @@ mod tests {
     #[test]
     fn test_normalize_strips_trailing_slash() {
         let repo = PathBuf::from("/repo");
-        assert_eq!(normalize_path(".claude/", &repo).unwrap(), ".claude");
+        assert_eq!(normalize_path(".claude/", "", &repo).unwrap(), ".claude");
     }
 
     #[test]
     fn test_normalize_strips_trailing_slash_nested() {
         let repo = PathBuf::from("/repo");
         assert_eq!(
-            normalize_path("src/components/", &repo).unwrap(),
+            normalize_path("src/components/", "", &repo).unwrap(),
             "src/components"
         );
     }
@@ -193,12 +458,92 @@ mod tests {
     #[test]
     fn test_normalize_dir_with_leading_dot_slash() {
         let repo = PathBuf::from("/repo");
-        assert_eq!(normalize_path("./.claude/", &repo).unwrap(), ".claude");
+        assert_eq!(normalize_path("./.claude/", "", &repo).unwrap(), ".claude");
     }
 
     #[test]
     fn test_normalize_strips_multiple_leading_dot_slash() {
         let repo = PathBuf::from("/repo");
-        assert_eq!(normalize_path("././CLAUDE.md", &repo).unwrap(), "CLAUDE.md");
+        assert_eq!(
+            normalize_path("././CLAUDE.md", "", &repo).unwrap(),
+            "CLAUDE.md"
+        );
+    }
+
+    #[test]
+    fn test_normalize_resolves_parent_segments() {
+        let repo = PathBuf::from("/repo");
+        assert_eq!(
+            normalize_path("a/../CLAUDE.md", "", &repo).unwrap(),
+            "CLAUDE.md"
+        );
+        assert_eq!(
+            normalize_path("src/./lib/../CLAUDE.md", "", &repo).unwrap(),
+            "src/CLAUDE.md"
+        );
+        assert_eq!(
+            normalize_path("/repo/src/../CLAUDE.md", "", &repo).unwrap(),
+            "CLAUDE.md"
+        );
+    }
+
+    #[test]
+    fn test_normalize_relative_to_cwd_prefix() {
+        let repo = PathBuf::from("/repo");
+        assert_eq!(
+            normalize_path("CLAUDE.md", "src/", &repo).unwrap(),
+            "src/CLAUDE.md"
+        );
+        assert_eq!(
+            normalize_path("../CLAUDE.md", "src/", &repo).unwrap(),
+            "CLAUDE.md"
+        );
+        assert_eq!(
+            normalize_path("/repo/CLAUDE.md", "src/", &repo).unwrap(),
+            "CLAUDE.md"
+        );
+        assert!(normalize_path("../../CLAUDE.md", "src/", &repo).is_err());
+    }
+
+    #[test]
+    fn test_normalize_rejects_escaping_repo() {
+        let repo = PathBuf::from("/repo");
+        assert!(normalize_path("../outside", "", &repo).is_err());
+        assert!(normalize_path("a/../../outside", "", &repo).is_err());
+        assert!(normalize_path("/repo/../etc/passwd", "", &repo).is_err());
+    }
+
+    #[test]
+    fn test_normalize_rejects_sibling_sharing_root_prefix() {
+        let repo = PathBuf::from("/repo");
+        assert!(normalize_path("/repo-other/x", "", &repo).is_err());
+        assert!(normalize_path("/repository", "", &repo).is_err());
+        assert_eq!(normalize_path("/repo/x", "", &repo).unwrap(), "x");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_normalize_symlinks() {
+        let repo = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(repo.path().join("real")).unwrap();
+        std::fs::write(repo.path().join("real/CLAUDE.md"), "x").unwrap();
+        std::fs::write(outside.path().join("secret.md"), "x").unwrap();
+        std::os::unix::fs::symlink(repo.path().join("real"), repo.path().join("alias")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), repo.path().join("out")).unwrap();
+        std::os::unix::fs::symlink(
+            outside.path().join("secret.md"),
+            repo.path().join("secret.md"),
+        )
+        .unwrap();
+
+        // Symlinked parent inside the repo resolves to the real directory
+        assert_eq!(
+            normalize_path("alias/CLAUDE.md", "", repo.path()).unwrap(),
+            "real/CLAUDE.md"
+        );
+        // Symlinks leading outside are rejected
+        assert!(normalize_path("out/secret.md", "", repo.path()).is_err());
+        assert!(normalize_path("secret.md", "", repo.path()).is_err());
     }
 }
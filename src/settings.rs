@@ -0,0 +1,278 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+use crate::fs_util;
+use crate::lock::DEFAULT_LOCK_TIMEOUT;
+use crate::merge::ConflictStyle;
+
+/// Environment variable overriding `max_file_size`
+pub const MAX_SIZE_ENV: &str = "GIT_SHADOW_MAX_SIZE";
+
+/// Environment variable overriding `lock_timeout`
+pub const LOCK_TIMEOUT_ENV: &str = "GIT_SHADOW_LOCK_TIMEOUT";
+
+/// Environment variable overriding `suppress_warnings`
+pub const QUIET_ENV: &str = "GIT_SHADOW_QUIET";
+
+/// Number of phantom snapshots kept when `snapshot_limit` is unset
+pub const DEFAULT_SNAPSHOT_LIMIT: usize = 10;
+
+/// Per-repository settings stored in `.git/shadow/settings.json`.
+/// Every field is optional; a missing file means all defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Settings {
+    /// Size limit for overlay files in bytes (default 1 MB)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_size: Option<u64>,
+    /// Conflict marker style for rebase/resume merges
+    /// (default: `git config merge.conflictStyle`, then diff3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflict_style: Option<ConflictStyle>,
+    /// Snapshots kept by `snapshot`; older ones are deleted (0 keeps all)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_limit: Option<usize>,
+    /// Seconds after which a held lock is reported as suspicious (default 600)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_timeout: Option<u64>,
+    /// Rebase drifted overlays from the post-merge hook instead of only
+    /// warning (default false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_rebase_on_merge: bool,
+    /// Keep new baselines as blobs in git's object store instead of files
+    /// in `baselines/` (experimental, default false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub object_baselines: bool,
+    /// Stage overlay baselines straight into the index in pre-commit
+    /// instead of writing them to the working tree (experimental, default
+    /// false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub index_only_swap: bool,
+    /// Hide the pre-commit soft check warnings (e.g. outdated baselines);
+    /// errors are still shown (default false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suppress_warnings: bool,
+    /// Fail the commit, instead of warning, when an overlay has grown past
+    /// the size limit or become binary since `add` (default false)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict_overlay_checks: bool,
+    /// Paths or globs registered by `init` and `install --apply-defaults`,
+    /// replacing the global `add` list in `defaults.json` (`[]` disables it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_add: Option<Vec<String>>,
+}
+
+impl Settings {
+    pub fn load(shadow_dir: &Path) -> anyhow::Result<Self> {
+        let settings_path = shadow_dir.join("settings.json");
+        if !settings_path.exists() {
+            return Ok(Self::default());
+        }
+        let content =
+            std::fs::read_to_string(&settings_path).context("failed to read settings.json")?;
+        serde_json::from_str(&content).context("failed to parse settings.json")
+    }
+
+    pub fn save(&self, shadow_dir: &Path) -> anyhow::Result<()> {
+        let settings_path = shadow_dir.join("settings.json");
+        let content =
+            serde_json::to_string_pretty(self).context("failed to serialize settings.json")?;
+        fs_util::atomic_write(&settings_path, content.as_bytes())
+            .context("failed to write settings.json")?;
+        Ok(())
+    }
+
+    /// Effective size limit: `GIT_SHADOW_MAX_SIZE`, then `max_file_size`,
+    /// then the built-in default
+    pub fn max_file_size(&self) -> anyhow::Result<u64> {
+        self.max_file_size_with_env(std::env::var(MAX_SIZE_ENV).ok().as_deref())
+    }
+
+    /// Effective snapshot limit (0 means unlimited)
+    pub fn snapshot_limit(&self) -> usize {
+        self.snapshot_limit.unwrap_or(DEFAULT_SNAPSHOT_LIMIT)
+    }
+
+    /// Effective lock timeout: `GIT_SHADOW_LOCK_TIMEOUT`, then
+    /// `lock_timeout`, then the built-in default
+    pub fn lock_timeout(&self) -> anyhow::Result<Duration> {
+        self.lock_timeout_with_env(std::env::var(LOCK_TIMEOUT_ENV).ok().as_deref())
+    }
+
+    fn lock_timeout_with_env(&self, env: Option<&str>) -> anyhow::Result<Duration> {
+        if let Some(value) = env.filter(|v| !v.trim().is_empty()) {
+            return parse_duration(value).with_context(|| format!("invalid {}", LOCK_TIMEOUT_ENV));
+        }
+        Ok(self
+            .lock_timeout
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_LOCK_TIMEOUT))
+    }
+
+    /// Whether pre-commit soft check warnings are hidden: `GIT_SHADOW_QUIET`
+    /// (`1`/`true` or `0`/`false`), then `suppress_warnings`
+    pub fn suppress_warnings(&self) -> bool {
+        self.suppress_warnings_with_env(std::env::var(QUIET_ENV).ok().as_deref())
+    }
+
+    fn suppress_warnings_with_env(&self, env: Option<&str>) -> bool {
+        match env.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            Some("1" | "true" | "yes" | "on") => true,
+            Some("0" | "false" | "no" | "off") => false,
+            _ => self.suppress_warnings,
+        }
+    }
+
+    fn max_file_size_with_env(&self, env: Option<&str>) -> anyhow::Result<u64> {
+        if let Some(value) = env.filter(|v| !v.trim().is_empty()) {
+            return parse_size(value).with_context(|| format!("invalid {}", MAX_SIZE_ENV));
+        }
+        Ok(self.max_file_size.unwrap_or(fs_util::DEFAULT_SIZE_LIMIT))
+    }
+}
+
+/// Parse a byte count with an optional K/M/G suffix (binary units),
+/// e.g. `1048576`, `512K`, `5MB`
+pub fn parse_size(input: &str) -> anyhow::Result<u64> {
+    let trimmed = input.trim();
+    let upper = trimmed.to_ascii_uppercase();
+    let number = upper.strip_suffix('B').unwrap_or(&upper);
+    let (digits, multiplier) = match number.chars().last() {
+        Some('K') => (&number[..number.len() - 1], 1024),
+        Some('M') => (&number[..number.len() - 1], 1024 * 1024),
+        Some('G') => (&number[..number.len() - 1], 1024 * 1024 * 1024),
+        _ => (number, 1),
+    };
+    let Ok(value) = digits.trim().parse::<u64>() else {
+        bail!(
+            "'{}' is not a size (expected e.g. 1048576, 512K, 5MB)",
+            trimmed
+        );
+    };
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| anyhow::anyhow!("'{}' is too large", trimmed))
+}
+
+/// Parse seconds with an optional s/m/h suffix, e.g. `600`, `90s`, `10m`, `2h`
+pub fn parse_duration(input: &str) -> anyhow::Result<Duration> {
+    let trimmed = input.trim();
+    let lower = trimmed.to_ascii_lowercase();
+    let (digits, multiplier) = match lower.chars().last() {
+        Some('s') => (&lower[..lower.len() - 1], 1),
+        Some('m') => (&lower[..lower.len() - 1], 60),
+        Some('h') => (&lower[..lower.len() - 1], 60 * 60),
+        _ => (lower.as_str(), 1),
+    };
+    let Ok(value) = digits.trim().parse::<u64>() else {
+        bail!(
+            "'{}' is not a duration (expected e.g. 600, 90s, 10m, 2h)",
+            trimmed
+        );
+    };
+    value
+        .checked_mul(multiplier)
+        .map(Duration::from_secs)
+        .ok_or_else(|| anyhow::anyhow!("'{}' is too large", trimmed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_returns_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = Settings::load(dir.path()).unwrap();
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = Settings {
+            max_file_size: Some(5_000_000),
+            conflict_style: Some(ConflictStyle::Zdiff3),
+            snapshot_limit: Some(3),
+            lock_timeout: Some(120),
+            auto_rebase_on_merge: true,
+            object_baselines: true,
+            index_only_swap: true,
+            suppress_warnings: true,
+            strict_overlay_checks: true,
+            default_add: Some(vec!["CLAUDE.md".to_string()]),
+        };
+        settings.save(dir.path()).unwrap();
+        assert_eq!(Settings::load(dir.path()).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_max_file_size_precedence() {
+        let settings = Settings {
+            max_file_size: Some(2048),
+            ..Default::default()
+        };
+        assert_eq!(settings.max_file_size_with_env(None).unwrap(), 2048);
+        assert_eq!(settings.max_file_size_with_env(Some("4K")).unwrap(), 4096);
+        assert_eq!(
+            Settings::default().max_file_size_with_env(None).unwrap(),
+            fs_util::DEFAULT_SIZE_LIMIT
+        );
+        assert!(settings.max_file_size_with_env(Some("lots")).is_err());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1048576").unwrap(), 1_048_576);
+        assert_eq!(parse_size("512K").unwrap(), 512 * 1024);
+        assert_eq!(parse_size("5MB").unwrap(), 5 * 1024 * 1024);
+        assert_eq!(parse_size("1g").unwrap(), 1024 * 1024 * 1024);
+        assert!(parse_size("").is_err());
+        assert!(parse_size("-1").is_err());
+        assert!(parse_size("99999999999G").is_err());
+    }
+
+    #[test]
+    fn test_lock_timeout_precedence() {
+        let settings = Settings {
+            lock_timeout: Some(120),
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.lock_timeout_with_env(None).unwrap(),
+            Duration::from_secs(120)
+        );
+        assert_eq!(
+            settings.lock_timeout_with_env(Some("10m")).unwrap(),
+            Duration::from_secs(600)
+        );
+        assert_eq!(
+            Settings::default().lock_timeout_with_env(None).unwrap(),
+            DEFAULT_LOCK_TIMEOUT
+        );
+        assert!(settings.lock_timeout_with_env(Some("soon")).is_err());
+    }
+
+    #[test]
+    fn test_suppress_warnings_precedence() {
+        let quiet = Settings {
+            suppress_warnings: true,
+            ..Default::default()
+        };
+        assert!(quiet.suppress_warnings_with_env(None));
+        assert!(!quiet.suppress_warnings_with_env(Some("0")));
+        assert!(Settings::default().suppress_warnings_with_env(Some("1")));
+        assert!(!Settings::default().suppress_warnings_with_env(Some("")));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("600").unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("2H").unwrap(), Duration::from_secs(7200));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("-5m").is_err());
+    }
+}
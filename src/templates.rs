@@ -0,0 +1,123 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+
+use crate::defaults;
+
+/// Templates that ship with git-shadow, as (name, content)
+const BUILTIN: &[(&str, &str)] = &[
+    (
+        "claude-md",
+        "# CLAUDE.md\n\
+         \n\
+         Personal instructions for this repository (managed by git-shadow, never committed).\n\
+         \n\
+         ## Context\n\
+         \n\
+         ## Conventions\n\
+         \n\
+         ## Commands\n",
+    ),
+    (
+        "env-local",
+        "# Local environment overrides (managed by git-shadow, never committed)\n\
+         # KEY=value\n",
+    ),
+];
+
+/// Initial content for a new phantom file (`add --template`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    pub name: String,
+    pub content: Vec<u8>,
+}
+
+/// User templates: `templates/` next to the global `defaults.json`
+pub fn user_dir() -> Option<PathBuf> {
+    defaults::config_dir().map(|dir| dir.join("templates"))
+}
+
+/// Load template `name`; a user template overrides a built-in one
+pub fn load(name: &str) -> anyhow::Result<Template> {
+    load_from(user_dir().as_deref(), name)
+}
+
+fn load_from(user_dir: Option<&Path>, name: &str) -> anyhow::Result<Template> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        bail!("invalid template name '{}'", name);
+    }
+
+    if let Some(path) = user_dir.map(|dir| dir.join(name)).filter(|p| p.is_file()) {
+        let content = std::fs::read(&path)
+            .with_context(|| format!("failed to read template {}", path.display()))?;
+        return Ok(Template {
+            name: name.to_string(),
+            content,
+        });
+    }
+    if let Some((_, content)) = BUILTIN.iter().find(|(builtin, _)| *builtin == name) {
+        return Ok(Template {
+            name: name.to_string(),
+            content: content.as_bytes().to_vec(),
+        });
+    }
+
+    bail!(
+        "unknown template '{}' (available: {})",
+        name,
+        available(user_dir).join(", ")
+    )
+}
+
+/// Names of the built-in and user templates, sorted
+fn available(user_dir: Option<&Path>) -> Vec<String> {
+    let mut names: Vec<String> = BUILTIN.iter().map(|(name, _)| name.to_string()).collect();
+    if let Some(entries) = user_dir.and_then(|dir| std::fs::read_dir(dir).ok()) {
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if entry.path().is_file() && !name.starts_with('.') {
+                names.push(name);
+            }
+        }
+    }
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_and_user_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let builtin = load_from(Some(dir.path()), "claude-md").unwrap();
+        assert!(builtin.content.starts_with(b"# CLAUDE.md\n"));
+
+        std::fs::write(dir.path().join("claude-md"), "# Mine\n").unwrap();
+        std::fs::write(dir.path().join("env"), "API_KEY=\n").unwrap();
+        assert_eq!(
+            load_from(Some(dir.path()), "claude-md").unwrap().content,
+            b"# Mine\n"
+        );
+        assert_eq!(
+            load_from(Some(dir.path()), "env").unwrap().content,
+            b"API_KEY=\n"
+        );
+    }
+
+    #[test]
+    fn test_unknown_and_invalid_names() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes"), "").unwrap();
+
+        let err = load_from(Some(dir.path()), "missing").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown template 'missing' (available: claude-md, env-local, notes)"
+        );
+        assert!(load_from(Some(dir.path()), "../notes").is_err());
+        assert!(load_from(None, ".hidden").is_err());
+    }
+}
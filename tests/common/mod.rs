@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
@@ -50,6 +52,25 @@ impl TestRepo {
         self.root.join(".git").join("shadow")
     }
 
+    /// Create a linked worktree on a new branch outside the main working tree.
+    /// The returned TempDir must be kept alive for the worktree to exist.
+    pub fn add_worktree(&self, branch: &str) -> (TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(branch);
+        run_git(
+            &self.root,
+            &[
+                "worktree",
+                "add",
+                "-q",
+                "-b",
+                branch,
+                path.to_str().unwrap(),
+            ],
+        );
+        (dir, path)
+    }
+
     pub fn init_shadow(&self) {
         let shadow_dir = self.shadow_dir();
         std::fs::create_dir_all(shadow_dir.join("baselines")).unwrap();
@@ -3,10 +3,12 @@
 
 mod common;
 
+use git_shadow::baseline;
 use git_shadow::config::ShadowConfig;
 use git_shadow::git::GitRepo;
 use git_shadow::hooks;
 use git_shadow::path;
+use git_shadow::settings::Settings;
 use git_shadow::{fs_util, lock};
 
 #[test]
@@ -53,8 +55,12 @@ fn test_full_overlay_commit_cycle() {
     );
 
     // Verify: stash has shadow content
-    let stash_content =
-        std::fs::read_to_string(git.shadow_dir.join("stash").join("CLAUDE.md")).unwrap();
+    let stash_content = std::fs::read_to_string(
+        git.shadow_dir
+            .join("stash")
+            .join(path::encode_path("CLAUDE.md")),
+    )
+    .unwrap();
     assert_eq!(
         stash_content, "# Team\n# My personal notes\n",
         "Stash should have shadow content"
@@ -79,7 +85,10 @@ fn test_full_overlay_commit_cycle() {
 
     // Verify: stash is clean
     assert!(
-        !git.shadow_dir.join("stash").join("CLAUDE.md").exists(),
+        !git.shadow_dir
+            .join("stash")
+            .join(path::encode_path("CLAUDE.md"))
+            .exists(),
         "Stash should be clean after post-commit"
     );
 
@@ -101,6 +110,60 @@ fn test_full_overlay_commit_cycle() {
     );
 }
 
+#[test]
+fn test_overlay_commit_cycle_with_object_baselines() {
+    let repo = common::TestRepo::new();
+    repo.create_file("CLAUDE.md", "# Team\n");
+    repo.commit("initial commit");
+
+    let git = GitRepo::discover(&repo.root).unwrap();
+    repo.init_shadow();
+    install_hooks_for_test(&git);
+    Settings {
+        object_baselines: true,
+        ..Default::default()
+    }
+    .save(&git.shadow_dir)
+    .unwrap();
+
+    // Baseline goes to the object store, pinned by a ref. Its content is in
+    // no commit, so only the ref keeps it alive.
+    let blob = baseline::write(&git, "CLAUDE.md", b"# Team v2\n").unwrap();
+    assert!(blob.is_some());
+    assert!(!baseline::file_path(&git, "CLAUDE.md").exists());
+    let mut config = ShadowConfig::new();
+    config
+        .add_overlay("CLAUDE.md".to_string(), git.head_commit().unwrap())
+        .unwrap();
+    config.set_baseline_blob("CLAUDE.md", blob);
+    config.save(&git.shadow_dir).unwrap();
+
+    // The blob survives garbage collection
+    std::process::Command::new("git")
+        .args(["gc", "--prune=now", "--quiet"])
+        .current_dir(&git.root)
+        .output()
+        .unwrap();
+
+    std::fs::write(git.root.join("CLAUDE.md"), "# Team v2\n# Mine\n").unwrap();
+    git.add("CLAUDE.md").unwrap();
+    hooks::pre_commit::handle(&git).unwrap();
+    assert_eq!(repo.read_file("CLAUDE.md"), "# Team v2\n");
+
+    std::process::Command::new("git")
+        .args(["commit", "-m", "update", "--no-verify"])
+        .current_dir(&git.root)
+        .output()
+        .unwrap();
+    hooks::post_commit::handle(&git).unwrap();
+
+    assert_eq!(repo.read_file("CLAUDE.md"), "# Team v2\n# Mine\n");
+    assert_eq!(
+        String::from_utf8_lossy(&git.show_file("HEAD", "CLAUDE.md").unwrap()),
+        "# Team v2\n"
+    );
+}
+
 #[test]
 fn test_full_phantom_commit_cycle() {
     let repo = common::TestRepo::new();
@@ -138,8 +201,12 @@ fn test_full_phantom_commit_cycle() {
     hooks::pre_commit::handle(&git).unwrap();
 
     // Verify: phantom file is stashed
-    let stash_content =
-        std::fs::read_to_string(git.shadow_dir.join("stash").join("local-notes.md")).unwrap();
+    let stash_content = std::fs::read_to_string(
+        git.shadow_dir
+            .join("stash")
+            .join(path::encode_path("local-notes.md")),
+    )
+    .unwrap();
     assert_eq!(stash_content, "# My local notes\n");
 
     // 6. Commit
@@ -370,8 +437,12 @@ fn test_mixed_overlay_and_phantom_directory() {
     let wt_content = std::fs::read_to_string(git.root.join("CLAUDE.md")).unwrap();
     assert_eq!(wt_content, "# Team\n", "Overlay should show baseline");
 
-    let stash_content =
-        std::fs::read_to_string(git.shadow_dir.join("stash").join("CLAUDE.md")).unwrap();
+    let stash_content = std::fs::read_to_string(
+        git.shadow_dir
+            .join("stash")
+            .join(path::encode_path("CLAUDE.md")),
+    )
+    .unwrap();
     assert_eq!(
         stash_content, "# Team\n# My personal notes\n",
         "Overlay shadow should be stashed"
@@ -401,6 +472,89 @@ fn test_mixed_overlay_and_phantom_directory() {
     assert!(git.root.join(".claude/config.json").exists());
 }
 
+/// `git commit <paths>` must leave overlays outside <paths> alone. Runs the
+/// real hooks through the built binary, since only git itself sets up the
+/// temporary index of a partial commit.
+#[cfg(unix)]
+#[test]
+fn test_partial_commit_skips_overlays_outside_paths() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let repo = common::TestRepo::new();
+    repo.create_file("CLAUDE.md", "# Team\n");
+    repo.create_file("NOTES.md", "# Notes\n");
+    repo.create_file("other.txt", "one\n");
+    repo.commit("initial commit");
+
+    let git = GitRepo::discover(&repo.root).unwrap();
+    repo.init_shadow();
+    let mut config = ShadowConfig::new();
+    for file in ["CLAUDE.md", "NOTES.md"] {
+        fs_util::atomic_write(
+            &git.shadow_dir
+                .join("baselines")
+                .join(path::encode_path(file)),
+            &git.show_file("HEAD", file).unwrap(),
+        )
+        .unwrap();
+        config
+            .add_overlay(file.to_string(), git.head_commit().unwrap())
+            .unwrap();
+    }
+    config.save(&git.shadow_dir).unwrap();
+
+    // The team updates CLAUDE.md; its baseline is now behind HEAD, so staging
+    // the baseline would revert the team change
+    repo.create_file("CLAUDE.md", "# Team v2\n");
+    repo.commit("team update");
+
+    let hooks_dir = git.git_dir.join("hooks");
+    for name in ["pre-commit", "post-commit"] {
+        let hook = hooks_dir.join(name);
+        let content = format!(
+            "#!/bin/sh\nexec \"{}\" hook {}\n",
+            env!("CARGO_BIN_EXE_git-shadow"),
+            name
+        );
+        std::fs::write(&hook, content).unwrap();
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    repo.create_file("CLAUDE.md", "# Team v2\n# Mine\n");
+    repo.create_file("NOTES.md", "# Notes\n# Mine\n");
+    repo.create_file("other.txt", "two\n");
+
+    let output = std::process::Command::new("git")
+        .args(["commit", "-m", "partial", "NOTES.md", "other.txt"])
+        .current_dir(&git.root)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "commit failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Only other.txt changed: NOTES.md went in as its baseline, CLAUDE.md
+    // was not touched
+    let changed = std::process::Command::new("git")
+        .args(["diff", "--name-only", "HEAD~1", "HEAD"])
+        .current_dir(&git.root)
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&changed.stdout), "other.txt\n");
+    let committed = git.show_file("HEAD", "CLAUDE.md").unwrap();
+    assert_eq!(committed, b"# Team v2\n");
+
+    // Shadow changes of both overlays are still in the working tree
+    assert_eq!(repo.read_file("CLAUDE.md"), "# Team v2\n# Mine\n");
+    assert_eq!(repo.read_file("NOTES.md"), "# Notes\n# Mine\n");
+    assert!(matches!(
+        lock::check_lock(&git.shadow_dir).unwrap(),
+        lock::LockStatus::Free
+    ));
+}
+
 fn install_hooks_for_test(git: &GitRepo) {
     let hooks_dir = git.git_dir.join("hooks");
     std::fs::create_dir_all(&hooks_dir).unwrap();
@@ -0,0 +1,110 @@
+//! E2E integration test: shadow management inside a linked git worktree or a GIT_WORK_TREE
+
+mod common;
+
+use git_shadow::config::ShadowConfig;
+use git_shadow::git::GitRepo;
+use git_shadow::hooks;
+use git_shadow::{fs_util, path};
+
+#[test]
+fn test_discover_worktree_shares_shadow_dir() {
+    let repo = common::TestRepo::new();
+    repo.create_file("CLAUDE.md", "# Team\n");
+    repo.commit("initial commit");
+
+    let (_wt_dir, wt_root) = repo.add_worktree("feature");
+
+    let main = GitRepo::discover(&repo.root).unwrap();
+    let wt = GitRepo::discover(&wt_root).unwrap();
+
+    assert_eq!(wt.root, wt_root);
+    assert_eq!(wt.shadow_dir, repo.shadow_dir());
+    assert_eq!(wt.shadow_dir, main.shadow_dir);
+    assert_eq!(wt.hooks_dir(), repo.git_dir().join("hooks"));
+    // `.git` is a file inside a linked worktree
+    assert!(wt_root.join(".git").is_file());
+}
+
+#[test]
+fn test_overlay_commit_cycle_in_worktree() {
+    let repo = common::TestRepo::new();
+    repo.create_file("CLAUDE.md", "# Team\n");
+    repo.commit("initial commit");
+    repo.init_shadow();
+
+    let (_wt_dir, wt_root) = repo.add_worktree("feature");
+    let git = GitRepo::discover(&wt_root).unwrap();
+
+    let commit = git.head_commit().unwrap();
+    let baseline_content = git.show_file("HEAD", "CLAUDE.md").unwrap();
+    let encoded = path::encode_path("CLAUDE.md");
+    fs_util::atomic_write(
+        &git.shadow_dir.join("baselines").join(&encoded),
+        &baseline_content,
+    )
+    .unwrap();
+    let mut config = ShadowConfig::new();
+    config.add_overlay("CLAUDE.md".to_string(), commit).unwrap();
+    config.save(&git.shadow_dir).unwrap();
+
+    std::fs::write(wt_root.join("CLAUDE.md"), "# Team\n# Worktree notes\n").unwrap();
+    git.add("CLAUDE.md").unwrap();
+
+    hooks::pre_commit::handle(&git).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(wt_root.join("CLAUDE.md")).unwrap(),
+        "# Team\n"
+    );
+
+    hooks::post_commit::handle(&git).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(wt_root.join("CLAUDE.md")).unwrap(),
+        "# Team\n# Worktree notes\n"
+    );
+}
+
+#[test]
+fn test_git_dir_and_work_tree_env() {
+    // Dotfiles-style setup: a bare repository driven by GIT_DIR/GIT_WORK_TREE
+    let dir = tempfile::tempdir().unwrap();
+    let git_dir = dir.path().join("repo.git");
+    let work_tree = dir.path().join("work");
+    std::fs::create_dir_all(&work_tree).unwrap();
+    let init = std::process::Command::new("git")
+        .args(["init", "--bare"])
+        .arg(&git_dir)
+        .output()
+        .unwrap();
+    assert!(init.status.success());
+    std::fs::write(work_tree.join("local.md"), "# Local\n").unwrap();
+
+    let shadow = |args: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_git-shadow"))
+            .args(args)
+            .current_dir(&work_tree)
+            .env("GIT_DIR", &git_dir)
+            .env("GIT_WORK_TREE", &work_tree)
+            .output()
+            .unwrap()
+    };
+    assert!(shadow(&["install"]).status.success());
+    let add = shadow(&["add", "--phantom", "local.md"]);
+    assert!(
+        add.status.success(),
+        "{}",
+        String::from_utf8_lossy(&add.stderr)
+    );
+
+    let config = ShadowConfig::load(&git_dir.join("shadow")).unwrap();
+    assert!(config.get("local.md").is_some());
+
+    // Without GIT_WORK_TREE the bare repository is rejected with a clear error
+    let bare = std::process::Command::new(env!("CARGO_BIN_EXE_git-shadow"))
+        .arg("status")
+        .current_dir(&git_dir)
+        .output()
+        .unwrap();
+    assert!(!bare.status.success());
+    assert!(String::from_utf8_lossy(&bare.stderr).contains("bare repository"));
+}